@@ -83,7 +83,7 @@ impl<T: EItemInfoAdapter> ValueAdapter for Vec<T> {
             .map(|value| value.into_evalue(registry))
             .collect::<miette::Result<Vec<_>>>()?;
 
-        let info = Self::edata_type(registry);
+        let info = T::edata_type(registry);
 
         Ok(EValue::List {
             values,
@@ -10,6 +10,7 @@ use crate::json_utils::repr::{JsonRepr, Repr};
 use crate::json_utils::{json_kind, JsonMap, JsonValue};
 use crate::m_try;
 use crate::registry::ETypesRegistry;
+use crate::validation::Validator;
 use crate::value::id::ETypeId;
 use crate::value::EValue;
 use itertools::Itertools;
@@ -30,6 +31,10 @@ pub struct EStructData {
     // pub id_field: Option<usize>,
     pub repr: Option<Repr>,
     pub extra_properties: HashMap<ObjectPropertyId, ETypeConst>,
+    /// Validators of the whole struct, like cross-field rules
+    pub validators: Vec<Validator>,
+    /// Structs whose fields are included into this struct
+    pub includes: Vec<ETypeId>,
     title: ObjectTitle,
 }
 
@@ -60,6 +65,8 @@ impl EStructData {
             // id_field: None,
             repr,
             extra_properties,
+            validators: vec![],
+            includes: vec![],
             title: Default::default(),
             generic_parent_id: None,
         }
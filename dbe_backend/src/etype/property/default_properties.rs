@@ -1,6 +1,7 @@
 use crate::etype::econst::ETypeConst;
 use crate::etype::property::wrappers::parser::ParsedFmtProp;
 use crate::extra_properties;
+use crate::value::ENumber;
 use ustr::Ustr;
 
 extra_properties! {
@@ -8,6 +9,30 @@ extra_properties! {
     pub prop<field> default: ETypeConst;
     pub prop<field> inline: bool;
 
+    /// Minimum allowed value of the number field
+    pub prop<field> min: ENumber;
+
+    /// Maximum allowed value of the number field
+    pub prop<field> max: ENumber;
+
+    /// Whether the number field only accepts whole numbers
+    pub prop<field> integer: bool;
+
+    /// Regex pattern that the whole value of the string field must match
+    pub prop<field> pattern: Ustr;
+
+    /// Whether the string, list or map field must not be empty
+    pub prop<field> non_empty: bool;
+
+    /// Minimum length of the string, list or map field
+    pub prop<field> min_length: ENumber;
+
+    /// Maximum length of the string, list or map field
+    pub prop<field> max_length: ENumber;
+
+    /// Whether all items of the list field must be unique
+    pub prop<field> unique: bool;
+
     /// Whether to save default values for fields in this strict
     pub prop<object> save_default_values: bool;
 
@@ -1,7 +1,14 @@
 use crate::graph::editing::GraphEditingContext;
+use crate::graph::history::{GraphEditLog, GraphHistory, GraphState};
 use crate::graph::inputs::{GraphInput, GraphOutput};
 use crate::graph::node::colors::{NodeColorScheme, PackedNodeColorScheme};
 use crate::graph::node::commands::SnarlCommands;
+use crate::graph::node::functional::transient_storage::{
+    GET_TRANSIENT_VALUE, HAS_TRANSIENT_VALUE, SET_TRANSIENT_VALUE, TRY_GET_TRANSIENT_VALUE,
+};
+use crate::graph::node::groups::subgraph::SubgraphNode;
+use crate::graph::node::groups::tree_subgraph::TreeSubgraph;
+use crate::graph::node::script_node::ScriptNode;
 use crate::graph::node::{get_node_factory, NodeContext, SnarlNode};
 use crate::graph::region::region_graph::RegionGraph;
 use crate::graph::region::RegionInfo;
@@ -15,18 +22,18 @@ use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
 use emath::Pos2;
 use itertools::Itertools;
 use miette::{miette, Context, IntoDiagnostic};
-use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use ustr::Ustr;
-use utils::map::{HashMap, OrderMap};
+use utils::map::{HashMap, HashSet, OrderMap};
 use uuid::Uuid;
 
 pub mod cache;
 pub mod editing;
 pub mod execution;
+pub mod history;
 pub mod inputs;
 pub mod node;
 pub mod region;
@@ -41,6 +48,7 @@ pub struct Graph {
     outputs: SmallVec<[GraphOutput; 1]>,
     regions: OrderMap<Uuid, RegionInfo>,
     region_graph: RegionGraph,
+    history: GraphHistory,
 }
 
 impl Clone for Graph {
@@ -52,35 +60,14 @@ impl Clone for Graph {
             outputs: self.outputs.clone(),
             regions: self.regions.clone(),
             region_graph: RegionGraph::default(),
+            history: GraphHistory::default(),
         }
     }
 }
 
 impl Hash for Graph {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.inline_values.hash(state);
-        self.inputs.hash(state);
-        self.outputs.hash(state);
-
-        for (id, node) in self
-            .snarl
-            .nodes_ids_data()
-            .sorted_unstable_by_key(|(id, _)| *id)
-        {
-            id.hash(state);
-            node.value.hash(state);
-            OrderedFloat(node.pos.x).hash(state);
-            OrderedFloat(node.pos.y).hash(state);
-            node.open.hash(state);
-        }
-
-        self.snarl
-            .wires()
-            .sorted_unstable()
-            .for_each(|(out_pin, in_pin)| {
-                in_pin.hash(state);
-                out_pin.hash(state);
-            });
+        self.state().hash(state);
     }
 }
 
@@ -133,6 +120,7 @@ impl Graph {
             outputs: packed.outputs,
             regions: packed.regions.into_iter().map(|r| (r.id(), r)).collect(),
             region_graph: Default::default(),
+            history: Default::default(),
         };
 
         m_try(|| {
@@ -337,6 +325,98 @@ impl Graph {
         )
     }
 
+    /// IDs of all node groups that are used by this graph directly
+    pub fn subgraph_ids(&self) -> HashSet<Uuid> {
+        let mut ids = HashSet::default();
+        for node in self.snarl.nodes() {
+            if let Some(subgraph) = node.node.downcast_ref::<SubgraphNode>() {
+                ids.insert(subgraph.graph_id);
+            } else if let Some(tree) = node.node.downcast_ref::<TreeSubgraph>() {
+                ids.extend(tree.inner_graph().subgraph_ids());
+            }
+        }
+        ids
+    }
+
+    /// IDs of scripts that are used by this graph, including the scripts
+    /// used by tree subgraphs
+    pub fn script_ids(&self) -> HashSet<Uuid> {
+        let mut ids = HashSet::default();
+        for node in self.snarl.nodes() {
+            if let Some(script) = node.node.downcast_ref::<ScriptNode>() {
+                ids.insert(script.script_id);
+            } else if let Some(tree) = node.node.downcast_ref::<TreeSubgraph>() {
+                ids.extend(tree.inner_graph().script_ids());
+            }
+        }
+        ids
+    }
+
+    /// Transient storage keys that are written and read by this graph, as
+    /// `(written, read)`
+    ///
+    /// Only keys that are provided as inline values are known, keys that are
+    /// computed during execution are not included. Node groups used by this
+    /// graph are not scanned, except for tree subgraphs
+    pub fn transient_storage_keys(&self) -> (HashSet<String>, HashSet<String>) {
+        let mut written = HashSet::default();
+        let mut read = HashSet::default();
+        self.collect_transient_storage_keys(&mut written, &mut read);
+        (written, read)
+    }
+
+    fn collect_transient_storage_keys(
+        &self,
+        written: &mut HashSet<String>,
+        read: &mut HashSet<String>,
+    ) {
+        for (id, node) in self.snarl.nodes_ids_data() {
+            if let Some(tree) = node.value.node.downcast_ref::<TreeSubgraph>() {
+                tree.inner_graph()
+                    .collect_transient_storage_keys(written, read);
+                continue;
+            }
+
+            let target = match node.value.id().as_str() {
+                SET_TRANSIENT_VALUE => &mut *written,
+                HAS_TRANSIENT_VALUE | TRY_GET_TRANSIENT_VALUE | GET_TRANSIENT_VALUE => &mut *read,
+                _ => continue,
+            };
+
+            let key_pin = InPinId { node: id, input: 0 };
+            if !self.snarl.in_pin(key_pin).remotes.is_empty() {
+                continue;
+            }
+
+            if let Some(EValue::String { value }) = self.inline_values.get(&key_pin) {
+                target.insert(value.clone());
+            }
+        }
+    }
+
+    /// Calls the provided closure for every inline value of this graph,
+    /// including inline values of tree subgraphs
+    pub fn for_each_inline_value_mut(
+        &mut self,
+        cb: &mut dyn FnMut(&mut EValue) -> miette::Result<()>,
+    ) -> miette::Result<()> {
+        for value in self.inline_values.values_mut() {
+            cb(value)?;
+        }
+
+        for (_, node) in self.snarl.nodes_ids_mut() {
+            if let Some(tree) = node.node.downcast_mut::<TreeSubgraph>() {
+                tree.inner_graph_mut().for_each_inline_value_mut(cb)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn inline_values(&self) -> &OrderMap<InPinId, EValue> {
+        &self.inline_values
+    }
+
     pub fn inputs(&self) -> &SmallVec<[GraphInput; 1]> {
         &self.inputs
     }
@@ -372,6 +452,32 @@ impl Graph {
     pub fn ensure_region_graph_ready(&mut self) {
         self.region_graph.ensure_ready(&self.snarl);
     }
+
+    /// Takes the edits recorded since the last call
+    ///
+    /// Recording starts with the first call, so the first log is always
+    /// empty. Callers should compare [GraphEditLog::base] with the graph
+    /// state they know about, to check that the log is complete
+    pub fn take_edits(&mut self) -> GraphEditLog {
+        let state = GraphState {
+            snarl: &self.snarl,
+            inline_values: &self.inline_values,
+            inputs: &self.inputs,
+            outputs: &self.outputs,
+            regions: &self.regions,
+        };
+        self.history.take(state)
+    }
+
+    fn state(&self) -> GraphState {
+        GraphState {
+            snarl: &self.snarl,
+            inline_values: &self.inline_values,
+            inputs: &self.inputs,
+            outputs: &self.outputs,
+            regions: &self.regions,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,9 +1,11 @@
 use crate::graph::execution::GraphExecutionContext;
+use crate::graph::history::{set_inline_value, GraphHistory, GraphState, Touch};
 use crate::graph::inputs::{GraphInput, GraphOutput};
 use crate::graph::node::commands::{SnarlCommand, SnarlCommands};
 use crate::graph::node::enum_node::EnumNode;
 use crate::graph::node::groups::subgraph::SubgraphNode;
 use crate::graph::node::list::ListNode;
+use crate::graph::node::script_node::ScriptNode;
 use crate::graph::node::struct_node::StructNode;
 use crate::graph::node::{get_node_factory, Node, NodeContext, SnarlNode};
 use crate::graph::region::region_graph::RegionGraph;
@@ -179,6 +181,87 @@ impl GraphEditingContext<'_, '_> {
         ))
     }
 
+    /// Edits the inline input value of the given pin, recording the change
+    /// in the graph history
+    ///
+    /// Returns `None` if the pin has no inline value
+    pub fn edit_inline_input<T>(
+        &mut self,
+        pin: InPinId,
+        edit: impl FnOnce(&mut EValue) -> T,
+    ) -> miette::Result<Option<T>> {
+        let value_of = |values: &OrderMap<InPinId, EValue>| {
+            values
+                .get_full(&pin)
+                .map(|(idx, _, value)| (idx, value.clone()))
+        };
+
+        let before = value_of(self.inline_values);
+        let result = self.get_inline_input_mut(pin)?.map(edit);
+        let after = value_of(self.inline_values);
+
+        if before != after {
+            // The value is temporarily reverted, so the history sees the
+            // state before the edit
+            set_inline_value(self.ctx.inline_values, pin, before);
+            self.touch(Touch::Nodes(&[pin.node]));
+            let (history, state) = self.history_state();
+            history.begin_inline_value(state, pin);
+            set_inline_value(self.ctx.inline_values, pin, after);
+            let (history, state) = self.history_state();
+            history.end(state);
+        }
+
+        Ok(result)
+    }
+
+    /// Runs the edit, recording all its changes in the graph history as a
+    /// single action
+    ///
+    /// Edits done by the methods of the context are recorded already, this
+    /// is only needed to group multiple edits together. Parts of the graph
+    /// changed directly must be reported with [GraphEditingContext::touch]
+    /// beforehand, otherwise the action is not recorded
+    pub fn record<T>(
+        &mut self,
+        edit: impl FnOnce(&mut Self) -> miette::Result<T>,
+    ) -> miette::Result<T> {
+        let (history, state) = self.history_state();
+        history.begin_action(state);
+
+        let result = edit(self);
+
+        let (history, state) = self.history_state();
+        history.end(state);
+
+        result
+    }
+
+    /// Reports the part of the graph that is about to be changed by the
+    /// recorded action
+    pub(crate) fn touch(&mut self, touch: Touch) {
+        let (history, state) = self.history_state();
+        history.touch(state, touch);
+    }
+
+    fn nodes_inserted(&mut self, ids: &[NodeId]) {
+        let (history, state) = self.history_state();
+        history.nodes_inserted(state, ids);
+    }
+
+    fn history_state(&mut self) -> (&mut GraphHistory, GraphState) {
+        (
+            &mut *self.ctx.history,
+            GraphState {
+                snarl: &*self.snarl,
+                inline_values: &*self.ctx.inline_values,
+                inputs: &*self.ctx.inputs,
+                outputs: &*self.ctx.outputs,
+                regions: &*self.ctx.regions,
+            },
+        )
+    }
+
     pub fn update_all_nodes_state(&mut self, commands: &mut SnarlCommands) -> miette::Result<()> {
         for (id, node) in self.snarl.nodes_ids_mut() {
             node.update_state(node_context!(self.ctx), commands, id)
@@ -193,55 +276,58 @@ impl GraphEditingContext<'_, '_> {
         to: &InPin,
         commands: &mut SnarlCommands,
     ) -> miette::Result<bool> {
-        let success = m_try(|| -> miette::Result<bool> {
-            let from_node = &self.snarl[from.id.node];
-
-            let from_pin = from_node.try_output(node_context!(self), from.id.output)?;
-
-            let based_on_input = from_pin.ty.is_based_on_target();
-            let can_output = if based_on_input {
-                let to_node = &self.snarl[to.id.node];
-                let ty = to_node.try_input(node_context!(self), to.id.input)?;
-                from_node.can_output_to(node_context!(self), from, to, &ty.ty)?
-            } else {
-                true
-            };
-
-            if can_output {
-                let to_node = &mut self.snarl[to.id.node];
-
-                if !to_node.try_connect(
-                    node_context!(self.ctx),
-                    commands,
-                    from,
-                    to,
-                    &from_pin.ty,
-                )? {
-                    return Ok(false);
-                }
-
-                if based_on_input {
-                    let to_pin = to_node.try_input(node_context!(self.ctx), to.id.input)?;
-                    let from_node = &mut self.snarl[from.id.node];
-                    from_node.connected_to_output(
-                        node_context!(self.ctx),
+        self.record(|ctx| {
+            ctx.touch(Touch::Nodes(&[from.id.node, to.id.node]));
+            let success = m_try(|| -> miette::Result<bool> {
+                let from_node = &ctx.snarl[from.id.node];
+
+                let from_pin = from_node.try_output(node_context!(ctx), from.id.output)?;
+
+                let based_on_input = from_pin.ty.is_based_on_target();
+                let can_output = if based_on_input {
+                    let to_node = &ctx.snarl[to.id.node];
+                    let ty = to_node.try_input(node_context!(ctx), to.id.input)?;
+                    from_node.can_output_to(node_context!(ctx), from, to, &ty.ty)?
+                } else {
+                    true
+                };
+
+                if can_output {
+                    let to_node = &mut ctx.snarl[to.id.node];
+
+                    if !to_node.try_connect(
+                        node_context!(ctx.ctx),
                         commands,
                         from,
                         to,
-                        &to_pin.ty,
-                    )?;
-                }
+                        &from_pin.ty,
+                    )? {
+                        return Ok(false);
+                    }
 
-                Ok(true)
-            } else {
-                Ok(false)
-            }
-        })
-        .with_context(|| format!("failed to connect pins: {:?} -> {:?}", from.id, to.id))?;
+                    if based_on_input {
+                        let to_pin = to_node.try_input(node_context!(ctx.ctx), to.id.input)?;
+                        let from_node = &mut ctx.snarl[from.id.node];
+                        from_node.connected_to_output(
+                            node_context!(ctx.ctx),
+                            commands,
+                            from,
+                            to,
+                            &to_pin.ty,
+                        )?;
+                    }
 
-        commands.execute(self)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            })
+            .with_context(|| format!("failed to connect pins: {:?} -> {:?}", from.id, to.id))?;
+
+            commands.execute(ctx)?;
 
-        Ok(success)
+            Ok(success)
+        })
     }
 
     pub fn disconnect(
@@ -250,9 +336,12 @@ impl GraphEditingContext<'_, '_> {
         to: &InPin,
         commands: &mut SnarlCommands,
     ) -> miette::Result<()> {
-        self.snarl[to.id.node].try_disconnect(node_context!(self.ctx), commands, from, to)?;
+        self.record(|ctx| {
+            ctx.touch(Touch::Nodes(&[from.id.node, to.id.node]));
+            ctx.snarl[to.id.node].try_disconnect(node_context!(ctx.ctx), commands, from, to)?;
 
-        commands.execute(self)
+            commands.execute(ctx)
+        })
     }
 
     pub fn remove_node(
@@ -260,71 +349,81 @@ impl GraphEditingContext<'_, '_> {
         node: NodeId,
         commands: &mut SnarlCommands,
     ) -> miette::Result<()> {
-        commands.push(SnarlCommand::DeleteNode { node });
+        self.record(|ctx| {
+            commands.push(SnarlCommand::DeleteNode { node });
 
-        commands
-            .execute(self)
-            .with_context(|| format!("failed to remove node: {:?}", node))
+            commands
+                .execute(ctx)
+                .with_context(|| format!("failed to remove node: {:?}", node))
+        })
     }
 
     pub fn duplicate_node(&mut self, node_id: NodeId) -> miette::Result<()> {
-        let node = self.snarl.get_node_info(node_id).unwrap();
-        let factory_id = node.value.id();
-        let duplicate_nodes = node.value.duplicate();
-        let pos = node.pos + Vec2::splat(tweak!(50.0));
-        let created_ids = get_node_factory(&factory_id)
-            .unwrap()
-            .create_nodes(self.snarl, pos);
-
-        if duplicate_nodes.len() != created_ids.len() {
-            for node in created_ids.iter() {
-                self.snarl.remove_node(*node);
-            }
-            return Err(miette!(
+        self.record(|ctx| {
+            let node = ctx.snarl.get_node_info(node_id).unwrap();
+            let factory_id = node.value.id();
+            let duplicate_nodes = node.value.duplicate();
+            let pos = node.pos + Vec2::splat(tweak!(50.0));
+            let created_ids = get_node_factory(&factory_id)
+                .unwrap()
+                .create_nodes(ctx.snarl, pos);
+            ctx.nodes_inserted(&created_ids);
+
+            if duplicate_nodes.len() != created_ids.len() {
+                for node in created_ids.iter() {
+                    ctx.snarl.remove_node(*node);
+                }
+                return Err(miette!(
                 help =
                     "This is a bug in the node implementation, please report it to the developers.",
                 "created node count mismatch. Factory: {}, created: {}",
                 duplicate_nodes.len(),
                 created_ids.len()
             ))
-            .with_context(|| format!("failed to duplicate node {}({:?})", factory_id, node_id));
-        }
+                .with_context(|| {
+                    format!("failed to duplicate node {}({:?})", factory_id, node_id)
+                });
+            }
 
-        self.mark_dirty();
+            ctx.mark_dirty();
 
-        for (id, duplicated_node) in created_ids.iter().zip_eq(duplicate_nodes) {
-            let node = &mut self.snarl[*id].node;
-            *node = duplicated_node;
-        }
-        self.process_created_nodes(created_ids.iter().copied())?;
-
-        let inline_values = self
-            .inline_values
-            .iter()
-            .filter(|(in_pin, _)| in_pin.node == node_id)
-            .map(|(in_pin, value)| (in_pin.input, value.clone()))
-            .collect_vec();
-
-        for (input, value) in inline_values {
-            self.inline_values.insert(
-                InPinId {
-                    node: created_ids[0],
-                    input,
-                },
-                value.clone(),
-            );
-        }
+            for (id, duplicated_node) in created_ids.iter().zip_eq(duplicate_nodes) {
+                let node = &mut ctx.snarl[*id].node;
+                *node = duplicated_node;
+            }
+            ctx.process_created_nodes(created_ids.iter().copied())?;
+
+            let inline_values = ctx
+                .inline_values
+                .iter()
+                .filter(|(in_pin, _)| in_pin.node == node_id)
+                .map(|(in_pin, value)| (in_pin.input, value.clone()))
+                .collect_vec();
+
+            for (input, value) in inline_values {
+                ctx.inline_values.insert(
+                    InPinId {
+                        node: created_ids[0],
+                        input,
+                    },
+                    value.clone(),
+                );
+            }
 
-        Ok(())
+            Ok(())
+        })
     }
 
     pub fn create_node(&mut self, id: Ustr, pos: Pos2) -> miette::Result<SmallVec<[NodeId; 2]>> {
-        let ids = get_node_factory(&id).unwrap().create_nodes(self.snarl, pos);
-        self.mark_dirty();
+        self.record(|ctx| {
+            let ids = get_node_factory(&id).unwrap().create_nodes(ctx.snarl, pos);
+            ctx.nodes_inserted(&ids);
+            ctx.mark_dirty();
 
-        self.process_created_nodes(ids.iter().copied())?;
+            ctx.process_created_nodes(ids.iter().copied())?;
 
-        Ok(ids)
+            Ok(ids)
+        })
     }
 
     fn process_created_nodes(
@@ -333,7 +432,11 @@ impl GraphEditingContext<'_, '_> {
     ) -> miette::Result<()> {
         for id in ids {
             let node = &self.snarl[id].node;
-            for reg in [node.region_source(), node.region_end()].iter().flatten() {
+            let regions = [node.region_source(), node.region_end()];
+            if regions.iter().any(Option::is_some) {
+                self.touch(Touch::Regions);
+            }
+            for reg in regions.iter().flatten() {
                 self.ctx
                     .regions
                     .entry(*reg)
@@ -350,12 +453,31 @@ impl GraphEditingContext<'_, '_> {
         id: Uuid,
         pos: Pos2,
     ) -> miette::Result<SmallVec<[NodeId; 2]>> {
-        let node = Box::new(SubgraphNode::with_graph(id));
-        let id = self.snarl.insert_node(pos, SnarlNode::new(node));
-        self.mark_dirty();
-        self.process_created_nodes([id])?;
+        self.record(|ctx| {
+            let node = Box::new(SubgraphNode::with_graph(id));
+            let id = ctx.snarl.insert_node(pos, SnarlNode::new(node));
+            ctx.nodes_inserted(&[id]);
+            ctx.mark_dirty();
+            ctx.process_created_nodes([id])?;
+
+            Ok(smallvec![id])
+        })
+    }
 
-        Ok(smallvec![id])
+    pub fn create_script_node(
+        &mut self,
+        id: Uuid,
+        pos: Pos2,
+    ) -> miette::Result<SmallVec<[NodeId; 2]>> {
+        self.record(|ctx| {
+            let node = Box::new(ScriptNode::with_script(id));
+            let id = ctx.snarl.insert_node(pos, SnarlNode::new(node));
+            ctx.nodes_inserted(&[id]);
+            ctx.mark_dirty();
+            ctx.process_created_nodes([id])?;
+
+            Ok(smallvec![id])
+        })
     }
 
     pub fn create_object_node(
@@ -364,45 +486,48 @@ impl GraphEditingContext<'_, '_> {
         pos: Pos2,
         apply_value: Option<EValue>,
     ) -> miette::Result<SmallVec<[NodeId; 2]>> {
-        let info = self
-            .registry
-            .get_object(&object)
-            .expect("object id should be valid");
-        let info = info.deref();
-        let node: Box<dyn Node> = match (info, &apply_value) {
-            (EObjectType::Struct(_), None) => Box::new(StructNode::new(object)),
-            (EObjectType::Struct(_), Some(value)) => Box::new(StructNode::from_value(value)?),
-            (EObjectType::Enum(data), None) => Box::new(EnumNode::new(data.variant_ids()[0])),
-            (EObjectType::Enum(_), Some(value)) => Box::new(EnumNode::from_value(value)?),
-        };
+        self.record(|ctx| {
+            let info = ctx
+                .registry
+                .get_object(&object)
+                .expect("object id should be valid");
+            let info = info.deref();
+            let node: Box<dyn Node> = match (info, &apply_value) {
+                (EObjectType::Struct(_), None) => Box::new(StructNode::new(object)),
+                (EObjectType::Struct(_), Some(value)) => Box::new(StructNode::from_value(value)?),
+                (EObjectType::Enum(data), None) => Box::new(EnumNode::new(data.variant_ids()[0])),
+                (EObjectType::Enum(_), Some(value)) => Box::new(EnumNode::from_value(value)?),
+            };
 
-        let id = self.snarl.insert_node(pos, SnarlNode::new(node));
-        self.inline_values.retain(|in_pin, _| in_pin.node != id);
-
-        if let Some(value) = apply_value {
-            match (info, value) {
-                (EObjectType::Struct(_), EValue::Struct { fields, .. }) => {
-                    for (idx, (_, value)) in fields.into_iter().enumerate() {
-                        self.inline_values.insert(
-                            InPinId {
-                                node: id,
-                                input: idx,
-                            },
-                            value,
-                        );
+            let id = ctx.snarl.insert_node(pos, SnarlNode::new(node));
+            ctx.nodes_inserted(&[id]);
+            ctx.inline_values.retain(|in_pin, _| in_pin.node != id);
+
+            if let Some(value) = apply_value {
+                match (info, value) {
+                    (EObjectType::Struct(_), EValue::Struct { fields, .. }) => {
+                        for (idx, (_, value)) in fields.into_iter().enumerate() {
+                            ctx.inline_values.insert(
+                                InPinId {
+                                    node: id,
+                                    input: idx,
+                                },
+                                value,
+                            );
+                        }
                     }
+                    (EObjectType::Enum(_), EValue::Enum { data, .. }) => {
+                        ctx.inline_values
+                            .insert(InPinId { node: id, input: 0 }, *data);
+                    }
+                    _ => unreachable!(),
                 }
-                (EObjectType::Enum(_), EValue::Enum { data, .. }) => {
-                    self.inline_values
-                        .insert(InPinId { node: id, input: 0 }, *data);
-                }
-                _ => unreachable!(),
             }
-        }
 
-        self.mark_dirty();
+            ctx.mark_dirty();
 
-        Ok(smallvec![id])
+            Ok(smallvec![id])
+        })
     }
 
     pub fn create_list_node(
@@ -410,17 +535,20 @@ impl GraphEditingContext<'_, '_> {
         item_ty: EListId,
         pos: Pos2,
     ) -> miette::Result<SmallVec<[NodeId; 2]>> {
-        let item_ty = self
-            .registry
-            .get_list(&item_ty)
-            .expect("list id should be valid")
-            .value_type;
-        let node = Box::new(ListNode::of_type(item_ty));
-        let id = self.snarl.insert_node(pos, SnarlNode::new(node));
-        self.inline_values.retain(|in_pin, _| in_pin.node != id);
-        self.mark_dirty();
-
-        Ok(smallvec![id])
+        self.record(|ctx| {
+            let item_ty = ctx
+                .registry
+                .get_list(&item_ty)
+                .expect("list id should be valid")
+                .value_type;
+            let node = Box::new(ListNode::of_type(item_ty));
+            let id = ctx.snarl.insert_node(pos, SnarlNode::new(node));
+            ctx.nodes_inserted(&[id]);
+            ctx.inline_values.retain(|in_pin, _| in_pin.node != id);
+            ctx.mark_dirty();
+
+            Ok(smallvec![id])
+        })
     }
 
     pub fn read_output(&mut self, id: OutPinId) -> miette::Result<EValue> {
@@ -451,6 +579,7 @@ pub struct PartialGraphEditingContext<'a> {
     pub registry: &'a ETypesRegistry,
     pub docs: &'a Docs,
     pub graphs: Option<&'a ProjectGraphs>,
+    pub(crate) history: &'a mut GraphHistory,
     side_effects: SideEffectsContext<'a>,
     is_node_group: bool,
     input_values: &'a [EValue],
@@ -483,6 +612,7 @@ impl<'a> PartialGraphEditingContext<'a> {
                 output_values,
                 regions: &mut graph.regions,
                 region_graph: &mut graph.region_graph,
+                history: &mut graph.history,
             },
             &mut graph.snarl,
         )
@@ -510,6 +640,7 @@ impl<'a> PartialGraphEditingContext<'a> {
                 is_node_group: self.is_node_group,
                 regions: self.regions,
                 region_graph: self.region_graph,
+                history: self.history,
             },
         }
     }
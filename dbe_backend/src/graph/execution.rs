@@ -1,4 +1,6 @@
 use crate::graph::cache::GraphCache;
+use crate::graph::execution::profiler::ExecutionProfile;
+use crate::graph::execution::trace::ExecutionTrace;
 use crate::graph::inputs::{GraphInput, GraphOutput};
 use crate::graph::node::extras::ExecutionExtras;
 use crate::graph::node::ports::NodePortType;
@@ -21,6 +23,9 @@ use std::ops::{Deref, DerefMut};
 use utils::map::{HashMap, OrderMap};
 use uuid::Uuid;
 
+pub mod profiler;
+pub mod trace;
+
 macro_rules! node_context {
     ($source:expr) => {
         NodeContext {
@@ -123,9 +128,23 @@ impl<'a> GraphExecutionContext<'a, 'a> {
                 regions,
                 region_graph,
                 regional_data: Default::default(),
+                trace: None,
+                profile: None,
             },
         }
     }
+
+    /// Records all node evaluations into the given trace
+    pub fn with_trace(mut self, trace: &'a mut ExecutionTrace) -> Self {
+        self.ctx.trace = Some(trace);
+        self
+    }
+
+    /// Collects execution timings of all nodes into the given profile
+    pub fn with_profile(mut self, profile: &'a mut ExecutionProfile) -> Self {
+        self.ctx.profile = Some(profile);
+        self
+    }
 }
 
 impl<'a> Deref for GraphExecutionContext<'a, '_> {
@@ -148,6 +167,20 @@ impl GraphExecutionContext<'_, '_> {
     }
 
     pub fn full_eval(&mut self, side_effects: bool) -> miette::Result<()> {
+        if let Some(profile) = self.ctx.profile.as_deref_mut() {
+            profile.enter_graph();
+        }
+
+        let result = self.full_eval_inner(side_effects);
+
+        if let Some(profile) = self.ctx.profile.as_deref_mut() {
+            profile.exit_graph();
+        }
+
+        result
+    }
+
+    fn full_eval_inner(&mut self, side_effects: bool) -> miette::Result<()> {
         self.region_graph
             .try_as_data()
             .context("Graph structure is invalid")?;
@@ -311,8 +344,19 @@ impl GraphExecutionContext<'_, '_> {
 
     fn eval_node_inner(&mut self, id: NodeId, run_side_effects: bool) -> miette::Result<()> {
         // trace!("Evaluating node {:?}", id);
+        if let Some(profile) = self.ctx.profile.as_deref_mut() {
+            if !profile.has_entry(id) {
+                let title = self.snarl.get_node(id).map_or_else(
+                    || format!("{:?}", id),
+                    |node| node.title(node_context!(self.ctx)),
+                );
+                profile.add_entry(id, title);
+            }
+            profile.enter_node(id);
+        }
+
         let mut iteration = 0;
-        m_try(|| {
+        let result = m_try(|| {
             loop {
                 iteration += 1;
                 let node = self
@@ -325,6 +369,9 @@ impl GraphExecutionContext<'_, '_> {
 
                 let region_graph = expect_region_graph(self.region_graph);
                 if let Some(region) = node.region_end() {
+                    if let Some(trace) = self.ctx.trace.as_deref_mut() {
+                        trace.enter_iteration(region, iteration - 1);
+                    }
                     let start = region_graph.region_data(&region).start_node;
                     // always execute the start node of the region
                     self.eval_node_if_uncached(start, run_side_effects)?;
@@ -338,6 +385,7 @@ impl GraphExecutionContext<'_, '_> {
                         self.ctx.output_values,
                         self.ctx.regional_data.as_mut(),
                         self.ctx.side_effects.with_node(id),
+                        self.ctx.profile.as_deref_mut(),
                     ),
                 )? {
                     for i in 0..inputs_count {
@@ -394,8 +442,18 @@ impl GraphExecutionContext<'_, '_> {
                         self.ctx.output_values,
                         self.ctx.regional_data.as_mut(),
                         self.ctx.side_effects.with_node(id),
+                        self.ctx.profile.as_deref_mut(),
                     ),
-                )?;
+                );
+
+                if let Some(trace) = self.ctx.trace.as_deref_mut() {
+                    trace.record(id, &input_values, result.as_ref().map(|_| &*outputs));
+                    if node.region_end().is_some() {
+                        trace.exit_iteration();
+                    }
+                }
+
+                let result = result?;
 
                 match result {
                     ExecutionResult::Done => {
@@ -423,7 +481,13 @@ impl GraphExecutionContext<'_, '_> {
                     iteration, id
                 )
             }
-        })
+        });
+
+        if let Some(profile) = self.ctx.profile.as_deref_mut() {
+            profile.exit_node();
+        }
+
+        result
     }
 }
 
@@ -486,6 +550,10 @@ pub struct PartialGraphExecutionContext<'a> {
     #[debug("(...)")]
     pub regional_data: MaybeOwnedMut<'a, HashMap<Uuid, Box<dyn RegionExecutionData>>>,
     cache: MaybeOwnedMut<'a, GraphCache>,
+    #[debug("(...)")]
+    trace: Option<&'a mut ExecutionTrace>,
+    #[debug("(...)")]
+    profile: Option<&'a mut ExecutionProfile>,
 }
 
 impl<'a> PartialGraphExecutionContext<'a> {
@@ -516,6 +584,8 @@ impl<'a> PartialGraphExecutionContext<'a> {
                 regions: graph.regions(),
                 region_graph: graph.region_graph(),
                 regional_data: Default::default(),
+                trace: None,
+                profile: None,
             },
             &graph.snarl,
         )
@@ -545,6 +615,8 @@ impl<'a> PartialGraphExecutionContext<'a> {
                 regions: self.regions,
                 region_graph: self.region_graph,
                 regional_data: MaybeOwnedMut::Borrowed(&mut self.regional_data),
+                trace: self.trace.as_deref_mut(),
+                profile: self.profile.as_deref_mut(),
             },
         }
     }
@@ -0,0 +1,270 @@
+//! Per-node execution profiling
+//!
+//! Nodes are profiled as a call tree: a node evaluation includes the
+//! evaluation of its dependencies, all iterations of the regions it ends,
+//! and the whole node group graph for the group nodes.
+
+use egui_snarl::NodeId;
+use smallvec::SmallVec;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use utils::map::HashMap;
+
+/// Aggregated timings of a single node
+#[derive(Debug, Clone)]
+pub struct ProfileEntry {
+    pub node: NodeId,
+    /// Entry of the node group node that this node was evaluated in, if any
+    pub parent: Option<usize>,
+    pub title: String,
+    /// Amount of times the node was evaluated
+    pub calls: usize,
+    /// Total time spent in the node, including its dependencies
+    pub total: Duration,
+    /// Time spent in the node itself
+    pub self_time: Duration,
+}
+
+#[derive(Debug)]
+struct Frame {
+    entry: usize,
+    start: Instant,
+    children: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct ExecutionProfile {
+    entries: Vec<ProfileEntry>,
+    index: HashMap<(Option<usize>, NodeId), usize>,
+    /// Self time of every observed call stack of entries
+    stacks: HashMap<Vec<usize>, Duration>,
+    frames: Vec<Frame>,
+    /// Parent entries of the graphs that are currently evaluated
+    scopes: SmallVec<[Option<usize>; 2]>,
+}
+
+impl ExecutionProfile {
+    pub fn entries(&self) -> &[ProfileEntry] {
+        &self.entries
+    }
+
+    /// Full title of the entry, including the titles of the node group nodes
+    /// that it was evaluated in
+    pub fn entry_path(&self, entry: usize) -> String {
+        let mut path = self.entries[entry].title.clone();
+        let mut parent = self.entries[entry].parent;
+        while let Some(idx) = parent {
+            path = format!("{} / {}", self.entries[idx].title, path);
+            parent = self.entries[idx].parent;
+        }
+        path
+    }
+
+    /// Total time spent in the top-level nodes
+    pub fn total_time(&self) -> Duration {
+        self.stacks.values().sum()
+    }
+
+    /// Writes the profile in the folded stacks format, as accepted by
+    /// `inferno` and `flamegraph.pl`. Sample counts are in microseconds
+    pub fn write_folded(&self, out: &mut impl Write) -> std::fmt::Result {
+        let mut stacks = self.stacks.iter().collect::<Vec<_>>();
+        stacks.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (stack, time) in stacks {
+            let micros = time.as_micros();
+            if micros == 0 {
+                continue;
+            }
+            for (i, entry) in stack.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(';')?;
+                }
+                // Semicolons separate frames, so they can't be a part of the title
+                out.write_str(&self.entries[*entry].title.replace(';', ","))?;
+            }
+            writeln!(out, " {}", micros)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a human-readable report, sorted by the self time
+    pub fn write_report(&self, out: &mut impl Write) -> std::fmt::Result {
+        let mut entries = (0..self.entries.len()).collect::<Vec<_>>();
+        entries.sort_by_key(|e| std::cmp::Reverse(self.entries[*e].self_time));
+
+        writeln!(
+            out,
+            "{:>12} {:>12} {:>8}  node",
+            "self, ms", "total, ms", "calls"
+        )?;
+        for idx in entries {
+            let entry = &self.entries[idx];
+            writeln!(
+                out,
+                "{:>12.3} {:>12.3} {:>8}  {}",
+                entry.self_time.as_secs_f64() * 1000.0,
+                entry.total.as_secs_f64() * 1000.0,
+                entry.calls,
+                self.entry_path(idx)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Whenever the node has an entry in the current graph
+    pub(crate) fn has_entry(&self, node: NodeId) -> bool {
+        self.index.contains_key(&(self.scope(), node))
+    }
+
+    pub(crate) fn add_entry(&mut self, node: NodeId, title: String) {
+        let parent = self.scope();
+        self.index.insert((parent, node), self.entries.len());
+        self.entries.push(ProfileEntry {
+            node,
+            parent,
+            title,
+            calls: 0,
+            total: Duration::ZERO,
+            self_time: Duration::ZERO,
+        });
+    }
+
+    /// Starts the node evaluation, [ExecutionProfile::add_entry] must be
+    /// called for the node beforehand
+    pub(crate) fn enter_node(&mut self, node: NodeId) {
+        let entry = self.index[&(self.scope(), node)];
+        self.frames.push(Frame {
+            entry,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    pub(crate) fn exit_node(&mut self) {
+        let Some(frame) = self.frames.pop() else {
+            return;
+        };
+        let total = frame.start.elapsed();
+        let self_time = total.saturating_sub(frame.children);
+
+        let entry = &mut self.entries[frame.entry];
+        entry.calls += 1;
+        entry.total += total;
+        entry.self_time += self_time;
+
+        let stack = self
+            .frames
+            .iter()
+            .map(|f| f.entry)
+            .chain([frame.entry])
+            .collect::<Vec<_>>();
+        *self.stacks.entry(stack).or_default() += self_time;
+
+        if let Some(parent) = self.frames.last_mut() {
+            parent.children += total;
+        }
+    }
+
+    /// Starts the graph evaluation, nodes of the graph are attributed to the
+    /// node that is currently evaluated
+    pub(crate) fn enter_graph(&mut self) {
+        self.scopes.push(self.frames.last().map(|f| f.entry));
+    }
+
+    pub(crate) fn exit_graph(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn scope(&self) -> Option<usize> {
+        self.scopes.last().copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates `node` of the current graph, the evaluation of `inner` is
+    /// performed in the node group graph of the node
+    fn eval(profile: &mut ExecutionProfile, node: NodeId, inner: &[(NodeId, &str)]) {
+        profile.enter_node(node);
+        if !inner.is_empty() {
+            profile.enter_graph();
+            for (id, title) in inner {
+                if !profile.has_entry(*id) {
+                    profile.add_entry(*id, title.to_string());
+                }
+                profile.enter_node(*id);
+                profile.exit_node();
+            }
+            profile.exit_graph();
+        }
+        profile.exit_node();
+    }
+
+    fn find(profile: &ExecutionProfile, title: &str, parent: Option<usize>) -> usize {
+        profile
+            .entries()
+            .iter()
+            .position(|e| e.title == title && e.parent == parent)
+            .unwrap_or_else(|| panic!("entry `{}` is missing", title))
+    }
+
+    #[test]
+    fn should_count_calls_and_attribute_group_nodes() {
+        let mut profile = ExecutionProfile::default();
+        profile.enter_graph();
+        profile.add_entry(NodeId(0), "Print".to_string());
+        profile.add_entry(NodeId(1), "Sum".to_string());
+        profile.add_entry(NodeId(2), "Other Sum".to_string());
+
+        for _ in 0..3 {
+            profile.enter_node(NodeId(0));
+            // Inner node shares the id with the top-level node
+            eval(&mut profile, NodeId(1), &[(NodeId(0), "Add")]);
+            profile.exit_node();
+        }
+        eval(&mut profile, NodeId(2), &[(NodeId(0), "Add")]);
+        profile.exit_graph();
+
+        assert_eq!(profile.entries().len(), 5);
+
+        let print = find(&profile, "Print", None);
+        let sum = find(&profile, "Sum", None);
+        let other = find(&profile, "Other Sum", None);
+        let add = find(&profile, "Add", Some(sum));
+        let other_add = find(&profile, "Add", Some(other));
+
+        let calls = |idx: usize| profile.entries()[idx].calls;
+        assert_eq!(calls(print), 3);
+        assert_eq!(calls(sum), 3);
+        assert_eq!(calls(add), 3);
+        assert_eq!(calls(other), 1);
+        assert_eq!(calls(other_add), 1);
+
+        assert_eq!(profile.entry_path(print), "Print");
+        assert_eq!(profile.entry_path(add), "Sum / Add");
+        assert_eq!(profile.entry_path(other_add), "Other Sum / Add");
+
+        for entry in profile.entries() {
+            assert!(entry.self_time <= entry.total);
+        }
+        assert!(profile.entries()[print].total >= profile.entries()[sum].total);
+    }
+
+    #[test]
+    fn should_report_full_entry_paths() {
+        let mut profile = ExecutionProfile::default();
+        profile.enter_graph();
+        profile.add_entry(NodeId(0), "Sum".to_string());
+        eval(&mut profile, NodeId(0), &[(NodeId(0), "Add")]);
+        profile.exit_graph();
+
+        let mut report = String::new();
+        profile.write_report(&mut report).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().any(|l| l.ends_with("  Sum / Add")));
+        assert!(lines.iter().any(|l| l.ends_with("  Sum")));
+    }
+}
@@ -0,0 +1,106 @@
+//! Execution tracing
+//!
+//! When a trace is attached to the [GraphExecutionContext], every node
+//! evaluation is recorded together with its inputs, outputs, and the region
+//! iterations it ran in. The recorded trace can then be stepped through
+//! after the evaluation is done.
+//!
+//! [GraphExecutionContext]: crate::graph::execution::GraphExecutionContext
+
+use crate::value::EValue;
+use egui_snarl::NodeId;
+use smallvec::SmallVec;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Maximum amount of events recorded in a single trace, events past this
+/// limit are dropped
+pub const MAX_TRACE_EVENTS: usize = 50_000;
+
+/// Iteration of the region that was running during the node evaluation
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RegionIteration {
+    pub region: Uuid,
+    /// Zero-based iteration index
+    pub iteration: usize,
+}
+
+/// Single node evaluation
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    pub node: NodeId,
+    /// Iterations of the regions that were running when the node was
+    /// evaluated, outermost first
+    pub iterations: SmallVec<[RegionIteration; 1]>,
+    pub inputs: Vec<EValue>,
+    /// Output values of the node, or the error message if the node failed
+    pub outputs: Result<Vec<EValue>, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ExecutionTrace {
+    events: Vec<TraceEvent>,
+    iterations: SmallVec<[RegionIteration; 1]>,
+    truncated: bool,
+}
+
+impl ExecutionTrace {
+    /// Recorded events, in the order of the node evaluation completion
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Whenever some events were dropped due to the [MAX_TRACE_EVENTS] limit
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Finds the first event after `from` that was produced by one of the
+    /// given nodes
+    pub fn next_hit(&self, from: Option<usize>, nodes: &BTreeSet<NodeId>) -> Option<usize> {
+        let start = from.map_or(0, |i| i + 1);
+        self.events
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, e)| nodes.contains(&e.node))
+            .map(|(i, _)| i)
+    }
+
+    /// Finds the last event before `from` that was produced by one of the
+    /// given nodes
+    pub fn prev_hit(&self, from: usize, nodes: &BTreeSet<NodeId>) -> Option<usize> {
+        self.events[..from.min(self.events.len())]
+            .iter()
+            .rposition(|e| nodes.contains(&e.node))
+    }
+
+    pub(crate) fn enter_iteration(&mut self, region: Uuid, iteration: usize) {
+        self.iterations.push(RegionIteration { region, iteration });
+    }
+
+    pub(crate) fn exit_iteration(&mut self) {
+        self.iterations.pop();
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        node: NodeId,
+        inputs: &[EValue],
+        outputs: Result<&[EValue], &miette::Report>,
+    ) {
+        if self.events.len() >= MAX_TRACE_EVENTS {
+            self.truncated = true;
+            return;
+        }
+
+        self.events.push(TraceEvent {
+            node,
+            iterations: self.iterations.clone(),
+            inputs: inputs.to_vec(),
+            outputs: outputs
+                .map(<[EValue]>::to_vec)
+                .map_err(|err| format!("{:?}", err)),
+        });
+    }
+}
@@ -0,0 +1,1548 @@
+//! Recording of graph edits for fine-grained undo and redo
+//!
+//! Edits made through [GraphEditingContext](crate::graph::editing::GraphEditingContext)
+//! are recorded as [GraphAction]s, which hold both the previous and the new
+//! state of everything the edit changed, so they can be applied in both
+//! directions without restoring the whole graph. Only hashes of the graph
+//! parts are kept when an action starts, and the parts are captured once the
+//! action reports them as touched, see [Touch]. Node moves are done by the
+//! graph view directly, so they are detected between the recorded edits.
+//! Any other change made outside the context breaks the record, and the
+//! project history falls back to snapshots of the whole graph
+
+use crate::graph::inputs::{GraphInput, GraphOutput};
+use crate::graph::node::SnarlNode;
+use crate::graph::region::RegionInfo;
+use crate::graph::Graph;
+use crate::value::EValue;
+use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
+use emath::Pos2;
+use itertools::Itertools;
+use miette::{bail, miette, Context};
+use ordered_float::OrderedFloat;
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+use tracing::error;
+use utils::map::{hash_of, HashMap, HashSet, OrderMap};
+use uuid::Uuid;
+
+/// Maximum number of actions kept by [GraphHistory] between
+/// [Graph::take_edits] calls
+const MAX_ACTIONS: usize = 256;
+
+/// Maximum number of temporary nodes inserted to restore a node ID, see
+/// [insert_node_at]
+const MAX_PLACEHOLDERS: usize = 1 << 16;
+
+/// Parts of the graph that are tracked by the history
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GraphState<'a> {
+    pub snarl: &'a Snarl<SnarlNode>,
+    pub inline_values: &'a OrderMap<InPinId, EValue>,
+    pub inputs: &'a SmallVec<[GraphInput; 1]>,
+    pub outputs: &'a SmallVec<[GraphOutput; 1]>,
+    pub regions: &'a OrderMap<Uuid, RegionInfo>,
+}
+
+impl Hash for GraphState<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash_with_placements(state, None);
+    }
+}
+
+impl GraphState<'_> {
+    /// Hashes the state, optionally replacing the placements of the nodes
+    fn hash_with_placements<H: Hasher>(
+        &self,
+        state: &mut H,
+        placements: Option<&HashMap<NodeId, NodePlacement>>,
+    ) {
+        self.inline_values.hash(state);
+        self.inputs.hash(state);
+        self.outputs.hash(state);
+        self.regions.hash(state);
+
+        for (id, node) in self
+            .snarl
+            .nodes_ids_data()
+            .sorted_unstable_by_key(|(id, _)| *id)
+        {
+            let placement = placements
+                .and_then(|placements| placements.get(&id).copied())
+                .unwrap_or_else(|| NodePlacement::of(node));
+            id.hash(state);
+            node.value.hash(state);
+            OrderedFloat(placement.pos.x).hash(state);
+            OrderedFloat(placement.pos.y).hash(state);
+            placement.open.hash(state);
+        }
+
+        self.snarl
+            .wires()
+            .sorted_unstable()
+            .for_each(|(out_pin, in_pin)| {
+                in_pin.hash(state);
+                out_pin.hash(state);
+            });
+    }
+
+    fn placements(&self) -> HashMap<NodeId, NodePlacement> {
+        self.snarl
+            .nodes_ids_data()
+            .map(|(id, node)| (id, NodePlacement::of(node)))
+            .collect()
+    }
+}
+
+/// Part of the graph that is about to be changed by the current action
+///
+/// Actions must report the parts before changing them, so their previous
+/// state can be captured. Changes of the parts that were not reported make
+/// the action unexplained, and the project history falls back to a snapshot
+/// of the whole graph
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Touch<'a> {
+    /// Nodes along with their wires and inline values
+    Nodes(&'a [NodeId]),
+    Inputs,
+    Outputs,
+    Regions,
+    /// Any part of the graph
+    All,
+}
+
+/// Hash of the node value and placement
+fn node_hash(node: &egui_snarl::Node<SnarlNode>) -> u64 {
+    hash_of(&(
+        &node.value,
+        OrderedFloat(node.pos.x),
+        OrderedFloat(node.pos.y),
+        node.open,
+    ))
+}
+
+/// Hash of the wires, independent of their order
+fn wires_hash(wires: impl Iterator<Item = (OutPinId, InPinId)>) -> u64 {
+    hash_of(&wires.sorted_unstable().collect_vec())
+}
+
+/// Position and collapsed state of a node
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NodePlacement {
+    pub pos: Pos2,
+    pub open: bool,
+}
+
+impl NodePlacement {
+    fn of(node: &egui_snarl::Node<SnarlNode>) -> Self {
+        Self {
+            pos: node.pos,
+            open: node.open,
+        }
+    }
+}
+
+/// Node along with its placement, used to restore removed nodes
+#[derive(Debug, Clone)]
+pub struct NodeSnapshot {
+    pub node: SnarlNode,
+    pub placement: NodePlacement,
+}
+
+impl NodeSnapshot {
+    fn of(node: &egui_snarl::Node<SnarlNode>) -> Self {
+        Self {
+            node: node.value.clone(),
+            placement: NodePlacement::of(node),
+        }
+    }
+}
+
+/// A single change of the graph, holding both the previous and the new state
+#[derive(Debug, Clone)]
+pub enum GraphEdit {
+    Disconnect {
+        from: OutPinId,
+        to: InPinId,
+    },
+    RemoveNode {
+        id: NodeId,
+        node: Box<NodeSnapshot>,
+    },
+    /// Inserts a node with the ID it had when the edit was recorded
+    InsertNode {
+        id: NodeId,
+        node: Box<NodeSnapshot>,
+    },
+    ChangeNode {
+        id: NodeId,
+        before: Box<SnarlNode>,
+        after: Box<SnarlNode>,
+    },
+    MoveNode {
+        id: NodeId,
+        before: NodePlacement,
+        after: NodePlacement,
+    },
+    Connect {
+        from: OutPinId,
+        to: InPinId,
+    },
+    /// Changes the inline value of the pin. Values are stored together with
+    /// their index in the inline values map, `None` means no value
+    InlineValue {
+        pin: InPinId,
+        before: Option<(usize, EValue)>,
+        after: Option<(usize, EValue)>,
+    },
+    Inputs {
+        before: Box<SmallVec<[GraphInput; 1]>>,
+        after: Box<SmallVec<[GraphInput; 1]>>,
+    },
+    Outputs {
+        before: Box<SmallVec<[GraphOutput; 1]>>,
+        after: Box<SmallVec<[GraphOutput; 1]>>,
+    },
+    Regions {
+        before: Box<OrderMap<Uuid, RegionInfo>>,
+        after: Box<OrderMap<Uuid, RegionInfo>>,
+    },
+}
+
+impl GraphEdit {
+    /// Order in which the edits of a single action are applied. Wires must
+    /// be removed before their nodes and added after them
+    fn priority(&self) -> u8 {
+        match self {
+            GraphEdit::Disconnect { .. } => 0,
+            GraphEdit::RemoveNode { .. } => 1,
+            GraphEdit::InsertNode { .. } => 2,
+            GraphEdit::ChangeNode { .. } | GraphEdit::MoveNode { .. } => 3,
+            GraphEdit::Connect { .. } => 4,
+            GraphEdit::InlineValue { .. }
+            | GraphEdit::Inputs { .. }
+            | GraphEdit::Outputs { .. }
+            | GraphEdit::Regions { .. } => 5,
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        match self.clone() {
+            GraphEdit::Disconnect { from, to } => GraphEdit::Connect { from, to },
+            GraphEdit::Connect { from, to } => GraphEdit::Disconnect { from, to },
+            GraphEdit::RemoveNode { id, node } => GraphEdit::InsertNode { id, node },
+            GraphEdit::InsertNode { id, node } => GraphEdit::RemoveNode { id, node },
+            GraphEdit::ChangeNode { id, before, after } => GraphEdit::ChangeNode {
+                id,
+                before: after,
+                after: before,
+            },
+            GraphEdit::MoveNode { id, before, after } => GraphEdit::MoveNode {
+                id,
+                before: after,
+                after: before,
+            },
+            GraphEdit::InlineValue { pin, before, after } => GraphEdit::InlineValue {
+                pin,
+                before: after,
+                after: before,
+            },
+            GraphEdit::Inputs { before, after } => GraphEdit::Inputs {
+                before: after,
+                after: before,
+            },
+            GraphEdit::Outputs { before, after } => GraphEdit::Outputs {
+                before: after,
+                after: before,
+            },
+            GraphEdit::Regions { before, after } => GraphEdit::Regions {
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    /// Applies the edit. Inline values are applied by the whole action at
+    /// once, see [apply_inline_values]
+    fn apply(&self, graph: &mut Graph) -> miette::Result<()> {
+        let snarl = &mut graph.snarl;
+        let ensure_node = |snarl: &Snarl<SnarlNode>, id: NodeId| {
+            if snarl.get_node(id).is_none() {
+                bail!("node {:?} not found", id);
+            }
+            Ok(())
+        };
+
+        match self {
+            GraphEdit::Disconnect { from, to } => {
+                ensure_node(snarl, from.node)?;
+                ensure_node(snarl, to.node)?;
+                snarl.disconnect(*from, *to);
+            }
+            GraphEdit::Connect { from, to } => {
+                ensure_node(snarl, from.node)?;
+                ensure_node(snarl, to.node)?;
+                snarl.connect(*from, *to);
+            }
+            GraphEdit::RemoveNode { id, .. } => {
+                ensure_node(snarl, *id)?;
+                snarl.remove_node(*id);
+            }
+            GraphEdit::InsertNode { id, node } => insert_node_at(snarl, *id, node)?,
+            GraphEdit::ChangeNode { id, after, .. } => {
+                let Some(node) = snarl.get_node_info_mut(*id) else {
+                    bail!("node {:?} not found", id);
+                };
+                node.value = (**after).clone();
+            }
+            GraphEdit::MoveNode { id, after, .. } => {
+                let Some(node) = snarl.get_node_info_mut(*id) else {
+                    bail!("node {:?} not found", id);
+                };
+                node.pos = after.pos;
+                node.open = after.open;
+            }
+            GraphEdit::InlineValue { .. } => {}
+            GraphEdit::Inputs { after, .. } => graph.inputs = (**after).clone(),
+            GraphEdit::Outputs { after, .. } => graph.outputs = (**after).clone(),
+            GraphEdit::Regions { after, .. } => graph.regions = (**after).clone(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Key of the actions that are merged together in the project history when
+/// done in a quick succession, like dragging a node or a value slider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeKey {
+    Move(Vec<NodeId>),
+    InlineValue(InPinId),
+}
+
+/// A group of edits done by a single user action
+#[derive(Debug, Clone)]
+pub struct GraphAction {
+    edits: Vec<GraphEdit>,
+    /// Hash of the graph before the action
+    before: u64,
+    /// Hash of the graph after the action
+    after: u64,
+}
+
+impl GraphAction {
+    fn inverse(&self) -> Self {
+        let mut edits = self
+            .edits
+            .iter()
+            .rev()
+            .map(GraphEdit::inverse)
+            .collect_vec();
+        edits.sort_by_key(GraphEdit::priority);
+        Self {
+            edits,
+            before: self.after,
+            after: self.before,
+        }
+    }
+
+    /// Applies the action, leaving the graph untouched on failure
+    fn apply(&self, graph: &mut Graph) -> miette::Result<()> {
+        if hash_of(&*graph) != self.before {
+            bail!("graph history is out of sync with the graph");
+        }
+
+        for (applied, edit) in self.edits.iter().enumerate() {
+            if let Err(err) = edit.apply(graph) {
+                revert_edits(graph, &self.edits[..applied]);
+                return Err(err);
+            }
+        }
+        apply_inline_values(graph, &self.edits, false);
+
+        if hash_of(&*graph) != self.after {
+            apply_inline_values(graph, &self.edits, true);
+            revert_edits(graph, &self.edits);
+            bail!("graph history produced an unexpected graph state");
+        }
+
+        Ok(())
+    }
+
+    fn merge_key(&self) -> Option<MergeKey> {
+        match self.edits.as_slice() {
+            [GraphEdit::InlineValue { pin, .. }] => Some(MergeKey::InlineValue(*pin)),
+            edits => {
+                let mut ids = Vec::with_capacity(edits.len());
+                for edit in edits {
+                    let GraphEdit::MoveNode { id, .. } = edit else {
+                        return None;
+                    };
+                    ids.push(*id);
+                }
+                ids.sort_unstable();
+                (!ids.is_empty()).then_some(MergeKey::Move(ids))
+            }
+        }
+    }
+
+    /// Merges the following action into this one, if both are merged by the
+    /// same key
+    fn fuse(&mut self, next: &GraphAction) -> bool {
+        if self.after != next.before
+            || self.merge_key().is_none()
+            || self.merge_key() != next.merge_key()
+        {
+            return false;
+        }
+
+        for edit in &mut self.edits {
+            match edit {
+                GraphEdit::MoveNode { id, after, .. } => {
+                    for next in &next.edits {
+                        if let GraphEdit::MoveNode {
+                            id: next_id,
+                            after: next_after,
+                            ..
+                        } = next
+                        {
+                            if next_id == id {
+                                *after = *next_after;
+                            }
+                        }
+                    }
+                }
+                GraphEdit::InlineValue { after, .. } => {
+                    if let Some(GraphEdit::InlineValue {
+                        after: next_after, ..
+                    }) = next.edits.first()
+                    {
+                        after.clone_from(next_after);
+                    }
+                }
+                _ => unreachable!("only moves and inline values are merged"),
+            }
+        }
+        self.after = next.after;
+
+        true
+    }
+}
+
+/// Inserts the node with the given ID
+///
+/// Snarl doesn't allow choosing node IDs and hands out the vacant ones,
+/// so the vacant IDs that come before the requested one are filled with
+/// temporary nodes, which are removed afterwards
+fn insert_node_at(
+    snarl: &mut Snarl<SnarlNode>,
+    id: NodeId,
+    node: &NodeSnapshot,
+) -> miette::Result<()> {
+    if snarl.get_node(id).is_some() {
+        bail!("node {:?} already exists", id);
+    }
+
+    let mut placeholders = vec![];
+    let result = loop {
+        let inserted = if node.placement.open {
+            snarl.insert_node(node.placement.pos, node.node.clone())
+        } else {
+            snarl.insert_node_collapsed(node.placement.pos, node.node.clone())
+        };
+
+        if inserted == id {
+            break Ok(());
+        }
+
+        placeholders.push(inserted);
+        if placeholders.len() >= MAX_PLACEHOLDERS {
+            break Err(miette!("node {:?} can't be restored with its ID", id));
+        }
+    };
+
+    // Removed in the reverse order, so the vacant IDs are handed out in the
+    // same order as before
+    for placeholder in placeholders.into_iter().rev() {
+        snarl.remove_node(placeholder);
+    }
+
+    result
+}
+
+/// Reverts the edits that were applied, in the reverse order
+fn revert_edits(graph: &mut Graph, edits: &[GraphEdit]) {
+    for edit in edits.iter().rev() {
+        if let Err(err) = edit.inverse().apply(graph) {
+            error!("Failed to revert a partially applied graph edit: {:?}", err);
+        }
+    }
+}
+
+/// Applies all inline value changes of the action, restoring the exact order
+/// of the inline values
+///
+/// All changed values are removed first, and then inserted at their indices
+/// in the ascending order, so every value lands at its recorded position
+fn apply_inline_values(graph: &mut Graph, edits: &[GraphEdit], revert: bool) {
+    let changes = edits
+        .iter()
+        .filter_map(|edit| match edit {
+            GraphEdit::InlineValue { pin, before, after } => {
+                Some((pin, if revert { before } else { after }))
+            }
+            _ => None,
+        })
+        .collect_vec();
+
+    for (pin, _) in &changes {
+        graph.inline_values.remove(*pin);
+    }
+
+    for (pin, (index, value)) in changes
+        .into_iter()
+        .filter_map(|(pin, value)| Some((pin, value.as_ref()?)))
+        .sorted_by_key(|(_, (index, _))| *index)
+    {
+        let index = (*index).min(graph.inline_values.len());
+        graph.inline_values.shift_insert(index, *pin, value.clone());
+    }
+}
+
+fn inline_value_of(values: &OrderMap<InPinId, EValue>, pin: InPinId) -> Option<(usize, EValue)> {
+    values
+        .get_full(&pin)
+        .map(|(index, _, value)| (index, value.clone()))
+}
+
+/// Sets the inline value of the pin, placing it at the provided index
+pub(crate) fn set_inline_value(
+    values: &mut OrderMap<InPinId, EValue>,
+    pin: InPinId,
+    value: Option<(usize, EValue)>,
+) {
+    values.remove(&pin);
+    if let Some((index, value)) = value {
+        let index = index.min(values.len());
+        values.shift_insert(index, pin, value);
+    }
+}
+
+/// A sequence of actions that can be applied to the graph
+#[derive(Debug, Clone, Default)]
+pub struct GraphEdits {
+    actions: Vec<GraphAction>,
+}
+
+impl Hash for GraphEdits {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for action in &self.actions {
+            action.before.hash(state);
+            action.after.hash(state);
+        }
+    }
+}
+
+impl GraphEdits {
+    /// Edits that revert the provided actions
+    pub fn undo_of(actions: &[GraphAction]) -> Self {
+        Self {
+            actions: actions.iter().rev().map(GraphAction::inverse).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Applies the edits to the graph, returning the edits that revert them
+    ///
+    /// The graph is left untouched on failure
+    pub fn apply(&self, graph: &mut Graph) -> miette::Result<Self> {
+        for (applied, action) in self.actions.iter().enumerate() {
+            if let Err(err) = action.apply(graph) {
+                for action in self.actions[..applied].iter().rev() {
+                    if let Err(err) = action.inverse().apply(graph) {
+                        error!(
+                            "Failed to revert a partially applied graph action: {:?}",
+                            err
+                        );
+                    }
+                }
+                return Err(err).context("failed to apply graph edits");
+            }
+        }
+
+        graph.region_graph.mark_dirty();
+        graph.history.reset();
+
+        Ok(Self::undo_of(&self.actions))
+    }
+
+    /// Appends the other edits to these, merging actions where possible
+    pub fn then(mut self, other: Self) -> Self {
+        for action in other.actions {
+            match self.actions.last_mut() {
+                Some(last) if last.fuse(&action) => {}
+                _ => self.actions.push(action),
+            }
+        }
+        self
+    }
+
+    /// Key by which these edits can be merged with others
+    pub fn merge_key(&self) -> Option<MergeKey> {
+        merge_key_of(&self.actions)
+    }
+}
+
+/// Common merge key of all the actions
+fn merge_key_of(actions: &[GraphAction]) -> Option<MergeKey> {
+    let (first, rest) = actions.split_first()?;
+    let key = first.merge_key()?;
+    rest.iter()
+        .all(|action| action.merge_key().as_ref() == Some(&key))
+        .then_some(key)
+}
+
+/// Actions recorded by the graph since the last [Graph::take_edits] call
+#[derive(Debug, Default)]
+pub struct GraphEditLog {
+    /// Hash of the graph before the first action
+    pub base: u64,
+    pub actions: Vec<GraphAction>,
+}
+
+impl GraphEditLog {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Hash of the graph after the last action
+    pub fn head(&self) -> u64 {
+        self.actions.last().map_or(self.base, |action| action.after)
+    }
+
+    /// Key by which the recorded actions can be merged with others
+    pub fn merge_key(&self) -> Option<MergeKey> {
+        merge_key_of(&self.actions)
+    }
+}
+
+/// Recorder of the graph edits
+#[derive(Debug, Default)]
+pub struct GraphHistory {
+    /// Whether the edits are recorded. Enabled by the first
+    /// [Graph::take_edits] call, so graphs that are never edited don't pay
+    /// for the recording
+    tracking: bool,
+    actions: Vec<GraphAction>,
+    /// Hash of the graph before the first recorded action
+    base: u64,
+    /// Hash of the graph after the last recorded action, `None` if unknown
+    head: Option<u64>,
+    /// Node placements after the last recorded action
+    placements: HashMap<NodeId, NodePlacement>,
+    pending: Option<PendingAction>,
+}
+
+#[derive(Debug)]
+struct PendingAction {
+    /// Number of the nested actions, only the outermost one is recorded
+    depth: usize,
+    before: u64,
+    state: PendingState,
+}
+
+#[derive(Debug)]
+enum PendingState {
+    Graph(Box<ActionCapture>),
+    InlineValue {
+        pin: InPinId,
+        value: Option<(usize, EValue)>,
+    },
+}
+
+/// State of the graph before the action
+///
+/// Hashes of the whole graph are recorded when the action starts, and the
+/// parts touched by the action are captured before being changed. Untouched
+/// parts are taken from the graph once the action ends, after checking that
+/// their hashes didn't change
+#[derive(Debug)]
+struct ActionCapture {
+    /// Hashes of the nodes that existed before the action
+    node_hashes: HashMap<NodeId, u64>,
+    wires_hash: u64,
+    /// Pins of the inline values in their order, along with the hashes of
+    /// the values
+    inline_values: Vec<(InPinId, u64)>,
+    inputs_hash: u64,
+    outputs_hash: u64,
+    regions_hash: u64,
+    /// Nodes touched by the action, including the inserted ones
+    touched: HashSet<NodeId>,
+    /// Touched nodes that existed before the action
+    nodes: HashMap<NodeId, NodeSnapshot>,
+    /// Wires of the touched nodes that existed before the action
+    wires: BTreeSet<(OutPinId, InPinId)>,
+    /// Inline values of the touched nodes
+    values: HashMap<InPinId, EValue>,
+    inputs: Option<Box<SmallVec<[GraphInput; 1]>>>,
+    outputs: Option<Box<SmallVec<[GraphOutput; 1]>>>,
+    regions: Option<Box<OrderMap<Uuid, RegionInfo>>>,
+    /// Nodes removed by the action, in the removal order
+    removed: Vec<NodeId>,
+    /// Nodes inserted by the action, in the insertion order
+    inserted: Vec<NodeId>,
+    /// Whether a touched part was changed before being captured
+    broken: bool,
+}
+
+impl GraphHistory {
+    /// Starts an action that can change any part of the graph
+    ///
+    /// The action must report the parts it changes with
+    /// [GraphHistory::touch]
+    pub(crate) fn begin_action(&mut self, state: GraphState) {
+        self.begin(state, |state| {
+            PendingState::Graph(Box::new(ActionCapture::new(state)))
+        });
+    }
+
+    /// Starts an action that only changes the inline value of the pin
+    pub(crate) fn begin_inline_value(&mut self, state: GraphState, pin: InPinId) {
+        self.begin(state, |state| PendingState::InlineValue {
+            pin,
+            value: inline_value_of(state.inline_values, pin),
+        });
+    }
+
+    fn begin(&mut self, state: GraphState, capture: impl FnOnce(GraphState) -> PendingState) {
+        if !self.tracking {
+            return;
+        }
+
+        if let Some(pending) = &mut self.pending {
+            pending.depth += 1;
+            return;
+        }
+
+        let hash = hash_of(&state);
+        self.sync(state, hash);
+        self.pending = Some(PendingAction {
+            depth: 1,
+            before: hash,
+            state: capture(state),
+        });
+    }
+
+    /// Finishes the action started by [GraphHistory::begin_action] or
+    /// [GraphHistory::begin_inline_value]
+    pub(crate) fn end(&mut self, state: GraphState) {
+        let Some(pending) = &mut self.pending else {
+            return;
+        };
+
+        pending.depth -= 1;
+        if pending.depth > 0 {
+            return;
+        }
+
+        let pending = self.pending.take().expect("pending action was checked");
+        let after = hash_of(&state);
+        if after == pending.before {
+            return;
+        }
+
+        match pending.state.diff(state) {
+            Some(edits) => self.push(GraphAction {
+                edits,
+                before: pending.before,
+                after,
+            }),
+            // The action changed parts of the graph it didn't report, so it
+            // can't be recorded
+            None => self.restart(after),
+        }
+        self.head = Some(after);
+        self.placements = state.placements();
+    }
+
+    /// Notifies the history that the part of the graph is about to be
+    /// changed by the current action
+    pub(crate) fn touch(&mut self, state: GraphState, touch: Touch) {
+        if let Some(capture) = self.capture() {
+            capture.touch(state, touch);
+        }
+    }
+
+    /// Notifies the history that the node is about to be removed. The node
+    /// must be touched beforehand
+    pub(crate) fn node_removed(&mut self, id: NodeId) {
+        if let Some(capture) = self.capture() {
+            capture.removed.push(id);
+        }
+    }
+
+    /// Notifies the history that the nodes were inserted
+    pub(crate) fn nodes_inserted(&mut self, state: GraphState, ids: &[NodeId]) {
+        if let Some(capture) = self.capture() {
+            capture.touch(state, Touch::Nodes(ids));
+            capture.inserted.extend_from_slice(ids);
+        }
+    }
+
+    fn capture(&mut self) -> Option<&mut ActionCapture> {
+        match &mut self.pending {
+            Some(PendingAction {
+                state: PendingState::Graph(capture),
+                ..
+            }) => Some(capture),
+            _ => None,
+        }
+    }
+
+    /// Takes the recorded actions, see [Graph::take_edits]
+    pub(crate) fn take(&mut self, state: GraphState) -> GraphEditLog {
+        debug_assert!(
+            self.pending.is_none(),
+            "edits should not be taken in the middle of an action"
+        );
+        self.tracking = true;
+
+        let hash = hash_of(&state);
+        self.sync(state, hash);
+
+        let log = GraphEditLog {
+            base: self.base,
+            actions: std::mem::take(&mut self.actions),
+        };
+        self.base = hash;
+        log
+    }
+
+    /// Forgets the recorded actions. Should be called after the graph was
+    /// changed without recording
+    pub(crate) fn reset(&mut self) {
+        self.actions.clear();
+        self.head = None;
+        self.placements.clear();
+    }
+
+    /// Brings the record up to date with the graph, detecting node moves
+    fn sync(&mut self, state: GraphState, hash: u64) {
+        if self.head == Some(hash) {
+            return;
+        }
+
+        let placements = state.placements();
+        let moves = self
+            .head
+            .and_then(|head| self.moves_since(state, &placements, head));
+
+        match moves {
+            Some(action) => self.push(action),
+            None => self.restart(hash),
+        }
+
+        self.head = Some(hash);
+        self.placements = placements;
+    }
+
+    /// Explains the difference between the last recorded state and the
+    /// current one as node moves
+    fn moves_since(
+        &self,
+        state: GraphState,
+        placements: &HashMap<NodeId, NodePlacement>,
+        head: u64,
+    ) -> Option<GraphAction> {
+        if placements.len() != self.placements.len() {
+            return None;
+        }
+
+        let mut edits = vec![];
+        for (id, after) in placements.iter().sorted_unstable_by_key(|(id, _)| **id) {
+            let before = self.placements.get(id)?;
+            if before != after {
+                edits.push(GraphEdit::MoveNode {
+                    id: *id,
+                    before: *before,
+                    after: *after,
+                });
+            }
+        }
+
+        if edits.is_empty() {
+            return None;
+        }
+
+        let mut hasher = utils::map::Hasher::default();
+        state.hash_with_placements(&mut hasher, Some(&self.placements));
+        if hasher.finish() != head {
+            return None;
+        }
+
+        Some(GraphAction {
+            edits,
+            before: head,
+            after: hash_of(&state),
+        })
+    }
+
+    fn push(&mut self, action: GraphAction) {
+        if self.actions.len() >= MAX_ACTIONS {
+            self.restart(action.before);
+        }
+        self.actions.push(action);
+    }
+
+    /// Drops the recorded actions, starting a new record from the state
+    fn restart(&mut self, hash: u64) {
+        self.actions.clear();
+        self.base = hash;
+    }
+}
+
+impl PendingState {
+    /// Edits done by the action, or `None` if the action changed parts of
+    /// the graph that were not captured
+    fn diff(self, state: GraphState) -> Option<Vec<GraphEdit>> {
+        match self {
+            PendingState::Graph(capture) => capture.diff(state),
+            PendingState::InlineValue { pin, value } => Some(vec![GraphEdit::InlineValue {
+                pin,
+                before: value,
+                after: inline_value_of(state.inline_values, pin),
+            }]),
+        }
+    }
+}
+
+impl ActionCapture {
+    fn new(state: GraphState) -> Self {
+        Self {
+            node_hashes: state
+                .snarl
+                .nodes_ids_data()
+                .map(|(id, node)| (id, node_hash(node)))
+                .collect(),
+            wires_hash: wires_hash(state.snarl.wires()),
+            inline_values: state
+                .inline_values
+                .iter()
+                .map(|(pin, value)| (*pin, hash_of(value)))
+                .collect(),
+            inputs_hash: hash_of(state.inputs),
+            outputs_hash: hash_of(state.outputs),
+            regions_hash: hash_of(state.regions),
+            touched: Default::default(),
+            nodes: Default::default(),
+            wires: Default::default(),
+            values: Default::default(),
+            inputs: None,
+            outputs: None,
+            regions: None,
+            removed: vec![],
+            inserted: vec![],
+            broken: false,
+        }
+    }
+
+    fn touch(&mut self, state: GraphState, touch: Touch) {
+        match touch {
+            Touch::Nodes(ids) => {
+                for id in ids {
+                    self.touch_node(state, *id);
+                }
+            }
+            Touch::Inputs => {
+                if self.inputs.is_none() {
+                    self.broken |= hash_of(state.inputs) != self.inputs_hash;
+                    self.inputs = Some(Box::new(state.inputs.clone()));
+                }
+            }
+            Touch::Outputs => {
+                if self.outputs.is_none() {
+                    self.broken |= hash_of(state.outputs) != self.outputs_hash;
+                    self.outputs = Some(Box::new(state.outputs.clone()));
+                }
+            }
+            Touch::Regions => {
+                if self.regions.is_none() {
+                    self.broken |= hash_of(state.regions) != self.regions_hash;
+                    self.regions = Some(Box::new(state.regions.clone()));
+                }
+            }
+            Touch::All => {
+                let ids = state
+                    .snarl
+                    .nodes_ids_data()
+                    .map(|(id, _)| id)
+                    .chain(state.inline_values.keys().map(|pin| pin.node))
+                    .unique()
+                    .collect_vec();
+                self.touch(state, Touch::Nodes(&ids));
+                self.touch(state, Touch::Inputs);
+                self.touch(state, Touch::Outputs);
+                self.touch(state, Touch::Regions);
+            }
+        }
+    }
+
+    fn touch_node(&mut self, state: GraphState, id: NodeId) {
+        if !self.touched.insert(id) {
+            return;
+        }
+
+        for (pin, value) in state.inline_values {
+            if pin.node == id {
+                self.values.insert(*pin, value.clone());
+            }
+        }
+
+        // Nodes inserted by the action have no previous state
+        let Some(hash) = self.node_hashes.get(&id) else {
+            return;
+        };
+        match state.snarl.get_node_info(id) {
+            Some(node) if node_hash(node) == *hash => {
+                self.nodes.insert(id, NodeSnapshot::of(node));
+            }
+            _ => self.broken = true,
+        }
+
+        // Wires to the nodes that were touched earlier are captured already
+        for (from, to) in state.snarl.wires() {
+            let other = match (from.node == id, to.node == id) {
+                (true, _) => to.node,
+                (false, true) => from.node,
+                (false, false) => continue,
+            };
+            if other == id || !self.is_touched(other) {
+                self.wires.insert((from, to));
+            }
+        }
+    }
+
+    /// Whether the previous state of the node is captured, nodes that didn't
+    /// exist before the action have none
+    fn is_touched(&self, id: NodeId) -> bool {
+        self.touched.contains(&id) || !self.node_hashes.contains_key(&id)
+    }
+
+    fn diff(&self, state: GraphState) -> Option<Vec<GraphEdit>> {
+        if self.broken {
+            return None;
+        }
+
+        let mut edits = vec![];
+
+        let nodes_after = state.snarl.nodes_ids_data().collect::<BTreeMap<_, _>>();
+
+        // Nodes that were not touched must stay the same
+        for (id, hash) in &self.node_hashes {
+            if !self.touched.contains(id)
+                && nodes_after.get(id).map(|node| node_hash(node)) != Some(*hash)
+            {
+                return None;
+            }
+        }
+
+        let wires_after = state.snarl.wires().collect::<BTreeSet<_>>();
+        let is_touched = |(from, to): &(OutPinId, InPinId)| {
+            self.is_touched(from.node) || self.is_touched(to.node)
+        };
+        let wires_before = self
+            .wires
+            .iter()
+            .copied()
+            .chain(wires_after.iter().copied().filter(|wire| !is_touched(wire)))
+            .collect::<BTreeSet<_>>();
+        if wires_hash(wires_before.iter().copied()) != self.wires_hash {
+            return None;
+        }
+
+        for (from, to) in wires_before.difference(&wires_after) {
+            edits.push(GraphEdit::Disconnect {
+                from: *from,
+                to: *to,
+            });
+        }
+
+        // Node IDs are restored as they were, but the nodes are removed and
+        // inserted in the same order as the action did, to keep the vacant
+        // IDs in the same order too
+        let removed = ordered_ids(&self.removed, self.node_hashes.keys().sorted(), |id| {
+            !nodes_after.contains_key(id)
+        });
+        for id in removed {
+            edits.push(GraphEdit::RemoveNode {
+                id,
+                node: Box::new(self.nodes.get(&id)?.clone()),
+            });
+        }
+
+        let inserted = ordered_ids(&self.inserted, nodes_after.keys(), |id| {
+            !self.node_hashes.contains_key(id)
+        });
+        for id in inserted {
+            edits.push(GraphEdit::InsertNode {
+                id,
+                node: Box::new(NodeSnapshot::of(nodes_after[&id])),
+            });
+        }
+
+        for (id, after) in &nodes_after {
+            if !self.node_hashes.contains_key(id) || !self.touched.contains(id) {
+                continue;
+            }
+            let before = self.nodes.get(id)?;
+
+            if hash_of(&before.node) != hash_of(&after.value) {
+                edits.push(GraphEdit::ChangeNode {
+                    id: *id,
+                    before: Box::new(before.node.clone()),
+                    after: Box::new(after.value.clone()),
+                });
+            }
+
+            let after = NodePlacement::of(after);
+            if before.placement != after {
+                edits.push(GraphEdit::MoveNode {
+                    id: *id,
+                    before: before.placement,
+                    after,
+                });
+            }
+        }
+
+        for (from, to) in wires_after.difference(&wires_before) {
+            edits.push(GraphEdit::Connect {
+                from: *from,
+                to: *to,
+            });
+        }
+
+        if hash_of(state.inputs) != self.inputs_hash {
+            edits.push(GraphEdit::Inputs {
+                before: self.inputs.clone()?,
+                after: Box::new(state.inputs.clone()),
+            });
+        }
+
+        if hash_of(state.outputs) != self.outputs_hash {
+            edits.push(GraphEdit::Outputs {
+                before: self.outputs.clone()?,
+                after: Box::new(state.outputs.clone()),
+            });
+        }
+
+        if hash_of(state.regions) != self.regions_hash {
+            edits.push(GraphEdit::Regions {
+                before: self.regions.clone()?,
+                after: Box::new(state.regions.clone()),
+            });
+        }
+
+        self.diff_inline_values(state.inline_values, &mut edits)?;
+
+        Some(edits)
+    }
+
+    /// Records the changed inline values. If the order of the unchanged
+    /// values was changed too, all values are recorded
+    ///
+    /// Values of the untouched nodes are taken from the current state, so
+    /// they must be unchanged
+    fn diff_inline_values(
+        &self,
+        values: &OrderMap<InPinId, EValue>,
+        edits: &mut Vec<GraphEdit>,
+    ) -> Option<()> {
+        let mut previous = OrderMap::default();
+        for (pin, hash) in &self.inline_values {
+            let value = if self.touched.contains(&pin.node) {
+                self.values.get(pin)?
+            } else {
+                values.get(pin)?
+            };
+            if hash_of(value) != *hash {
+                return None;
+            }
+            previous.insert(*pin, value);
+        }
+
+        // Values of the touched nodes that didn't exist before the action
+        // were added before being captured
+        let touched_values = previous
+            .keys()
+            .filter(|pin| self.touched.contains(&pin.node))
+            .count();
+        if touched_values != self.values.len() {
+            return None;
+        }
+
+        let is_kept = |pin: &InPinId| {
+            previous
+                .get(pin)
+                .is_some_and(|value| values.get(pin) == Some(*value))
+        };
+
+        let kept_before = previous.keys().filter(|pin| is_kept(pin));
+        let kept_after = values.keys().filter(|pin| is_kept(pin));
+        let reordered = !kept_before.eq(kept_after);
+
+        for pin in previous.keys().chain(values.keys()).unique() {
+            let before = previous
+                .get_full(pin)
+                .map(|(index, _, value)| (index, (*value).clone()));
+            let after = inline_value_of(values, *pin);
+            if reordered || before.as_ref().map(|v| &v.1) != after.as_ref().map(|v| &v.1) {
+                edits.push(GraphEdit::InlineValue {
+                    pin: *pin,
+                    before,
+                    after,
+                });
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// IDs matching the filter, starting with the ones in the provided order,
+/// followed by the rest in the ascending order
+fn ordered_ids<'a>(
+    order: &[NodeId],
+    ids: impl Iterator<Item = &'a NodeId>,
+    filter: impl Fn(&NodeId) -> bool,
+) -> Vec<NodeId> {
+    let mut result = order
+        .iter()
+        .filter(|id| filter(*id))
+        .copied()
+        .unique()
+        .collect_vec();
+    for id in ids {
+        if filter(id) && !result.contains(id) {
+            result.push(*id);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::editing::GraphEditingContext;
+    use crate::graph::history::{
+        insert_node_at, GraphEdit, GraphEditLog, GraphEdits, NodePlacement, NodeSnapshot,
+    };
+    use crate::graph::node::commands::{SnarlCommand, SnarlCommands};
+    use crate::graph::node::{get_node_factory, SnarlNode};
+    use crate::graph::region::RegionVariable;
+    use crate::graph::Graph;
+    use crate::project::docs::Docs;
+    use crate::project::side_effects::SideEffectsContext;
+    use crate::project::test_utils::test_project;
+    use crate::registry::ETypesRegistry;
+    use crate::value::EValue;
+    use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
+    use emath::{pos2, Pos2};
+    use utils::map::hash_of;
+    use utils::vec_utils::VecOperation;
+    use uuid::Uuid;
+
+    fn registry() -> ETypesRegistry {
+        test_project(&[]).registry
+    }
+
+    /// Empty graph that records its edits
+    fn graph() -> Graph {
+        let mut graph = Graph::default();
+        graph.take_edits();
+        graph
+    }
+
+    /// Runs the edit with the editing context of the graph, returning the
+    /// recorded edits
+    fn edit<T>(
+        graph: &mut Graph,
+        registry: &ETypesRegistry,
+        edit: impl FnOnce(&mut GraphEditingContext) -> miette::Result<T>,
+    ) -> (T, GraphEditLog) {
+        let mut outputs = None;
+        let mut ctx = GraphEditingContext::from_graph(
+            graph,
+            registry,
+            &Docs::Stub,
+            None,
+            SideEffectsContext::Unavailable,
+            false,
+            &[],
+            &mut outputs,
+        );
+        let result = edit(&mut ctx).unwrap();
+        (result, graph.take_edits())
+    }
+
+    fn create_node(graph: &mut Graph, registry: &ETypesRegistry, id: &str) -> NodeId {
+        let (ids, _) = edit(graph, registry, |ctx| {
+            ctx.create_node(id.into(), Pos2::ZERO)
+        });
+        ids[0]
+    }
+
+    fn add_node() -> SnarlNode {
+        SnarlNode::new(get_node_factory(&"add".into()).unwrap().create())
+    }
+
+    /// Checks that undoing the edits restores the state the edits started
+    /// from, and redoing them restores the current state
+    fn assert_undo_redo(graph: &mut Graph, before: u64, log: &GraphEditLog) {
+        assert!(!log.is_empty());
+        assert_eq!(log.base, before);
+
+        let after = hash_of(&*graph);
+        let redo = GraphEdits::undo_of(&log.actions).apply(graph).unwrap();
+        assert_eq!(hash_of(&*graph), before);
+
+        redo.apply(graph).unwrap();
+        assert_eq!(hash_of(&*graph), after);
+    }
+
+    /// Runs the edit, checking that it's recorded as a single undoable step
+    /// that can be undone and redone
+    fn assert_single_step<T>(
+        graph: &mut Graph,
+        registry: &ETypesRegistry,
+        op: impl FnOnce(&mut GraphEditingContext) -> miette::Result<T>,
+    ) -> T {
+        let before = hash_of(&*graph);
+        let (result, log) = edit(graph, registry, op);
+        assert_eq!(log.actions.len(), 1, "{:?}", log.actions);
+        assert_undo_redo(graph, before, &log);
+        result
+    }
+
+    fn edits(log: &GraphEditLog) -> impl Iterator<Item = &GraphEdit> {
+        log.actions.iter().flat_map(|action| &action.edits)
+    }
+
+    #[test]
+    fn should_undo_node_insertion() {
+        let registry = registry();
+        let mut graph = graph();
+        let before = hash_of(&graph);
+
+        let (ids, log) = edit(&mut graph, &registry, |ctx| {
+            ctx.create_node("add".into(), pos2(10.0, 20.0))
+        });
+        assert!(edits(&log).all(|edit| matches!(edit, GraphEdit::InsertNode { .. })));
+
+        assert_undo_redo(&mut graph, before, &log);
+        assert_eq!(
+            graph.snarl.get_node_info(ids[0]).unwrap().pos,
+            pos2(10.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn should_restore_removed_node_with_its_id() {
+        let registry = registry();
+        let mut graph = graph();
+        let a = create_node(&mut graph, &registry, "add");
+        let b = create_node(&mut graph, &registry, "add");
+        let before = hash_of(&graph);
+
+        let (_, log) = edit(&mut graph, &registry, |ctx| {
+            ctx.remove_node(a, &mut SnarlCommands::new())?;
+            ctx.remove_node(b, &mut SnarlCommands::new())
+        });
+        assert!(graph.snarl.get_node(a).is_none());
+
+        assert_undo_redo(&mut graph, before, &log);
+        assert!(graph.snarl.get_node(a).is_none());
+        assert!(graph.snarl.get_node(b).is_none());
+
+        GraphEdits::undo_of(&log.actions).apply(&mut graph).unwrap();
+        assert!(graph.snarl.get_node(a).is_some());
+        assert!(graph.snarl.get_node(b).is_some());
+    }
+
+    #[test]
+    fn should_insert_nodes_at_vacant_ids() {
+        let mut snarl = Snarl::new();
+        let ids = (0..3)
+            .map(|_| snarl.insert_node(Pos2::ZERO, add_node()))
+            .collect::<Vec<_>>();
+        snarl.remove_node(ids[0]);
+        snarl.remove_node(ids[1]);
+
+        let node = NodeSnapshot {
+            node: add_node(),
+            placement: NodePlacement {
+                pos: pos2(1.0, 2.0),
+                open: false,
+            },
+        };
+
+        // The vacant ID that would be handed out first is skipped, and stays
+        // vacant afterwards
+        insert_node_at(&mut snarl, ids[0], &node).unwrap();
+        let restored = snarl.get_node_info(ids[0]).unwrap();
+        assert_eq!(restored.pos, pos2(1.0, 2.0));
+        assert!(!restored.open);
+        assert_eq!(snarl.insert_node(Pos2::ZERO, add_node()), ids[1]);
+
+        // IDs past the end are reached too
+        let far = NodeId(ids[2].0 + 3);
+        insert_node_at(&mut snarl, far, &node).unwrap();
+        assert!(snarl.get_node(far).is_some());
+        assert_eq!(snarl.nodes_ids_data().count(), 4);
+
+        assert!(insert_node_at(&mut snarl, ids[2], &node).is_err());
+        assert_eq!(snarl.nodes_ids_data().count(), 4);
+    }
+
+    #[test]
+    fn should_undo_wire_changes() {
+        let registry = registry();
+        let mut graph = graph();
+        let a = create_node(&mut graph, &registry, "add");
+        let b = create_node(&mut graph, &registry, "add");
+        let from = OutPinId { node: a, output: 0 };
+        let to = InPinId { node: b, input: 0 };
+
+        let before = hash_of(&graph);
+        let (_, log) = edit(&mut graph, &registry, |ctx| {
+            let (from, to) = (ctx.snarl.out_pin(from), ctx.snarl.in_pin(to));
+            ctx.connect(&from, &to, &mut SnarlCommands::new())
+        });
+        assert!(graph.snarl.wires().any(|wire| wire == (from, to)));
+        assert_undo_redo(&mut graph, before, &log);
+
+        let before = hash_of(&graph);
+        let (_, log) = edit(&mut graph, &registry, |ctx| {
+            let (from, to) = (ctx.snarl.out_pin(from), ctx.snarl.in_pin(to));
+            ctx.disconnect(&from, &to, &mut SnarlCommands::new())
+        });
+        assert_eq!(graph.snarl.wires().count(), 0);
+        assert_undo_redo(&mut graph, before, &log);
+    }
+
+    #[test]
+    fn should_undo_node_moves() {
+        let registry = registry();
+        let mut graph = graph();
+        let id = create_node(&mut graph, &registry, "add");
+        let before = hash_of(&graph);
+
+        // Moves are done by the graph view, outside the editing context
+        graph.snarl.get_node_info_mut(id).unwrap().pos = pos2(5.0, 5.0);
+        let log = graph.take_edits();
+        assert!(edits(&log).all(|edit| matches!(edit, GraphEdit::MoveNode { .. })));
+
+        assert_undo_redo(&mut graph, before, &log);
+        assert_eq!(graph.snarl.get_node_info(id).unwrap().pos, pos2(5.0, 5.0));
+    }
+
+    #[test]
+    fn should_undo_inline_value_edits() {
+        let registry = registry();
+        let mut graph = graph();
+        let id = create_node(&mut graph, &registry, "add");
+        let pin = InPinId { node: id, input: 0 };
+        let before = hash_of(&graph);
+
+        let (_, log) = edit(&mut graph, &registry, |ctx| {
+            ctx.edit_inline_input(pin, |value| *value = EValue::from(5.0))
+        });
+        assert_eq!(graph.inline_values.get(&pin), Some(&EValue::from(5.0)));
+
+        assert_undo_redo(&mut graph, before, &log);
+        assert_eq!(graph.inline_values.get(&pin), Some(&EValue::from(5.0)));
+    }
+
+    #[test]
+    fn should_undo_region_edits() {
+        let registry = registry();
+        let mut graph = graph();
+        create_node(&mut graph, &registry, "for_each");
+        let region = *graph.regions.keys().next().unwrap();
+        let before = hash_of(&graph);
+
+        let (_, log) = edit(&mut graph, &registry, |ctx| {
+            ctx.record(|ctx| {
+                let mut commands = SnarlCommands::new();
+                commands.push(SnarlCommand::EditRegionVariables {
+                    region,
+                    operation: VecOperation::Push(RegionVariable {
+                        ty: None,
+                        id: Uuid::new_v4(),
+                        name: "value".to_string(),
+                    }),
+                });
+                commands.execute(ctx)
+            })
+        });
+        assert!(edits(&log).all(|edit| matches!(edit, GraphEdit::Regions { .. })));
+        assert_eq!(graph.regions[&region].variables.len(), 1);
+
+        assert_undo_redo(&mut graph, before, &log);
+        assert_eq!(graph.regions[&region].variables.len(), 1);
+    }
+
+    #[test]
+    fn should_record_each_editing_op_as_single_step() {
+        let registry = registry();
+        let mut graph = graph();
+        let a = assert_single_step(&mut graph, &registry, |ctx| {
+            ctx.create_node("add".into(), Pos2::ZERO)
+        })[0];
+        let b = assert_single_step(&mut graph, &registry, |ctx| {
+            ctx.create_node("add".into(), pos2(10.0, 0.0))
+        })[0];
+        let from = OutPinId { node: a, output: 0 };
+        let to = InPinId { node: b, input: 0 };
+
+        assert!(assert_single_step(&mut graph, &registry, |ctx| {
+            let (from, to) = (ctx.snarl.out_pin(from), ctx.snarl.in_pin(to));
+            ctx.connect(&from, &to, &mut SnarlCommands::new())
+        }));
+        assert_single_step(&mut graph, &registry, |ctx| {
+            let (from, to) = (ctx.snarl.out_pin(from), ctx.snarl.in_pin(to));
+            ctx.disconnect(&from, &to, &mut SnarlCommands::new())
+        });
+        assert_eq!(graph.snarl.wires().count(), 0);
+
+        let pin = InPinId { node: b, input: 1 };
+        assert_single_step(&mut graph, &registry, |ctx| {
+            ctx.edit_inline_input(pin, |value| *value = EValue::from(5.0))
+        });
+        assert_eq!(graph.inline_values.get(&pin), Some(&EValue::from(5.0)));
+
+        assert_single_step(&mut graph, &registry, |ctx| {
+            ctx.remove_node(a, &mut SnarlCommands::new())
+        });
+        assert!(graph.snarl.get_node(a).is_none());
+    }
+
+    #[test]
+    fn should_drop_record_of_unreported_changes() {
+        let registry = registry();
+        let mut graph = graph();
+        let id = create_node(&mut graph, &registry, "add");
+
+        let (_, log) = edit(&mut graph, &registry, |ctx| {
+            ctx.record(|ctx| {
+                ctx.snarl.remove_node(id);
+                Ok(())
+            })
+        });
+        assert!(log.is_empty());
+        assert_eq!(log.base, hash_of(&graph));
+    }
+}
@@ -10,6 +10,7 @@ use crate::graph::node::extras::ExecutionExtras;
 use crate::graph::node::format_node::FormatNodeFactory;
 use crate::graph::node::functional::functional_nodes;
 use crate::graph::node::generic::destructuring::DestructuringNodeFactory;
+use crate::graph::node::generic::zip::ListZipNodeFactory;
 use crate::graph::node::groups::input::GroupInputNodeFactory;
 use crate::graph::node::groups::output::GroupOutputNodeFactory;
 use crate::graph::node::groups::subgraph::SubgraphNodeFactory;
@@ -17,6 +18,7 @@ use crate::graph::node::groups::tree_subgraph::TreeSubgraphFactory;
 use crate::graph::node::list::ListNodeFactory;
 use crate::graph::node::ports::{InputData, NodePortType, OutputData};
 use crate::graph::node::regional::generic_regional::construct::ConstructListNode;
+use crate::graph::node::regional::generic_regional::fold::ListFoldNode;
 use crate::graph::node::regional::generic_regional::for_each::{
     ListFilterMapNode, ListFilterNode, ListFlatMapNode, ListForEachNode, ListMapNode,
 };
@@ -25,6 +27,7 @@ use crate::graph::node::regional::repeat::RepeatNode;
 use crate::graph::node::regional::RegionalNodeFactory;
 use crate::graph::node::reroute::RerouteFactory;
 use crate::graph::node::saving_node::SavingNodeFactory;
+use crate::graph::node::script_node::ScriptNodeFactory;
 use crate::graph::node::struct_node::StructNodeFactory;
 use crate::graph::node::variables::coalesce::{CoalesceNode, CoalesceOrDefaultNode};
 use crate::graph::node::variables::VariablesNodeFactory;
@@ -70,6 +73,7 @@ pub mod ports;
 pub mod regional;
 pub mod reroute;
 pub mod saving_node;
+pub mod script_node;
 pub mod serde_node;
 pub mod stateful;
 pub mod struct_node;
@@ -112,7 +116,9 @@ fn default_nodes() -> impl Iterator<Item = (Ustr, Arc<dyn NodeFactory>)> {
     push(v, FormatNodeFactory);
     push(v, ExpressionNodeFactory);
     push(v, DestructuringNodeFactory);
+    push(v, ListZipNodeFactory);
     push(v, TreeSubgraphFactory);
+    push(v, ScriptNodeFactory);
     // regional
     push(v, RegionalNodeFactory::<RepeatNode>::INSTANCE);
     push(v, RegionalNodeFactory::<ConditionalIfNode>::INSTANCE);
@@ -123,6 +129,7 @@ fn default_nodes() -> impl Iterator<Item = (Ustr, Arc<dyn NodeFactory>)> {
     push(v, RegionalNodeFactory::<ListFilterMapNode>::INSTANCE);
     push(v, RegionalNodeFactory::<ConstructListNode>::INSTANCE);
     push(v, RegionalNodeFactory::<ListFlatMapNode>::INSTANCE);
+    push(v, RegionalNodeFactory::<ListFoldNode>::INSTANCE);
     push(v, RegionalNodeFactory::<ForEachDbeItem>::INSTANCE);
     // variables
     push(v, VariablesNodeFactory::<CoalesceNode>::INSTANCE);
@@ -1,5 +1,6 @@
 use crate::etype::EDataType;
 use crate::graph::editing::GraphEditingContext;
+use crate::graph::history::Touch;
 use crate::graph::region::{RegionInfo, RegionVariable};
 use egui_snarl::{InPinId, NodeId, OutPinId};
 use itertools::Itertools;
@@ -111,6 +112,38 @@ impl SnarlCommand {
         ctx: &mut GraphEditingContext,
         commands: &mut SnarlCommands,
     ) -> miette::Result<()> {
+        // Commands running the node logic go through the context methods,
+        // which report the changed nodes to the history
+        match &self {
+            SnarlCommand::ConnectRaw { from, to } | SnarlCommand::DisconnectRaw { from, to } => {
+                ctx.touch(Touch::Nodes(&[from.node, to.node]))
+            }
+            SnarlCommand::InputMovedRaw { from, to } => {
+                ctx.touch(Touch::Nodes(&[from.node, to.node]))
+            }
+            SnarlCommand::OutputMovedRaw { from, to } => {
+                ctx.touch(Touch::Nodes(&[from.node, to.node]))
+            }
+            SnarlCommand::DropInputsRaw { to } => ctx.touch(Touch::Nodes(&[to.node])),
+            SnarlCommand::DeletePinValue { pin } => ctx.touch(Touch::Nodes(&[pin.node])),
+            SnarlCommand::InputsRearrangedRaw { node, .. }
+            | SnarlCommand::OutputsRearrangedRaw { node, .. }
+            | SnarlCommand::DeleteNode { node } => ctx.touch(Touch::Nodes(&[*node])),
+            SnarlCommand::SetGroupInputType { .. } => ctx.touch(Touch::Inputs),
+            SnarlCommand::SetGroupOutputType { .. } => ctx.touch(Touch::Outputs),
+            SnarlCommand::EditRegionVariables { .. } => ctx.touch(Touch::Regions),
+            SnarlCommand::Custom { .. } => ctx.touch(Touch::All),
+            SnarlCommand::Connect { .. }
+            | SnarlCommand::Disconnect { .. }
+            | SnarlCommand::DropOutputs { .. }
+            | SnarlCommand::DropInputs { .. }
+            | SnarlCommand::DropNodeOutputs { .. }
+            | SnarlCommand::DropNodeInputs { .. }
+            | SnarlCommand::ReconnectOutput { .. }
+            | SnarlCommand::ReconnectInput { .. }
+            | SnarlCommand::RequireRegionRebuild => {}
+        }
+
         match self {
             SnarlCommand::DisconnectRaw { from, to } => {
                 ctx.snarl.disconnect(from, to);
@@ -334,6 +367,7 @@ impl SnarlCommand {
                     }
                     .execute(ctx, commands)?;
                 }
+                ctx.history.node_removed(node);
                 ctx.snarl.remove_node(node);
                 ctx.mark_dirty();
             }
@@ -1,3 +1,4 @@
+use crate::graph::execution::profiler::ExecutionProfile;
 use crate::graph::region::RegionExecutionData;
 use crate::project::side_effects::SideEffectsContext;
 use crate::value::EValue;
@@ -14,6 +15,9 @@ pub struct ExecutionExtras<'a> {
     #[debug("(...)")]
     regional_data: &'a mut HashMap<Uuid, Box<dyn RegionExecutionData>>,
     pub side_effects: SideEffectsContext<'a>,
+    /// Profile of the current evaluation, passed down to the node group graphs
+    #[debug("(...)")]
+    pub(crate) profile: Option<&'a mut ExecutionProfile>,
 }
 
 impl<'a> ExecutionExtras<'a> {
@@ -23,6 +27,7 @@ impl<'a> ExecutionExtras<'a> {
         group_outputs: &'a mut Option<Vec<EValue>>,
         regional_data: &'a mut HashMap<Uuid, Box<dyn RegionExecutionData>>,
         side_effects: SideEffectsContext<'a>,
+        profile: Option<&'a mut ExecutionProfile>,
     ) -> Self {
         Self {
             is_node_group,
@@ -30,6 +35,7 @@ impl<'a> ExecutionExtras<'a> {
             group_outputs,
             regional_data,
             side_effects,
+            profile,
         }
     }
 
@@ -19,12 +19,18 @@ mod macros;
 mod values;
 
 mod debug;
+mod diagnostics;
 mod list;
+mod logic;
+mod map;
 mod mappings;
 mod math;
 mod optional;
 mod raw_manip;
-mod transient_storage;
+mod string;
+#[cfg(test)]
+mod test_utils;
+pub(crate) mod transient_storage;
 
 pub type FunctionalArgNames = &'static [&'static str];
 
@@ -318,69 +324,6 @@ fn functional_node<I, O, Arg: IntoFunctionalNode<I, O>>(
 
 pub fn functional_nodes() -> Vec<Arc<dyn NodeFactory>> {
     let mut nodes: Vec<Arc<dyn NodeFactory>> = vec![
-        functional_node(
-            |_: C, a: bool, b: bool| a == b,
-            "bool_equals",
-            &["a", "b"],
-            &["a == b"],
-            &["boolean"],
-        ),
-        functional_node(
-            |_: C, a: bool, b: bool| a != b,
-            "bool_not_equals",
-            &["a", "b"],
-            &["a != b"],
-            &["boolean"],
-        ),
-        functional_node(
-            |_: C, a: bool| !a,
-            "bool_invert",
-            &["a"],
-            &["not a"],
-            &["boolean"],
-        ),
-        functional_node(
-            |_: C, a: bool, b: bool| a && b,
-            "bool_and",
-            &["a", "b"],
-            &["a and b"],
-            &["boolean"],
-        ),
-        functional_node(
-            |_: C, a: bool, b: bool| a || b,
-            "bool_or",
-            &["a", "b"],
-            &["a or b"],
-            &["boolean"],
-        ),
-        functional_node(
-            |_: C, a: String, b: String| a + &b,
-            "concat",
-            &["a", "b"],
-            &["result"],
-            &["string"],
-        ),
-        functional_node(
-            |_: C, a: String| a.to_lowercase(),
-            "lower_case",
-            &["a"],
-            &["result"],
-            &["string"],
-        ),
-        functional_node(
-            |_: C, a: String| a.to_uppercase(),
-            "upper_case",
-            &["a"],
-            &["result"],
-            &["string"],
-        ),
-        functional_node(
-            |_: C, a: String| ENumber::from(a.len() as f64),
-            "string_length",
-            &["a"],
-            &["length"],
-            &["string"],
-        ),
         functional_node(
             |_: C, a: ENumber| a,
             "numeric_value",
@@ -412,11 +355,15 @@ pub fn functional_nodes() -> Vec<Arc<dyn NodeFactory>> {
     ];
 
     nodes.extend(debug::nodes());
+    nodes.extend(diagnostics::nodes());
     nodes.extend(list::nodes());
+    nodes.extend(logic::nodes());
+    nodes.extend(map::nodes());
     nodes.extend(mappings::nodes());
     nodes.extend(math::nodes());
     nodes.extend(optional::nodes());
     nodes.extend(raw_manip::nodes());
+    nodes.extend(string::nodes());
     nodes.extend(transient_storage::nodes());
 
     nodes
@@ -0,0 +1,38 @@
+use crate::graph::node::functional::{side_effects_node, C};
+use crate::graph::node::NodeFactory;
+use crate::project::side_effects::SideEffect;
+use diagnostic::diagnostic::DiagnosticLevel;
+use std::sync::Arc;
+
+pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
+    vec![
+        side_effects_node(
+            |ctx: C, file: String, path: String, message: String| {
+                ctx.extras.side_effects.push(SideEffect::EmitDiagnostic {
+                    file,
+                    path,
+                    message,
+                    level: DiagnosticLevel::Error,
+                })
+            },
+            "emit_error",
+            &["file", "path", "message"],
+            &[],
+            &["validation"],
+        ),
+        side_effects_node(
+            |ctx: C, file: String, path: String, message: String| {
+                ctx.extras.side_effects.push(SideEffect::EmitDiagnostic {
+                    file,
+                    path,
+                    message,
+                    level: DiagnosticLevel::Warning,
+                })
+            },
+            "emit_warning",
+            &["file", "path", "message"],
+            &[],
+            &["validation"],
+        ),
+    ]
+}
@@ -8,6 +8,7 @@ use crate::value::EValue;
 use arrayvec::ArrayVec;
 use itertools::Itertools;
 use miette::bail;
+use std::collections::BTreeMap;
 
 pub(super) const MAX_FIELDS: usize = 5;
 
@@ -206,6 +207,118 @@ impl<const N: usize> GenericFieldAdapter for Option<GenericValue<N>> {
     }
 }
 
+/// Type of the field that is derived from the generic type
+pub(super) trait TypeProjection: 'static {
+    type Value;
+
+    /// Derives the field type from the generic type
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType;
+
+    fn try_from_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value>;
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue>;
+}
+
+/// Value of the field with type derived from the generic type `N`
+///
+/// Derived fields only get their type once some other field specifies the
+/// generic type
+pub(super) struct Projected<const N: usize, P: TypeProjection>(pub P::Value);
+
+impl<const N: usize, P: TypeProjection> GenericFieldAdapter for Projected<N, P> {
+    fn type_index() -> Option<usize> {
+        Some(N)
+    }
+
+    fn field<'a>(_registry: &ETypesRegistry, ty: &'a Option<EDataType>) -> GenericNodeField<'a> {
+        GenericNodeField::Derived(ty, P::project)
+    }
+
+    fn field_mut<'a>(
+        _registry: &ETypesRegistry,
+        ty: &'a mut Option<EDataType>,
+    ) -> GenericNodeFieldMut<'a> {
+        GenericNodeFieldMut::Derived(ty, P::project)
+    }
+
+    fn try_from_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self>
+    where
+        Self: Sized,
+    {
+        P::try_from_evalue(registry, generic_ty, value).map(Self)
+    }
+
+    fn into_evalue(
+        self,
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+    ) -> miette::Result<EValue> {
+        P::into_evalue(registry, generic_ty, self.0)
+    }
+}
+
+/// Map value with the generic type `N` being the whole map type
+pub(super) struct GenericMap<const N: usize>(pub BTreeMap<EValue, EValue>);
+
+impl<const N: usize> GenericFieldAdapter for GenericMap<N> {
+    fn type_index() -> Option<usize> {
+        Some(N)
+    }
+
+    fn field<'a>(_registry: &ETypesRegistry, ty: &'a Option<EDataType>) -> GenericNodeField<'a> {
+        GenericNodeField::Map(ty)
+    }
+
+    fn field_mut<'a>(
+        _registry: &ETypesRegistry,
+        ty: &'a mut Option<EDataType>,
+    ) -> GenericNodeFieldMut<'a> {
+        GenericNodeFieldMut::Map(ty)
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self>
+    where
+        Self: Sized,
+    {
+        let EValue::Map { values, id: _ } = value else {
+            bail!("Expected map, got {:?}", value);
+        };
+
+        Ok(Self(values.clone()))
+    }
+
+    fn into_evalue(
+        self,
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+    ) -> miette::Result<EValue> {
+        let ty = generic_ty
+            .filter(EDataType::is_map)
+            .unwrap_or_else(|| registry.map_of(EDataType::null(), EDataType::null()));
+        let EDataType::Map { id } = ty else {
+            unreachable!("type was checked to be a map");
+        };
+
+        Ok(EValue::Map { id, values: self.0 })
+    }
+}
+
 impl<T: EItemInfoAdapter> GenericFieldAdapter for T {
     fn type_index() -> Option<usize> {
         None
@@ -164,6 +164,8 @@ impl<T: FunctionalNode + Clone> NodeFactory for T {
             GenericNodeField::Value(_) => true,
             GenericNodeField::Option(_) => is_type_option(ty),
             GenericNodeField::Object(_) => ty.is_object(),
+            GenericNodeField::Map(_) => ty.is_map(),
+            GenericNodeField::Derived(..) => false,
             GenericNodeField::Fixed(fixed_ty) => ty == fixed_ty,
         })
     }
@@ -175,6 +177,8 @@ impl<T: FunctionalNode + Clone> NodeFactory for T {
             GenericNodeField::Value(_) => true,
             GenericNodeField::Option(_) => is_type_option(ty),
             GenericNodeField::Object(_) => ty.is_object(),
+            GenericNodeField::Map(_) => ty.is_map(),
+            GenericNodeField::Derived(..) => false,
             GenericNodeField::Fixed(fixed_ty) => fixed_ty.is_unknown() || ty == fixed_ty,
         })
     }
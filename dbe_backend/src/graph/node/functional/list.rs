@@ -1,9 +1,14 @@
-use crate::graph::node::functional::generic::GenericValue;
+use crate::etype::EDataType;
+use crate::graph::node::functional::generic::{GenericValue, Projected, TypeProjection};
 use crate::graph::node::functional::{functional_node, C};
 use crate::graph::node::NodeFactory;
-use crate::value::ENumber;
+use crate::registry::pair_helpers::{make_pair, unwrap_pair};
+use crate::registry::ETypesRegistry;
+use crate::value::{ENumber, EValue};
+use itertools::Itertools;
 use miette::bail;
 use std::sync::Arc;
+use utils::map::HashSet;
 
 pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
     vec![
@@ -164,5 +169,376 @@ pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
             &["contains"],
             &["list"],
         ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>, item: GenericValue<0>| {
+                list.iter()
+                    .position(|i| i == &item)
+                    .map(|idx| ENumber::from(idx as f64))
+            },
+            "list_index_of",
+            &["list", "item"],
+            &["index"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>, item: GenericValue<0>| {
+                list.iter()
+                    .rposition(|i| i == &item)
+                    .map(|idx| ENumber::from(idx as f64))
+            },
+            "list_last_index_of",
+            &["list", "item"],
+            &["index"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>, start: ENumber, end: ENumber| {
+                let start = resolve_slice_index(start, list.len());
+                let end = resolve_slice_index(end, list.len()).max(start);
+                list[start..end].to_vec()
+            },
+            "list_slice",
+            &["list", "start", "end"],
+            &["list"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, mut list: Vec<GenericValue<0>>| {
+                list.sort();
+                list
+            },
+            "list_sort",
+            &["list"],
+            &["list"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>, keys: Vec<GenericValue<1>>| {
+                if list.len() != keys.len() {
+                    bail!(
+                        "List and keys must have the same length, but the list has {} elements, and there are {} keys",
+                        list.len(),
+                        keys.len()
+                    );
+                }
+                Ok(list
+                    .into_iter()
+                    .zip(keys)
+                    .sorted_by(|(_, a), (_, b)| a.cmp(b))
+                    .map(|(item, _)| item)
+                    .collect_vec())
+            },
+            "list_sort_by_key",
+            &["list", "keys"],
+            &["list"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>| {
+                let mut seen = HashSet::default();
+                list.into_iter()
+                    .filter(|item| seen.insert(item.clone()))
+                    .collect_vec()
+            },
+            "list_dedupe",
+            &["list"],
+            &["list"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>| {
+                Projected::<0, Enumerated>(list.into_iter().map(|item| item.0).collect_vec())
+            },
+            "list_enumerate",
+            &["list"],
+            &["entries"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, lists: Vec<GenericValue<0>>| {
+                let mut items = vec![];
+                for list in lists {
+                    let EValue::List { values, id: _ } = list.0 else {
+                        bail!("Expected list, got {:?}", list.0);
+                    };
+                    items.extend(values);
+                }
+                Ok(Projected::<0, Flattened>(items))
+            },
+            "list_flatten",
+            &["lists"],
+            &["list"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<bool>| list.into_iter().any(|value| value),
+            "list_any",
+            &["list"],
+            &["any"],
+            &["list", "boolean"],
+        ),
+        functional_node(
+            |_: C, list: Vec<bool>| list.into_iter().all(|value| value),
+            "list_all",
+            &["list"],
+            &["all"],
+            &["list", "boolean"],
+        ),
+        functional_node(
+            |_: C, list: Vec<ENumber>| list.into_iter().sum::<ENumber>(),
+            "list_sum",
+            &["list"],
+            &["sum"],
+            &["list", "math"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>| list.into_iter().min(),
+            "list_min",
+            &["list"],
+            &["min"],
+            &["list"],
+        ),
+        functional_node(
+            |_: C, list: Vec<GenericValue<0>>| list.into_iter().max(),
+            "list_max",
+            &["list"],
+            &["max"],
+            &["list"],
+        ),
     ]
 }
+
+/// Converts the slice bound into the index, counting negative indices from
+/// the end of the list and clamping out of bounds indices
+fn resolve_slice_index(index: ENumber, len: usize) -> usize {
+    let index = index.0 as isize;
+    if index < 0 {
+        len.saturating_sub(index.unsigned_abs())
+    } else {
+        (index as usize).min(len)
+    }
+}
+
+/// List of index-item pairs, with the generic type being the item type
+struct Enumerated;
+
+impl TypeProjection for Enumerated {
+    type Value = Vec<EValue>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        registry.list_of(registry.pair_of(EDataType::Number, ty))
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        let EValue::List { values, id: _ } = value else {
+            bail!("Expected list, got {:?}", value);
+        };
+        values
+            .iter()
+            .map(|entry| unwrap_pair(entry).map(|(_, item)| item.clone()))
+            .collect()
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let item_ty = generic_ty.unwrap_or_else(EDataType::null);
+        Ok(EValue::List {
+            id: registry.list_id_of(registry.pair_of(EDataType::Number, item_ty)),
+            values: value
+                .into_iter()
+                .enumerate()
+                .map(|(idx, item)| {
+                    make_pair(
+                        registry,
+                        EDataType::Number,
+                        item_ty,
+                        EValue::from(idx as f64),
+                        item,
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Concatenated items of the nested lists, with the generic type being the
+/// nested list type
+struct Flattened;
+
+impl TypeProjection for Flattened {
+    type Value = Vec<EValue>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        if ty.is_list() {
+            ty
+        } else {
+            registry.list_of(EDataType::null())
+        }
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        let EValue::List { values, id: _ } = value else {
+            bail!("Expected list, got {:?}", value);
+        };
+        Ok(values.clone())
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let id = match generic_ty {
+            Some(EDataType::List { id }) => id,
+            _ => registry.list_id_of(EDataType::null()),
+        };
+        Ok(EValue::List { id, values: value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::etype::EDataType;
+    use crate::graph::node::functional::test_utils::{execute_node, numbers};
+    use crate::project::test_utils::test_project;
+    use crate::registry::optional_helpers::wrap_in_option;
+    use crate::registry::ETypesRegistry;
+    use crate::value::EValue;
+
+    fn registry() -> ETypesRegistry {
+        test_project(&[]).registry
+    }
+
+    fn slice(registry: &ETypesRegistry, list: &[f64], start: f64, end: f64) -> EValue {
+        let mut outputs = execute_node(
+            registry,
+            "list_slice",
+            EDataType::Number,
+            vec![numbers(registry, list), start.into(), end.into()],
+        )
+        .unwrap();
+        outputs.remove(0)
+    }
+
+    #[test]
+    fn should_clamp_out_of_range_slices() {
+        let registry = registry();
+        let list = [1.0, 2.0, 3.0];
+
+        assert_eq!(
+            slice(&registry, &list, 1.0, 10.0),
+            numbers(&registry, &[2.0, 3.0])
+        );
+        assert_eq!(slice(&registry, &list, 5.0, 10.0), numbers(&registry, &[]));
+        assert_eq!(
+            slice(&registry, &list, -2.0, -1.0),
+            numbers(&registry, &[2.0])
+        );
+        assert_eq!(
+            slice(&registry, &list, -10.0, 1.0),
+            numbers(&registry, &[1.0])
+        );
+        assert_eq!(slice(&registry, &list, 2.0, 1.0), numbers(&registry, &[]));
+    }
+
+    #[test]
+    fn should_fail_on_out_of_range_get() {
+        let registry = registry();
+        let get = |index: f64| {
+            execute_node(
+                &registry,
+                "list_get",
+                EDataType::Number,
+                vec![numbers(&registry, &[1.0, 2.0]), index.into()],
+            )
+        };
+
+        assert_eq!(get(-1.0).unwrap(), vec![EValue::from(2.0)]);
+        assert!(get(2.0).is_err());
+        assert!(get(-3.0).is_err());
+    }
+
+    #[test]
+    fn should_return_none_for_empty_min_max() {
+        let registry = registry();
+        let extreme = |id: &str, list: &[f64]| {
+            execute_node(
+                &registry,
+                id,
+                EDataType::Number,
+                vec![numbers(&registry, list)],
+            )
+            .unwrap()
+        };
+        let option = |value: Option<f64>| {
+            vec![wrap_in_option(
+                &registry,
+                EDataType::Number,
+                value.map(EValue::from),
+            )]
+        };
+
+        assert_eq!(extreme("list_min", &[]), option(None));
+        assert_eq!(extreme("list_max", &[]), option(None));
+        assert_eq!(extreme("list_min", &[3.0, 1.0, 2.0]), option(Some(1.0)));
+        assert_eq!(extreme("list_max", &[3.0, 1.0, 2.0]), option(Some(3.0)));
+    }
+
+    #[test]
+    fn should_keep_order_of_equal_keys_when_sorting() {
+        let registry = registry();
+        let outputs = execute_node(
+            &registry,
+            "list_sort_by_key",
+            EDataType::Number,
+            vec![
+                numbers(&registry, &[1.0, 2.0, 3.0, 4.0, 5.0]),
+                numbers(&registry, &[1.0, 0.0, 1.0, 0.0, -1.0]),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            outputs,
+            vec![numbers(&registry, &[5.0, 2.0, 4.0, 1.0, 3.0])]
+        );
+    }
+
+    #[test]
+    fn should_reject_keys_of_different_length() {
+        let registry = registry();
+        let result = execute_node(
+            &registry,
+            "list_sort_by_key",
+            EDataType::Number,
+            vec![numbers(&registry, &[1.0, 2.0]), numbers(&registry, &[1.0])],
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_dedupe_keeping_first_occurrences() {
+        let registry = registry();
+        let outputs = execute_node(
+            &registry,
+            "list_dedupe",
+            EDataType::Number,
+            vec![numbers(&registry, &[3.0, 1.0, 3.0, 2.0, 1.0])],
+        )
+        .unwrap();
+
+        assert_eq!(outputs, vec![numbers(&registry, &[3.0, 1.0, 2.0])]);
+    }
+}
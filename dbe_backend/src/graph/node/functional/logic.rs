@@ -0,0 +1,265 @@
+use crate::graph::node::functional::generic::GenericValue;
+use crate::graph::node::functional::{functional_node, C};
+use crate::graph::node::NodeFactory;
+use crate::value::ENumber;
+use std::sync::Arc;
+
+pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
+    vec![
+        functional_node(
+            |_: C, a: bool, b: bool| a == b,
+            "bool_equals",
+            &["a", "b"],
+            &["a == b"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool, b: bool| a != b,
+            "bool_not_equals",
+            &["a", "b"],
+            &["a != b"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool| !a,
+            "bool_invert",
+            &["a"],
+            &["not a"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool, b: bool| a && b,
+            "bool_and",
+            &["a", "b"],
+            &["a and b"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool, b: bool| a || b,
+            "bool_or",
+            &["a", "b"],
+            &["a or b"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool, b: bool| a ^ b,
+            "bool_xor",
+            &["a", "b"],
+            &["a xor b"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool, b: bool| !(a && b),
+            "bool_nand",
+            &["a", "b"],
+            &["not (a and b)"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: bool, b: bool| !(a || b),
+            "bool_nor",
+            &["a", "b"],
+            &["not (a or b)"],
+            &["boolean"],
+        ),
+        functional_node(
+            |_: C, a: GenericValue<0>, b: GenericValue<0>| a == b,
+            "equals",
+            &["a", "b"],
+            &["a == b"],
+            &["logic.comparison"],
+        ),
+        functional_node(
+            |_: C, a: GenericValue<0>, b: GenericValue<0>| a != b,
+            "not_equals",
+            &["a", "b"],
+            &["a != b"],
+            &["logic.comparison"],
+        ),
+        functional_node(
+            |_: C, value: ENumber, min: ENumber, max: ENumber| min <= value && value <= max,
+            "num_in_range",
+            &["value", "min", "max"],
+            &["in range"],
+            &["math.comparison", "logic.comparison"],
+        ),
+        functional_node(
+            |_: C, condition: bool, if_true: GenericValue<0>, if_false: GenericValue<0>| {
+                if condition {
+                    if_true
+                } else {
+                    if_false
+                }
+            },
+            "select",
+            &["condition", "if_true", "if_false"],
+            &["value"],
+            &["logic"],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::etype::EDataType;
+    use crate::graph::editing::GraphEditingContext;
+    use crate::graph::node::commands::SnarlCommands;
+    use crate::graph::node::functional::test_utils::{execute_node, numbers};
+    use crate::graph::Graph;
+    use crate::project::docs::Docs;
+    use crate::project::side_effects::SideEffectsContext;
+    use crate::project::test_utils::test_project;
+    use crate::registry::ETypesRegistry;
+    use crate::value::EValue;
+    use egui_snarl::{InPinId, NodeId, OutPinId};
+    use emath::Pos2;
+
+    fn registry() -> ETypesRegistry {
+        test_project(&[]).registry
+    }
+
+    fn equals(registry: &ETypesRegistry, ty: EDataType, a: EValue, b: EValue) -> bool {
+        let outputs = execute_node(registry, "equals", ty, vec![a, b]).unwrap();
+        let [EValue::Boolean { value }] = outputs.as_slice() else {
+            panic!("expected a single boolean output, got {:?}", outputs);
+        };
+        *value
+    }
+
+    /// Creates the node and connects the output of the `add` node into the
+    /// given input, returning types of the node inputs and outputs afterwards
+    fn connect_number(
+        node: &str,
+        input: usize,
+    ) -> (Vec<Option<EDataType>>, Vec<Option<EDataType>>) {
+        let registry = registry();
+        let mut graph = Graph::default();
+        let mut outputs = None;
+        let mut ctx = GraphEditingContext::from_graph(
+            &mut graph,
+            &registry,
+            &Docs::Stub,
+            None,
+            SideEffectsContext::Unavailable,
+            false,
+            &[],
+            &mut outputs,
+        );
+        let add = ctx.create_node("add".into(), Pos2::ZERO).unwrap()[0];
+        let id = ctx.create_node(node.into(), Pos2::ZERO).unwrap()[0];
+
+        let from = ctx.snarl.out_pin(OutPinId {
+            node: add,
+            output: 0,
+        });
+        let to = ctx.snarl.in_pin(InPinId { node: id, input });
+        assert!(ctx.connect(&from, &to, &mut SnarlCommands::new()).unwrap());
+
+        port_types(&ctx, id)
+    }
+
+    fn port_types(
+        ctx: &GraphEditingContext,
+        id: NodeId,
+    ) -> (Vec<Option<EDataType>>, Vec<Option<EDataType>>) {
+        let context = ctx.as_node_context();
+        let node = &ctx.snarl[id];
+        let inputs = (0..node.inputs_count(context))
+            .map(|i| {
+                let input = node.try_input(context, i).unwrap();
+                input.ty.item_info().map(|info| info.ty())
+            })
+            .collect();
+        let outputs = (0..node.outputs_count(context))
+            .map(|i| {
+                let output = node.try_output(context, i).unwrap();
+                output.ty.item_info().map(|info| info.ty())
+            })
+            .collect();
+        (inputs, outputs)
+    }
+
+    #[test]
+    fn should_infer_comparison_type_from_either_input() {
+        let number = Some(EDataType::Number);
+        let boolean = Some(EDataType::Boolean);
+        for input in 0..2 {
+            assert_eq!(
+                connect_number("equals", input),
+                (vec![number, number], vec![boolean])
+            );
+        }
+    }
+
+    #[test]
+    fn should_infer_select_output_from_branch() {
+        let number = Some(EDataType::Number);
+        assert_eq!(
+            connect_number("select", 2),
+            (vec![Some(EDataType::Boolean), number, number], vec![number])
+        );
+    }
+
+    #[test]
+    fn should_compare_values_of_same_type() {
+        let registry = registry();
+        let number = EDataType::Number;
+        assert!(equals(&registry, number, 1.0.into(), 1.0.into()));
+        assert!(!equals(&registry, number, 1.0.into(), 2.0.into()));
+
+        let list = registry.list_of(number);
+        assert!(equals(
+            &registry,
+            list,
+            numbers(&registry, &[1.0, 2.0]),
+            numbers(&registry, &[1.0, 2.0])
+        ));
+        assert!(!equals(
+            &registry,
+            list,
+            numbers(&registry, &[1.0, 2.0]),
+            numbers(&registry, &[2.0, 1.0])
+        ));
+    }
+
+    #[test]
+    fn should_not_equal_values_of_different_types() {
+        let registry = registry();
+        let number = EDataType::Number;
+        assert!(!equals(
+            &registry,
+            number,
+            1.0.into(),
+            "1".to_string().into()
+        ));
+        assert!(!equals(&registry, number, 1.0.into(), true.into()));
+        assert!(!equals(&registry, number, 0.0.into(), EValue::Null));
+
+        let outputs = execute_node(
+            &registry,
+            "not_equals",
+            number,
+            vec![1.0.into(), "1".to_string().into()],
+        )
+        .unwrap();
+        assert_eq!(outputs, vec![EValue::from(true)]);
+    }
+
+    #[test]
+    fn should_include_range_bounds() {
+        let registry = registry();
+        let in_range = |value: f64| {
+            execute_node(
+                &registry,
+                "num_in_range",
+                EDataType::Number,
+                vec![value.into(), 1.0.into(), 3.0.into()],
+            )
+            .unwrap()
+        };
+        assert_eq!(in_range(1.0), vec![EValue::from(true)]);
+        assert_eq!(in_range(3.0), vec![EValue::from(true)]);
+        assert_eq!(in_range(0.5), vec![EValue::from(false)]);
+        assert_eq!(in_range(3.5), vec![EValue::from(false)]);
+    }
+}
@@ -0,0 +1,485 @@
+use crate::etype::EDataType;
+use crate::graph::node::functional::generic::{
+    GenericMap, GenericValue, Projected, TypeProjection,
+};
+use crate::graph::node::functional::{functional_node, C};
+use crate::graph::node::NodeFactory;
+use crate::registry::optional_helpers::{unwrap_optional_value, wrap_in_option};
+use crate::registry::pair_helpers::{make_pair, pair_types, unwrap_pair};
+use crate::registry::ETypesRegistry;
+use crate::value::{ENumber, EValue};
+use itertools::Itertools;
+use miette::bail;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
+    vec![
+        functional_node(
+            |_: C, map: GenericMap<0>| ENumber::from(map.0.len() as f64),
+            "map_length",
+            &["map"],
+            &["length"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, map: GenericMap<0>, key: Projected<0, MapKey>| {
+                let Some(value) = map.0.get(&key.0) else {
+                    bail!("Key {} is not present in the map", key.0);
+                };
+                Ok(Projected::<0, MapItem>(value.clone()))
+            },
+            "map_get",
+            &["map", "key"],
+            &["value"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, map: GenericMap<0>, key: Projected<0, MapKey>| {
+                Projected::<0, OptionalMapItem>(map.0.get(&key.0).cloned())
+            },
+            "map_try_get",
+            &["map", "key"],
+            &["value"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, map: GenericMap<0>, key: Projected<0, MapKey>| map.0.contains_key(&key.0),
+            "map_contains_key",
+            &["map", "key"],
+            &["contains"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C,
+             mut map: GenericMap<0>,
+             key: Projected<0, MapKey>,
+             value: Projected<0, MapItem>| {
+                map.0.insert(key.0, value.0);
+                map
+            },
+            "map_insert",
+            &["map", "key", "value"],
+            &["map"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, mut map: GenericMap<0>, key: Projected<0, MapKey>| {
+                let value = map.0.remove(&key.0);
+                (map, Projected::<0, OptionalMapItem>(value))
+            },
+            "map_remove",
+            &["map", "key"],
+            &["map", "value"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, map: GenericMap<0>| Projected::<0, MapKeys>(map.0.into_keys().collect_vec()),
+            "map_keys",
+            &["map"],
+            &["keys"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, map: GenericMap<0>| Projected::<0, MapItems>(map.0.into_values().collect_vec()),
+            "map_values",
+            &["map"],
+            &["values"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, map: GenericMap<0>| Projected::<0, MapEntries>(map.0.into_iter().collect_vec()),
+            "map_entries",
+            &["map"],
+            &["entries"],
+            &["map"],
+        ),
+        functional_node(
+            |_: C, entries: Vec<GenericValue<0>>| {
+                let mut map = BTreeMap::new();
+                for entry in &entries {
+                    let (key, value) = unwrap_pair(&entry.0)?;
+                    map.insert(key.clone(), value.clone());
+                }
+                Ok(Projected::<0, MapFromEntries>(map))
+            },
+            "map_from_entries",
+            &["entries"],
+            &["map"],
+            &["map"],
+        ),
+    ]
+}
+
+/// Returns the key and value types of the map type, or null types if the
+/// type is not a map
+fn map_types(registry: &ETypesRegistry, ty: EDataType) -> (EDataType, EDataType) {
+    let EDataType::Map { id } = ty else {
+        return (EDataType::null(), EDataType::null());
+    };
+    registry.get_map(&id).map_or_else(
+        || (EDataType::null(), EDataType::null()),
+        |data| (data.key_type, data.value_type),
+    )
+}
+
+fn generic_map_types(
+    registry: &ETypesRegistry,
+    generic_ty: Option<EDataType>,
+) -> (EDataType, EDataType) {
+    map_types(registry, generic_ty.unwrap_or_else(EDataType::null))
+}
+
+fn list_values(value: &EValue) -> miette::Result<&Vec<EValue>> {
+    let EValue::List { values, id: _ } = value else {
+        bail!("Expected list, got {:?}", value);
+    };
+    Ok(values)
+}
+
+/// Key of the map
+struct MapKey;
+
+impl TypeProjection for MapKey {
+    type Value = EValue;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        map_types(registry, ty).0
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        Ok(value.clone())
+    }
+
+    fn into_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        Ok(value)
+    }
+}
+
+/// Value of the map
+struct MapItem;
+
+impl TypeProjection for MapItem {
+    type Value = EValue;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        map_types(registry, ty).1
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        Ok(value.clone())
+    }
+
+    fn into_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        Ok(value)
+    }
+}
+
+/// Optional value of the map
+struct OptionalMapItem;
+
+impl TypeProjection for OptionalMapItem {
+    type Value = Option<EValue>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        EDataType::Object {
+            ident: registry.option_id_of(map_types(registry, ty).1),
+        }
+    }
+
+    fn try_from_evalue(
+        registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        Ok(unwrap_optional_value(registry, value)?.cloned())
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let (_, value_ty) = generic_map_types(registry, generic_ty);
+        Ok(wrap_in_option(registry, value_ty, value))
+    }
+}
+
+/// List of the map keys
+struct MapKeys;
+
+impl TypeProjection for MapKeys {
+    type Value = Vec<EValue>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        registry.list_of(map_types(registry, ty).0)
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        list_values(value).cloned()
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let (key_ty, _) = generic_map_types(registry, generic_ty);
+        Ok(EValue::List {
+            id: registry.list_id_of(key_ty),
+            values: value,
+        })
+    }
+}
+
+/// List of the map values
+struct MapItems;
+
+impl TypeProjection for MapItems {
+    type Value = Vec<EValue>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        registry.list_of(map_types(registry, ty).1)
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        list_values(value).cloned()
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let (_, value_ty) = generic_map_types(registry, generic_ty);
+        Ok(EValue::List {
+            id: registry.list_id_of(value_ty),
+            values: value,
+        })
+    }
+}
+
+/// List of the map entries, as key-value pairs
+struct MapEntries;
+
+impl TypeProjection for MapEntries {
+    type Value = Vec<(EValue, EValue)>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        let (key_ty, value_ty) = map_types(registry, ty);
+        registry.list_of(registry.pair_of(key_ty, value_ty))
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        list_values(value)?
+            .iter()
+            .map(|entry| {
+                let (key, value) = unwrap_pair(entry)?;
+                Ok((key.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let (key_ty, value_ty) = generic_map_types(registry, generic_ty);
+        Ok(EValue::List {
+            id: registry.list_id_of(registry.pair_of(key_ty, value_ty)),
+            values: value
+                .into_iter()
+                .map(|(key, value)| make_pair(registry, key_ty, value_ty, key, value))
+                .collect(),
+        })
+    }
+}
+
+/// Map built from the key-value pairs, with the generic type being the pair
+/// type
+struct MapFromEntries;
+
+impl MapFromEntries {
+    fn map_type(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        let (key_ty, value_ty) =
+            pair_types(registry, ty).unwrap_or((EDataType::null(), EDataType::null()));
+        registry.map_of(key_ty, value_ty)
+    }
+}
+
+impl TypeProjection for MapFromEntries {
+    type Value = BTreeMap<EValue, EValue>;
+
+    fn project(registry: &ETypesRegistry, ty: EDataType) -> EDataType {
+        Self::map_type(registry, ty)
+    }
+
+    fn try_from_evalue(
+        _registry: &ETypesRegistry,
+        _generic_ty: Option<EDataType>,
+        value: &EValue,
+    ) -> miette::Result<Self::Value> {
+        let EValue::Map { values, id: _ } = value else {
+            bail!("Expected map, got {:?}", value);
+        };
+        Ok(values.clone())
+    }
+
+    fn into_evalue(
+        registry: &ETypesRegistry,
+        generic_ty: Option<EDataType>,
+        value: Self::Value,
+    ) -> miette::Result<EValue> {
+        let ty = Self::map_type(registry, generic_ty.unwrap_or_else(EDataType::null));
+        let EDataType::Map { id } = ty else {
+            unreachable!("map_of always returns a map type");
+        };
+        Ok(EValue::Map { id, values: value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::etype::EDataType;
+    use crate::graph::node::functional::test_utils::{execute_node, list};
+    use crate::project::test_utils::test_project;
+    use crate::registry::optional_helpers::wrap_in_option;
+    use crate::registry::pair_helpers::make_pair;
+    use crate::registry::ETypesRegistry;
+    use crate::value::EValue;
+
+    fn registry() -> ETypesRegistry {
+        test_project(&[]).registry
+    }
+
+    fn key(key: &str) -> EValue {
+        EValue::from(key.to_string())
+    }
+
+    /// Map of string keys to numbers
+    fn map(registry: &ETypesRegistry, entries: &[(&str, f64)]) -> EValue {
+        let EDataType::Map { id } = map_ty(registry) else {
+            unreachable!("map_of always returns a map type");
+        };
+        EValue::Map {
+            id,
+            values: entries
+                .iter()
+                .map(|(k, v)| (key(k), EValue::from(*v)))
+                .collect(),
+        }
+    }
+
+    fn map_ty(registry: &ETypesRegistry) -> EDataType {
+        registry.map_of(EDataType::String, EDataType::Number)
+    }
+
+    #[test]
+    fn should_keep_last_value_of_duplicate_keys() {
+        let registry = registry();
+        let pair_ty = registry.pair_of(EDataType::String, EDataType::Number);
+        let entries = [("a", 1.0), ("b", 2.0), ("a", 3.0)]
+            .into_iter()
+            .map(|(k, v)| {
+                make_pair(
+                    &registry,
+                    EDataType::String,
+                    EDataType::Number,
+                    key(k),
+                    v.into(),
+                )
+            })
+            .collect();
+
+        let outputs = execute_node(
+            &registry,
+            "map_from_entries",
+            pair_ty,
+            vec![list(&registry, pair_ty, entries)],
+        )
+        .unwrap();
+
+        assert_eq!(outputs, vec![map(&registry, &[("a", 3.0), ("b", 2.0)])]);
+    }
+
+    #[test]
+    fn should_overwrite_existing_key_on_insert() {
+        let registry = registry();
+        let outputs = execute_node(
+            &registry,
+            "map_insert",
+            map_ty(&registry),
+            vec![map(&registry, &[("a", 1.0)]), key("a"), 2.0.into()],
+        )
+        .unwrap();
+
+        assert_eq!(outputs, vec![map(&registry, &[("a", 2.0)])]);
+    }
+
+    #[test]
+    fn should_handle_missing_keys() {
+        let registry = registry();
+        let input = map(&registry, &[("a", 1.0)]);
+
+        let get = execute_node(
+            &registry,
+            "map_get",
+            map_ty(&registry),
+            vec![input.clone(), key("b")],
+        );
+        assert!(get.is_err());
+
+        let try_get = execute_node(
+            &registry,
+            "map_try_get",
+            map_ty(&registry),
+            vec![input.clone(), key("b")],
+        )
+        .unwrap();
+        assert_eq!(
+            try_get,
+            vec![wrap_in_option(&registry, EDataType::Number, None)]
+        );
+
+        let remove = execute_node(
+            &registry,
+            "map_remove",
+            map_ty(&registry),
+            vec![input.clone(), key("b")],
+        )
+        .unwrap();
+        assert_eq!(
+            remove,
+            vec![input, wrap_in_option(&registry, EDataType::Number, None)]
+        );
+    }
+}
@@ -223,5 +223,12 @@ pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
             &["a == b"],
             &["math.comparison"],
         ),
+        functional_node(
+            |_: C, a: ENumber, b: ENumber| a != b,
+            "num_not_equals",
+            &["a", "b"],
+            &["a != b"],
+            &["math.comparison"],
+        ),
     ]
 }
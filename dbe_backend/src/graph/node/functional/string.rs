@@ -0,0 +1,376 @@
+use crate::graph::node::functional::values::default::DefaultENumber;
+use crate::graph::node::functional::values::WithDefault;
+use crate::graph::node::functional::{functional_node, C};
+use crate::graph::node::NodeFactory;
+use crate::value::ENumber;
+use itertools::Itertools;
+use miette::{bail, Context, IntoDiagnostic};
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
+use utils::map::DashMap;
+
+/// Maximum amount of compiled patterns kept in [REGEXES]
+const MAX_CACHED_REGEXES: usize = 256;
+
+/// Compiled patterns, keyed by the pattern source, so regex nodes don't
+/// recompile them on every evaluation
+static REGEXES: LazyLock<DashMap<String, Regex>> = LazyLock::new(Default::default);
+
+pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
+    vec![
+        functional_node(
+            |_: C, a: String, b: String| a + &b,
+            "concat",
+            &["a", "b"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, a: String| a.to_lowercase(),
+            "lower_case",
+            &["a"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, a: String| a.to_uppercase(),
+            "upper_case",
+            &["a"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, a: String| ENumber::from(a.chars().count() as f64),
+            "string_length",
+            &["a"],
+            &["length"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, separator: String| {
+                if separator.is_empty() {
+                    return string.chars().map(String::from).collect_vec();
+                }
+                string.split(&separator).map(str::to_string).collect_vec()
+            },
+            "string_split",
+            &["string", "separator"],
+            &["parts"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, parts: Vec<String>, separator: String| parts.join(&separator),
+            "string_join",
+            &["parts", "separator"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, from: String, to: String| {
+                if from.is_empty() {
+                    return string;
+                }
+                string.replace(&from, &to)
+            },
+            "string_replace",
+            &["string", "from", "to"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, start: ENumber, length: ENumber| {
+                let start = index_arg(start, "start")?;
+                let length = index_arg(length, "length")?;
+                Ok(string.chars().skip(start).take(length).collect::<String>())
+            },
+            "substring",
+            &["string", "start", "length"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String| string.trim().to_string(),
+            "string_trim",
+            &["string"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String| string.trim_start().to_string(),
+            "string_trim_start",
+            &["string"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String| string.trim_end().to_string(),
+            "string_trim_end",
+            &["string"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, prefix: String| string.starts_with(&prefix),
+            "string_starts_with",
+            &["string", "prefix"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, suffix: String| string.ends_with(&suffix),
+            "string_ends_with",
+            &["string", "suffix"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, substring: String| string.contains(&substring),
+            "string_contains",
+            &["string", "substring"],
+            &["result"],
+            &["string"],
+        ),
+        functional_node(
+            |_: C, string: String, pattern: String| Ok(compile_regex(&pattern)?.is_match(&string)),
+            "regex_is_match",
+            &["string", "pattern"],
+            &["matches"],
+            &["string.regex"],
+        ),
+        functional_node(
+            |_: C, string: String, pattern: String| {
+                let regex = compile_regex(&pattern)?;
+                let Some(captures) = regex.captures(&string) else {
+                    return Ok((false, vec![]));
+                };
+                let groups = captures
+                    .iter()
+                    .map(|group| group.map(|m| m.as_str().to_string()).unwrap_or_default())
+                    .collect_vec();
+                Ok((true, groups))
+            },
+            "regex_captures",
+            &["string", "pattern"],
+            &["matches", "groups"],
+            &["string.regex"],
+        ),
+        functional_node(
+            |_: C, string: String, pattern: String| {
+                let regex = compile_regex(&pattern)?;
+                Ok(regex
+                    .find_iter(&string)
+                    .map(|m| m.as_str().to_string())
+                    .collect_vec())
+            },
+            "regex_find_all",
+            &["string", "pattern"],
+            &["matches"],
+            &["string.regex"],
+        ),
+        functional_node(
+            |_: C, string: String, pattern: String, replacement: String| {
+                let regex = compile_regex(&pattern)?;
+                Ok(regex
+                    .replace_all(&string, replacement.as_str())
+                    .into_owned())
+            },
+            "regex_replace",
+            &["string", "pattern", "replacement"],
+            &["result"],
+            &["string.regex"],
+        ),
+        functional_node(
+            |_: C, string: String| string.trim().parse::<f64>().ok().map(ENumber::from),
+            "parse_number",
+            &["string"],
+            &["number"],
+            &["string", "math"],
+        ),
+        functional_node(
+            |_: C, number: ENumber, precision: WithDefault<ENumber, DefaultENumber<2>>| {
+                let precision = index_arg(precision.0, "precision")?;
+                Ok(format!("{:.*}", precision, number.0))
+            },
+            "number_to_string",
+            &["number", "precision"],
+            &["result"],
+            &["string", "math"],
+        ),
+    ]
+}
+
+fn compile_regex(pattern: &str) -> miette::Result<Regex> {
+    if let Some(regex) = REGEXES.get(pattern) {
+        return Ok(regex.clone());
+    }
+
+    let regex = Regex::new(pattern)
+        .into_diagnostic()
+        .with_context(|| format!("invalid regex pattern `{}`", pattern))?;
+    // Patterns can be built at runtime, so the cache is bounded
+    if REGEXES.len() >= MAX_CACHED_REGEXES {
+        REGEXES.clear();
+    }
+    REGEXES.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+/// Converts the numeric input into a non-negative integer
+fn index_arg(value: ENumber, name: &str) -> miette::Result<usize> {
+    if !value.is_finite() || value.0 < 0.0 || value.fract() != 0.0 {
+        bail!("{} must be a non-negative integer, got {}", name, value);
+    }
+    Ok(value.0 as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::etype::EDataType;
+    use crate::graph::node::functional::test_utils::{execute_node, list};
+    use crate::project::test_utils::test_project;
+    use crate::registry::optional_helpers::wrap_in_option;
+    use crate::registry::ETypesRegistry;
+    use crate::value::EValue;
+
+    fn registry() -> ETypesRegistry {
+        test_project(&[]).registry
+    }
+
+    fn string(value: &str) -> EValue {
+        EValue::from(value.to_string())
+    }
+
+    fn strings(registry: &ETypesRegistry, values: &[&str]) -> EValue {
+        list(
+            registry,
+            EDataType::String,
+            values.iter().map(|value| string(value)).collect(),
+        )
+    }
+
+    fn execute(
+        registry: &ETypesRegistry,
+        id: &str,
+        inputs: Vec<EValue>,
+    ) -> miette::Result<Vec<EValue>> {
+        execute_node(registry, id, EDataType::String, inputs)
+    }
+
+    #[test]
+    fn should_capture_regex_groups() {
+        let registry = registry();
+        let captures = |value: &str, pattern: &str| {
+            execute(
+                &registry,
+                "regex_captures",
+                vec![string(value), string(pattern)],
+            )
+        };
+
+        assert_eq!(
+            captures("item_42", r"(\w+?)_(\d+)").unwrap(),
+            vec![true.into(), strings(&registry, &["item_42", "item", "42"])]
+        );
+        // Groups that did not participate in the match are empty
+        assert_eq!(
+            captures("b", "(a)|(b)").unwrap(),
+            vec![true.into(), strings(&registry, &["b", "", "b"])]
+        );
+        assert_eq!(
+            captures("item", r"(\d+)").unwrap(),
+            vec![false.into(), strings(&registry, &[])]
+        );
+        assert_eq!(
+            captures("item", "(").unwrap_err().to_string(),
+            "invalid regex pattern `(`"
+        );
+    }
+
+    #[test]
+    fn should_reuse_compiled_patterns() {
+        let registry = registry();
+        let is_match = |value: &str| {
+            execute(
+                &registry,
+                "regex_is_match",
+                vec![string(value), string(r"^\d+$")],
+            )
+            .unwrap()
+        };
+
+        assert_eq!(is_match("42"), vec![true.into()]);
+        assert!(super::REGEXES.contains_key(r"^\d+$"));
+        assert_eq!(is_match("x42"), vec![false.into()]);
+    }
+
+    #[test]
+    fn should_count_length_in_characters() {
+        let registry = registry();
+        let length = |value: &str| execute(&registry, "string_length", vec![string(value)]);
+
+        assert_eq!(length("hello").unwrap(), vec![5.0.into()]);
+        // Same as the indices of `substring`
+        assert_eq!(length("héllo wörld").unwrap(), vec![11.0.into()]);
+        assert_eq!(length("日本語").unwrap(), vec![3.0.into()]);
+    }
+
+    #[test]
+    fn should_take_substring_by_characters() {
+        let registry = registry();
+        let substring = |value: &str, start: f64, length: f64| {
+            execute(
+                &registry,
+                "substring",
+                vec![string(value), start.into(), length.into()],
+            )
+        };
+
+        assert_eq!(
+            substring("héllo wörld", 1.0, 4.0).unwrap(),
+            vec![string("éllo")]
+        );
+        assert_eq!(
+            substring("日本語テキスト", 2.0, 10.0).unwrap(),
+            vec![string("語テキスト")]
+        );
+        assert_eq!(substring("日本", 5.0, 1.0).unwrap(), vec![string("")]);
+        assert!(substring("日本", -1.0, 1.0).is_err());
+        assert!(substring("日本", 0.0, 1.5).is_err());
+    }
+
+    #[test]
+    fn should_parse_numbers() {
+        let registry = registry();
+        let parse = |value: &str| {
+            execute(&registry, "parse_number", vec![string(value)])
+                .unwrap()
+                .remove(0)
+        };
+        let number = |value: Option<f64>| {
+            wrap_in_option(&registry, EDataType::Number, value.map(EValue::from))
+        };
+
+        assert_eq!(parse(" 3.5 "), number(Some(3.5)));
+        assert_eq!(parse("-1e3"), number(Some(-1000.0)));
+        assert_eq!(parse("3.5abc"), number(None));
+        assert_eq!(parse(""), number(None));
+    }
+
+    #[test]
+    fn should_format_numbers_with_precision() {
+        let registry = registry();
+        let format = |value: f64, precision: f64| {
+            execute(
+                &registry,
+                "number_to_string",
+                vec![value.into(), precision.into()],
+            )
+        };
+
+        assert_eq!(format(1.23456, 2.0).unwrap(), vec![string("1.23")]);
+        assert_eq!(format(2.6, 0.0).unwrap(), vec![string("3")]);
+        assert_eq!(format(-1.5, 3.0).unwrap(), vec![string("-1.500")]);
+        assert!(format(1.0, -1.0).is_err());
+        assert!(format(1.0, 0.5).is_err());
+    }
+}
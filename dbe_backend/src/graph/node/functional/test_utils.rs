@@ -0,0 +1,71 @@
+use crate::etype::EDataType;
+use crate::graph::node::extras::ExecutionExtras;
+use crate::graph::node::functional::generic::MAX_FIELDS;
+use crate::graph::node::{get_node_factory, NodeContext};
+use crate::graph::Graph;
+use crate::project::docs::Docs;
+use crate::project::side_effects::SideEffectsContext;
+use crate::registry::ETypesRegistry;
+use crate::value::EValue;
+use miette::IntoDiagnostic;
+use utils::map::HashMap;
+
+/// Executes the functional node standalone, with all of its generic fields
+/// set to the given type, and returns its outputs
+pub fn execute_node(
+    registry: &ETypesRegistry,
+    id: &str,
+    generic_ty: EDataType,
+    inputs: Vec<EValue>,
+) -> miette::Result<Vec<EValue>> {
+    let mut node = get_node_factory(&id.into())
+        .unwrap_or_else(|| panic!("node `{}` is not registered", id))
+        .create();
+    let types = vec![Some(generic_ty); MAX_FIELDS];
+    node.parse_json(
+        registry,
+        &mut serde_json::to_value((&types, &types)).into_diagnostic()?,
+    )?;
+
+    let graph = Graph::default();
+    let context = NodeContext {
+        registry,
+        docs: &Docs::Stub,
+        inputs: graph.inputs(),
+        outputs: graph.outputs(),
+        regions: graph.regions(),
+        region_graph: graph.region_graph(),
+        graphs: None,
+    };
+    let mut group_outputs = None;
+    let mut regional_data = HashMap::default();
+    let mut extras = ExecutionExtras::new(
+        false,
+        &[],
+        &mut group_outputs,
+        &mut regional_data,
+        SideEffectsContext::Unavailable,
+        None,
+    );
+
+    let mut outputs = vec![];
+    node.execute(context, &inputs, &mut outputs, &mut extras)?;
+    Ok(outputs)
+}
+
+/// List of the given values with the given item type
+pub fn list(registry: &ETypesRegistry, ty: EDataType, values: Vec<EValue>) -> EValue {
+    EValue::List {
+        id: registry.list_id_of(ty),
+        values,
+    }
+}
+
+/// List of the given numbers
+pub fn numbers(registry: &ETypesRegistry, values: &[f64]) -> EValue {
+    list(
+        registry,
+        EDataType::Number,
+        values.iter().map(EValue::from).collect(),
+    )
+}
@@ -4,6 +4,11 @@ use crate::graph::node::NodeFactory;
 use miette::bail;
 use std::sync::Arc;
 
+pub(crate) const SET_TRANSIENT_VALUE: &str = "set_transistent_value";
+pub(crate) const HAS_TRANSIENT_VALUE: &str = "has_transistent_value";
+pub(crate) const TRY_GET_TRANSIENT_VALUE: &str = "try_get_transistent_value";
+pub(crate) const GET_TRANSIENT_VALUE: &str = "get_transistent_value";
+
 pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
     vec![
         side_effects_node(
@@ -14,7 +19,7 @@ pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
 
                 Ok(())
             },
-            "set_transistent_value",
+            SET_TRANSIENT_VALUE,
             &["key", "value"],
             &[],
             &["utility.storage"],
@@ -25,7 +30,7 @@ pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
 
                 Ok(result)
             },
-            "has_transistent_value",
+            HAS_TRANSIENT_VALUE,
             &["key"],
             &["result"],
             &["utility.storage"],
@@ -36,7 +41,7 @@ pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
 
                 Ok(mappings.map(|v| AnyEValue(v.clone())))
             },
-            "try_get_transistent_value",
+            TRY_GET_TRANSIENT_VALUE,
             &["key"],
             &["value"],
             &["utility.storage"],
@@ -51,7 +56,7 @@ pub(super) fn nodes() -> Vec<Arc<dyn NodeFactory>> {
 
                 Ok(AnyEValue(mappings.clone()))
             },
-            "get_transistent_value",
+            GET_TRANSIENT_VALUE,
             &["key"],
             &["value"],
             &["utility.storage"],
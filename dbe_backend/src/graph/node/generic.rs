@@ -26,6 +26,7 @@ use ustr::Ustr;
 
 pub mod destructuring;
 pub mod macros;
+pub mod zip;
 
 #[derive(Debug)]
 pub enum GenericNodeField<'a> {
@@ -37,16 +38,28 @@ pub enum GenericNodeField<'a> {
     Option(&'a Option<EDataType>),
     /// A type is a struct id.
     Object(&'a Option<ETypeId>),
+    /// A type is a map type.
+    Map(&'a Option<EDataType>),
+    /// A type is derived from the generic type using the given function.
+    ///
+    /// Derived fields can't be specified by connections, so some other field
+    /// must specify the generic type first.
+    Derived(&'a Option<EDataType>, DeriveTypeFn),
     /// A type is fixed.
     Fixed(EDataType),
 }
 
+/// Function that derives the field type from the generic type
+pub type DeriveTypeFn = fn(&ETypesRegistry, EDataType) -> EDataType;
+
 #[derive(Debug)]
 pub enum GenericNodeFieldMut<'a> {
     List(&'a mut Option<EDataType>),
     Value(&'a mut Option<EDataType>),
     Option(&'a mut Option<EDataType>),
     Object(&'a mut Option<ETypeId>),
+    Map(&'a mut Option<EDataType>),
+    Derived(&'a mut Option<EDataType>, DeriveTypeFn),
     Fixed(EDataType),
 }
 
@@ -57,6 +70,8 @@ impl GenericNodeFieldMut<'_> {
             GenericNodeFieldMut::Value(ty) => GenericNodeField::Value(ty),
             GenericNodeFieldMut::Option(ty) => GenericNodeField::Option(ty),
             GenericNodeFieldMut::Object(id) => GenericNodeField::Object(id),
+            GenericNodeFieldMut::Map(ty) => GenericNodeField::Map(ty),
+            GenericNodeFieldMut::Derived(ty, derive) => GenericNodeField::Derived(ty, *derive),
             GenericNodeFieldMut::Fixed(ty) => GenericNodeField::Fixed(*ty),
         }
     }
@@ -123,6 +138,22 @@ impl GenericNodeFieldMut<'_> {
 
                 **id = Some(ty);
             }
+            GenericNodeFieldMut::Map(ty) => {
+                if ty.is_some() {
+                    bail!("Map type already set");
+                }
+
+                let incoming = incoming.ty();
+                if !incoming.is_map() {
+                    return Ok(false);
+                }
+
+                **ty = Some(incoming);
+            }
+            GenericNodeFieldMut::Derived(..) => {
+                // derived types are never specified by connections
+                return Ok(false);
+            }
             GenericNodeFieldMut::Fixed(_) => {
                 bail!("Fixed type cannot be changed");
             }
@@ -135,7 +166,9 @@ impl GenericNodeFieldMut<'_> {
         match self {
             GenericNodeFieldMut::List(ty)
             | GenericNodeFieldMut::Value(ty)
-            | GenericNodeFieldMut::Option(ty) => {
+            | GenericNodeFieldMut::Option(ty)
+            | GenericNodeFieldMut::Map(ty)
+            | GenericNodeFieldMut::Derived(ty, _) => {
                 **ty = incoming;
             }
             GenericNodeFieldMut::Object(ident) => {
@@ -159,7 +192,9 @@ impl GenericNodeField<'_> {
         match self {
             GenericNodeField::List(ty)
             | GenericNodeField::Value(ty)
-            | GenericNodeField::Option(ty) => ty.is_some(),
+            | GenericNodeField::Option(ty)
+            | GenericNodeField::Map(ty)
+            | GenericNodeField::Derived(ty, _) => ty.is_some(),
             GenericNodeField::Object(ident) => ident.is_some(),
             GenericNodeField::Fixed(_) => true,
         }
@@ -175,6 +210,12 @@ impl GenericNodeField<'_> {
             GenericNodeField::Object(ident) => ident
                 .map(|ident| EDataType::Object { ident })
                 .unwrap_or_else(EDataType::null),
+            GenericNodeField::Map(ty) => {
+                ty.unwrap_or_else(|| registry.map_of(EDataType::null(), EDataType::null()))
+            }
+            GenericNodeField::Derived(ty, derive) => {
+                derive(registry, ty.unwrap_or_else(EDataType::null))
+            }
             GenericNodeField::Fixed(ty) => *ty,
         }
     }
@@ -218,6 +259,18 @@ impl GenericNodeField<'_> {
                 }
                 Ok(incoming.ty().is_object())
             }
+            GenericNodeField::Map(ty) => {
+                if ty.is_some() {
+                    bail!("Map type already set");
+                }
+                Ok(incoming.ty().is_map())
+            }
+            GenericNodeField::Derived(ty, _) => {
+                if ty.is_some() {
+                    bail!("Derived type already set");
+                }
+                Ok(false)
+            }
             GenericNodeField::Fixed(_) => {
                 bail!("Fixed type cannot be changed");
             }
@@ -250,7 +303,9 @@ impl GenericNodeField<'_> {
         match self {
             GenericNodeField::List(ty)
             | GenericNodeField::Value(ty)
-            | GenericNodeField::Option(ty) => **ty,
+            | GenericNodeField::Option(ty)
+            | GenericNodeField::Map(ty)
+            | GenericNodeField::Derived(ty, _) => **ty,
             GenericNodeField::Fixed(ty) => Some(*ty),
             GenericNodeField::Object(ident) => ident.map(|ident| EDataType::Object { ident }),
         }
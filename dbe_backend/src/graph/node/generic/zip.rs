@@ -0,0 +1,114 @@
+use crate::etype::EDataType;
+use crate::graph::node::extras::ExecutionExtras;
+use crate::graph::node::generic::{GenericNode, GenericNodeField, GenericNodeFieldMut};
+use crate::graph::node::{ExecutionResult, Node, NodeContext, NodeFactory};
+use crate::registry::pair_helpers::make_pair;
+use crate::registry::ETypesRegistry;
+use crate::value::EValue;
+use itertools::Itertools;
+use miette::bail;
+use ustr::Ustr;
+
+/// Combines two lists into a list of pairs
+///
+/// This node is not functional, since the output type depends on both input
+/// types at once
+#[derive(Debug, Clone, Hash, Default)]
+pub struct ListZipNode {
+    first: Option<EDataType>,
+    second: Option<EDataType>,
+}
+
+impl ListZipNode {
+    fn output_ty(&self, registry: &ETypesRegistry) -> EDataType {
+        registry.list_of(self.pair_ty(registry))
+    }
+
+    fn pair_ty(&self, registry: &ETypesRegistry) -> EDataType {
+        registry.pair_of(
+            self.first.unwrap_or_else(EDataType::null),
+            self.second.unwrap_or_else(EDataType::null),
+        )
+    }
+}
+
+impl GenericNode for ListZipNode {
+    fn id(&self) -> Ustr {
+        ListZipNodeFactory.id()
+    }
+
+    fn input_names(&self) -> &[&str] {
+        &["first", "second"]
+    }
+
+    fn output_names(&self) -> &[&str] {
+        &["pairs"]
+    }
+
+    fn inputs(&self, _registry: &ETypesRegistry) -> impl AsRef<[GenericNodeField]> {
+        [
+            GenericNodeField::List(&self.first),
+            GenericNodeField::List(&self.second),
+        ]
+    }
+
+    fn outputs(&self, registry: &ETypesRegistry) -> impl AsRef<[GenericNodeField]> {
+        [GenericNodeField::Fixed(self.output_ty(registry))]
+    }
+
+    fn inputs_mut(&mut self, _registry: &ETypesRegistry) -> impl AsMut<[GenericNodeFieldMut]> {
+        [
+            GenericNodeFieldMut::List(&mut self.first),
+            GenericNodeFieldMut::List(&mut self.second),
+        ]
+    }
+
+    fn outputs_mut(&mut self, registry: &ETypesRegistry) -> impl AsMut<[GenericNodeFieldMut]> {
+        [GenericNodeFieldMut::Fixed(self.output_ty(registry))]
+    }
+
+    fn execute(
+        &self,
+        context: NodeContext,
+        inputs: &[EValue],
+        outputs: &mut Vec<EValue>,
+        _variables: &mut ExecutionExtras,
+    ) -> miette::Result<ExecutionResult> {
+        let [EValue::List { values: first, .. }, EValue::List { values: second, .. }] = inputs
+        else {
+            bail!("Expected two lists, got {:?}", inputs);
+        };
+
+        let first_ty = self.first.unwrap_or_else(EDataType::null);
+        let second_ty = self.second.unwrap_or_else(EDataType::null);
+        let pairs = first
+            .iter()
+            .zip(second)
+            .map(|(a, b)| make_pair(context.registry, first_ty, second_ty, a.clone(), b.clone()))
+            .collect_vec();
+
+        outputs.push(EValue::List {
+            id: context.registry.list_id_of(self.pair_ty(context.registry)),
+            values: pairs,
+        });
+
+        Ok(ExecutionResult::Done)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListZipNodeFactory;
+
+impl NodeFactory for ListZipNodeFactory {
+    fn id(&self) -> Ustr {
+        "list_zip".into()
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        &["list"]
+    }
+
+    fn create(&self) -> Box<dyn Node> {
+        Box::new(ListZipNode::default())
+    }
+}
@@ -168,6 +168,9 @@ impl Node for SubgraphNode {
                 &graph_in,
                 &mut graph_out,
             );
+            if let Some(profile) = variables.profile.as_deref_mut() {
+                ctx = ctx.with_profile(profile);
+            }
 
             ctx.full_eval(side_effects_available)?;
 
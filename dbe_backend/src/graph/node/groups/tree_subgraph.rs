@@ -48,6 +48,19 @@ impl TreeSubgraph {
         }
     }
 
+    /// The graph that holds the nodes of this tree
+    pub fn inner_graph(&self) -> &Graph {
+        self.tree.graph()
+    }
+
+    /// Mutable access to the graph that holds the nodes of this tree
+    ///
+    /// Only meant for editing inline values, changing the graph structure
+    /// will desync it from the tree
+    pub(crate) fn inner_graph_mut(&mut self) -> &mut Graph {
+        self.tree.graph_mut()
+    }
+
     pub fn create_input(
         &mut self,
         context: NodeContext,
@@ -488,6 +501,14 @@ mod tree {
             }
         }
 
+        pub fn graph(&self) -> &Graph {
+            &self.graph
+        }
+
+        pub fn graph_mut(&mut self) -> &mut Graph {
+            &mut self.graph
+        }
+
         pub fn group_inputs(&self) -> &[GraphInput] {
             self.graph.inputs.as_slice()
         }
@@ -689,6 +710,9 @@ mod tree {
                 inputs,
                 &mut sub_outputs,
             );
+            if let Some(profile) = variables.profile.as_deref_mut() {
+                ctx = ctx.with_profile(profile);
+            }
 
             ctx.full_eval(side_effects_available)
                 .context("failed to execute tree_subgraph")?;
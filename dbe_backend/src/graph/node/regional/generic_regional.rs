@@ -1,4 +1,5 @@
 pub mod conditional;
 pub mod construct;
+pub mod fold;
 pub mod for_each;
 pub mod for_each_dbeitem;
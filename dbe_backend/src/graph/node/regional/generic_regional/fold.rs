@@ -0,0 +1,295 @@
+use crate::etype::EDataType;
+use crate::graph::node::commands::{SnarlCommand, SnarlCommands};
+use crate::graph::node::extras::ExecutionExtras;
+use crate::graph::node::generic::macros::generic_node_io;
+use crate::graph::node::regional::{NodeWithVariables, RegionIONode, RegionIoData, RegionIoKind};
+use crate::graph::node::stateful::generic::GenericStatefulNode;
+use crate::graph::node::variables::remember_variables;
+use crate::graph::node::{ExecutionResult, NodeContext};
+use crate::graph::region::{get_region_execution_data, RegionExecutionData};
+use crate::registry::ETypesRegistry;
+use crate::value::{ENumber, EValue};
+use egui_snarl::NodeId;
+use miette::bail;
+use ustr::Ustr;
+
+/// Iterates over the list, carrying the accumulator value from one iteration
+/// to the next
+#[derive(Debug, Clone, Hash)]
+pub struct ListFoldNode {
+    input_ty: Option<EDataType>,
+    accumulator_ty: Option<EDataType>,
+}
+
+impl NodeWithVariables for ListFoldNode {
+    type State<'a> = &'a RegionIoData;
+}
+
+impl GenericStatefulNode for ListFoldNode {
+    type State<'a> = &'a RegionIoData;
+
+    fn id() -> Ustr {
+        "fold".into()
+    }
+
+    fn input_names(&self, data: &Self::State<'_>) -> &[&str] {
+        match data.kind {
+            RegionIoKind::Start => &["values", "initial"],
+            RegionIoKind::End => &["accumulator"],
+        }
+    }
+
+    fn output_names(&self, data: &Self::State<'_>) -> &[&str] {
+        match data.kind {
+            RegionIoKind::Start => &["value", "index", "length", "accumulator"],
+            RegionIoKind::End => &["result"],
+        }
+    }
+
+    generic_node_io! {
+        inputs {
+            Start => [2; List(self.input_ty), Value(self.accumulator_ty)],
+            End => [2; Value(self.accumulator_ty)]
+        }
+    }
+
+    generic_node_io! {
+        outputs {
+            Start => [4; Value(self.input_ty), Fixed(EDataType::Number), Fixed(EDataType::Number), Value(self.accumulator_ty)],
+            End => [4; Value(self.accumulator_ty)]
+        }
+    }
+
+    fn types_changed(
+        &mut self,
+        context: NodeContext,
+        region_data: &RegionIoData,
+        _node: NodeId,
+        commands: &mut SnarlCommands,
+    ) {
+        let Some(ty) = self.accumulator_ty else {
+            return;
+        };
+
+        let Ok(data) = context.region_graph.try_as_data() else {
+            self.accumulator_ty = None;
+            return;
+        };
+
+        let other_id = if region_data.is_start() {
+            data.region_data(&region_data.region).end_node
+        } else {
+            data.region_data(&region_data.region).start_node
+        };
+
+        commands.push(SnarlCommand::Custom {
+            cb: Box::new(move |ctx, _| {
+                let other = ctx.snarl[other_id]
+                    .downcast_mut::<RegionIONode<Self>>()
+                    .unwrap();
+                other.node.accumulator_ty = Some(ty);
+                Ok(())
+            }),
+        });
+    }
+
+    fn should_execute(
+        &self,
+        _context: NodeContext,
+        region: &RegionIoData,
+        variables: &mut ExecutionExtras,
+    ) -> miette::Result<bool> {
+        let state = get_region_execution_data::<FoldNodeState>(region.region, variables)?;
+
+        Ok(state.index < state.length)
+    }
+
+    fn execute(
+        &self,
+        context: NodeContext,
+        region: &RegionIoData,
+        inputs: &[EValue],
+        outputs: &mut Vec<EValue>,
+        variables: &mut ExecutionExtras,
+    ) -> miette::Result<ExecutionResult> {
+        if region.is_start() {
+            assert!(inputs.len() > 2);
+            let EValue::List { values, .. } = &inputs[0] else {
+                bail!("Expected list input, got: {}", inputs[0].ty().name());
+            };
+            let state = variables.get_or_init_region_data(region.region, |_| FoldNodeState {
+                index: 0,
+                length: values.len(),
+                accumulator: inputs[1].clone(),
+                values: None,
+            });
+
+            outputs.clear();
+            if let Some(value) = values.get(state.index) {
+                outputs.push(value.clone());
+            } else {
+                outputs.push(
+                    self.input_ty
+                        .unwrap_or_else(EDataType::null)
+                        .default_value(context.registry)
+                        .into_owned(),
+                );
+            }
+            outputs.push(ENumber::from(state.index as f64).into());
+            outputs.push(ENumber::from(state.length as f64).into());
+            outputs.push(state.accumulator.clone());
+
+            remember_variables(&mut state.values, &inputs[2..], outputs);
+
+            Ok(ExecutionResult::Done)
+        } else {
+            let state = get_region_execution_data::<FoldNodeState>(region.region, variables)?;
+
+            // For empty lists the region never runs, so the initial value is
+            // returned as is
+            if state.length > 0 {
+                state.accumulator = inputs[0].clone();
+            }
+            state.index += 1;
+
+            if state.index >= state.length {
+                outputs.clear();
+                outputs.push(state.accumulator.clone());
+                outputs.extend(inputs.iter().skip(1).cloned());
+                variables.remove_region_data(region.region);
+                Ok(ExecutionResult::Done)
+            } else {
+                state.values = Some(inputs[1..].to_vec());
+                Ok(ExecutionResult::RerunRegion {
+                    region: region.region,
+                })
+            }
+        }
+    }
+
+    fn categories() -> &'static [&'static str] {
+        &["list", "utility.iterators"]
+    }
+
+    fn create() -> Self {
+        Self {
+            input_ty: None,
+            accumulator_ty: None,
+        }
+    }
+
+    fn input_port_for(ty: EDataType, _registry: &ETypesRegistry) -> Option<usize> {
+        ty.is_list().then_some(0)
+    }
+}
+
+#[derive(Debug)]
+struct FoldNodeState {
+    index: usize,
+    length: usize,
+    accumulator: EValue,
+    values: Option<Vec<EValue>>,
+}
+
+impl RegionExecutionData for FoldNodeState {}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{graph_id, graph_node, test_project, TEST_GRAPH_PATH};
+    use crate::project::Project;
+    use crate::value::EValue;
+    use itertools::Itertools;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const REGION: Uuid = Uuid::from_u128(1);
+
+    /// Graph summing the list into the initial value and printing the result
+    fn project(values: &[f64], initial: f64) -> Project<MemoryIO> {
+        let fold = |kind: &str, data: serde_json::Value| json!({ "ids": [], "kind": kind, "node": data, "region": REGION });
+        let graph = json!({
+            "version": "V1",
+            "id": Uuid::from_u128(2),
+            "name": "Graph",
+            "is_node_group": false,
+            "graph": {
+                "nodes": [
+                    [0, graph_node("fold", fold("Start", json!([
+                        ["Number", "Number"],
+                        ["Number", "Number", "Number", "Number"],
+                    ])))],
+                    [1, graph_node("fold", fold("End", json!([["Number"], ["Number"]])))],
+                    [2, graph_node("add", json!(null))],
+                    [3, graph_node("debug_print", json!([["Number"], []]))],
+                ],
+                "edges": [
+                    [{ "node": 0, "output": 3 }, { "node": 2, "input": 0 }],
+                    [{ "node": 0, "output": 0 }, { "node": 2, "input": 1 }],
+                    [{ "node": 2, "output": 0 }, { "node": 1, "input": 0 }],
+                    [{ "node": 1, "output": 0 }, { "node": 3, "input": 0 }],
+                ],
+                "inline_values": [
+                    [{ "node": 0, "input": 0 }, values],
+                    [{ "node": 0, "input": 1 }, initial],
+                ],
+                "inputs": [],
+                "outputs": [],
+                "regions": [{ "color": null, "id": REGION, "variables": [] }],
+            },
+        })
+        .to_string();
+        test_project(&[(TEST_GRAPH_PATH, &graph)])
+    }
+
+    /// Runs the graph, returning the inputs of all evaluations of the nodes
+    /// of the given type
+    fn run(values: &[f64], initial: f64, ty: &str) -> Vec<Vec<EValue>> {
+        let mut project = project(values, initial);
+        let id = graph_id(&project, TEST_GRAPH_PATH);
+        project.debugger.graph_mut(id).traced = true;
+        project.run_graphs(true).unwrap();
+
+        let graph = project.graphs.graphs[&id].graph();
+        let trace = &project
+            .debugger
+            .graph(id)
+            .unwrap()
+            .trace
+            .as_ref()
+            .unwrap()
+            .trace;
+        trace
+            .events()
+            .iter()
+            .filter(|event| graph.snarl()[event.node].id().as_str() == ty)
+            .map(|event| event.inputs.clone())
+            .collect_vec()
+    }
+
+    fn numbers(values: &[f64]) -> Vec<EValue> {
+        values.iter().map(|v| EValue::from(*v)).collect()
+    }
+
+    #[test]
+    fn should_thread_accumulator_through_iterations() {
+        assert_eq!(
+            run(&[1.0, 2.0, 3.0], 10.0, "add"),
+            vec![
+                numbers(&[10.0, 1.0]),
+                numbers(&[11.0, 2.0]),
+                numbers(&[13.0, 3.0]),
+            ]
+        );
+        assert_eq!(
+            run(&[1.0, 2.0, 3.0], 10.0, "debug_print"),
+            vec![numbers(&[16.0])]
+        );
+    }
+
+    #[test]
+    fn should_return_initial_value_for_empty_list() {
+        assert!(run(&[], 10.0, "add").is_empty());
+        assert_eq!(run(&[], 10.0, "debug_print"), vec![numbers(&[10.0])]);
+    }
+}
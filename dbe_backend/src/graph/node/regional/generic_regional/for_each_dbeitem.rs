@@ -11,7 +11,6 @@ use crate::graph::node::variables::remember_variables;
 use crate::graph::node::{ExecutionResult, NodeContext};
 use crate::graph::region::{get_region_execution_data, RegionExecutionData};
 use crate::json_utils::JsonValue;
-use crate::project::ProjectFile;
 use crate::registry::ETypesRegistry;
 use crate::value::EValue;
 use egui_snarl::{NodeId, OutPinId};
@@ -292,30 +291,21 @@ impl GenericStatefulNode for ForEachDbeItem {
 
             let state = variables.get_or_try_init_region_data(region.region, |effects| {
                 let files: Vec<_> = effects
-                    .project_files_iter()
+                    .project_values_of_type(ty)
                     .expect("side effects were checked for")
-                    .filter_map(|(path, file)| {
-                        let (ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) = file
-                        else {
-                            return None;
+                    .filter_map(|(path, value)| {
+                        let Some((ty, expected_variant)) = self.enum_variant else {
+                            return Some(Ok((path.to_string(), value.clone())));
                         };
 
-                        if value.ty() == ty {
-                            let Some((ty, expected_variant)) = self.enum_variant else {
-                                return Some(Ok((path.to_string(), value.clone())));
-                            };
-
-                            let EValue::Enum { variant, data } = value else {
-                                return Some(Err(miette!("Expected enum value")));
-                            };
+                        let EValue::Enum { variant, data } = value else {
+                            return Some(Err(miette!("Expected enum value")));
+                        };
 
-                            if variant == &expected_variant {
-                                debug_assert_eq!(ty, data.ty());
+                        if variant == &expected_variant {
+                            debug_assert_eq!(ty, data.ty());
 
-                                Some(Ok((path.to_string(), data.deref().clone())))
-                            } else {
-                                None
-                            }
+                            Some(Ok((path.to_string(), data.deref().clone())))
                         } else {
                             None
                         }
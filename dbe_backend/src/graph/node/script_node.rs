@@ -0,0 +1,296 @@
+use crate::etype::eitem::EItemInfo;
+use crate::etype::EDataType;
+use crate::graph::node::commands::SnarlCommands;
+use crate::graph::node::groups::utils::{
+    get_port_input, get_port_output, map_group_inputs, map_group_outputs, sync_fields,
+};
+use crate::graph::node::ports::fields::IoDirection;
+use crate::graph::node::ports::{InputData, OutputData};
+use crate::graph::node::serde_node::impl_serde_node;
+use crate::graph::node::{ExecutionExtras, ExecutionResult, Node, NodeContext, NodeFactory};
+use crate::m_try;
+use crate::project::scripts::ProjectScript;
+use crate::value::EValue;
+use egui_snarl::NodeId;
+use miette::{miette, Context};
+use serde::{Deserialize, Serialize};
+use ustr::Ustr;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct ScriptNode {
+    pub script_id: Uuid,
+    inputs: Vec<Uuid>,
+    outputs: Vec<Uuid>,
+
+    input_types: Vec<EDataType>,
+    output_types: Vec<EDataType>,
+}
+
+impl ScriptNode {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_script(script_id: Uuid) -> Self {
+        Self {
+            script_id,
+            ..Default::default()
+        }
+    }
+
+    fn get_script<'ctx>(&self, context: NodeContext<'ctx>) -> miette::Result<&'ctx ProjectScript> {
+        let graphs = context.graphs.ok_or_else(|| miette!("No graph context"))?;
+        graphs
+            .scripts
+            .scripts
+            .get(&self.script_id)
+            .ok_or_else(|| miette!("Script {} is not found", self.script_id))
+    }
+}
+
+impl Node for ScriptNode {
+    impl_serde_node!();
+
+    fn id(&self) -> Ustr {
+        ScriptNodeFactory.id()
+    }
+
+    fn title(&self, context: NodeContext) -> String {
+        let Ok(script) = self.get_script(context) else {
+            return "!!unknown script!!".to_string();
+        };
+        script.name.clone()
+    }
+
+    fn update_state(
+        &mut self,
+        context: NodeContext,
+        commands: &mut SnarlCommands,
+        id: NodeId,
+    ) -> miette::Result<()> {
+        let Ok(script) = self.get_script(context) else {
+            return Ok(());
+        };
+
+        sync_fields(
+            commands,
+            &script.inputs,
+            &mut self.inputs,
+            Some(&mut self.input_types),
+            id,
+            IoDirection::Input(0),
+        );
+        sync_fields(
+            commands,
+            &script.outputs,
+            &mut self.outputs,
+            Some(&mut self.output_types),
+            id,
+            IoDirection::Output(0),
+        );
+
+        Ok(())
+    }
+
+    fn inputs_count(&self, _context: NodeContext) -> usize {
+        self.inputs.len()
+    }
+
+    fn input_unchecked(&self, context: NodeContext, input: usize) -> miette::Result<InputData> {
+        if context.graphs.is_none() {
+            return Ok(InputData::new(
+                EItemInfo::simple_type(self.input_types[input]).into(),
+                Default::default(),
+            ));
+        }
+        let script = self.get_script(context)?;
+        get_port_input(&script.inputs, &self.inputs, input)
+    }
+
+    fn outputs_count(&self, _context: NodeContext) -> usize {
+        self.outputs.len()
+    }
+
+    fn output_unchecked(&self, context: NodeContext, output: usize) -> miette::Result<OutputData> {
+        if context.graphs.is_none() {
+            return Ok(OutputData::new(
+                EItemInfo::simple_type(self.output_types[output]).into(),
+                Default::default(),
+            ));
+        }
+        let script = self.get_script(context)?;
+        get_port_output(&script.outputs, &self.outputs, output)
+    }
+
+    fn execute(
+        &self,
+        context: NodeContext,
+        inputs: &[EValue],
+        outputs: &mut Vec<EValue>,
+        _variables: &mut ExecutionExtras,
+    ) -> miette::Result<ExecutionResult> {
+        let script = self.get_script(context)?;
+
+        m_try(|| {
+            let mut script_in = Vec::with_capacity(script.inputs.len());
+            map_group_inputs(
+                context.registry,
+                &script.inputs,
+                &self.inputs,
+                inputs,
+                &mut script_in,
+            )?;
+
+            let script_out = script.execute(context.registry, &script_in)?;
+
+            map_group_outputs(
+                context.registry,
+                &script.outputs,
+                &self.outputs,
+                &script_out,
+                outputs,
+            )
+        })
+        .with_context(|| format!("failed to execute script `{}` ({})", script.name, script.id))?;
+
+        Ok(ExecutionResult::Done)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScriptNodeFactory;
+
+impl NodeFactory for ScriptNodeFactory {
+    fn id(&self) -> Ustr {
+        "script".into()
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        &["scripts"]
+    }
+
+    fn create(&self) -> Box<dyn Node> {
+        Box::new(ScriptNode::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::node::commands::SnarlCommands;
+    use crate::graph::node::extras::ExecutionExtras;
+    use crate::graph::node::script_node::ScriptNode;
+    use crate::graph::node::{Node, NodeContext};
+    use crate::graph::Graph;
+    use crate::project::docs::Docs;
+    use crate::project::io::MemoryIO;
+    use crate::project::side_effects::SideEffectsContext;
+    use crate::project::test_utils::test_project;
+    use crate::project::Project;
+    use crate::value::EValue;
+    use egui_snarl::NodeId;
+    use utils::map::HashMap;
+    use uuid::Uuid;
+
+    const SCRIPT_ID: Uuid = Uuid::from_u128(1);
+
+    const SCRIPT: &str = r#"
+id = "00000000-0000-0000-0000-000000000001"
+name = "Divide"
+source = '''
+if divisor == 0.0 {
+    throw "division by zero";
+}
+result = value / divisor;
+'''
+
+[[inputs]]
+name = "value"
+ty = "Number"
+
+[[inputs]]
+name = "divisor"
+ty = "Number"
+
+[[outputs]]
+name = "result"
+ty = "Number"
+"#;
+
+    /// Syncs the node with its script and executes it
+    fn execute(
+        project: &Project<MemoryIO>,
+        node: &mut ScriptNode,
+        inputs: &[EValue],
+    ) -> miette::Result<Vec<EValue>> {
+        let graph = Graph::default();
+        let context = NodeContext {
+            registry: &project.registry,
+            docs: &Docs::Stub,
+            inputs: graph.inputs(),
+            outputs: graph.outputs(),
+            regions: graph.regions(),
+            region_graph: graph.region_graph(),
+            graphs: Some(&project.graphs),
+        };
+        node.update_state(context, &mut SnarlCommands::new(), NodeId(0))?;
+
+        let mut group_outputs = None;
+        let mut regional_data = HashMap::default();
+        let mut extras = ExecutionExtras::new(
+            false,
+            &[],
+            &mut group_outputs,
+            &mut regional_data,
+            SideEffectsContext::Unavailable,
+            None,
+        );
+        let mut outputs = vec![];
+        node.execute(context, inputs, &mut outputs, &mut extras)?;
+        Ok(outputs)
+    }
+
+    #[test]
+    fn should_run_script() {
+        let project = test_project(&[("divide.dbescript", SCRIPT)]);
+        let mut node = ScriptNode::with_script(SCRIPT_ID);
+
+        let outputs = execute(&project, &mut node, &[6.0.into(), 4.0.into()]).unwrap();
+
+        assert_eq!(outputs, vec![EValue::from(1.5)]);
+        assert_eq!(node.inputs.len(), 2);
+        assert_eq!(node.outputs.len(), 1);
+    }
+
+    #[test]
+    fn should_report_script_errors() {
+        let project = test_project(&[("divide.dbescript", SCRIPT)]);
+        let mut node = ScriptNode::with_script(SCRIPT_ID);
+
+        let err = execute(&project, &mut node, &[1.0.into(), 0.0.into()]).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            format!("failed to execute script `Divide` ({})", SCRIPT_ID)
+        );
+        assert!(
+            err.chain()
+                .any(|cause| cause.to_string().contains("division by zero")),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn should_report_missing_script() {
+        let project = test_project(&[("divide.dbescript", SCRIPT)]);
+        let mut node = ScriptNode::with_script(Uuid::from_u128(2));
+
+        let err = execute(&project, &mut node, &[]).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            format!("Script {} is not found", Uuid::from_u128(2))
+        );
+    }
+}
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 pub mod region_graph;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct RegionVariable {
     pub ty: Option<EDataType>,
     pub id: Uuid,
@@ -35,7 +35,7 @@ impl GraphIoData for RegionVariable {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct RegionInfo {
     id: Uuid,
     pub color: Option<Color32>,
@@ -1,15 +1,20 @@
 use crate::etype::EDataType;
-use crate::graph::execution::GraphExecutionContext;
 use crate::json_utils::formatter::DBEJsonFormatter;
 use crate::json_utils::{json_kind, JsonValue};
 use crate::m_try;
+use crate::project::debugger::GraphDebugger;
 use crate::project::docs::{Docs, DocsFile};
-use crate::project::io::{FilesystemIO, ProjectIO};
+use crate::project::golden::GoldenOutput;
+use crate::project::graph_tests::GraphTests;
+use crate::project::incremental::GraphRuns;
+use crate::project::io::{sha256, FilesystemIO, ProjectIO};
+use crate::project::migrations::MigrationReport;
 use crate::project::module::{find_dbemodule_path, DbeModule};
-use crate::project::project_graph::{EvaluationStage, ProjectGraph, ProjectGraphs};
-use crate::project::side_effects::SideEffectsContext;
+use crate::project::project_graph::{ProjectGraph, ProjectGraphs};
+use crate::project::scripts::ProjectScript;
 use crate::project::undo::{UndoHistory, UndoSettings};
 use crate::registry::ETypesRegistry;
+use crate::validation::ids::numeric::NumericIDRegistry;
 use crate::validation::{clear_validation_cache, validate};
 use crate::value::id::editor_id::Namespace;
 use crate::value::id::ETypeId;
@@ -21,20 +26,29 @@ use miette::{bail, miette, Context, IntoDiagnostic, Report};
 use rayon::iter::ParallelDrainFull;
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
-use std::collections::{hash_map, BTreeMap};
+use std::collections::{hash_map, BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
-use strum::VariantArray;
-use tracing::{error_span, info};
-use utils::map::{HashMap, HashSet};
+use tracing::{error, error_span, info};
+use utils::map::{hash_of, HashMap, HashSet};
 use uuid::Uuid;
 
+pub mod debugger;
 pub mod docs;
+pub mod golden;
+pub mod graph_tests;
+mod incremental;
 pub mod io;
+pub mod migrations;
 pub mod module;
 pub mod project_graph;
+mod refactor;
+pub mod scripts;
 pub mod side_effects;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod undo;
+pub mod watch;
 
 pub const EXTENSION_TYPE: &str = "kdl";
 pub const EXTENSION_GRAPH: &str = "dbegraph";
@@ -42,12 +56,19 @@ pub const EXTENSION_VALUE: &str = "dbevalue";
 pub const EXTENSION_MODULE: &str = "dbemodule";
 pub const EXTENSION_ITEM: &str = "json";
 pub const EXTENSION_DOCS: &str = "docs.toml";
+pub const EXTENSION_TEST: &str = "dbetest";
+pub const EXTENSION_SCRIPT: &str = "dbescript";
 
 pub const TYPES_FOLDER: &str = "types";
 
 pub const MODULE_FILE: &str = "mod.toml";
 pub const PROJECT_FILE: &str = "project.toml";
 
+/// Folder for the editor state that is not a part of the project itself
+pub const CACHE_FOLDER: &str = ".dbecache";
+/// Undo history file, relative to the cache folder
+pub const UNDO_HISTORY_FILE: &str = "undo_history.json";
+
 #[derive(Debug)]
 pub struct Project<IO> {
     /// Types registry
@@ -60,8 +81,25 @@ pub struct Project<IO> {
     /// Loaded modules
     pub modules: HashMap<Namespace, DbeModule>,
     pub graphs: ProjectGraphs,
+    /// Node group test files
+    pub tests: GraphTests,
+    /// Golden output snapshot of the generated files
+    pub golden: GoldenOutput,
+    /// Files migrated when the project was loaded
+    pub migrations: MigrationReport,
     /// Files that should be deleted on save
     pub to_delete: HashSet<Utf8PathBuf>,
+    /// State hashes of files as they were last loaded from or written to
+    /// the disk, used to detect unsaved changes
+    saved_state: HashMap<Utf8PathBuf, u64>,
+    /// Files that were changed on disk while having unsaved changes
+    pub conflicts: BTreeSet<Utf8PathBuf>,
+    /// Graphs whose inputs changed since the last evaluation
+    pub dirty_graphs: HashSet<Uuid>,
+    /// Inputs and outputs of the last evaluation of each graph
+    graph_runs: GraphRuns,
+    /// Graph tracing and breakpoints
+    pub debugger: GraphDebugger,
     pub history: UndoHistory,
     /// Root folder of the project
     pub root: Utf8PathBuf,
@@ -137,7 +175,16 @@ impl Project<FilesystemIO> {
 
         let paths = fs.list_files()?;
 
-        Self::from_files(root, config, paths, fs)
+        let mut project = Self::from_files(root, config, paths, fs)?;
+
+        if let Err(err) = project.load_history() {
+            error!(
+                "failed to load undo history, starting from scratch: {:?}",
+                err
+            );
+        }
+
+        Ok(project)
     }
 }
 
@@ -182,6 +229,8 @@ impl<IO: ProjectIO> Project<IO> {
         let mut import_jsons = HashMap::<Utf8PathBuf, (JsonValue, Option<EDataType>)>::default();
         let mut types_jsons = HashMap::<Utf8PathBuf, JsonValue>::default();
         let mut graphs = HashMap::<Utf8PathBuf, JsonValue>::default();
+        let mut tests = GraphTests::default();
+        let mut scripts = HashMap::<Utf8PathBuf, ProjectScript>::default();
         let mut docs = Docs::Docs(Default::default());
         let mut modules = HashMap::<Utf8PathBuf, DbeModule>::default();
 
@@ -196,6 +245,10 @@ impl<IO: ProjectIO> Project<IO> {
             let path = Utf8Path::from_path(relative)
                 .ok_or_else(|| miette!("Got non-UTF8 path at {}", relative.display()))?;
 
+            if is_cache_path(path) {
+                continue;
+            }
+
             let Some(ext) = path.extension().map(str::to_lowercase) else {
                 continue;
             };
@@ -249,6 +302,22 @@ impl<IO: ProjectIO> Project<IO> {
                             .context("failed to deserialize graph JSON")?;
                         graphs.insert(path.to_path_buf(), data);
                     }
+                    EXTENSION_SCRIPT => {
+                        if let Some(module_path) = module_path {
+                            if path.starts_with(module_path.join(TYPES_FOLDER)) {
+                                bail!("scripts are not allowed inside types folder");
+                            }
+                        }
+                        let script =
+                            ProjectScript::parse_toml(&utf8str(path, io.read_file(path)?)?)?;
+                        scripts.insert(path.to_path_buf(), script);
+                    }
+                    EXTENSION_TEST => {
+                        if module_path.is_some() {
+                            bail!("test files are not allowed inside dbemodule");
+                        }
+                        tests.load(path.to_path_buf(), &utf8str(path, io.read_file(path)?)?);
+                    }
                     "toml" if path_has_suffix(path, EXTENSION_DOCS) => {
                         if module_path.is_none() {
                             bail!("docs file is outside of dbemodule");
@@ -316,7 +385,15 @@ impl<IO: ProjectIO> Project<IO> {
             files: Default::default(),
             modules: project_modules,
             graphs: Default::default(),
+            tests,
+            golden: Default::default(),
+            migrations: Default::default(),
             to_delete: Default::default(),
+            saved_state: Default::default(),
+            conflicts: Default::default(),
+            dirty_graphs: Default::default(),
+            graph_runs: Default::default(),
+            debugger: Default::default(),
             history: UndoHistory::new(UndoSettings::default()),
             root,
             io,
@@ -339,8 +416,24 @@ impl<IO: ProjectIO> Project<IO> {
             }
         }
 
-        for (path, (json, ty)) in import_jsons {
+        project.migrations = MigrationReport::load(&project.modules, &project.io)?;
+
+        for (path, (mut json, ty)) in import_jsons {
             error_span!("Loading value file", %path, ?ty);
+            let root_ty = ty.unwrap_or_else(|| project.import_root());
+            let applied = match project
+                .migrations
+                .migrate(&project.registry, root_ty, &mut json)
+            {
+                Ok(applied) => applied,
+                Err(err) => {
+                    let err = err.wrap_err(format!("failed to migrate JSON at `{}`", path));
+                    let item = ProjectFile::BadValue(err);
+                    project.migrations.record(&path, vec![], &item);
+                    project.files.insert(path, item);
+                    continue;
+                }
+            };
             let item = match project
                 .deserialize_json(json, ty)
                 .with_context(|| format!("failed to deserialize JSON at `{}`", path))
@@ -360,9 +453,18 @@ impl<IO: ProjectIO> Project<IO> {
                 }
                 Err(err) => ProjectFile::BadValue(err),
             };
+            project.migrations.record(&path, applied, &item);
             project.files.insert(path, item);
         }
 
+        for (path, script) in scripts {
+            project
+                .graphs
+                .scripts
+                .add_script(path.clone(), script)
+                .with_context(|| format!("failed to process script at `{}`", path))?;
+        }
+
         for (path, mut json) in graphs {
             let graph = ProjectGraph::parse_json(&project.registry, &mut json)
                 .with_context(|| format!("failed to deserialize Graph at `{}`", path))?;
@@ -373,9 +475,18 @@ impl<IO: ProjectIO> Project<IO> {
             project.files.insert(path, file);
         }
 
+        project.golden =
+            GoldenOutput::load(&project.io).context("failed to load golden output snapshot")?;
+
         // Validate again after all files are loaded
         project.validate_all()?;
 
+        project.mark_all_saved();
+        // Migrated files are only written to the disk on save
+        for path in project.migrations.files.keys() {
+            project.saved_state.remove(path);
+        }
+
         Ok(project)
     }
 
@@ -391,92 +502,42 @@ impl<IO: ProjectIO> Project<IO> {
         Ok(())
     }
 
+    /// Evaluates all graphs, running side effects
+    ///
+    /// All previously generated files are removed, unless emitted again
     pub fn evaluate_graphs(&mut self) -> miette::Result<()> {
-        let mut side_effects = side_effects::SideEffects::new();
-        let mut generated = vec![];
-
-        for graph in self.graphs.graphs.values_mut() {
-            graph.graph_mut().ensure_region_graph_ready();
-        }
-
-        let mut stages: [Vec<_>; EvaluationStage::len()] = [vec![], vec![], vec![], vec![], vec![]];
-
-        for (path, file) in &self.files {
-            if file.is_generated() {
-                generated.push(path.clone());
-                continue;
-            }
-            let ProjectFile::Graph(id) = file else {
-                continue;
-            };
-
-            let Some(graph) = self.graphs.graphs.get(id) else {
-                bail!("graph {:?} at path {} is not found", id, path);
-            };
-
-            if graph.is_node_group {
-                continue;
-            }
-            let stage = graph.stage;
-            stages[stage as usize].push((path.clone(), *id));
-        }
-
-        for path in generated {
-            self.delete_file(&path)?;
-        }
-
-        for (stage_index, stage_graphs) in stages.into_iter().enumerate() {
-            let stage = EvaluationStage::VARIANTS[stage_index];
-            side_effects.set_stage(stage);
-            for (path, id) in stage_graphs {
-                m_try(|| {
-                    let Some(graph) = self.graphs.graphs.get(&id) else {
-                        bail!("!!INTERNAL!! graph {:?} at path {} is missing, even tho it passed the stages check", id, path);
-                    };
-
-                    if graph.is_node_group {
-                        bail!("!!INTERNAL!! graph {:?} at path {} is a node group", id, path);
-                    }
-
-                    side_effects.clear_transient_storage();
-
-                    let out_values = &mut None;
-                    let mut ctx = GraphExecutionContext::from_graph(
-                        graph.graph(),
-                        &self.registry,
-                        &self.docs,
-                        Some(&self.graphs),
-                        SideEffectsContext::new(&mut side_effects, path.clone(), &self.files),
-                        graph.is_node_group,
-                        &[],
-                        out_values,
-                    );
-                    ctx.full_eval(true)?;
-                    drop(ctx);
-                    if out_values.is_some() {
-                        bail!("graph {:?} at path {} has outputs", id, path);
-                    }
-                    Ok(())
-                })
-                    .with_context(|| format!("failed to evaluate graph at `{}`", path))?;
-            }
-            side_effects.execute(self)?;
-        }
-
+        self.run_graphs(true)?;
         Ok(())
     }
 
     /// Clean and validate the project, evaluating all graphs and running side effects
     pub fn clean_validate(&mut self) -> miette::Result<()> {
+        self.evaluate_and_validate(false)
+    }
+
+    /// Validate the project, evaluating only the graphs whose inputs changed
+    /// since the last evaluation
+    ///
+    /// See [Project::evaluate_graphs_incremental]
+    pub fn incremental_validate(&mut self) -> miette::Result<()> {
+        self.evaluate_and_validate(true)
+    }
+
+    fn evaluate_and_validate(&mut self, incremental: bool) -> miette::Result<()> {
         self.diagnostics.diagnostics.clear();
         let graph_eval_time = Instant::now();
-        self.evaluate_graphs()?;
+        if incremental {
+            self.evaluate_graphs_incremental()?;
+        } else {
+            self.evaluate_graphs()?;
+        }
         let graph_eval_time = graph_eval_time.elapsed().as_secs_f32();
         clear_validation_cache(&self.registry);
         let validate_time = Instant::now();
         // Double validate to ensure that validation cache is populated
         self.validate_all()?;
         self.validate_all()?;
+        self.check_golden_output()?;
         let validate_time = validate_time.elapsed().as_secs_f32();
         info!(
             graph_eval_time,
@@ -485,7 +546,12 @@ impl<IO: ProjectIO> Project<IO> {
         Ok(())
     }
 
+    /// Validates all project values
+    ///
+    /// Diagnostics emitted by graphs during the last evaluation are reported
+    /// alongside the validation results
     pub fn validate_all(&mut self) -> miette::Result<()> {
+        let graph_diagnostics = self.graph_runs.diagnostics_by_file();
         for (path, file) in &self.files {
             match file {
                 ProjectFile::Value(file) | ProjectFile::GeneratedValue(file) => {
@@ -495,6 +561,9 @@ impl<IO: ProjectIO> Project<IO> {
                         None,
                         file,
                     )?;
+                    for diagnostic in graph_diagnostics.get(path).into_iter().flatten() {
+                        diagnostic.emit(self.diagnostics.enter(path.as_str()));
+                    }
                 }
                 ProjectFile::BadValue(_) => {
                     let mut ctx = self.diagnostics.enter(path.as_str());
@@ -502,8 +571,15 @@ impl<IO: ProjectIO> Project<IO> {
                     ctx
                         .emit_error(miette!("failed to deserialize JSON at `{path}`, open the file in editor for details"));
                 }
-                &ProjectFile::Graph(_) => {
-                    // TODO: validate graph
+                &ProjectFile::Graph(id) => {
+                    // Graph inline values are only indexed for numeric ID
+                    // references, they are not validated against their types
+                    // TODO: validate graph inline values
+                    if let Some(graph) = self.graphs.graphs.get(&id) {
+                        NumericIDRegistry::of(&self.registry)
+                            .index_graph_refs(path.as_str(), graph.graph())
+                            .with_context(|| format!("failed to index IDs in `{}`", path))?;
+                    }
                 }
             }
         }
@@ -511,12 +587,31 @@ impl<IO: ProjectIO> Project<IO> {
     }
 
     pub fn save(&mut self) -> miette::Result<()> {
+        if let Some(path) = self.conflicts.first() {
+            bail!(
+                "file `{}` was changed on disk while having unsaved changes, resolve the conflict before saving",
+                path
+            );
+        }
+
         self.clean_validate()?;
 
         if self.diagnostics.has_diagnostics(DiagnosticLevel::Error) {
             return Err(miette!("project has unresolved errors, cannot save"));
         }
 
+        self.write_files()
+    }
+
+    /// Writes all project files to the disk as-is, without evaluating graphs
+    /// or validating values
+    ///
+    /// Fails if the project contains any files that failed to load
+    pub fn write_files(&mut self) -> miette::Result<()> {
+        if let Some((path, _)) = self.files.iter().find(|(_, file)| file.is_bad()) {
+            bail!("file at `{}` failed to load, cannot save", path);
+        }
+
         let (no_delete_sender, no_delete_receiver) = std::sync::mpsc::channel::<Utf8PathBuf>();
 
         self.files.par_iter().try_for_each_with(
@@ -603,29 +698,154 @@ impl<IO: ProjectIO> Project<IO> {
                 Ok(())
             })?;
 
+        self.migrations
+            .data_versions(&self.modules, &self.files)
+            .write(&self.io)?;
+
         self.io.flush()?;
 
+        self.mark_all_saved();
+
+        if let Err(err) = self.save_history() {
+            error!("failed to save undo history: {:?}", err);
+        }
+
         Ok(())
     }
+
+    /// Writes the undo history to the project cache folder, so it can be
+    /// restored when the project is opened again
+    ///
+    /// Called automatically after the project is written to the disk
+    pub fn save_history(&mut self) -> miette::Result<()> {
+        let io = &self.io;
+        let data = self
+            .history
+            .serialize(&self.files, &self.graphs, &self.registry, |path| {
+                disk_hash(io, path)
+            })?;
+
+        self.io
+            .write_file(undo_history_path(), &data)
+            .context("failed to write undo history")?;
+        self.io.flush()
+    }
+
+    /// Loads the undo history from the project cache folder, if present
+    fn load_history(&mut self) -> miette::Result<()> {
+        let path = undo_history_path();
+        if !self.io.file_exists(&path)? {
+            return Ok(());
+        }
+
+        let data = self.io.read_file(&path)?;
+        let io = &self.io;
+        self.history = UndoHistory::deserialize(
+            self.history.settings().clone(),
+            &self.registry,
+            &data,
+            |path| disk_hash(io, path),
+        )?;
+        self.io.flush()
+    }
 }
 
 impl<IO> Project<IO> {
     /// See [UndoHistory::undo]
     pub fn undo(&mut self) -> miette::Result<Utf8PathBuf> {
-        self.history.undo(&mut self.files, &mut self.graphs)
+        self.history
+            .undo(&mut self.files, &mut self.graphs, &mut self.to_delete)
     }
 
     /// See [UndoHistory::redo]
     pub fn redo(&mut self) -> miette::Result<Utf8PathBuf> {
-        self.history.redo(&mut self.files, &mut self.graphs)
+        self.history
+            .redo(&mut self.files, &mut self.graphs, &mut self.to_delete)
+    }
+
+    /// Runs the provided function as a single undo history step
+    ///
+    /// All file changes, creations and deletions made by the function are
+    /// undone and redone together. Changes are recorded even if the function
+    /// fails, so they can be reverted
+    pub fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> miette::Result<T>,
+    ) -> miette::Result<T> {
+        let start = self
+            .history
+            .begin_transaction(&self.files, &self.graphs, &self.to_delete)?;
+
+        let result = f(self);
+
+        self.history
+            .commit_transaction(start, &self.files, &self.graphs, &self.to_delete)?;
+
+        result
     }
 
     /// See [UndoHistory::check_file]
+    ///
+    /// Graph files are checked with [UndoHistory::check_graph], using the
+    /// edits recorded by the graph since the last check
     pub fn file_changed(&mut self, path: &Utf8PathBuf, force_snapshot: bool) -> miette::Result<()> {
+        let graph_id = match self.files.get(path) {
+            Some(ProjectFile::Graph(id)) => Some(*id),
+            _ => None,
+        };
+        if let Some(graph) = graph_id.and_then(|id| self.graphs.graphs.get_mut(&id)) {
+            let edits = graph.graph_mut().take_edits();
+            return self.history.check_graph(
+                &self.files,
+                &self.graphs,
+                path,
+                edits,
+                force_snapshot,
+            );
+        }
+
         self.history
             .check_file(&self.files, &self.graphs, path, force_snapshot)
     }
 
+    /// Checks if the file has changes that were not yet written to the disk
+    pub fn is_dirty(&self, path: &Utf8Path) -> bool {
+        let Some(file) = self.files.get(path) else {
+            return false;
+        };
+        match file_state(file, &self.graphs) {
+            None => false,
+            Some(state) => self.saved_state.get(path) != Some(&state),
+        }
+    }
+
+    /// Records the current state of all files as the state that is present
+    /// on the disk
+    fn mark_all_saved(&mut self) {
+        self.saved_state = self
+            .files
+            .iter()
+            .filter_map(|(path, file)| Some((path.clone(), file_state(file, &self.graphs)?)))
+            .collect();
+    }
+
+    /// Records the current state of a single file as the state that is
+    /// present on the disk
+    fn mark_saved(&mut self, path: &Utf8Path) {
+        match self
+            .files
+            .get(path)
+            .and_then(|file| file_state(file, &self.graphs))
+        {
+            None => {
+                self.saved_state.remove(path);
+            }
+            Some(state) => {
+                self.saved_state.insert(path.to_path_buf(), state);
+            }
+        }
+    }
+
     pub fn import_root(&self) -> EDataType {
         EDataType::Object {
             ident: self.registry.project_config().types_config.import,
@@ -633,6 +853,34 @@ impl<IO> Project<IO> {
     }
 }
 
+/// Hash of the file state for the purpose of tracking unsaved changes
+///
+/// Returns `None` for files that can't have unsaved changes
+fn file_state(file: &ProjectFile, graphs: &ProjectGraphs) -> Option<u64> {
+    match file {
+        ProjectFile::Value(value) => Some(hash_of(value)),
+        ProjectFile::Graph(id) => graphs.graphs.get(id).map(|graph| hash_of(&(id, graph))),
+        ProjectFile::GeneratedValue(_) | ProjectFile::BadValue(_) => None,
+    }
+}
+
+fn undo_history_path() -> Utf8PathBuf {
+    Utf8Path::new(CACHE_FOLDER).join(UNDO_HISTORY_FILE)
+}
+
+/// Checks if the path points to the project cache folder
+fn is_cache_path(path: &Utf8Path) -> bool {
+    path.starts_with(CACHE_FOLDER)
+}
+
+/// Hash of the file content on the disk, or `None` if the file doesn't exist
+fn disk_hash<IO: ProjectIO>(io: &IO, path: &Utf8Path) -> miette::Result<Option<Vec<u8>>> {
+    if !io.file_exists(path)? {
+        return Ok(None);
+    }
+    Ok(Some(sha256(&io.read_file(path)?)))
+}
+
 fn generated_marker_path(file: impl AsRef<Utf8Path>) -> Utf8PathBuf {
     let file = file.as_ref();
     file.parent()
@@ -0,0 +1,228 @@
+use crate::graph::execution::profiler::ExecutionProfile;
+use crate::graph::execution::trace::ExecutionTrace;
+use egui_snarl::NodeId;
+use std::collections::BTreeSet;
+use utils::map::HashMap;
+use uuid::Uuid;
+
+/// Debugging state of the project graphs
+///
+/// Graphs that are traced get their node evaluations recorded during the
+/// project evaluation, and the recorded trace can be stepped through
+/// afterward. Profiled graphs get their node timings collected. This state
+/// is not saved
+#[derive(Debug, Default)]
+pub struct GraphDebugger {
+    graphs: HashMap<Uuid, GraphDebugState>,
+    /// Whenever all graphs should be profiled, regardless of their own
+    /// settings
+    pub profile_all: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct GraphDebugState {
+    /// Whenever the graph should be traced during the evaluation
+    pub traced: bool,
+    /// Nodes that the trace stepping stops at
+    pub breakpoints: BTreeSet<NodeId>,
+    /// Trace recorded during the last evaluation
+    pub trace: Option<RecordedTrace>,
+    /// Whenever node timings should be collected during the evaluation
+    pub profiled: bool,
+    /// Profile collected during the last evaluation
+    pub profile: Option<ExecutionProfile>,
+}
+
+#[derive(Debug)]
+pub struct RecordedTrace {
+    pub trace: ExecutionTrace,
+    /// Hash of the graph at the time of the evaluation
+    pub graph_hash: u64,
+    /// Currently inspected event
+    pub position: Option<usize>,
+}
+
+impl GraphDebugger {
+    pub fn graph(&self, id: Uuid) -> Option<&GraphDebugState> {
+        self.graphs.get(&id)
+    }
+
+    pub fn graph_mut(&mut self, id: Uuid) -> &mut GraphDebugState {
+        self.graphs.entry(id).or_default()
+    }
+
+    /// Checks if the graph should be traced during the evaluation
+    pub fn is_traced(&self, id: Uuid) -> bool {
+        self.graphs.get(&id).is_some_and(|s| s.traced)
+    }
+
+    /// Checks if the graph should be profiled during the evaluation
+    pub fn is_profiled(&self, id: Uuid) -> bool {
+        self.profile_all || self.graphs.get(&id).is_some_and(|s| s.profiled)
+    }
+
+    /// Iterates over the collected profiles of all graphs
+    pub fn profiles(&self) -> impl Iterator<Item = (Uuid, &ExecutionProfile)> {
+        self.graphs
+            .iter()
+            .filter_map(|(id, state)| Some((*id, state.profile.as_ref()?)))
+    }
+
+    /// Stores a trace recorded for the graph
+    pub fn set_trace(&mut self, id: Uuid, trace: ExecutionTrace, graph_hash: u64) {
+        let state = self.graph_mut(id);
+        // Start at the first breakpoint, if there is any
+        let position = trace.next_hit(None, &state.breakpoints);
+        state.trace = Some(RecordedTrace {
+            trace,
+            graph_hash,
+            position,
+        });
+    }
+}
+
+impl GraphDebugState {
+    /// Toggles the breakpoint on the node
+    pub fn toggle_breakpoint(&mut self, node: NodeId) {
+        if !self.breakpoints.remove(&node) {
+            self.breakpoints.insert(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::execution::trace::RegionIteration;
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{graph_id, graph_node, test_project, TEST_GRAPH_PATH};
+    use crate::project::Project;
+    use crate::value::EValue;
+    use egui_snarl::NodeId;
+    use itertools::Itertools;
+    use serde_json::json;
+    use std::collections::BTreeSet;
+    use uuid::Uuid;
+
+    const REGION: Uuid = Uuid::from_u128(1);
+
+    /// Graph printing the iteration index of the three times repeated region
+    fn project() -> Project<MemoryIO> {
+        let repeat =
+            |kind: &str| json!({ "ids": [], "kind": kind, "node": null, "region": REGION });
+        let graph = json!({
+            "version": "V1",
+            "id": Uuid::from_u128(2),
+            "name": "Graph",
+            "is_node_group": false,
+            "graph": {
+                "nodes": [
+                    [0, graph_node("repeat", repeat("Start"))],
+                    [1, graph_node("repeat", repeat("End"))],
+                    [2, graph_node("debug_print", json!([["Number"], []]))],
+                ],
+                "edges": [
+                    [{ "node": 0, "output": 0 }, { "node": 2, "input": 0 }],
+                ],
+                "inline_values": [[{ "node": 0, "input": 0 }, 3]],
+                "inputs": [],
+                "outputs": [],
+                "regions": [{ "color": null, "id": REGION, "variables": [] }],
+            },
+        })
+        .to_string();
+        test_project(&[(TEST_GRAPH_PATH, &graph)])
+    }
+
+    /// Finds the node of the graph by its node type
+    fn find_node(project: &Project<MemoryIO>, ty: &str) -> NodeId {
+        let graph = project.graphs.graphs[&graph_id(project, TEST_GRAPH_PATH)].graph();
+        graph
+            .snarl()
+            .node_ids()
+            .find(|(_, node)| node.id().as_str() == ty)
+            .map(|(id, _)| id)
+            .unwrap_or_else(|| panic!("node `{}` is missing", ty))
+    }
+
+    #[test]
+    fn should_record_iterations_of_repeated_region() {
+        let mut project = project();
+        let id = graph_id(&project, TEST_GRAPH_PATH);
+        project.debugger.graph_mut(id).traced = true;
+        project.run_graphs(true).unwrap();
+
+        let print = find_node(&project, "debug_print");
+        let trace = &project
+            .debugger
+            .graph(id)
+            .unwrap()
+            .trace
+            .as_ref()
+            .unwrap()
+            .trace;
+        assert!(!trace.is_truncated());
+
+        let prints = trace
+            .events()
+            .iter()
+            .filter(|event| event.node == print)
+            .map(|event| (event.iterations.to_vec(), event.inputs.clone()))
+            .collect_vec();
+        assert_eq!(
+            prints,
+            (0..3)
+                .map(|iteration| (
+                    vec![RegionIteration {
+                        region: REGION,
+                        iteration
+                    }],
+                    vec![EValue::from(iteration as f64)]
+                ))
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn should_not_trace_untraced_graphs() {
+        let mut project = project();
+        project.run_graphs(true).unwrap();
+
+        assert!(project
+            .debugger
+            .graph(graph_id(&project, TEST_GRAPH_PATH))
+            .is_none());
+    }
+
+    #[test]
+    fn should_stop_at_breakpoints() {
+        let mut project = project();
+        let id = graph_id(&project, TEST_GRAPH_PATH);
+        let print = find_node(&project, "debug_print");
+        let state = project.debugger.graph_mut(id);
+        state.traced = true;
+        state.toggle_breakpoint(print);
+        project.run_graphs(true).unwrap();
+
+        let recorded = project.debugger.graph(id).unwrap().trace.as_ref().unwrap();
+        let breakpoints = BTreeSet::from([print]);
+        let trace = &recorded.trace;
+
+        // Stepping starts at the first hit of the breakpoint
+        let first = recorded.position.expect("breakpoint should be hit");
+        assert_eq!(trace.events()[first].node, print);
+        assert_eq!(trace.events()[first].iterations[0].iteration, 0);
+        assert!(trace.prev_hit(first, &breakpoints).is_none());
+
+        let second = trace.next_hit(Some(first), &breakpoints).unwrap();
+        assert_eq!(trace.events()[second].iterations[0].iteration, 1);
+        let third = trace.next_hit(Some(second), &breakpoints).unwrap();
+        assert_eq!(trace.events()[third].iterations[0].iteration, 2);
+        assert!(trace.next_hit(Some(third), &breakpoints).is_none());
+        assert_eq!(trace.prev_hit(third, &breakpoints), Some(second));
+
+        // Toggling the breakpoint again removes it
+        let state = project.debugger.graph_mut(id);
+        state.toggle_breakpoint(print);
+        assert!(state.breakpoints.is_empty());
+    }
+}
@@ -0,0 +1,568 @@
+//! Golden output snapshots
+//!
+//! When enabled, the JSON of all generated files is recorded into a snapshot
+//! file in the project root. Every evaluation compares the generated files
+//! against the snapshot, and reports the differences per file and per field,
+//! so unexpected changes to the emitted items get noticed.
+//!
+//! Differences can be accepted, which updates the snapshot, or rejected,
+//! which turns them into errors until the generated output matches the
+//! snapshot again.
+
+use crate::json_utils::formatter::DBEJsonFormatter;
+use crate::json_utils::JsonValue;
+use crate::project::io::ProjectIO;
+use crate::project::{Project, ProjectFile};
+use crate::validation::validate;
+use camino::{Utf8Path, Utf8PathBuf};
+use diagnostic::context::DiagnosticContextMut;
+use diagnostic::diagnostic::DiagnosticLevel;
+use itertools::Itertools;
+use miette::{miette, Context, IntoDiagnostic};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use tracing::info;
+
+/// Golden output snapshot file, relative to the project root
+pub const GOLDEN_OUTPUT_FILE: &str = "golden_output.dbesnapshot";
+
+#[derive(Debug, Default)]
+pub struct GoldenOutput {
+    /// Recorded JSON of the generated files, `None` if golden output mode is
+    /// disabled
+    recorded: Option<BTreeMap<Utf8PathBuf, JsonValue>>,
+    /// Differences found during the last evaluation
+    diff: BTreeMap<Utf8PathBuf, FileDiff>,
+    /// Files whose differences were rejected
+    rejected: BTreeSet<Utf8PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FileDiff {
+    /// File was generated, but is not present in the snapshot
+    Added(JsonValue),
+    /// File is present in the snapshot, but was not generated
+    Removed,
+    /// File content differs from the snapshot
+    Changed {
+        current: JsonValue,
+        changes: Vec<FieldChange>,
+    },
+}
+
+/// Single changed value within the file
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub path: Vec<JsonPathSegment>,
+    /// Value in the snapshot, `None` if the field was added
+    pub recorded: Option<JsonValue>,
+    /// Value in the generated file, `None` if the field was removed
+    pub current: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JsonPathSegment {
+    Field(String),
+    Index(usize),
+}
+
+impl GoldenOutput {
+    /// Loads the snapshot from the project root, if present
+    pub fn load(io: &impl ProjectIO) -> miette::Result<Self> {
+        if !io.file_exists(GOLDEN_OUTPUT_FILE)? {
+            return Ok(Self::default());
+        }
+
+        let data = io.read_file(GOLDEN_OUTPUT_FILE)?;
+        let recorded = serde_json::from_slice(&data)
+            .into_diagnostic()
+            .with_context(|| format!("failed to deserialize `{}`", GOLDEN_OUTPUT_FILE))?;
+
+        Ok(Self {
+            recorded: Some(recorded),
+            ..Default::default()
+        })
+    }
+
+    /// Whenever the golden output mode is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    /// Differences found during the last evaluation
+    pub fn diff(&self) -> &BTreeMap<Utf8PathBuf, FileDiff> {
+        &self.diff
+    }
+
+    pub fn is_rejected(&self, path: &Utf8Path) -> bool {
+        self.rejected.contains(path)
+    }
+
+    /// Compares the generated files against the snapshot
+    fn compare(&mut self, mut current: BTreeMap<Utf8PathBuf, JsonValue>) {
+        self.diff.clear();
+        let Some(recorded) = &self.recorded else {
+            self.rejected.clear();
+            return;
+        };
+
+        for (path, recorded) in recorded {
+            match current.remove(path) {
+                None => {
+                    self.diff.insert(path.clone(), FileDiff::Removed);
+                }
+                Some(current) => {
+                    let mut changes = vec![];
+                    diff_json(&mut vec![], Some(recorded), Some(&current), &mut changes);
+                    if !changes.is_empty() {
+                        self.diff
+                            .insert(path.clone(), FileDiff::Changed { current, changes });
+                    }
+                }
+            }
+        }
+        for (path, current) in current {
+            self.diff.insert(path, FileDiff::Added(current));
+        }
+
+        // Rejections are dropped once the output matches the snapshot again
+        self.rejected.retain(|path| self.diff.contains_key(path));
+    }
+
+    /// Applies the difference of the file to the snapshot
+    ///
+    /// Returns `false` if the file has no differences
+    fn accept(&mut self, path: &Utf8Path) -> bool {
+        let (Some(recorded), Some(diff)) = (&mut self.recorded, self.diff.remove(path)) else {
+            return false;
+        };
+        self.rejected.remove(path);
+        match diff {
+            FileDiff::Removed => {
+                recorded.remove(path);
+            }
+            FileDiff::Added(current) | FileDiff::Changed { current, .. } => {
+                recorded.insert(path.to_path_buf(), current);
+            }
+        }
+        true
+    }
+
+    fn write(&self, io: &impl ProjectIO) -> miette::Result<()> {
+        let Some(recorded) = &self.recorded else {
+            return Ok(());
+        };
+
+        let mut buf = vec![];
+        let mut serializer =
+            serde_json::ser::Serializer::with_formatter(&mut buf, DBEJsonFormatter::pretty());
+        recorded.serialize(&mut serializer).into_diagnostic()?;
+
+        io.write_file(GOLDEN_OUTPUT_FILE, &buf)
+            .with_context(|| format!("failed to write `{}`", GOLDEN_OUTPUT_FILE))
+    }
+
+    /// Reports differences of the file into the diagnostic context
+    ///
+    /// Pending differences are reported as warnings, rejected ones as errors
+    fn report(&self, path: &Utf8Path, mut ctx: DiagnosticContextMut) {
+        let Some(diff) = self.diff.get(path) else {
+            return;
+        };
+        let level = if self.rejected.contains(path) {
+            DiagnosticLevel::Error
+        } else {
+            DiagnosticLevel::Warning
+        };
+
+        match diff {
+            FileDiff::Added(_) => ctx.emit(
+                miette!("generated file is not present in the golden output snapshot"),
+                level,
+            ),
+            FileDiff::Removed => ctx.emit(
+                miette!("file from the golden output snapshot was not generated"),
+                level,
+            ),
+            FileDiff::Changed { changes, .. } => {
+                for change in changes {
+                    emit_at(&mut ctx, &change.path, miette!("{}", change), level);
+                }
+            }
+        }
+    }
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fn value(value: &Option<JsonValue>) -> String {
+            value
+                .as_ref()
+                .map_or_else(|| "<missing>".to_string(), ToString::to_string)
+        }
+
+        let path = self.path.iter().join("");
+        write!(
+            f,
+            "output differs from the golden output snapshot at `{}`: `{}` was recorded, but `{}` was generated",
+            if path.is_empty() { "." } else { path.as_str() },
+            value(&self.recorded),
+            value(&self.current)
+        )
+    }
+}
+
+impl Display for JsonPathSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonPathSegment::Field(name) => write!(f, ".{}", name),
+            JsonPathSegment::Index(idx) => write!(f, "[{}]", idx),
+        }
+    }
+}
+
+/// Emits the report at the given JSON path within the context
+fn emit_at(
+    ctx: &mut DiagnosticContextMut,
+    path: &[JsonPathSegment],
+    report: miette::Report,
+    level: DiagnosticLevel,
+) {
+    let Some((segment, rest)) = path.split_first() else {
+        ctx.emit(report, level);
+        return;
+    };
+    let mut ctx = match segment {
+        JsonPathSegment::Field(name) => ctx.enter_field(name.clone()),
+        JsonPathSegment::Index(idx) => ctx.enter_index(*idx),
+    };
+    emit_at(&mut ctx, rest, report, level);
+}
+
+/// Collects all leaf-level differences between two JSON values
+fn diff_json(
+    path: &mut Vec<JsonPathSegment>,
+    recorded: Option<&JsonValue>,
+    current: Option<&JsonValue>,
+    changes: &mut Vec<FieldChange>,
+) {
+    if recorded == current {
+        return;
+    }
+
+    match (recorded, current) {
+        (Some(JsonValue::Object(recorded)), Some(JsonValue::Object(current))) => {
+            let keys = recorded
+                .keys()
+                .chain(current.keys())
+                .collect::<BTreeSet<_>>();
+            for key in keys {
+                path.push(JsonPathSegment::Field(key.clone()));
+                diff_json(path, recorded.get(key), current.get(key), changes);
+                path.pop();
+            }
+        }
+        (Some(JsonValue::Array(recorded)), Some(JsonValue::Array(current))) => {
+            for idx in 0..recorded.len().max(current.len()) {
+                path.push(JsonPathSegment::Index(idx));
+                diff_json(path, recorded.get(idx), current.get(idx), changes);
+                path.pop();
+            }
+        }
+        _ => changes.push(FieldChange {
+            path: path.clone(),
+            recorded: recorded.cloned(),
+            current: current.cloned(),
+        }),
+    }
+}
+
+impl<IO: ProjectIO> Project<IO> {
+    /// Records JSON of all generated files as the golden output snapshot,
+    /// enabling the golden output mode
+    ///
+    /// Graphs should be evaluated beforehand
+    pub fn record_golden_output(&mut self) -> miette::Result<()> {
+        let current = self.generated_output()?;
+        let count = current.len();
+        let paths = self.golden.diff.keys().cloned().collect_vec();
+        self.golden = GoldenOutput {
+            recorded: Some(current),
+            ..Default::default()
+        };
+        self.golden.write(&self.io)?;
+        self.io.flush()?;
+        for path in paths {
+            self.revalidate_golden(&path)?;
+        }
+        info!(files = count, "Golden output snapshot recorded");
+        Ok(())
+    }
+
+    /// Disables the golden output mode, deleting the snapshot file
+    pub fn disable_golden_output(&mut self) -> miette::Result<()> {
+        let paths = self.golden.diff.keys().cloned().collect_vec();
+        self.golden = GoldenOutput::default();
+        if self.io.file_exists(GOLDEN_OUTPUT_FILE)? {
+            self.io.delete_file(GOLDEN_OUTPUT_FILE)?;
+        }
+        self.io.flush()?;
+        for path in paths {
+            self.revalidate_golden(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Accepts the difference of the generated file, updating the snapshot
+    pub fn accept_golden_diff(&mut self, path: &Utf8Path) -> miette::Result<()> {
+        if !self.golden.accept(path) {
+            return Ok(());
+        }
+        self.golden.write(&self.io)?;
+        self.io.flush()?;
+        self.revalidate_golden(path)
+    }
+
+    /// Accepts differences of all generated files, updating the snapshot
+    pub fn accept_all_golden_diffs(&mut self) -> miette::Result<()> {
+        let paths = self.golden.diff.keys().cloned().collect_vec();
+        for path in &paths {
+            self.golden.accept(path);
+        }
+        self.golden.write(&self.io)?;
+        self.io.flush()?;
+        for path in paths {
+            self.revalidate_golden(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Rejects the difference of the generated file
+    ///
+    /// Rejected differences are reported as errors, which prevents the
+    /// project from being saved until the generated file matches the
+    /// snapshot again
+    pub fn reject_golden_diff(&mut self, path: &Utf8Path) -> miette::Result<()> {
+        if !self.golden.diff.contains_key(path) {
+            return Ok(());
+        }
+        self.golden.rejected.insert(path.to_path_buf());
+        self.revalidate_golden(path)
+    }
+
+    /// Compares the generated files against the golden output snapshot, and
+    /// reports all differences
+    ///
+    /// Must be called after the validation, since it clears diagnostics of
+    /// the files
+    pub(super) fn check_golden_output(&mut self) -> miette::Result<()> {
+        let current = if self.golden.is_enabled() {
+            self.generated_output()?
+        } else {
+            Default::default()
+        };
+        self.golden.compare(current);
+
+        for path in self.golden.diff.keys() {
+            self.golden
+                .report(path, self.diagnostics.enter(path.as_str()));
+        }
+
+        Ok(())
+    }
+
+    /// Validates the file again and reports its golden output differences
+    fn revalidate_golden(&mut self, path: &Utf8Path) -> miette::Result<()> {
+        match self.files.get(path) {
+            Some(ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) => {
+                validate(
+                    &self.registry,
+                    self.diagnostics.enter(path.as_str()),
+                    None,
+                    value,
+                )?;
+            }
+            _ => self.diagnostics.enter(path.as_str()).clear_downstream(),
+        }
+        self.golden
+            .report(path, self.diagnostics.enter(path.as_str()));
+        Ok(())
+    }
+
+    /// JSON of all generated files
+    fn generated_output(&self) -> miette::Result<BTreeMap<Utf8PathBuf, JsonValue>> {
+        self.files
+            .iter()
+            .filter_map(|(path, file)| match file {
+                ProjectFile::GeneratedValue(value) => Some((path, value)),
+                _ => None,
+            })
+            .map(|(path, value)| {
+                let json = self
+                    .serialize_json(value)
+                    .with_context(|| format!("failed to serialize file at `{}`", path))?;
+                Ok((path.clone(), json))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::golden::{
+        diff_json, FileDiff, GoldenOutput, JsonPathSegment, GOLDEN_OUTPUT_FILE,
+    };
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{set_field, test_project, value_file};
+    use crate::project::{Project, ProjectFile};
+    use camino::Utf8Path;
+    use diagnostic::diagnostic::DiagnosticLevel;
+    use itertools::Itertools;
+    use serde_json::json;
+
+    fn project() -> Project<MemoryIO> {
+        test_project(&[
+            (
+                "test.dbemodule/types/item.kdl",
+                r#"struct { number "Damage"; number "Speed"; }"#,
+            ),
+            ("a.json", r#"{ "Damage": 1, "Speed": 2 }"#),
+            ("b.json", r#"{ "Damage": 3, "Speed": 4 }"#),
+            ("c.json", r#"{ "Damage": 5, "Speed": 6 }"#),
+        ])
+    }
+
+    /// Turns the value file into a generated one
+    fn generate(project: &mut Project<MemoryIO>, path: &str) {
+        let value = value_file(project, path).clone();
+        project
+            .files
+            .insert(path.into(), ProjectFile::GeneratedValue(value));
+    }
+
+    fn snapshot(project: &Project<MemoryIO>) -> serde_json::Value {
+        serde_json::from_slice(&project.io.get(GOLDEN_OUTPUT_FILE).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn should_record_generated_files() {
+        let mut project = project();
+        generate(&mut project, "a.json");
+        assert!(!project.golden.is_enabled());
+
+        project.record_golden_output().unwrap();
+        assert!(project.golden.is_enabled());
+        assert_eq!(
+            snapshot(&project),
+            json!({ "a.json": { "Damage": 1.0, "Speed": 2.0 } })
+        );
+        assert_eq!(
+            GoldenOutput::load(&project.io).unwrap().recorded,
+            project.golden.recorded
+        );
+
+        project.check_golden_output().unwrap();
+        assert!(project.golden.diff().is_empty());
+    }
+
+    #[test]
+    fn should_report_changed_fields() {
+        let mut project = project();
+        generate(&mut project, "a.json");
+        project.record_golden_output().unwrap();
+
+        set_field(&mut project, "a.json", "Speed", 7.0);
+        project.check_golden_output().unwrap();
+
+        let a = Utf8Path::new("a.json");
+        let Some(FileDiff::Changed { changes, .. }) = project.golden.diff().get(a) else {
+            panic!("a.json should be changed");
+        };
+        let changes = changes
+            .iter()
+            .map(|c| (c.path.clone(), c.recorded.clone(), c.current.clone()))
+            .collect_vec();
+        assert_eq!(
+            changes,
+            vec![(
+                vec![JsonPathSegment::Field("Speed".to_string())],
+                Some(json!(2.0)),
+                Some(json!(7.0))
+            )]
+        );
+        assert!(project
+            .diagnostics
+            .has_diagnostics(DiagnosticLevel::Warning));
+        assert!(!project.diagnostics.has_diagnostics(DiagnosticLevel::Error));
+
+        project.reject_golden_diff(a).unwrap();
+        assert!(project.golden.is_rejected(a));
+        assert!(project.diagnostics.has_diagnostics(DiagnosticLevel::Error));
+
+        project.accept_golden_diff(a).unwrap();
+        assert!(!project.golden.is_rejected(a));
+        assert!(!project
+            .diagnostics
+            .has_diagnostics(DiagnosticLevel::Warning));
+        assert_eq!(
+            snapshot(&project),
+            json!({ "a.json": { "Damage": 1.0, "Speed": 7.0 } })
+        );
+        project.check_golden_output().unwrap();
+        assert!(project.golden.diff().is_empty());
+    }
+
+    #[test]
+    fn should_detect_added_and_removed_files() {
+        let mut project = project();
+        generate(&mut project, "a.json");
+        generate(&mut project, "b.json");
+        project.record_golden_output().unwrap();
+
+        project.files.remove(Utf8Path::new("b.json"));
+        generate(&mut project, "c.json");
+        project.check_golden_output().unwrap();
+
+        let diff = project.golden.diff();
+        assert_eq!(
+            diff.keys().map(|path| path.as_str()).collect_vec(),
+            vec!["b.json", "c.json"]
+        );
+        assert!(matches!(diff[Utf8Path::new("b.json")], FileDiff::Removed));
+        assert!(matches!(
+            &diff[Utf8Path::new("c.json")],
+            FileDiff::Added(json) if json == &json!({ "Damage": 5.0, "Speed": 6.0 })
+        ));
+
+        project.accept_all_golden_diffs().unwrap();
+        project.check_golden_output().unwrap();
+        assert!(project.golden.diff().is_empty());
+        assert_eq!(
+            snapshot(&project),
+            json!({
+                "a.json": { "Damage": 1.0, "Speed": 2.0 },
+                "c.json": { "Damage": 5.0, "Speed": 6.0 },
+            })
+        );
+    }
+
+    #[test]
+    fn should_diff_array_elements() {
+        let mut changes = vec![];
+        diff_json(
+            &mut vec![],
+            Some(&json!({ "List": [1, 2, 3] })),
+            Some(&json!({ "List": [1, 5] })),
+            &mut changes,
+        );
+
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect_vec(),
+            vec![
+                "output differs from the golden output snapshot at `.List[1]`: `2` was recorded, but `5` was generated",
+                "output differs from the golden output snapshot at `.List[2]`: `3` was recorded, but `<missing>` was generated",
+            ]
+        );
+    }
+}
@@ -0,0 +1,418 @@
+//! Data-driven tests for node groups
+//!
+//! Test files (`.dbetest`) list cases for a single node group. Every case
+//! gives values for the group inputs and the expected values of the group
+//! outputs, keyed by the input and output names. Values are parsed using
+//! the group input and output types when the tests are run, so test files
+//! stay loadable when the group signature changes.
+
+use crate::graph::execution::GraphExecutionContext;
+use crate::json_utils::JsonValue;
+use crate::m_try;
+use crate::project::docs::Docs;
+use crate::project::io::ProjectIO;
+use crate::project::project_graph::{ProjectGraph, ProjectGraphs};
+use crate::project::side_effects::SideEffectsContext;
+use crate::project::Project;
+use crate::registry::ETypesRegistry;
+use crate::value::EValue;
+use camino::{Utf8Path, Utf8PathBuf};
+use diagnostic::context::DiagnosticContextMut;
+use miette::{bail, miette, Context, IntoDiagnostic, Report};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+use uuid::Uuid;
+
+/// Content of a `.dbetest` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphTestSuite {
+    /// ID of the tested node group
+    pub group: Uuid,
+    #[serde(default)]
+    pub cases: Vec<GraphTestCase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphTestCase {
+    pub name: String,
+    /// Values of the group inputs, keyed by the input name
+    ///
+    /// Inputs that are not listed get the default value of their type
+    #[serde(default)]
+    pub inputs: BTreeMap<String, JsonValue>,
+    /// Expected values of the group outputs, keyed by the output name
+    ///
+    /// Outputs that are not listed are not checked
+    #[serde(default)]
+    pub outputs: BTreeMap<String, JsonValue>,
+}
+
+/// Test files of the project
+#[derive(Debug, Default)]
+pub struct GraphTests {
+    /// Loaded test files, or the errors that occurred while loading them
+    pub suites: BTreeMap<Utf8PathBuf, Result<GraphTestSuite, Report>>,
+}
+
+impl GraphTests {
+    /// Loads the test file from its source, replacing the old version
+    pub fn load(&mut self, path: Utf8PathBuf, source: &str) {
+        let suite = serde_json5::from_str::<GraphTestSuite>(source)
+            .into_diagnostic()
+            .with_context(|| format!("failed to deserialize test file at `{}`", path));
+        self.suites.insert(path, suite);
+    }
+
+    /// Paths of the test files for the given node group
+    pub fn suites_of(&self, group: Uuid) -> impl Iterator<Item = &Utf8Path> {
+        self.suites.iter().filter_map(move |(path, suite)| {
+            suite
+                .as_ref()
+                .is_ok_and(|s| s.group == group)
+                .then_some(path.as_path())
+        })
+    }
+}
+
+/// Output value that didn't match the expectation
+#[derive(Debug, Clone)]
+pub struct OutputMismatch {
+    pub output: String,
+    pub expected: EValue,
+    pub actual: EValue,
+}
+
+/// Outcome of running a single test case
+#[derive(Debug)]
+pub enum TestOutcome {
+    Passed,
+    Failed(Vec<OutputMismatch>),
+    Error(Report),
+}
+
+/// Outcome of [Project::run_graph_tests]
+#[derive(Debug, Default)]
+pub struct TestReport {
+    pub passed: usize,
+    pub failed: usize,
+    /// Test files that failed to load or whose group is missing
+    pub broken_files: usize,
+}
+
+impl TestReport {
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.broken_files == 0
+    }
+}
+
+/// Runs a single test case against the node group, returning the mismatched
+/// outputs
+pub fn run_test_case(
+    registry: &ETypesRegistry,
+    docs: &Docs,
+    graphs: &ProjectGraphs,
+    group: &ProjectGraph,
+    case: &GraphTestCase,
+) -> miette::Result<Vec<OutputMismatch>> {
+    for name in case.inputs.keys() {
+        if !group.inputs().iter().any(|i| &i.name == name) {
+            bail!("node group has no input named `{}`", name);
+        }
+    }
+
+    let mut inputs = Vec::with_capacity(group.inputs().len());
+    for input in group.inputs() {
+        let value = m_try(|| match (input.ty, case.inputs.get(&input.name)) {
+            (Some(ty), Some(json)) => ty.parse_json(registry, &mut json.clone(), false),
+            (Some(ty), None) => Ok(ty.default_value(registry).into_owned()),
+            (None, Some(_)) => bail!("input has no type"),
+            (None, None) => Ok(EValue::Null),
+        })
+        .with_context(|| format!("failed to get value for input `{}`", input.name))?;
+        inputs.push(value);
+    }
+
+    let mut outputs = None;
+    let mut ctx = GraphExecutionContext::from_graph(
+        group.graph(),
+        registry,
+        docs,
+        Some(graphs),
+        SideEffectsContext::unavailable(),
+        true,
+        &inputs,
+        &mut outputs,
+    );
+    ctx.full_eval(false)?;
+    drop(ctx);
+
+    let outputs = match outputs {
+        Some(outputs) => outputs,
+        None if group.outputs().is_empty() => vec![],
+        None => bail!("node group did not emit any outputs"),
+    };
+
+    let mut mismatches = vec![];
+    for (name, json) in &case.outputs {
+        let Some((idx, output)) = group
+            .outputs()
+            .iter()
+            .enumerate()
+            .find(|(_, o)| &o.name == name)
+        else {
+            bail!("node group has no output named `{}`", name);
+        };
+
+        let Some(ty) = output.ty else {
+            bail!("output `{}` has no type", name);
+        };
+
+        let expected = ty
+            .parse_json(registry, &mut json.clone(), false)
+            .with_context(|| format!("failed to parse expected value of output `{}`", name))?;
+
+        let actual = outputs
+            .get(idx)
+            .ok_or_else(|| miette!("node group did not emit output `{}`", name))?;
+
+        if actual != &expected {
+            mismatches.push(OutputMismatch {
+                output: name.clone(),
+                expected,
+                actual: actual.clone(),
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+impl<IO: ProjectIO> Project<IO> {
+    /// Runs all test cases of the project test files
+    ///
+    /// Failed cases are reported as diagnostics of their test files
+    pub fn run_graph_tests(&mut self) -> miette::Result<TestReport> {
+        for graph in self.graphs.graphs.values_mut() {
+            graph.graph_mut().ensure_region_graph_ready();
+        }
+
+        let mut report = TestReport::default();
+
+        for (path, suite) in &self.tests.suites {
+            let mut diagnostics = self.diagnostics.enter(path.as_str());
+            diagnostics.clear_downstream();
+
+            let suite = match suite {
+                Ok(suite) => suite,
+                Err(err) => {
+                    report.broken_files += 1;
+                    diagnostics.emit_error(miette!("{:?}", err));
+                    continue;
+                }
+            };
+
+            let Some(group) = self.graphs.graphs.get(&suite.group) else {
+                report.broken_files += 1;
+                diagnostics
+                    .enter_field("group")
+                    .emit_error(miette!("node group {} is not found", suite.group));
+                continue;
+            };
+
+            if !group.is_node_group {
+                report.broken_files += 1;
+                diagnostics
+                    .enter_field("group")
+                    .emit_error(miette!("graph {} is not a node group", suite.group));
+                continue;
+            }
+
+            let mut cases_ctx = diagnostics.enter_field("cases");
+            for (idx, case) in suite.cases.iter().enumerate() {
+                let outcome =
+                    match run_test_case(&self.registry, &self.docs, &self.graphs, group, case) {
+                        Ok(mismatches) if mismatches.is_empty() => TestOutcome::Passed,
+                        Ok(mismatches) => TestOutcome::Failed(mismatches),
+                        Err(err) => TestOutcome::Error(err),
+                    };
+
+                if matches!(outcome, TestOutcome::Passed) {
+                    report.passed += 1;
+                } else {
+                    report.failed += 1;
+                }
+
+                report_outcome(&mut cases_ctx.enter_index(idx), &case.name, outcome);
+            }
+        }
+
+        info!(
+            passed = report.passed,
+            failed = report.failed,
+            broken_files = report.broken_files,
+            "Node group tests finished"
+        );
+
+        Ok(report)
+    }
+}
+
+fn report_outcome(ctx: &mut DiagnosticContextMut, case: &str, outcome: TestOutcome) {
+    match outcome {
+        TestOutcome::Passed => {}
+        TestOutcome::Failed(mismatches) => {
+            let mut outputs = ctx.enter_field("outputs");
+            for mismatch in mismatches {
+                outputs
+                    .enter_map_key(mismatch.output.clone())
+                    .emit_error(miette!(
+                        "test case `{}`: output `{}` is `{}`, but `{}` was expected",
+                        case,
+                        mismatch.output,
+                        mismatch.actual,
+                        mismatch.expected
+                    ));
+            }
+        }
+        TestOutcome::Error(err) => {
+            ctx.emit_error(err.wrap_err(format!("test case `{}` failed", case)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::graph_tests::{run_test_case, GraphTestCase};
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{graph_node, test_project};
+    use crate::project::Project;
+    use crate::value::EValue;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use uuid::Uuid;
+
+    const GROUP: Uuid = Uuid::from_u128(1);
+
+    /// Node group that sums inputs `A` and `B` into output `Sum`
+    fn group_json() -> String {
+        let (a, b, sum) = (
+            Uuid::from_u128(10),
+            Uuid::from_u128(11),
+            Uuid::from_u128(20),
+        );
+        json!({
+            "version": "V1",
+            "id": GROUP,
+            "name": "Sum",
+            "is_node_group": true,
+            "graph": {
+                "nodes": [
+                    [0, graph_node("group_input", json!({ "ids": [a, b] }))],
+                    [1, graph_node("group_output", json!({ "ids": [sum] }))],
+                    [2, graph_node("add", json!(null))],
+                ],
+                "edges": [
+                    [{ "node": 0, "output": 0 }, { "node": 2, "input": 0 }],
+                    [{ "node": 0, "output": 1 }, { "node": 2, "input": 1 }],
+                    [{ "node": 2, "output": 0 }, { "node": 1, "input": 0 }],
+                ],
+                "inline_values": [],
+                "inputs": [
+                    { "id": a, "name": "A", "ty": "Number" },
+                    { "id": b, "name": "B", "ty": "Number" },
+                ],
+                "outputs": [{ "id": sum, "name": "Sum", "ty": "Number" }],
+                "regions": [],
+            },
+        })
+        .to_string()
+    }
+
+    fn project(tests: serde_json::Value) -> Project<MemoryIO> {
+        test_project(&[
+            ("sum.dbegraph", &group_json()),
+            ("sum.dbetest", &tests.to_string()),
+        ])
+    }
+
+    fn case(inputs: serde_json::Value, outputs: serde_json::Value) -> GraphTestCase {
+        GraphTestCase {
+            name: "case".to_string(),
+            inputs: serde_json::from_value::<BTreeMap<_, _>>(inputs).unwrap(),
+            outputs: serde_json::from_value::<BTreeMap<_, _>>(outputs).unwrap(),
+        }
+    }
+
+    fn run_case(case: &GraphTestCase) -> miette::Result<Vec<super::OutputMismatch>> {
+        let mut project = project(json!({ "group": GROUP }));
+        for graph in project.graphs.graphs.values_mut() {
+            graph.graph_mut().ensure_region_graph_ready();
+        }
+        run_test_case(
+            &project.registry,
+            &project.docs,
+            &project.graphs,
+            &project.graphs.graphs[&GROUP],
+            case,
+        )
+    }
+
+    #[test]
+    fn should_pass_matching_case() {
+        let mut project = project(json!({
+            "group": GROUP,
+            "cases": [
+                { "name": "sum", "inputs": { "A": 2, "B": 3 }, "outputs": { "Sum": 5 } },
+                { "name": "defaults", "outputs": { "Sum": 0 } },
+            ],
+        }));
+
+        let report = project.run_graph_tests().unwrap();
+        assert_eq!(report.passed, 2);
+        assert!(report.is_success());
+    }
+
+    #[test]
+    fn should_report_output_mismatch() {
+        let mismatches = run_case(&case(json!({ "A": 2, "B": 3 }), json!({ "Sum": 6 }))).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].output, "Sum");
+        assert_eq!(mismatches[0].expected, EValue::from(6.0));
+        assert_eq!(mismatches[0].actual, EValue::from(5.0));
+
+        let mut project = project(json!({
+            "group": GROUP,
+            "cases": [{ "name": "wrong", "inputs": { "A": 2 }, "outputs": { "Sum": 3 } }],
+        }));
+        let report = project.run_graph_tests().unwrap();
+        assert_eq!(report.failed, 1);
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn should_reject_unknown_names() {
+        let err = run_case(&case(json!({ "C": 1 }), json!({}))).unwrap_err();
+        assert!(err.to_string().contains("no input named `C`"), "{}", err);
+
+        let err = run_case(&case(json!({}), json!({ "Product": 1 }))).unwrap_err();
+        assert!(
+            err.to_string().contains("no output named `Product`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn should_report_missing_group() {
+        let mut project = project(json!({
+            "group": Uuid::from_u128(2),
+            "cases": [{ "name": "sum", "outputs": { "Sum": 0 } }],
+        }));
+
+        let report = project.run_graph_tests().unwrap();
+        assert_eq!(report.broken_files, 1);
+        assert_eq!(report.passed + report.failed, 0);
+        assert!(!report.is_success());
+    }
+}
@@ -0,0 +1,824 @@
+//! Incremental evaluation of project graphs
+//!
+//! Every graph evaluation records the project state that the graph has read
+//! and the files that it has emitted. On the following evaluations, graphs
+//! whose contents and inputs did not change are skipped, and their
+//! previously generated files are kept as-is.
+
+use crate::graph::execution::profiler::ExecutionProfile;
+use crate::graph::execution::trace::ExecutionTrace;
+use crate::graph::execution::GraphExecutionContext;
+use crate::m_try;
+use crate::project::io::ProjectIO;
+use crate::project::project_graph::{
+    depends_on, order_by_dependencies, transitive_dependencies, EvaluationStage,
+};
+use crate::project::side_effects::diagnostics::GraphDiagnostic;
+use crate::project::side_effects::{GraphInputs, SideEffects, SideEffectsContext};
+use crate::project::{file_state, generated_marker_path, Project, ProjectFile};
+use crate::value::EValue;
+use camino::Utf8PathBuf;
+use itertools::Itertools;
+use miette::{bail, miette, WrapErr};
+use std::collections::{btree_map, BTreeMap, BTreeSet};
+use strum::VariantArray;
+use tracing::info;
+use utils::map::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Records of the last evaluation of each graph
+#[derive(Debug, Default)]
+pub(crate) struct GraphRuns {
+    /// Records keyed by the graph path
+    runs: HashMap<Utf8PathBuf, GraphRun>,
+    /// States of mapping files at the end of the last evaluation
+    mappings: HashMap<Utf8PathBuf, Option<u64>>,
+    /// Diagnostics emitted by each graph, keyed by the graph path
+    ///
+    /// Kept separately from the records, since the side effects are executed
+    /// after the graph record is created
+    diagnostics: BTreeMap<Utf8PathBuf, Vec<GraphDiagnostic>>,
+    /// Paths of the graphs that were evaluated by the last run
+    last_evaluated: BTreeSet<Utf8PathBuf>,
+}
+
+impl GraphRuns {
+    pub(super) fn push_diagnostic(&mut self, graph: Utf8PathBuf, diagnostic: GraphDiagnostic) {
+        self.diagnostics.entry(graph).or_default().push(diagnostic);
+    }
+
+    /// Whenever the graph was evaluated by the last run, as opposed to being
+    /// skipped
+    #[cfg(test)]
+    pub(super) fn was_evaluated(&self, graph: &camino::Utf8Path) -> bool {
+        self.last_evaluated.contains(graph)
+    }
+
+    /// Returns diagnostics emitted by all graphs, grouped by the file they
+    /// are reported against
+    pub(super) fn diagnostics_by_file(&self) -> BTreeMap<&Utf8PathBuf, Vec<&GraphDiagnostic>> {
+        let mut by_file: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for diagnostic in self.diagnostics.values().flatten() {
+            by_file
+                .entry(&diagnostic.file)
+                .or_default()
+                .push(diagnostic);
+        }
+        by_file
+    }
+}
+
+#[derive(Debug)]
+struct GraphRun {
+    /// Hash of the graph and all node groups it uses
+    contents_hash: u64,
+    /// Project state read by the graph
+    inputs: GraphInputs,
+    /// Transient storage entries written by the graph
+    transient: Vec<(String, EValue)>,
+    /// Files emitted by the graph side effects
+    outputs: Vec<Utf8PathBuf>,
+}
+
+impl GraphRun {
+    /// Checks whenever this graph has read any of the files or transient
+    /// values emitted by the other graph
+    fn reads_outputs_of(
+        &self,
+        other: &GraphRun,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+    ) -> bool {
+        let reads_transient = other
+            .transient
+            .iter()
+            .any(|(key, _)| self.inputs.transient_keys.contains_key(key));
+        let reads_files = other.outputs.iter().any(|path| {
+            let Some(ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) =
+                files.get(path)
+            else {
+                return false;
+            };
+            self.inputs.all_files.is_some() || self.inputs.files_of_type.contains_key(&value.ty())
+        });
+        reads_transient || reads_files
+    }
+}
+
+/// Evaluation order of graphs of a single stage
+#[derive(Debug)]
+struct StagePlan {
+    /// Graphs in the evaluation order
+    graphs: Vec<(Utf8PathBuf, Uuid)>,
+    /// Paths of the graphs
+    paths: HashMap<Uuid, Utf8PathBuf>,
+    /// Direct and transitive dependencies of each graph on other graphs of
+    /// the stage, in the evaluation order
+    dependencies: HashMap<Uuid, Vec<Uuid>>,
+    /// Graphs that have other graphs of the stage depending on them
+    has_dependents: HashSet<Uuid>,
+}
+
+impl<IO: ProjectIO> Project<IO> {
+    /// Evaluates graphs whose contents or inputs changed since the last
+    /// evaluation, running side effects
+    ///
+    /// Files generated by the skipped graphs are kept. Falls back to the
+    /// full evaluation when the skipped and evaluated graphs share mappings
+    pub fn evaluate_graphs_incremental(&mut self) -> miette::Result<()> {
+        // Planning reports dependency problems, so the plans are reused by
+        // the fallback evaluation instead of being reported twice
+        let plans = self.plan_graphs()?;
+        if !self.run_planned_graphs(&plans, false)? {
+            info!("Graphs that share mappings need to be re-evaluated, falling back to full evaluation");
+            self.run_planned_graphs(&plans, true)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluates the project graphs, recording their inputs and outputs
+    ///
+    /// When `clean` is true, all graphs are evaluated. Otherwise, only the
+    /// graphs whose contents or inputs changed are evaluated.
+    ///
+    /// Returns `false` if the evaluation was aborted and needs to be
+    /// restarted as a clean one
+    pub(super) fn run_graphs(&mut self, clean: bool) -> miette::Result<bool> {
+        let plans = self.plan_graphs()?;
+        self.run_planned_graphs(&plans, clean)
+    }
+
+    /// Plans the evaluation order of the graphs of every stage
+    ///
+    /// Plans use the records of the previous evaluation, so they must be
+    /// made before the records are cleared
+    fn plan_graphs(&mut self) -> miette::Result<[StagePlan; EvaluationStage::len()]> {
+        for graph in self.graphs.graphs.values_mut() {
+            graph.graph_mut().ensure_region_graph_ready();
+        }
+
+        let mut stages: [Vec<_>; EvaluationStage::len()] = [vec![], vec![], vec![], vec![], vec![]];
+
+        for (path, file) in &self.files {
+            let ProjectFile::Graph(id) = file else {
+                continue;
+            };
+
+            let Some(graph) = self.graphs.graphs.get(id) else {
+                bail!("graph {:?} at path {} is not found", id, path);
+            };
+
+            if graph.is_node_group {
+                continue;
+            }
+            let stage = graph.stage;
+            stages[stage as usize].push((path.clone(), *id));
+        }
+
+        Ok(stages.map(|graphs| self.plan_stage(graphs)))
+    }
+
+    /// See [Project::run_graphs]
+    fn run_planned_graphs(
+        &mut self,
+        plans: &[StagePlan; EvaluationStage::len()],
+        clean: bool,
+    ) -> miette::Result<bool> {
+        let generated = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.is_generated())
+            .map(|(path, _)| path.clone())
+            .collect_vec();
+
+        if clean {
+            self.graph_runs = Default::default();
+        }
+        self.graph_runs.last_evaluated.clear();
+
+        let forced = self.forced_graphs(plans);
+
+        // Generated files are put aside, and restored once the skipped graph
+        // that emitted them would have emitted them again
+        let mut stashed = BTreeMap::new();
+        for path in generated {
+            if let Some(file) = self.files.remove(&path) {
+                stashed.insert(path, file);
+            }
+        }
+
+        let mut evaluated_count = 0usize;
+        let mut skipped_count = 0usize;
+        let mut present = HashSet::default();
+
+        let result = m_try(|| {
+            let mut side_effects = SideEffects::new();
+            // Mapping files used by skipped and evaluated graphs
+            let mut skipped_mappings = BTreeSet::new();
+            let mut used_mappings = BTreeSet::new();
+
+            for (stage_index, plan) in plans.iter().enumerate() {
+                let stage = EvaluationStage::VARIANTS[stage_index];
+                side_effects.set_stage(stage);
+                let mut evaluated = vec![];
+                let mut skipped = vec![];
+                for (path, id) in &plan.graphs {
+                    present.insert(path.clone());
+                    side_effects.start_graph();
+
+                    // Transient values written by the direct and transitive
+                    // dependencies are visible to the graph
+                    for dep in plan.dependencies.get(id).into_iter().flatten() {
+                        let Some(run) = self.graph_runs.runs.get(&plan.paths[dep]) else {
+                            continue;
+                        };
+                        for (key, value) in &run.transient {
+                            side_effects.provide_transient_storage(key.clone(), value.clone());
+                        }
+                    }
+
+                    let traced = self.debugger.is_traced(*id);
+                    let profiled = self.debugger.is_profiled(*id);
+                    // Traced and profiled graphs are always evaluated to
+                    // record a fresh trace or profile
+                    if !forced.contains(path) && !traced && !profiled {
+                        let run = &self.graph_runs.runs[path];
+                        let changed = run
+                            .inputs
+                            .mappings
+                            .iter()
+                            .any(|m| used_mappings.contains(m))
+                            || run.inputs.changed(&self.files, &mut side_effects);
+                        if !changed {
+                            skipped_mappings.extend(run.inputs.mappings.iter().cloned());
+                            if plan.has_dependents.contains(id) {
+                                restore_outputs(
+                                    &mut self.files,
+                                    &mut stashed,
+                                    &mut side_effects,
+                                    &run.outputs,
+                                );
+                            }
+                            skipped.push(path);
+                            continue;
+                        }
+                    }
+
+                    self.graph_runs.diagnostics.remove(path);
+
+                    let mut trace = traced.then(ExecutionTrace::default);
+                    let mut profile = profiled.then(ExecutionProfile::default);
+                    let eval_result = m_try(|| {
+                        let Some(graph) = self.graphs.graphs.get(id) else {
+                            bail!("!!INTERNAL!! graph {:?} at path {} is missing, even tho it passed the stages check", id, path);
+                        };
+
+                        if graph.is_node_group {
+                            bail!(
+                                "!!INTERNAL!! graph {:?} at path {} is a node group",
+                                id,
+                                path
+                            );
+                        }
+
+                        let out_values = &mut None;
+                        let mut ctx = GraphExecutionContext::from_graph(
+                            graph.graph(),
+                            &self.registry,
+                            &self.docs,
+                            Some(&self.graphs),
+                            SideEffectsContext::new(&mut side_effects, path.clone(), &self.files),
+                            graph.is_node_group,
+                            &[],
+                            out_values,
+                        );
+                        if let Some(trace) = &mut trace {
+                            ctx = ctx.with_trace(trace);
+                        }
+                        if let Some(profile) = &mut profile {
+                            ctx = ctx.with_profile(profile);
+                        }
+                        ctx.full_eval(true)?;
+                        drop(ctx);
+                        if out_values.is_some() {
+                            bail!("graph {:?} at path {} has outputs", id, path);
+                        }
+                        Ok(())
+                    });
+
+                    if let Some(trace) = trace {
+                        let graph_hash = self.graphs.contents_hash(*id);
+                        self.debugger.set_trace(*id, trace, graph_hash);
+                    }
+                    if let Some(profile) = profile {
+                        self.debugger.graph_mut(*id).profile = Some(profile);
+                    }
+
+                    eval_result
+                        .with_context(|| format!("failed to evaluate graph at `{}`", path))?;
+
+                    let inputs = side_effects.take_inputs();
+                    if inputs.mappings.iter().any(|m| skipped_mappings.contains(m)) {
+                        // Mappings state of the skipped graphs is not restored
+                        return Ok(false);
+                    }
+                    used_mappings.extend(inputs.mappings.iter().cloned());
+
+                    let run = GraphRun {
+                        contents_hash: self.graphs.contents_hash(*id),
+                        inputs,
+                        transient: side_effects.transient_outputs(),
+                        outputs: vec![],
+                    };
+                    self.graph_runs.runs.insert(path.clone(), run);
+                    evaluated.push(path);
+
+                    // Files emitted by the graph should be visible to the
+                    // graphs that depend on it
+                    if plan.has_dependents.contains(id) {
+                        side_effects.execute(self)?;
+                    }
+                }
+
+                side_effects.execute(self)?;
+
+                for path in evaluated {
+                    let outputs = side_effects.take_emitted(path);
+                    if let Some(run) = self.graph_runs.runs.get_mut(path) {
+                        run.outputs = outputs;
+                    }
+                    self.graph_runs.last_evaluated.insert(path.clone());
+                    evaluated_count += 1;
+                }
+
+                for path in skipped {
+                    let run = &self.graph_runs.runs[path];
+                    restore_outputs(
+                        &mut self.files,
+                        &mut stashed,
+                        &mut side_effects,
+                        &run.outputs,
+                    );
+                    skipped_count += 1;
+                }
+            }
+
+            Ok(true)
+        });
+
+        // Generated files that were not emitted again are deleted
+        for path in stashed.into_keys() {
+            if !self.files.contains_key(&path) {
+                self.to_delete.insert(generated_marker_path(&path));
+                self.to_delete.insert(path);
+            }
+        }
+
+        match result {
+            Ok(true) => {}
+            Ok(false) => return Ok(false),
+            Err(err) => {
+                self.graph_runs = Default::default();
+                return Err(err);
+            }
+        }
+
+        self.graph_runs
+            .runs
+            .retain(|path, _| present.contains(path));
+        self.graph_runs
+            .diagnostics
+            .retain(|path, _| present.contains(path));
+        self.graph_runs.mappings = self
+            .graph_runs
+            .runs
+            .values()
+            .flat_map(|run| run.inputs.mappings.iter())
+            .map(|path| {
+                let state = self
+                    .files
+                    .get(path)
+                    .and_then(|file| file_state(file, &self.graphs));
+                (path.clone(), state)
+            })
+            .collect();
+        self.dirty_graphs.clear();
+
+        info!(
+            evaluated = evaluated_count,
+            skipped = skipped_count,
+            "Graphs evaluated"
+        );
+
+        Ok(true)
+    }
+
+    /// Orders graphs of a single stage by their dependencies, reporting
+    /// dependency cycles and invalid declared dependencies
+    ///
+    /// In addition to dependencies provided by
+    /// [ProjectGraphs::stage_dependencies], graphs that have read files or
+    /// transient values emitted by other graphs of the same stage during the
+    /// previous evaluation are made dependent on them, unless that would
+    /// form a cycle. Side effects of graphs that nothing depends on are
+    /// executed at the end of the stage
+    fn plan_stage(&mut self, graphs: Vec<(Utf8PathBuf, Uuid)>) -> StagePlan {
+        for (path, id) in &graphs {
+            let Some(graph) = self.graphs.graphs.get(id) else {
+                continue;
+            };
+            for dep in &graph.dependencies {
+                let problem = match self.graphs.graphs.get(dep) {
+                    None => "does not exist",
+                    Some(dep) if dep.is_node_group => "is a node group",
+                    Some(dep) if dep.stage > graph.stage => "is evaluated in a later stage",
+                    Some(_) => continue,
+                };
+                self.diagnostics.enter(path.as_str()).emit_warning(miette!(
+                    "graph dependency {} {}",
+                    dep,
+                    problem
+                ));
+            }
+        }
+
+        let ids = graphs.iter().map(|(_, id)| *id).collect_vec();
+        let mut direct = self.graphs.stage_dependencies(&ids);
+
+        for (path, id) in &graphs {
+            let Some(run) = self.graph_runs.runs.get(path) else {
+                continue;
+            };
+            for (other_path, other_id) in &graphs {
+                if other_id == id {
+                    continue;
+                }
+                let Some(other) = self.graph_runs.runs.get(other_path) else {
+                    continue;
+                };
+                if run.reads_outputs_of(other, &self.files) && !depends_on(&direct, *other_id, *id)
+                {
+                    direct.entry(*id).or_default().insert(*other_id);
+                }
+            }
+        }
+
+        let (order, cycles) = order_by_dependencies(&ids, &direct);
+        let paths: HashMap<Uuid, Utf8PathBuf> =
+            graphs.into_iter().map(|(path, id)| (id, path)).collect();
+
+        for cycle in cycles {
+            let description = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|id| format!("`{}`", paths[id]))
+                .join(" -> ");
+            for id in &cycle {
+                self.diagnostics
+                    .enter(paths[id].as_str())
+                    .emit_error(miette!("graphs form a dependency cycle: {}", description));
+            }
+        }
+
+        let has_dependents = direct.values().flatten().copied().collect();
+        let position: HashMap<Uuid, usize> =
+            order.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let dependencies = ids
+            .iter()
+            .map(|id| {
+                let deps = transitive_dependencies(&direct, *id)
+                    .into_iter()
+                    .sorted_by_key(|dep| position[dep])
+                    .collect_vec();
+                (*id, deps)
+            })
+            .collect();
+
+        StagePlan {
+            graphs: order
+                .into_iter()
+                .map(|id| (paths[&id].clone(), id))
+                .collect(),
+            paths,
+            dependencies,
+            has_dependents,
+        }
+    }
+
+    /// Collects paths of graphs that need to be evaluated regardless of
+    /// their inputs
+    fn forced_graphs(&self, plans: &[StagePlan]) -> HashSet<Utf8PathBuf> {
+        let mut forced = HashSet::default();
+        let mut forced_mappings = BTreeSet::new();
+
+        for (path, id) in plans.iter().flat_map(|plan| &plan.graphs) {
+            let Some(run) = self.graph_runs.runs.get(path) else {
+                forced.insert(path.clone());
+                continue;
+            };
+
+            let mappings_changed = run.inputs.mappings.iter().any(|mapping| {
+                let state = self
+                    .files
+                    .get(mapping)
+                    .and_then(|file| file_state(file, &self.graphs));
+                self.graph_runs.mappings.get(mapping) != Some(&state)
+            });
+
+            if mappings_changed
+                || self.dirty_graphs.contains(id)
+                || run.contents_hash != self.graphs.contents_hash(*id)
+            {
+                forced.insert(path.clone());
+                forced_mappings.extend(run.inputs.mappings.iter().cloned());
+            }
+        }
+
+        // Mappings are shared between all graphs that use them, so such
+        // graphs are re-evaluated together
+        loop {
+            let mut changed = false;
+            for (path, _) in plans.iter().flat_map(|plan| &plan.graphs) {
+                if forced.contains(path) {
+                    continue;
+                }
+                let run = &self.graph_runs.runs[path];
+                if run
+                    .inputs
+                    .mappings
+                    .iter()
+                    .any(|m| forced_mappings.contains(m))
+                {
+                    forced.insert(path.clone());
+                    forced_mappings.extend(run.inputs.mappings.iter().cloned());
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        forced
+    }
+}
+
+/// Moves previously generated files back into the project, unless they were
+/// emitted again
+fn restore_outputs(
+    files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
+    stashed: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
+    side_effects: &mut SideEffects,
+    outputs: &[Utf8PathBuf],
+) {
+    for output in outputs {
+        let Some(file) = stashed.remove(output) else {
+            continue;
+        };
+        if let btree_map::Entry::Vacant(entry) = files.entry(output.clone()) {
+            let file = entry.insert(file);
+            side_effects.file_changed(output, Some(&*file));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::etype::EDataType;
+    use crate::project::io::MemoryIO;
+    use crate::project::project_graph::EvaluationStage;
+    use crate::project::side_effects::{SideEffects, ValueHashes};
+    use crate::project::test_utils::{
+        damage_project, graph_id, graph_node, set_field, test_project, DAMAGE_ITEM_TYPE,
+        TEST_GRAPH_PATH,
+    };
+    use crate::project::Project;
+    use crate::value::id::ETypeId;
+    use camino::{Utf8Path, Utf8PathBuf};
+    use diagnostic::diagnostic::DiagnosticLevel;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn item_ty() -> EDataType {
+        EDataType::Object {
+            ident: ETypeId::parse("test:item").unwrap(),
+        }
+    }
+
+    fn project() -> Project<MemoryIO> {
+        damage_project(&[
+            (
+                "test.dbemodule/types/weapon.kdl",
+                r#"struct { number "Speed"; }"#,
+            ),
+            (
+                "b.dbevalue",
+                r#"{ "ty": { "Object": { "ident": "test:weapon" } }, "value": { "Speed": 1 } }"#,
+            ),
+        ])
+    }
+
+    /// Makes the last run of the graph look like it has read values of the
+    /// item type
+    fn read_items(project: &mut Project<MemoryIO>) {
+        let mut side_effects = SideEffects::new();
+        side_effects.record_files_read(&project.files, Some(item_ty()));
+        project
+            .graph_runs
+            .runs
+            .get_mut(Utf8Path::new(TEST_GRAPH_PATH))
+            .unwrap()
+            .inputs = side_effects.take_inputs();
+    }
+
+    fn was_evaluated(project: &Project<MemoryIO>) -> bool {
+        project
+            .graph_runs
+            .last_evaluated
+            .contains(Utf8Path::new(TEST_GRAPH_PATH))
+    }
+
+    #[test]
+    fn should_skip_unchanged_graphs() {
+        let mut project = project();
+        assert!(project.run_graphs(true).unwrap());
+        assert!(was_evaluated(&project));
+
+        read_items(&mut project);
+        assert!(project.run_graphs(false).unwrap());
+        assert!(!was_evaluated(&project));
+
+        // Values of the other types are not read by the graph
+        set_field(&mut project, "b.dbevalue", "Speed", 2.0);
+        assert!(project.run_graphs(false).unwrap());
+        assert!(!was_evaluated(&project));
+    }
+
+    #[test]
+    fn should_rerun_graphs_with_changed_inputs() {
+        let mut project = project();
+        assert!(project.run_graphs(true).unwrap());
+        read_items(&mut project);
+
+        set_field(&mut project, "a.json", "Damage", 2.0);
+        assert!(project.run_graphs(false).unwrap());
+        assert!(was_evaluated(&project));
+    }
+
+    #[test]
+    fn should_rerun_dirty_graphs() {
+        let mut project = project();
+        assert!(project.run_graphs(true).unwrap());
+
+        let id = graph_id(&project, TEST_GRAPH_PATH);
+        project.dirty_graphs.insert(id);
+        assert!(project.run_graphs(false).unwrap());
+        assert!(was_evaluated(&project));
+        assert!(project.dirty_graphs.is_empty());
+
+        assert!(project.run_graphs(false).unwrap());
+        assert!(!was_evaluated(&project));
+    }
+
+    #[test]
+    fn value_hashes_should_track_changes() {
+        let mut project = project();
+        let mut hashes = ValueHashes::new(&project.files);
+        let initial = (hashes.all(), hashes.of_type(item_ty()));
+
+        set_field(&mut project, "a.json", "Damage", 2.0);
+        let path = Utf8PathBuf::from("a.json");
+        hashes.update(&path, project.files.get(&path));
+        let rebuilt = ValueHashes::new(&project.files);
+        assert_eq!(hashes.all(), rebuilt.all());
+        assert_eq!(hashes.of_type(item_ty()), rebuilt.of_type(item_ty()));
+        assert_ne!(hashes.of_type(item_ty()), initial.1);
+
+        let file = project.files.remove(&path).unwrap();
+        hashes.update(&path, None);
+        assert_eq!(hashes.of_type(item_ty()), 0);
+
+        project.files.insert(path.clone(), file);
+        set_field(&mut project, "a.json", "Damage", 1.0);
+        hashes.update(&path, project.files.get(&path));
+        assert_eq!((hashes.all(), hashes.of_type(item_ty())), initial);
+    }
+
+    #[test]
+    fn should_report_planning_diagnostics_once_on_fallback() {
+        // Declares a dependency on a graph that does not exist
+        let skipped = json!({
+            "version": "V1",
+            "id": Uuid::from_u128(1),
+            "dependencies": [Uuid::from_u128(99)],
+            "graph": { "nodes": [], "edges": [], "inline_values": [] },
+        })
+        .to_string();
+        let evaluated = json!({
+            "version": "V1",
+            "id": Uuid::from_u128(2),
+            "graph": {
+                "nodes": [[0, graph_node("set_mapping", json!(null))]],
+                "edges": [],
+                "inline_values": [
+                    [{ "node": 0, "input": 0 }, "ids"],
+                    [{ "node": 0, "input": 2 }, "a"],
+                    [{ "node": 0, "input": 3 }, 1],
+                ],
+            },
+        })
+        .to_string();
+        let mut project = test_project(&[("a.dbegraph", &skipped), ("b.dbegraph", &evaluated)]);
+        assert!(project.run_graphs(true).unwrap());
+
+        // The skipped graph shares the mappings with the evaluated one, which
+        // aborts the incremental evaluation
+        let mappings = Utf8PathBuf::from("ids.dbevalue");
+        project
+            .graph_runs
+            .runs
+            .get_mut(Utf8Path::new("a.dbegraph"))
+            .unwrap()
+            .inputs
+            .mappings
+            .insert(mappings);
+
+        project.diagnostics.diagnostics.clear();
+        project.dirty_graphs.insert(Uuid::from_u128(2));
+        project.evaluate_graphs_incremental().unwrap();
+        // Skipped graph is only evaluated by the clean fallback
+        assert!(project
+            .graph_runs
+            .last_evaluated
+            .contains(Utf8Path::new("a.dbegraph")));
+
+        let warnings = project
+            .diagnostics
+            .diagnostics
+            .get("a.dbegraph")
+            .into_iter()
+            .flat_map(|reports| reports.values().flatten())
+            .filter(|report| report.level == DiagnosticLevel::Warning)
+            .map(|report| report.info.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [format!(
+                "graph dependency {} does not exist",
+                Uuid::from_u128(99)
+            )]
+        );
+    }
+
+    #[test]
+    fn should_infer_dependencies_from_previous_outputs() {
+        let graph = |id: u128| {
+            json!({
+                "version": "V1",
+                "id": Uuid::from_u128(id),
+                "graph": { "nodes": [], "edges": [], "inline_values": [] },
+            })
+            .to_string()
+        };
+        let (reader, emitter) = (graph(1), graph(2));
+        let mut project = test_project(&[
+            ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+            ("a.json", r#"{ "Damage": 1 }"#),
+            ("c.json", r#"{ "Damage": 2 }"#),
+            ("a.dbegraph", &reader),
+            ("b.dbegraph", &emitter),
+        ]);
+        assert!(project.run_graphs(true).unwrap());
+
+        let order = |project: &mut Project<MemoryIO>| {
+            let plans = project.plan_graphs().unwrap();
+            plans[EvaluationStage::Main.index()]
+                .graphs
+                .iter()
+                .map(|(path, _)| path.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(&mut project), ["a.dbegraph", "b.dbegraph"]);
+
+        // Both graphs read the files emitted by each other, only the first
+        // inferred dependency is kept to avoid the cycle
+        let mut side_effects = SideEffects::new();
+        side_effects.record_files_read(&project.files, Some(item_ty()));
+        let inputs = side_effects.take_inputs();
+        for (path, emitted) in [("a.dbegraph", "c.json"), ("b.dbegraph", "a.json")] {
+            let run = project
+                .graph_runs
+                .runs
+                .get_mut(Utf8Path::new(path))
+                .unwrap();
+            run.inputs = inputs.clone();
+            run.outputs = vec![emitted.into()];
+        }
+
+        assert_eq!(order(&mut project), ["b.dbegraph", "a.dbegraph"]);
+        let plans = project.plan_graphs().unwrap();
+        let plan = &plans[EvaluationStage::Main.index()];
+        assert_eq!(plan.dependencies[&Uuid::from_u128(1)], [Uuid::from_u128(2)]);
+        assert!(plan.dependencies[&Uuid::from_u128(2)].is_empty());
+        assert!(!project.diagnostics.has_diagnostics(DiagnosticLevel::Error));
+    }
+}
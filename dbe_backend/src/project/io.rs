@@ -1,8 +1,10 @@
 pub use fs::FilesystemIO;
+pub use memory::MemoryIO;
 use std::path::{Path, PathBuf};
 
 mod embedded;
 mod fs;
+mod memory;
 
 pub trait ProjectIO: Send + Sync {
     fn list_files(&self) -> miette::Result<impl IntoIterator<Item = PathBuf> + 'static>;
@@ -16,9 +18,31 @@ pub trait ProjectIO: Send + Sync {
     /// Flush any pending state changes. Should be called after any calls to
     /// `read_file`, `write_file`, or `delete_file`.
     fn flush(&mut self) -> miette::Result<()>;
+
+    /// Returns files that were changed by external tools since the last call
+    /// to this method
+    ///
+    /// Only files that were previously read via `read_file` are reported as
+    /// modified, newly appeared files are always reported.
+    /// [FilesystemIO] only reports changes after [FilesystemIO::watch] is
+    /// called
+    fn poll_changes(&self) -> miette::Result<Vec<FileChange>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub kind: FileChangeKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Removed,
 }
 
-fn sha256(data: &impl AsRef<[u8]>) -> Vec<u8> {
+pub(crate) fn sha256(data: &impl AsRef<[u8]>) -> Vec<u8> {
     let mut hasher = <sha2::Sha256 as sha2::Digest>::new();
     sha2::Digest::update(&mut hasher, data);
     sha2::Digest::finalize(hasher).to_vec()
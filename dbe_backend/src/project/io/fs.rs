@@ -1,22 +1,34 @@
 use crate::m_try;
 use crate::project::io::embedded::{walk_files, MODULES};
-use crate::project::io::{sha256, ProjectIO};
-use crate::project::EXTENSION_MODULE;
+use crate::project::io::{sha256, FileChange, FileChangeKind, ProjectIO};
+use crate::project::{CACHE_FOLDER, EXTENSION_MODULE};
 use include_dir::DirEntry;
 use itertools::Itertools;
 use miette::{bail, Context, IntoDiagnostic};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+use parking_lot::Mutex;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 use tracing::{error, trace};
 use utils::map::dashmap::Entry;
 use utils::map::DashMap;
 use walkdir::WalkDir;
 use zip::ZipArchive;
 
+/// Folders that are not watched for changes
+const IGNORED_FOLDERS: &[&str] = &[".git", "target", CACHE_FOLDER];
+
 pub struct FilesystemIO {
     root: PathBuf,
     files: DashMap<PathBuf, FileData>,
+    /// File system watcher, present once [FilesystemIO::watch] is called
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Paths reported by the watcher since the last poll
+    events: Arc<Mutex<BTreeSet<PathBuf>>>,
 }
 
 impl FilesystemIO {
@@ -24,6 +36,8 @@ impl FilesystemIO {
         let mut fs = Self {
             root,
             files: Default::default(),
+            watcher: Default::default(),
+            events: Default::default(),
         };
         fs.load_files()?;
         Ok(fs)
@@ -71,8 +85,7 @@ impl FilesystemIO {
             },
         );
 
-        let wd = WalkDir::new(&self.root);
-        for entry in wd {
+        for entry in walk_project(&self.root, &self.root) {
             let entry = entry.into_diagnostic()?;
             if entry.path().is_dir() {
                 continue;
@@ -144,7 +157,10 @@ impl FilesystemIO {
                 self.files.insert(
                     path.clone(),
                     FileData {
-                        kind: FileKind::Fs { hash: None },
+                        kind: FileKind::Fs {
+                            hash: None,
+                            modified: None,
+                        },
                     },
                 );
             }
@@ -176,10 +192,14 @@ impl ProjectIO for FilesystemIO {
         let data = fs_err::read(&path).into_diagnostic()?;
 
         let hash = sha256(&data);
+        let modified = modified_time(&path);
         self.files.insert(
             path,
             FileData {
-                kind: FileKind::Fs { hash: Some(hash) },
+                kind: FileKind::Fs {
+                    hash: Some(hash),
+                    modified,
+                },
             },
         );
 
@@ -200,7 +220,9 @@ impl ProjectIO for FilesystemIO {
         let hash = sha256(&data);
         match self.files.entry(path.clone()) {
             Entry::Occupied(mut f) => match &mut f.get_mut().kind {
-                FileKind::Fs { hash: file_hash } => {
+                FileKind::Fs {
+                    hash: file_hash, ..
+                } => {
                     if file_hash.as_ref().is_some_and(|h| h == &hash) {
                         return Ok(());
                     }
@@ -230,7 +252,10 @@ impl ProjectIO for FilesystemIO {
             },
             Entry::Vacant(e) => {
                 e.insert(FileData {
-                    kind: FileKind::Fs { hash: Some(hash) },
+                    kind: FileKind::Fs {
+                        hash: Some(hash),
+                        modified: None,
+                    },
                 });
             }
         }
@@ -241,6 +266,14 @@ impl ProjectIO for FilesystemIO {
 
         fs_err::write(&path, data).into_diagnostic()?;
 
+        // Remember the modification time of our own write, so it doesn't get
+        // reported as an external change
+        if let Some(mut file) = self.files.get_mut(&path) {
+            if let FileKind::Fs { modified, .. } = &mut file.kind {
+                *modified = modified_time(&path);
+            }
+        }
+
         Ok(())
     }
 
@@ -273,6 +306,184 @@ impl ProjectIO for FilesystemIO {
     fn flush(&mut self) -> miette::Result<()> {
         Ok(())
     }
+
+    fn poll_changes(&self) -> miette::Result<Vec<FileChange>> {
+        let events = std::mem::take(&mut *self.events.lock());
+        let mut changes = BTreeMap::new();
+
+        for path in events {
+            let path = self.process_path(&path)?;
+            if path.is_dir() {
+                // Directories that were created or moved into the project
+                // after the watch started are not watched yet
+                self.watch_dirs(&path)?;
+                for entry in walk_project(&self.root, &path) {
+                    let entry = entry.into_diagnostic()?;
+                    if entry.file_type().is_file() {
+                        let path = self.process_path(entry.path())?;
+                        self.check_file(path, &mut changes)?;
+                    }
+                }
+            } else if path.is_file() {
+                self.check_file(path, &mut changes)?;
+            } else {
+                // Removal of a directory is only reported for the directory
+                // itself, so all the files inside are checked
+                let removed = self
+                    .files
+                    .iter()
+                    .filter(|entry| entry.value().is_writeable() && entry.key().starts_with(&path))
+                    .map(|entry| entry.key().clone())
+                    .collect_vec();
+                for path in removed {
+                    self.files.remove(&path);
+                    changes.insert(path, FileChangeKind::Removed);
+                }
+            }
+        }
+
+        Ok(changes
+            .into_iter()
+            .map(|(path, kind)| FileChange { path, kind })
+            .collect())
+    }
+}
+
+impl FilesystemIO {
+    /// Starts watching the project folder for changes made by external tools
+    ///
+    /// Changes are reported by [ProjectIO::poll_changes]. Version control,
+    /// build and project cache folders are not watched
+    pub fn watch(&self) -> miette::Result<()> {
+        let events = self.events.clone();
+        let root = self.root.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    error!("file watcher error: {}", err);
+                    return;
+                }
+            };
+            if event.kind.is_access() {
+                return;
+            }
+            events.lock().extend(
+                event
+                    .paths
+                    .into_iter()
+                    .filter(|path| !is_ignored(&root, path)),
+            );
+        })
+        .into_diagnostic()
+        .context("failed to create file watcher")?;
+
+        *self.watcher.lock() = Some(watcher);
+        self.watch_dirs(&self.root)
+    }
+
+    /// Adds the directory and all its subdirectories to the watcher, if it
+    /// is running
+    fn watch_dirs(&self, dir: &Path) -> miette::Result<()> {
+        let mut watcher = self.watcher.lock();
+        let Some(watcher) = watcher.as_mut() else {
+            return Ok(());
+        };
+
+        // Directories are watched one by one, so the ignored directories
+        // don't need to be watched at all
+        for entry in walk_project(&self.root, dir) {
+            let entry = entry.into_diagnostic()?;
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            watcher
+                .watch(entry.path(), RecursiveMode::NonRecursive)
+                .into_diagnostic()
+                .with_context(|| format!("failed to watch `{}`", entry.path().display()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the file for changes against the last known state
+    ///
+    /// Only files that were previously read are reported as modified
+    fn check_file(
+        &self,
+        path: PathBuf,
+        changes: &mut BTreeMap<PathBuf, FileChangeKind>,
+    ) -> miette::Result<()> {
+        let modified = modified_time(&path);
+
+        let known_hash = {
+            let Some(mut file) = self.files.get_mut(&path) else {
+                // Module archives are only expanded upon project load
+                if path.extension().and_then(|ext| ext.to_str()) != Some(EXTENSION_MODULE) {
+                    changes.insert(path, FileChangeKind::Created);
+                }
+                return Ok(());
+            };
+
+            let FileKind::Fs {
+                hash: Some(hash),
+                modified: known_modified,
+            } = &mut file.kind
+            else {
+                return Ok(());
+            };
+
+            if modified.is_some() && *known_modified == modified {
+                return Ok(());
+            }
+            *known_modified = modified;
+            hash.clone()
+        };
+
+        let data = fs_err::read(&path).into_diagnostic()?;
+        let hash = sha256(&data);
+        if hash == known_hash {
+            return Ok(());
+        }
+
+        if let Some(mut file) = self.files.get_mut(&path) {
+            if let FileKind::Fs {
+                hash: file_hash, ..
+            } = &mut file.kind
+            {
+                *file_hash = Some(hash);
+            }
+        }
+
+        changes.insert(path, FileChangeKind::Modified);
+        Ok(())
+    }
+}
+
+/// Walks the directory inside the project, skipping the ignored directories
+fn walk_project<'a>(
+    root: &'a Path,
+    dir: &Path,
+) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> + 'a {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(move |entry| !is_ignored(root, entry.path()))
+}
+
+/// Checks if the path is inside of the version control, build or project
+/// cache folder
+fn is_ignored(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root).is_ok_and(|relative| {
+        relative.components().any(|c| {
+            IGNORED_FOLDERS
+                .iter()
+                .any(|ignored| c.as_os_str() == *ignored)
+        })
+    })
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs_err::metadata(path).ok().and_then(|m| m.modified().ok())
 }
 
 #[derive(Debug)]
@@ -294,6 +505,9 @@ impl FileData {
 enum FileKind {
     Fs {
         hash: Option<Vec<u8>>,
+        /// Modification time of the file at the moment of the last read or
+        /// write, used to skip hashing of unchanged files when polling
+        modified: Option<SystemTime>,
     },
     Mem {
         content: Cow<'static, [u8]>,
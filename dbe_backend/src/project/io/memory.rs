@@ -0,0 +1,164 @@
+use crate::project::io::embedded::{walk_files, MODULES};
+use crate::project::io::{FileChange, FileChangeKind, ProjectIO};
+use include_dir::DirEntry;
+use itertools::Itertools;
+use miette::{bail, miette};
+use parking_lot::Mutex;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use utils::map::DashMap;
+
+/// Project IO that keeps all files in memory
+///
+/// Embedded modules are available as read-only files, same as with
+/// [super::FilesystemIO]. Changes made by external tools can be simulated
+/// with [MemoryIO::change_external]
+pub struct MemoryIO {
+    root: PathBuf,
+    files: DashMap<PathBuf, MemoryFile>,
+    changes: Mutex<Vec<FileChange>>,
+}
+
+struct MemoryFile {
+    content: Cow<'static, [u8]>,
+    writable: bool,
+}
+
+impl MemoryIO {
+    pub fn new(root: PathBuf) -> Self {
+        let io = Self {
+            root,
+            files: Default::default(),
+            changes: Default::default(),
+        };
+
+        let embedded_dir = io.root.join("\0<embedded>\0");
+        for file in walk_files(&MODULES).filter_map(DirEntry::as_file) {
+            io.files.insert(
+                embedded_dir.join(file.path()),
+                MemoryFile {
+                    content: Cow::Borrowed(file.contents()),
+                    writable: false,
+                },
+            );
+        }
+
+        io
+    }
+
+    /// Adds a writable file, without reporting it as a change
+    pub fn insert(&self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) {
+        self.files.insert(
+            self.root.join(path),
+            MemoryFile {
+                content: Cow::Owned(data.into()),
+                writable: true,
+            },
+        );
+    }
+
+    /// Returns content of the file, if present
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.files
+            .get(&self.root.join(path))
+            .map(|file| file.content.to_vec())
+    }
+
+    /// Changes or removes the file as if it was done by an external tool,
+    /// so the change is reported by [ProjectIO::poll_changes]
+    pub fn change_external(&self, path: impl AsRef<Path>, data: Option<Vec<u8>>) {
+        let path = self.root.join(path);
+        let kind = match data {
+            None => {
+                if self.files.remove(&path).is_none() {
+                    return;
+                }
+                FileChangeKind::Removed
+            }
+            Some(data) => {
+                let previous = self.files.insert(
+                    path.clone(),
+                    MemoryFile {
+                        content: Cow::Owned(data),
+                        writable: true,
+                    },
+                );
+                if previous.is_some() {
+                    FileChangeKind::Modified
+                } else {
+                    FileChangeKind::Created
+                }
+            }
+        };
+        self.changes.lock().push(FileChange { path, kind });
+    }
+
+    fn process_path(&self, path: impl AsRef<Path>) -> miette::Result<PathBuf> {
+        let path = path_clean::clean(self.root.join(path));
+        if !path.starts_with(&self.root) {
+            bail!("path `{}` is outside of the project root", path.display());
+        }
+        Ok(path)
+    }
+}
+
+impl ProjectIO for MemoryIO {
+    fn list_files(&self) -> miette::Result<impl IntoIterator<Item = PathBuf> + 'static> {
+        Ok(self
+            .files
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect_vec())
+    }
+
+    fn read_file(&self, path: impl AsRef<Path>) -> miette::Result<Vec<u8>> {
+        let path = self.process_path(path)?;
+        self.files
+            .get(&path)
+            .map(|file| file.content.to_vec())
+            .ok_or_else(|| miette!("file `{}` does not exist", path.display()))
+    }
+
+    fn file_exists(&self, path: impl AsRef<Path>) -> miette::Result<bool> {
+        Ok(self.files.contains_key(&self.process_path(path)?))
+    }
+
+    fn write_file(&self, path: impl AsRef<Path>, data: &[u8]) -> miette::Result<()> {
+        let path = self.process_path(path)?;
+        if !self.is_file_writable(&path)? {
+            bail!("file `{}` is read-only", path.display());
+        }
+        self.files.insert(
+            path,
+            MemoryFile {
+                content: Cow::Owned(data.to_vec()),
+                writable: true,
+            },
+        );
+        Ok(())
+    }
+
+    fn delete_file(&self, path: impl AsRef<Path>) -> miette::Result<()> {
+        let path = self.process_path(path)?;
+        if !self.is_file_writable(&path)? {
+            bail!("file `{}` is read-only", path.display());
+        }
+        if self.files.remove(&path).is_none() {
+            bail!("file `{}` does not exist", path.display());
+        }
+        Ok(())
+    }
+
+    fn is_file_writable(&self, path: impl AsRef<Path>) -> miette::Result<bool> {
+        let path = self.process_path(path)?;
+        Ok(self.files.get(&path).is_none_or(|file| file.writable))
+    }
+
+    fn flush(&mut self) -> miette::Result<()> {
+        Ok(())
+    }
+
+    fn poll_changes(&self) -> miette::Result<Vec<FileChange>> {
+        Ok(std::mem::take(&mut *self.changes.lock()))
+    }
+}
@@ -0,0 +1,770 @@
+//! Migrations of the project data after changes to the module types
+//!
+//! Modules declare migrations in their `mod.toml`, keyed by the module
+//! version that introduced the change:
+//!
+//! ```toml
+//! namespace = "eh"
+//! version = "1.2.0"
+//!
+//! [[migrations]]
+//! version = "1.2.0"
+//! type = "eh:weapon"
+//! steps = [
+//!     { op = "rename", from = "Dmg", to = "Damage" },
+//!     { op = "move", from = "Stats.Cooldown", to = "Cooldown" },
+//!     { op = "default", field = "Rarity", value = "Common" },
+//!     { op = "transform", field = "Damage", expression = "value * 2" },
+//! ]
+//! ```
+//!
+//! Module versions that the project data was last saved with are recorded
+//! in the [DATA_VERSIONS_FILE]. When the project is opened with a newer
+//! version of the module, migrations of all versions in between are applied
+//! to the JSON of the value files before they are parsed. Projects without
+//! the recorded versions are considered up to date.
+//!
+//! Migrations are applied to every value of the migrated type, including
+//! the values nested in other objects. All migrations of a value are applied
+//! before the values nested in it are visited, so the nested values are found
+//! by the current field names even if a later migration renamed the field
+//! that holds them. Values of types with custom representation are migrated
+//! as they are stored, but the values nested in them are not visited.
+//!
+//! Files that fail to load keep the data of the older module versions, so
+//! the migrations are applied to them again when they are reloaded, and the
+//! recorded versions of the migrated modules are not updated while such
+//! files remain.
+
+use crate::etype::eenum::pattern::{EnumPattern, Tagged};
+use crate::etype::eobject::EObject;
+use crate::etype::EDataType;
+use crate::json_utils::{JsonMap, JsonValue};
+use crate::project::io::ProjectIO;
+use crate::project::module::DbeModule;
+use crate::project::scripts::ENGINE;
+use crate::project::ProjectFile;
+use crate::registry::{EObjectType, ETypesRegistry};
+use crate::value::id::editor_id::Namespace;
+use crate::value::id::ETypeId;
+use camino::{Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+use miette::{bail, miette, Context, IntoDiagnostic};
+use rhai::{Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+use utils::map::HashMap;
+
+/// File in the project root that records module versions of the saved data
+pub const DATA_VERSIONS_FILE: &str = "data_versions.toml";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Migration {
+    /// Module version that introduced the change
+    pub version: semver::Version,
+    /// Type whose values are migrated
+    #[serde(rename = "type")]
+    pub ty: ETypeId,
+    pub steps: Vec<MigrationStep>,
+}
+
+/// Single change applied to the JSON object of the migrated value
+///
+/// Field paths are dot-separated field names, relative to the object
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum MigrationStep {
+    /// Renames the field of the object
+    Rename { from: String, to: String },
+    /// Moves the value to another path, creating the missing objects along
+    /// the way
+    Move { from: String, to: String },
+    /// Sets the value of the field if it's missing
+    Default { field: String, value: JsonValue },
+    /// Replaces the value of the field with the result of the Rhai
+    /// expression, with the old value available as `value`
+    Transform {
+        field: String,
+        expression: String,
+        #[serde(skip)]
+        ast: Option<Arc<AST>>,
+    },
+}
+
+/// Module versions that the project data was saved with, keyed by the
+/// module namespace
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DataVersions(BTreeMap<String, semver::Version>);
+
+impl DataVersions {
+    /// Versions of the currently loaded modules
+    pub fn current(modules: &HashMap<Namespace, DbeModule>) -> Self {
+        Self(
+            modules
+                .iter()
+                .map(|(namespace, module)| (namespace.to_string(), module.version.clone()))
+                .collect(),
+        )
+    }
+
+    /// Loads the versions from the project root, if present
+    pub fn load(io: &impl ProjectIO) -> miette::Result<Option<Self>> {
+        if !io.file_exists(DATA_VERSIONS_FILE)? {
+            return Ok(None);
+        }
+
+        let data = String::from_utf8(io.read_file(DATA_VERSIONS_FILE)?).into_diagnostic()?;
+        toml::de::from_str(&data)
+            .into_diagnostic()
+            .with_context(|| format!("failed to deserialize `{}`", DATA_VERSIONS_FILE))
+            .map(Some)
+    }
+
+    /// Writes the versions to the project root, unless the file is already
+    /// up to date
+    pub fn write(&self, io: &impl ProjectIO) -> miette::Result<()> {
+        if Self::load(io)?.as_ref() == Some(self) {
+            return Ok(());
+        }
+
+        let data = toml::ser::to_string_pretty(self).into_diagnostic()?;
+        io.write_file(DATA_VERSIONS_FILE, data.as_bytes())
+            .with_context(|| format!("failed to write `{}`", DATA_VERSIONS_FILE))
+    }
+}
+
+/// Files migrated when the project was loaded
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Descriptions of the migrations applied to each file
+    pub files: BTreeMap<Utf8PathBuf, Vec<String>>,
+    /// Files that failed to load while migrations were pending
+    pub failed: BTreeSet<Utf8PathBuf>,
+    /// Versions that the project data was saved with
+    saved: Option<DataVersions>,
+    pending: PendingMigrations,
+}
+
+impl MigrationReport {
+    /// Loads the saved data versions, collecting the pending migrations
+    pub(crate) fn load(
+        modules: &HashMap<Namespace, DbeModule>,
+        io: &impl ProjectIO,
+    ) -> miette::Result<Self> {
+        let saved = DataVersions::load(io)?;
+        let pending = PendingMigrations::new(modules, saved.as_ref())?;
+        Ok(Self {
+            files: Default::default(),
+            failed: Default::default(),
+            saved,
+            pending,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Whether the file failed to load while migrations were pending, so
+    /// it still needs to be migrated
+    pub fn is_failed(&self, path: &Utf8Path) -> bool {
+        self.failed.contains(path)
+    }
+
+    /// Applies the pending migrations to the JSON of the value file
+    ///
+    /// Returns descriptions of the migrations that changed the value
+    pub(crate) fn migrate(
+        &self,
+        registry: &ETypesRegistry,
+        ty: EDataType,
+        json: &mut JsonValue,
+    ) -> miette::Result<Vec<String>> {
+        if self.pending.is_empty() {
+            return Ok(vec![]);
+        }
+        self.pending.apply(registry, ty, json)
+    }
+
+    /// Records the result of loading the value file with the migrations
+    /// applied
+    ///
+    /// Returns whenever the file was migrated
+    pub(crate) fn record(
+        &mut self,
+        path: &Utf8Path,
+        applied: Vec<String>,
+        file: &ProjectFile,
+    ) -> bool {
+        if file.is_bad() {
+            if !self.pending.is_empty() {
+                self.failed.insert(path.to_path_buf());
+            }
+            return false;
+        }
+
+        self.failed.remove(path);
+        if applied.is_empty() {
+            return false;
+        }
+
+        info!(%path, migrations = applied.join(", "), "Migrated value file");
+        self.files.insert(path.to_path_buf(), applied);
+        true
+    }
+
+    /// Versions to record for the project data
+    ///
+    /// Modules with pending migrations keep the saved versions while any of
+    /// the files that failed to load remain in the project, so the files are
+    /// migrated on the next load
+    pub fn data_versions(
+        &self,
+        modules: &HashMap<Namespace, DbeModule>,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+    ) -> DataVersions {
+        let mut versions = DataVersions::current(modules);
+        let Some(saved) = &self.saved else {
+            return versions;
+        };
+        if !self.failed.iter().any(|path| files.contains_key(path)) {
+            return versions;
+        }
+
+        for (namespace, _) in &self.pending.0 {
+            let namespace = namespace.to_string();
+            if let Some(version) = saved.0.get(&namespace) {
+                versions.0.insert(namespace, version.clone());
+            }
+        }
+        versions
+    }
+}
+
+/// Migrations that need to be applied to the saved data, in the order of
+/// application
+#[derive(Debug, Default)]
+pub struct PendingMigrations(Vec<(Namespace, Migration)>);
+
+impl PendingMigrations {
+    /// Collects migrations of the modules that are newer than the versions
+    /// the data was saved with, compiling their expressions
+    pub fn new(
+        modules: &HashMap<Namespace, DbeModule>,
+        saved: Option<&DataVersions>,
+    ) -> miette::Result<Self> {
+        let Some(saved) = saved else {
+            return Ok(Self::default());
+        };
+
+        let mut pending = vec![];
+        for (namespace, module) in modules
+            .iter()
+            .sorted_by_key(|(namespace, _)| namespace.to_string())
+        {
+            let Some(saved) = saved.0.get(&namespace.to_string()) else {
+                continue;
+            };
+            if saved > &module.version {
+                warn!(
+                    %namespace,
+                    saved=%saved,
+                    current=%module.version,
+                    "Project data was saved with a newer module version"
+                );
+                continue;
+            }
+
+            // Sorting is stable, so migrations of the same version are
+            // applied in the declaration order
+            for migration in module
+                .migrations
+                .iter()
+                .filter(|m| &m.version > saved && m.version <= module.version)
+                .sorted_by(|a, b| a.version.cmp(&b.version))
+            {
+                let mut migration = migration.clone();
+                for step in &mut migration.steps {
+                    if let MigrationStep::Transform {
+                        expression, ast, ..
+                    } = step
+                    {
+                        let compiled = ENGINE
+                            .compile_expression(expression.as_str())
+                            .map_err(|err| miette!("{}", err))
+                            .with_context(|| {
+                                format!(
+                                    "failed to compile migration expression `{}` of module `{}` version {}",
+                                    expression, namespace, migration.version
+                                )
+                            })?;
+                        *ast = Some(Arc::new(compiled));
+                    }
+                }
+                pending.push((namespace.clone(), migration));
+            }
+        }
+
+        Ok(Self(pending))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Applies the migrations to the JSON of the value of the given type
+    ///
+    /// Values are migrated starting from the outermost ones, with all
+    /// migrations of the value applied before the nested values are visited
+    ///
+    /// Returns descriptions of the migrations that changed the value
+    pub fn apply(
+        &self,
+        registry: &ETypesRegistry,
+        ty: EDataType,
+        json: &mut JsonValue,
+    ) -> miette::Result<Vec<String>> {
+        let mut changed = vec![false; self.0.len()];
+        visit_values(registry, ty, json, &mut |id, json| {
+            let mut any_changed = false;
+            for (idx, (namespace, migration)) in self.0.iter().enumerate() {
+                if id != migration.ty {
+                    continue;
+                }
+                if apply_steps(&migration.steps, json).with_context(|| {
+                    format!(
+                        "failed to apply migration {}",
+                        describe(namespace, migration)
+                    )
+                })? {
+                    changed[idx] = true;
+                    any_changed = true;
+                }
+            }
+            Ok(any_changed)
+        })?;
+
+        Ok(self
+            .0
+            .iter()
+            .zip(changed)
+            .filter(|(_, changed)| *changed)
+            .map(|((namespace, migration), _)| describe(namespace, migration))
+            .collect())
+    }
+}
+
+fn describe(namespace: &Namespace, migration: &Migration) -> String {
+    format!("`{}` {} ({})", namespace, migration.version, migration.ty)
+}
+
+/// Calls the visitor for every object value in the JSON, starting from the
+/// outermost ones
+///
+/// Returns whenever any of the visitor calls reported a change
+fn visit_values(
+    registry: &ETypesRegistry,
+    ty: EDataType,
+    json: &mut JsonValue,
+    visitor: &mut impl FnMut(ETypeId, &mut JsonValue) -> miette::Result<bool>,
+) -> miette::Result<bool> {
+    match ty {
+        EDataType::Object { ident } => {
+            let Some(obj) = registry.get_object(&ident) else {
+                return Ok(false);
+            };
+            let mut changed = visitor(obj.generic_parent_id().unwrap_or(ident), json)?;
+            if obj.repr().is_some() {
+                return Ok(changed);
+            }
+
+            match &*obj {
+                EObjectType::Struct(data) => {
+                    for field in &data.fields {
+                        if field.is_inline() {
+                            changed |= visit_values(registry, field.ty.ty(), json, visitor)?;
+                        } else if let Some(value) = json
+                            .as_object_mut()
+                            .and_then(|fields| fields.get_mut(field.name.as_str()))
+                        {
+                            changed |= visit_values(registry, field.ty.ty(), value, visitor)?;
+                        }
+                    }
+                }
+                EObjectType::Enum(data) => {
+                    let Some(variant) = data.variants().iter().find(|v| v.pat.matches_json(json))
+                    else {
+                        return Ok(changed);
+                    };
+                    let content = match &variant.pat {
+                        EnumPattern::Tagged {
+                            repr: Tagged::External,
+                            tag,
+                        } => json
+                            .as_object_mut()
+                            .and_then(|fields| fields.get_mut(tag.as_json_key().as_str())),
+                        EnumPattern::Tagged {
+                            repr: Tagged::Adjacent { content_field, .. },
+                            ..
+                        } => json
+                            .as_object_mut()
+                            .and_then(|fields| fields.get_mut(content_field.as_str())),
+                        _ => Some(json),
+                    };
+                    if let Some(content) = content {
+                        changed |= visit_values(registry, variant.data.ty(), content, visitor)?;
+                    }
+                }
+            }
+
+            Ok(changed)
+        }
+        EDataType::List { id } => {
+            let (Some(list), Some(items)) = (registry.get_list(&id), json.as_array_mut()) else {
+                return Ok(false);
+            };
+            let mut changed = false;
+            for item in items {
+                changed |= visit_values(registry, list.value_type, item, visitor)?;
+            }
+            Ok(changed)
+        }
+        EDataType::Map { id } => {
+            let (Some(map), Some(entries)) = (registry.get_map(&id), json.as_object_mut()) else {
+                return Ok(false);
+            };
+            let mut changed = false;
+            for value in entries.values_mut() {
+                changed |= visit_values(registry, map.value_type, value, visitor)?;
+            }
+            Ok(changed)
+        }
+        EDataType::Boolean
+        | EDataType::Number
+        | EDataType::String
+        | EDataType::Const { .. }
+        | EDataType::Unknown => Ok(false),
+    }
+}
+
+/// Applies the migration steps to the object
+///
+/// Values that are not JSON objects are left as-is
+fn apply_steps(steps: &[MigrationStep], json: &mut JsonValue) -> miette::Result<bool> {
+    let Some(fields) = json.as_object_mut() else {
+        return Ok(false);
+    };
+
+    let mut changed = false;
+    for step in steps {
+        match step {
+            MigrationStep::Rename { from, to } => {
+                let Some(value) = fields.remove(from) else {
+                    continue;
+                };
+                if fields.contains_key(to) {
+                    bail!("can't rename `{}` to `{}`, field already exists", from, to);
+                }
+                fields.insert(to.clone(), value);
+                changed = true;
+            }
+            MigrationStep::Move { from, to } => {
+                let Some(value) = take_path(fields, from) else {
+                    continue;
+                };
+                let (parent, name) = parent_or_create(fields, to)?;
+                if parent.contains_key(name) {
+                    bail!("can't move `{}` to `{}`, field already exists", from, to);
+                }
+                parent.insert(name.to_string(), value);
+                changed = true;
+            }
+            MigrationStep::Default { field, value } => {
+                let (parent, name) = parent_or_create(fields, field)?;
+                if !parent.contains_key(name) {
+                    parent.insert(name.to_string(), value.clone());
+                    changed = true;
+                }
+            }
+            MigrationStep::Transform {
+                field,
+                expression,
+                ast,
+            } => {
+                let Some(value) = get_path_mut(fields, field) else {
+                    continue;
+                };
+                let Some(ast) = ast else {
+                    bail!(
+                        "!!INTERNAL ERROR!! migration expression `{}` is not compiled",
+                        expression
+                    );
+                };
+                let mut scope = Scope::new();
+                scope.push(
+                    "value",
+                    rhai::serde::to_dynamic(&*value).map_err(|err| miette!("{}", err))?,
+                );
+                let result = ENGINE
+                    .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, ast)
+                    .map_err(|err| miette!("{}", err))
+                    .with_context(|| format!("failed to evaluate `{}`", expression))?;
+                let result = rhai::serde::from_dynamic::<JsonValue>(&result)
+                    .map_err(|err| miette!("{}", err))
+                    .with_context(|| format!("bad result of `{}`", expression))?;
+                if *value != result {
+                    *value = result;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+fn get_path_mut<'a>(fields: &'a mut JsonMap, path: &str) -> Option<&'a mut JsonValue> {
+    let (parents, name) = split_path(path);
+    let mut fields = fields;
+    for parent in parents {
+        fields = fields.get_mut(parent)?.as_object_mut()?;
+    }
+    fields.get_mut(name)
+}
+
+fn take_path(fields: &mut JsonMap, path: &str) -> Option<JsonValue> {
+    let (parents, name) = split_path(path);
+    let mut fields = fields;
+    for parent in parents {
+        fields = fields.get_mut(parent)?.as_object_mut()?;
+    }
+    fields.remove(name)
+}
+
+/// Returns the object containing the value at the path, along with the
+/// value name, creating the missing parent objects
+fn parent_or_create<'a, 'b>(
+    fields: &'a mut JsonMap,
+    path: &'b str,
+) -> miette::Result<(&'a mut JsonMap, &'b str)> {
+    let (parents, name) = split_path(path);
+    let mut fields = fields;
+    for parent in parents {
+        let value = fields
+            .entry(parent)
+            .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+        let Some(object) = value.as_object_mut() else {
+            bail!("can't create `{}`, `{}` is not an object", path, parent);
+        };
+        fields = object;
+    }
+    Ok((fields, name))
+}
+
+/// Splits the dot-separated path into the parent field names and the last
+/// field name
+fn split_path(path: &str) -> (Vec<&str>, &str) {
+    match path.rsplit_once('.') {
+        None => (vec![], path),
+        Some((parents, name)) => (parents.split('.').collect(), name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_steps, visit_values, MigrationStep};
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{test_project, value_file};
+    use crate::project::Project;
+    use camino::{Utf8Path, Utf8PathBuf};
+    use serde_json::json;
+
+    /// Module that renamed a field of `test:stats` in 1.1.0, and then renamed
+    /// the field of `test:item` holding the stats in 1.2.0
+    const MODULE_TOML: &str = r#"
+namespace = "test"
+version = "1.2.0"
+
+[[migrations]]
+version = "1.1.0"
+type = "test:stats"
+steps = [{ op = "rename", from = "Cd", to = "Cooldown" }]
+
+[[migrations]]
+version = "1.2.0"
+type = "test:item"
+steps = [{ op = "rename", from = "Statistics", to = "Stats" }]
+"#;
+
+    const ITEM_TYPE: &str = r#"struct {
+    object "Stats" "test:stats"
+    list "Parts" {
+        object "Item" "test:stats"
+    }
+}"#;
+
+    const STATS_TYPE: &str = r#"struct { number "Cooldown"; }"#;
+
+    const OLD_ITEM: &str = r#"{ "Statistics": { "Cd": 2 }, "Parts": [{ "Cd": 3 }] }"#;
+
+    fn project(files: &[(&str, &str)]) -> Project<MemoryIO> {
+        let mut files = files.to_vec();
+        files.extend([
+            ("test.dbemodule/mod.toml", MODULE_TOML),
+            ("test.dbemodule/types/item.kdl", ITEM_TYPE),
+            ("test.dbemodule/types/stats.kdl", STATS_TYPE),
+            ("data_versions.toml", r#"test = "1.0.0""#),
+        ]);
+        test_project(&files)
+    }
+
+    fn value_json(project: &Project<MemoryIO>, path: &str) -> serde_json::Value {
+        project.serialize_json(value_file(project, path)).unwrap()
+    }
+
+    fn saved_version(project: &Project<MemoryIO>) -> String {
+        project
+            .migrations
+            .data_versions(&project.modules, &project.files)
+            .0["test"]
+            .to_string()
+    }
+
+    #[test]
+    fn migration_steps() {
+        let steps = vec![
+            MigrationStep::Rename {
+                from: "Dmg".to_string(),
+                to: "Damage".to_string(),
+            },
+            MigrationStep::Move {
+                from: "Stats.Cooldown".to_string(),
+                to: "Timing.Cooldown".to_string(),
+            },
+            MigrationStep::Default {
+                field: "Rarity".to_string(),
+                value: json!("Common"),
+            },
+            MigrationStep::Default {
+                field: "Name".to_string(),
+                value: json!("Unnamed"),
+            },
+        ];
+
+        let mut value = json!({
+            "Dmg": 5,
+            "Name": "Laser",
+            "Stats": { "Cooldown": 2 },
+        });
+        assert!(apply_steps(&steps, &mut value).unwrap());
+        assert_eq!(
+            value,
+            json!({
+                "Damage": 5,
+                "Name": "Laser",
+                "Stats": {},
+                "Timing": { "Cooldown": 2 },
+                "Rarity": "Common",
+            })
+        );
+
+        // Already migrated values are left as-is
+        assert!(!apply_steps(&steps, &mut value).unwrap());
+
+        let mut conflict = json!({ "Dmg": 5, "Damage": 6 });
+        assert!(apply_steps(&steps, &mut conflict).is_err());
+    }
+
+    #[test]
+    fn should_visit_outermost_values_first() {
+        let project = project(&[]);
+        let mut value = json!({ "Stats": {}, "Parts": [{}, {}] });
+
+        let mut visited = vec![];
+        visit_values(
+            &project.registry,
+            project.import_root(),
+            &mut value,
+            &mut |id, _| {
+                visited.push(id.to_string());
+                Ok(false)
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            visited,
+            ["test:item", "test:stats", "test:stats", "test:stats"]
+        );
+    }
+
+    #[test]
+    fn should_migrate_values_nested_in_renamed_fields() {
+        let project = project(&[]);
+        let mut value = serde_json::from_str(OLD_ITEM).unwrap();
+
+        let applied = project
+            .migrations
+            .pending
+            .apply(&project.registry, project.import_root(), &mut value)
+            .unwrap();
+        assert_eq!(
+            value,
+            json!({ "Stats": { "Cooldown": 2 }, "Parts": [{ "Cooldown": 3 }] })
+        );
+        assert_eq!(
+            applied,
+            ["`test` 1.1.0 (test:stats)", "`test` 1.2.0 (test:item)"]
+        );
+    }
+
+    #[test]
+    fn should_migrate_project_files() {
+        let project = project(&[("a.json", OLD_ITEM)]);
+
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Stats": { "Cooldown": 2.0 }, "Parts": [{ "Cooldown": 3.0 }] })
+        );
+        assert_eq!(project.migrations.files[Utf8Path::new("a.json")].len(), 2);
+        assert!(project.is_dirty(Utf8Path::new("a.json")));
+        assert_eq!(saved_version(&project), "1.2.0");
+    }
+
+    #[test]
+    fn should_keep_versions_while_failed_files_remain() {
+        let conflict = r#"{ "Statistics": { "Cd": 4 }, "Stats": { "Cooldown": 4 } }"#;
+        let mut project = project(&[("a.json", OLD_ITEM), ("b.json", conflict)]);
+
+        assert!(project.files[Utf8Path::new("b.json")].is_bad());
+        assert!(project.migrations.is_failed(Utf8Path::new("b.json")));
+        assert_eq!(saved_version(&project), "1.0.0");
+
+        // Fixed file is migrated when reloaded
+        project.io.change_external(
+            "b.json",
+            Some(br#"{ "Statistics": { "Cd": 4 }, "Parts": [] }"#.to_vec()),
+        );
+        let report = project.reload_changed_files().unwrap();
+        assert_eq!(report.reloaded, vec![Utf8PathBuf::from("b.json")]);
+        assert_eq!(
+            value_json(&project, "b.json"),
+            json!({ "Stats": { "Cooldown": 4.0 }, "Parts": [] })
+        );
+        assert!(project.is_dirty(Utf8Path::new("b.json")));
+        assert!(project.migrations.failed.is_empty());
+        assert_eq!(saved_version(&project), "1.2.0");
+    }
+
+    #[test]
+    fn should_bump_versions_once_failed_files_are_deleted() {
+        let conflict = r#"{ "Statistics": {}, "Stats": {} }"#;
+        let mut project = project(&[("b.json", conflict)]);
+        assert_eq!(saved_version(&project), "1.0.0");
+
+        project.delete_file("b.json").unwrap();
+        assert_eq!(saved_version(&project), "1.2.0");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::project::migrations::Migration;
 use crate::project::{path_has_suffix, EXTENSION_MODULE};
 use crate::value::id::editor_id::Namespace;
 use camino::{Utf8Path, Utf8PathBuf};
@@ -7,6 +8,9 @@ use serde::Deserialize;
 pub struct DbeModule {
     pub version: semver::Version,
     pub namespace: Namespace,
+    /// Migrations of the project data, see [crate::project::migrations]
+    #[serde(default)]
+    pub migrations: Vec<Migration>,
     #[serde(skip)]
     pub path: Utf8PathBuf,
 }
@@ -1,16 +1,19 @@
 use crate::graph::inputs::{GraphInput, GraphOutput};
 use crate::graph::Graph;
 use crate::json_utils::JsonValue;
+use crate::project::scripts::ProjectScripts;
 use crate::project::ProjectFile;
 use crate::registry::ETypesRegistry;
 use camino::Utf8PathBuf;
+use itertools::Itertools;
 use miette::{bail, Context, IntoDiagnostic};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::collections::hash_map::Entry;
-use std::hash::Hash;
+use std::collections::BTreeSet;
+use std::hash::{Hash, Hasher};
 use strum::{AsRefStr, EnumIs, VariantArray};
-use utils::map::HashMap;
+use utils::map::{hash_of, HashMap, HashSet};
 use uuid::Uuid;
 
 #[derive(
@@ -71,6 +74,8 @@ pub struct ProjectGraph {
     pub categories: Vec<String>,
     /// Evaluation stage of the graph
     pub stage: EvaluationStage,
+    /// Graphs of the same stage that should be evaluated before this graph
+    pub dependencies: Vec<Uuid>,
     graph: GraphHolder,
     inputs_cache: SmallVec<[GraphInput; 1]>,
     outputs_cache: SmallVec<[GraphOutput; 1]>,
@@ -110,6 +115,7 @@ impl ProjectGraph {
             hide_from_search: false,
             categories: Default::default(),
             stage: Default::default(),
+            dependencies: Default::default(),
             graph: GraphHolder::Graph(Box::default()),
             inputs_cache: Default::default(),
             outputs_cache: Default::default(),
@@ -158,6 +164,20 @@ impl ProjectGraph {
         }
     }
 
+    /// Hash of the graph properties, excluding the graph data
+    pub fn metadata_hash(&self) -> u64 {
+        hash_of(&(
+            &self.id,
+            &self.name,
+            self.is_node_group,
+            self.is_data_template,
+            self.hide_from_search,
+            &self.categories,
+            self.stage,
+            &self.dependencies,
+        ))
+    }
+
     pub fn parse_json(registry: &ETypesRegistry, value: &mut JsonValue) -> miette::Result<Self> {
         let data = if value.get("version").is_none() {
             SerializedGraphRepr::V0(value.take())
@@ -178,6 +198,7 @@ impl ProjectGraph {
                     hide_from_search: data.hide_from_search,
                     categories: data.categories,
                     stage: data.stage,
+                    dependencies: data.dependencies,
                     graph: GraphHolder::Graph(Box::new(graph)),
                     inputs_cache: Default::default(),
                     outputs_cache: Default::default(),
@@ -204,6 +225,7 @@ impl ProjectGraph {
                     hide_from_search: false,
                     categories: Default::default(),
                     stage: Default::default(),
+                    dependencies: Default::default(),
                     graph: GraphHolder::Graph(Box::new(graph)),
                     inputs_cache: Default::default(),
                     outputs_cache: Default::default(),
@@ -223,6 +245,7 @@ impl ProjectGraph {
             categories: self.categories.clone(),
             name: self.name.clone(),
             stage: self.stage,
+            dependencies: self.dependencies.clone(),
             graph,
         });
 
@@ -267,6 +290,8 @@ struct PackedProjectGraph {
     name: String,
     #[serde(default)]
     stage: EvaluationStage,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<Uuid>,
     graph: JsonValue,
 }
 
@@ -286,6 +311,8 @@ pub struct ProjectGraphs {
     /// All project graphs
     pub graphs: HashMap<Uuid, ProjectGraph>,
     paths: HashMap<Uuid, Utf8PathBuf>,
+    /// Scripts that can be used as nodes in graphs
+    pub scripts: ProjectScripts,
 }
 
 impl ProjectGraphs {
@@ -342,4 +369,283 @@ impl ProjectGraphs {
 
         Ok(ProjectFile::Graph(id))
     }
+
+    /// Removes the graph, returning it if it was present
+    pub fn remove_graph(&mut self, id: &Uuid) -> Option<ProjectGraph> {
+        self.paths.remove(id);
+        self.graphs.remove(id)
+    }
+
+    /// Returns IDs of the given graphs, along with all graphs that use any
+    /// of them as a node group, directly or transitively
+    pub fn dependents_of(&self, ids: impl IntoIterator<Item = Uuid>) -> HashSet<Uuid> {
+        let mut affected: HashSet<Uuid> = ids.into_iter().collect();
+        let uses = self
+            .graphs
+            .iter()
+            .map(|(id, graph)| (*id, graph.graph().subgraph_ids()))
+            .collect_vec();
+
+        loop {
+            let mut changed = false;
+            for (id, used) in &uses {
+                if !affected.contains(id) && used.iter().any(|used| affected.contains(used)) {
+                    affected.insert(*id);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        affected
+    }
+
+    /// Returns the given graph ID, along with IDs of all node groups that it
+    /// uses, directly or transitively
+    pub fn used_graphs(&self, id: Uuid) -> BTreeSet<Uuid> {
+        let mut used = BTreeSet::new();
+        let mut queue = vec![id];
+        while let Some(id) = queue.pop() {
+            if !used.insert(id) {
+                continue;
+            }
+            if let Some(graph) = self.graphs.get(&id) {
+                queue.extend(graph.graph().subgraph_ids());
+            }
+        }
+        used
+    }
+
+    /// Hash of the given graph and all node groups and scripts that it uses
+    pub fn contents_hash(&self, id: Uuid) -> u64 {
+        let mut hasher = utils::map::Hasher::default();
+        let mut scripts = BTreeSet::new();
+        for id in self.used_graphs(id) {
+            id.hash(&mut hasher);
+            let graph = self.graphs.get(&id);
+            graph.hash(&mut hasher);
+            if let Some(graph) = graph {
+                scripts.extend(graph.graph().script_ids());
+            }
+        }
+        for id in scripts {
+            id.hash(&mut hasher);
+            self.scripts.scripts.get(&id).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+impl ProjectGraphs {
+    /// Collects dependencies between the given graphs of the same stage
+    ///
+    /// Dependencies are either declared in [ProjectGraph::dependencies], or
+    /// inferred from transient storage keys: a graph that reads a key
+    /// without writing it depends on all graphs that write this key.
+    /// Declared dependencies on graphs outside the given list are ignored
+    pub fn stage_dependencies(&self, stage_graphs: &[Uuid]) -> HashMap<Uuid, BTreeSet<Uuid>> {
+        let in_stage: HashSet<Uuid> = stage_graphs.iter().copied().collect();
+        let keys = stage_graphs
+            .iter()
+            .map(|id| {
+                let mut written = HashSet::default();
+                let mut read = HashSet::default();
+                for used in self.used_graphs(*id) {
+                    if let Some(graph) = self.graphs.get(&used) {
+                        let (w, r) = graph.graph().transient_storage_keys();
+                        written.extend(w);
+                        read.extend(r);
+                    }
+                }
+                read.retain(|key| !written.contains(key));
+                (*id, written, read)
+            })
+            .collect_vec();
+
+        let mut dependencies: HashMap<Uuid, BTreeSet<Uuid>> = HashMap::default();
+        for (id, _, read) in &keys {
+            let entry = dependencies.entry(*id).or_default();
+            if let Some(graph) = self.graphs.get(id) {
+                entry.extend(
+                    graph
+                        .dependencies
+                        .iter()
+                        .filter(|dep| *dep != id && in_stage.contains(dep)),
+                );
+            }
+            for (other, written, _) in &keys {
+                if other != id && !read.is_disjoint(written) {
+                    entry.insert(*other);
+                }
+            }
+        }
+
+        dependencies
+    }
+}
+
+/// Checks if the graph `from` depends on the graph `to`, directly or
+/// transitively
+pub fn depends_on(dependencies: &HashMap<Uuid, BTreeSet<Uuid>>, from: Uuid, to: Uuid) -> bool {
+    let mut visited = HashSet::default();
+    let mut queue = vec![from];
+    while let Some(id) = queue.pop() {
+        if id == to {
+            return true;
+        }
+        if !visited.insert(id) {
+            continue;
+        }
+        if let Some(deps) = dependencies.get(&id) {
+            queue.extend(deps.iter().copied());
+        }
+    }
+    false
+}
+
+/// Collects all graphs that the graph depends on, directly or transitively
+///
+/// The graph itself is not included, even if it's a part of a dependency cycle
+pub fn transitive_dependencies(
+    dependencies: &HashMap<Uuid, BTreeSet<Uuid>>,
+    id: Uuid,
+) -> BTreeSet<Uuid> {
+    let mut visited = BTreeSet::new();
+    let mut queue = dependencies
+        .get(&id)
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect_vec();
+    while let Some(dep) = queue.pop() {
+        if dep == id || !visited.insert(dep) {
+            continue;
+        }
+        if let Some(deps) = dependencies.get(&dep) {
+            queue.extend(deps.iter().copied());
+        }
+    }
+    visited
+}
+
+/// Sorts graphs so that every graph goes after its dependencies
+///
+/// Graphs that don't depend on each other keep their relative order. Graphs
+/// that are part of or depend on dependency cycles are placed at the end in
+/// their original order.
+///
+/// Returns the sorted graphs and the detected cycles. Each cycle is listed
+/// starting from a graph, followed by its dependency, and so on
+pub fn order_by_dependencies(
+    graphs: &[Uuid],
+    dependencies: &HashMap<Uuid, BTreeSet<Uuid>>,
+) -> (Vec<Uuid>, Vec<Vec<Uuid>>) {
+    let in_list: HashSet<Uuid> = graphs.iter().copied().collect();
+    let mut order = Vec::with_capacity(graphs.len());
+    let mut done = HashSet::default();
+    let mut remaining = graphs.to_vec();
+
+    while let Some(pos) = remaining.iter().position(|id| {
+        dependencies
+            .get(id)
+            .into_iter()
+            .flatten()
+            .all(|dep| done.contains(dep) || !in_list.contains(dep))
+    }) {
+        let id = remaining.remove(pos);
+        done.insert(id);
+        order.push(id);
+    }
+
+    // Every remaining graph has at least one unresolved dependency among
+    // the remaining graphs, so walking over them always ends in a cycle
+    let remaining_set: HashSet<Uuid> = remaining.iter().copied().collect();
+    let mut visited = HashSet::default();
+    let mut cycles = vec![];
+    for start in &remaining {
+        let mut stack = vec![];
+        let mut current = *start;
+        loop {
+            if let Some(pos) = stack.iter().position(|id| *id == current) {
+                cycles.push(stack[pos..].to_vec());
+                break;
+            }
+            if visited.contains(&current) {
+                break;
+            }
+            stack.push(current);
+            current = dependencies
+                .get(&current)
+                .and_then(|deps| deps.iter().find(|dep| remaining_set.contains(dep)))
+                .copied()
+                .expect("remaining graph should have unresolved dependencies");
+        }
+        visited.extend(stack);
+    }
+
+    order.extend(remaining);
+    (order, cycles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{order_by_dependencies, transitive_dependencies};
+    use std::collections::BTreeSet;
+    use utils::map::HashMap;
+    use uuid::Uuid;
+
+    fn ids(count: u128) -> Vec<Uuid> {
+        (0..count).map(Uuid::from_u128).collect()
+    }
+
+    fn deps(edges: &[(usize, usize)], ids: &[Uuid]) -> HashMap<Uuid, BTreeSet<Uuid>> {
+        let mut deps: HashMap<Uuid, BTreeSet<Uuid>> = HashMap::default();
+        for (from, to) in edges {
+            deps.entry(ids[*from]).or_default().insert(ids[*to]);
+        }
+        deps
+    }
+
+    #[test]
+    fn should_keep_order_without_dependencies() {
+        let ids = ids(3);
+        let (order, cycles) = order_by_dependencies(&ids, &HashMap::default());
+        assert_eq!(order, ids);
+        assert!(cycles.is_empty());
+    }
+
+    #[test]
+    fn should_order_dependencies_first() {
+        let ids = ids(4);
+        let (order, cycles) = order_by_dependencies(&ids, &deps(&[(0, 2), (2, 3)], &ids));
+        assert_eq!(order, vec![ids[1], ids[3], ids[2], ids[0]]);
+        assert!(cycles.is_empty());
+    }
+
+    #[test]
+    fn should_detect_cycles() {
+        let ids = ids(4);
+        let (order, cycles) =
+            order_by_dependencies(&ids, &deps(&[(0, 1), (1, 2), (2, 1), (3, 0)], &ids));
+        assert_eq!(order, ids);
+        assert_eq!(cycles, vec![vec![ids[1], ids[2]]]);
+    }
+
+    #[test]
+    fn should_collect_transitive_dependencies() {
+        let ids = ids(5);
+        let deps = deps(&[(0, 1), (1, 2), (2, 0), (3, 1), (1, 4)], &ids);
+        assert_eq!(
+            transitive_dependencies(&deps, ids[3]),
+            BTreeSet::from([ids[0], ids[1], ids[2], ids[4]])
+        );
+        // Graphs in a cycle don't depend on themselves
+        assert_eq!(
+            transitive_dependencies(&deps, ids[0]),
+            BTreeSet::from([ids[1], ids[2], ids[4]])
+        );
+        assert!(transitive_dependencies(&deps, ids[4]).is_empty());
+    }
 }
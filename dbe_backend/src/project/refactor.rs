@@ -0,0 +1,614 @@
+use crate::project::io::ProjectIO;
+use crate::project::{Project, ProjectFile};
+use crate::registry::ETypesRegistry;
+use crate::validation::ids::numeric::{
+    split_location, visit_numeric_ids_mut, NumericIDRegistry, NumericIdKind,
+};
+use crate::validation::{clear_validation_cache, validate};
+use crate::value::{ENumber, EValue};
+use camino::Utf8PathBuf;
+use itertools::Itertools;
+use miette::{bail, Context};
+use std::ops::RangeInclusive;
+use ustr::Ustr;
+use utils::map::{HashMap, HashSet};
+
+impl<IO: ProjectIO> Project<IO> {
+    /// Changes the numeric ID `old` of the ID type `ty` to `new`, rewriting
+    /// all references to it in values and graph inline values
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type. All
+    /// changes are recorded as a single undo history entry. Returns paths of
+    /// the changed files, starting with the file that defines the ID
+    pub fn change_numeric_id(
+        &mut self,
+        ty: Ustr,
+        old: ENumber,
+        new: ENumber,
+    ) -> miette::Result<Vec<Utf8PathBuf>> {
+        self.remap_numeric_ids(ty, &[(old, new)])
+    }
+
+    /// Renumbers all IDs of the ID type `ty` defined within the range, so
+    /// they occupy the lowest free IDs of the range, preserving their order
+    ///
+    /// See [Project::remap_numeric_ids]
+    pub fn compact_numeric_ids(
+        &mut self,
+        ty: Ustr,
+        range: RangeInclusive<i64>,
+    ) -> miette::Result<Vec<Utf8PathBuf>> {
+        self.refresh_numeric_ids()?;
+        let ids = NumericIDRegistry::of(&self.registry);
+
+        let defined = ids
+            .defined_ids(ty)
+            .into_iter()
+            .filter(|id| range.contains(&(id.0 as i64)) && id.0.fract() == 0.0)
+            .collect_vec();
+
+        // IDs being compacted are free for each other
+        let free = ids.free_ids(ty, range)?.merge(defined.iter().copied());
+
+        let mapping = defined
+            .iter()
+            .copied()
+            .zip(free)
+            .filter(|(old, new)| old != new)
+            .collect_vec();
+
+        self.apply_id_mapping(ty, &mapping)
+    }
+
+    /// Gives the provided IDs of the ID type `ty` the lowest free IDs within
+    /// the target range, preserving their order. IDs that are already within
+    /// the range are left as is
+    ///
+    /// See [Project::remap_numeric_ids]
+    pub fn move_numeric_ids(
+        &mut self,
+        ty: Ustr,
+        ids: &[ENumber],
+        target: RangeInclusive<i64>,
+    ) -> miette::Result<Vec<Utf8PathBuf>> {
+        self.refresh_numeric_ids()?;
+        let moved = ids
+            .iter()
+            .copied()
+            .filter(|id| !target.contains(&(id.0 as i64)) || id.0.fract() != 0.0)
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+
+        let free = NumericIDRegistry::of(&self.registry)
+            .free_ids(ty, target.clone())?
+            .take(moved.len())
+            .collect_vec();
+
+        if free.len() < moved.len() {
+            bail!(
+                "not enough free IDs of type `{}` in range {}..={}: need {}, found {}",
+                ty,
+                target.start(),
+                target.end(),
+                moved.len(),
+                free.len()
+            );
+        }
+
+        let mapping = moved.into_iter().zip(free).collect_vec();
+
+        self.apply_id_mapping(ty, &mapping)
+    }
+
+    /// Changes numeric IDs of the ID type `ty` according to the provided
+    /// `(old, new)` pairs, rewriting all references to them in values and
+    /// graph inline values
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type. All
+    /// changes are recorded as a single undo history entry. Returns paths of
+    /// the changed files, starting with the file that defines the first ID
+    pub fn remap_numeric_ids(
+        &mut self,
+        ty: Ustr,
+        mapping: &[(ENumber, ENumber)],
+    ) -> miette::Result<Vec<Utf8PathBuf>> {
+        self.refresh_numeric_ids()?;
+        self.apply_id_mapping(ty, mapping)
+    }
+
+    /// Revalidates the whole project, so the index of numeric IDs reflects
+    /// the current state of all files, including unsaved edits
+    fn refresh_numeric_ids(&mut self) -> miette::Result<()> {
+        clear_validation_cache(&self.registry);
+        self.validate_all()
+    }
+
+    /// Implementation of [Project::remap_numeric_ids], that expects the
+    /// numeric IDs index to be up to date
+    fn apply_id_mapping(
+        &mut self,
+        ty: Ustr,
+        mapping: &[(ENumber, ENumber)],
+    ) -> miette::Result<Vec<Utf8PathBuf>> {
+        let mapping = mapping
+            .iter()
+            .copied()
+            .filter(|(old, new)| old != new)
+            .collect::<HashMap<_, _>>();
+
+        if mapping.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ids = NumericIDRegistry::of(&self.registry);
+
+        let mut definition = None;
+        for old in mapping.keys().sorted_unstable() {
+            match ids.definitions(ty, *old).as_slice() {
+                [] => bail!("ID {} of type `{}` is not defined", old, ty),
+                [location] => {
+                    definition.get_or_insert_with(|| Utf8PathBuf::from(split_location(location).0));
+                }
+                definitions => bail!(
+                    "ID {} of type `{}` is defined multiple times: {}",
+                    old,
+                    ty,
+                    definitions.join(", ")
+                ),
+            }
+        }
+
+        let taken = ids.taken_ids(ty)?;
+        let mut targets = HashSet::default();
+        for new in mapping.values() {
+            if !targets.insert(*new) {
+                bail!("multiple IDs of type `{}` would be changed to {}", ty, new);
+            }
+            if taken.contains(new) && !mapping.contains_key(new) {
+                bail!("ID {} of type `{}` is already taken", new, ty);
+            }
+        }
+
+        let mut rewrite = IdRewrite::new(ids, ty, mapping);
+
+        let mut values = vec![];
+        let mut graphs = vec![];
+        for (path, file) in &self.files {
+            match file {
+                ProjectFile::Value(value) => {
+                    let mut value = value.clone();
+                    if rewrite
+                        .apply(&self.registry, &mut value)
+                        .with_context(|| format!("failed to update IDs in `{}`", path))?
+                    {
+                        values.push((path.clone(), value));
+                    }
+                }
+                ProjectFile::Graph(id) => {
+                    let Some(graph) = self.graphs.graphs.get(id) else {
+                        continue;
+                    };
+                    let mut graph = graph.clone();
+                    let mut changed = false;
+                    graph
+                        .graph_mut()
+                        .for_each_inline_value_mut(&mut |value| {
+                            changed |= rewrite.apply(&self.registry, value)?;
+                            Ok(())
+                        })
+                        .with_context(|| format!("failed to update IDs in `{}`", path))?;
+                    if changed {
+                        graphs.push((path.clone(), *id, graph));
+                    }
+                }
+                ProjectFile::GeneratedValue(_) | ProjectFile::BadValue(_) => {}
+            }
+        }
+
+        let paths = values
+            .iter()
+            .map(|(path, _)| path)
+            .chain(graphs.iter().map(|(path, _, _)| path))
+            .cloned()
+            .sorted_by_key(|path| Some(path) != definition.as_ref())
+            .collect_vec();
+
+        for path in &paths {
+            self.history
+                .check_file(&self.files, &self.graphs, path, true)?;
+        }
+
+        for (path, value) in values {
+            self.files.insert(path, ProjectFile::Value(value));
+        }
+        for (_, id, graph) in graphs {
+            self.graphs.graphs.insert(id, graph);
+        }
+
+        self.history
+            .check_files_together(&self.files, &self.graphs, &paths)?;
+
+        for path in &paths {
+            if let Some(ProjectFile::Value(value)) = self.files.get(path) {
+                validate(
+                    &self.registry,
+                    self.diagnostics.enter(path.as_str()),
+                    None,
+                    value,
+                )?;
+            }
+        }
+
+        Ok(paths)
+    }
+}
+
+/// Rewrites ID definitions and references according to the mapping
+struct IdRewrite<'a> {
+    ids: NumericIDRegistry<'a>,
+    ty: Ustr,
+    mapping: HashMap<ENumber, ENumber>,
+    /// Other ID types that define the same ID. References that can be
+    /// satisfied by those types are ambiguous and are left untouched
+    others: HashMap<ENumber, Vec<Ustr>>,
+    /// Cache of [NumericIDRegistry::is_id_assignable] results
+    assignable: HashMap<(Ustr, Ustr), bool>,
+}
+
+impl<'a> IdRewrite<'a> {
+    fn new(ids: NumericIDRegistry<'a>, ty: Ustr, mapping: HashMap<ENumber, ENumber>) -> Self {
+        let mut others = HashMap::<ENumber, Vec<Ustr>>::default();
+        for other in ids.id_types() {
+            if other == ty {
+                continue;
+            }
+            for old in mapping.keys() {
+                if !ids.definitions(other, *old).is_empty() {
+                    others.entry(*old).or_default().push(other);
+                }
+            }
+        }
+
+        Self {
+            ids,
+            ty,
+            mapping,
+            others,
+            assignable: Default::default(),
+        }
+    }
+
+    /// Rewrites the IDs within the value, returning whether anything changed
+    fn apply(&mut self, registry: &ETypesRegistry, value: &mut EValue) -> miette::Result<bool> {
+        let mut changed = false;
+        visit_numeric_ids_mut(registry, value, &mut |kind, id_ty, id| {
+            let Some(new) = self.mapping.get(&*id).copied() else {
+                return Ok(());
+            };
+
+            let matches = match kind {
+                NumericIdKind::Id => id_ty == self.ty,
+                NumericIdKind::Ref => self.rewrites_ref(id_ty, *id)?,
+            };
+
+            if matches {
+                *id = new;
+                changed = true;
+            }
+
+            Ok(())
+        })?;
+        Ok(changed)
+    }
+
+    fn rewrites_ref(&mut self, ref_ty: Ustr, id: ENumber) -> miette::Result<bool> {
+        if !self.is_assignable(self.ty, ref_ty)? {
+            return Ok(false);
+        }
+
+        let others = self.others.get(&id).cloned().unwrap_or_default();
+        for other in others {
+            if self.is_assignable(other, ref_ty)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn is_assignable(&mut self, from: Ustr, to: Ustr) -> miette::Result<bool> {
+        if let Some(assignable) = self.assignable.get(&(from, to)) {
+            return Ok(*assignable);
+        }
+
+        let assignable = self.ids.is_id_assignable(from, to)?;
+        self.assignable.insert((from, to), assignable);
+        Ok(assignable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{
+        test_project, value_file, value_file_mut, NUMERIC_ID_ITEM_TYPE,
+    };
+    use crate::project::Project;
+    use crate::validation::ids::numeric::{visit_numeric_ids_mut, NumericIdKind};
+    use crate::value::ENumber;
+    use camino::Utf8PathBuf;
+    use serde_json::json;
+    use ustr::Ustr;
+
+    const HOLDER_TYPE: &str = r#"struct {
+        list "List" {
+            object "Item" "sys:ids/numeric_ref" {
+                const "Id" "test:item"
+            }
+        }
+        map "Map" {
+            string "Key"
+            object "Item" "sys:ids/numeric_ref" {
+                const "Id" "test:item"
+            }
+        }
+        object "Optional" "sys:optional" {
+            object "Item" "sys:ids/numeric_ref" {
+                const "Id" "test:item"
+            }
+        }
+    }"#;
+
+    fn project() -> Project<MemoryIO> {
+        let holder = json!({
+            "ty": { "Object": { "ident": "test:holder" } },
+            "value": {
+                "List": [1, 2, 1],
+                "Map": { "a": 1, "b": 2 },
+                "Optional": 1,
+            },
+        })
+        .to_string();
+        test_project(&[
+            ("test.dbemodule/types/item.kdl", NUMERIC_ID_ITEM_TYPE),
+            ("test.dbemodule/types/holder.kdl", HOLDER_TYPE),
+            ("a.json", r#"{ "Id": 1, "Target": 2 }"#),
+            ("b.json", r#"{ "Id": 2, "Target": 1 }"#),
+            ("holder.dbevalue", &holder),
+        ])
+    }
+
+    /// Project with sparse IDs, each referencing the previous one
+    fn sparse_project() -> Project<MemoryIO> {
+        test_project(&[
+            ("test.dbemodule/types/item.kdl", NUMERIC_ID_ITEM_TYPE),
+            ("a.json", r#"{ "Id": 3, "Target": 7 }"#),
+            ("b.json", r#"{ "Id": 5, "Target": 3 }"#),
+            ("c.json", r#"{ "Id": 7, "Target": 5 }"#),
+            ("d.json", r#"{ "Id": 20, "Target": 3 }"#),
+        ])
+    }
+
+    fn value_json(project: &Project<MemoryIO>, path: &str) -> serde_json::Value {
+        value_file(project, path)
+            .write_json(&project.registry)
+            .unwrap()
+    }
+
+    #[test]
+    fn should_visit_nested_ids() {
+        let project = project();
+        let value = value_file(&project, "holder.dbevalue");
+
+        let mut visited = vec![];
+        visit_numeric_ids_mut(
+            &project.registry,
+            &mut value.clone(),
+            &mut |kind, ty, id| {
+                visited.push((kind, ty, id.0));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        let item = Ustr::from("test:item");
+        visited.sort_by(|a, b| a.2.total_cmp(&b.2));
+        assert_eq!(visited.len(), 6);
+        assert!(visited
+            .iter()
+            .all(|(kind, ty, _)| *kind == NumericIdKind::Ref && *ty == item));
+        assert_eq!(
+            visited.iter().map(|(_, _, id)| *id).collect::<Vec<_>>(),
+            vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn should_rewrite_nested_refs() {
+        let mut project = project();
+        let changed = project
+            .change_numeric_id(
+                Ustr::from("test:item"),
+                ENumber::from(1.0),
+                ENumber::from(5.0),
+            )
+            .unwrap();
+
+        // The file defining the ID goes first
+        assert_eq!(
+            changed,
+            vec![
+                Utf8PathBuf::from("a.json"),
+                Utf8PathBuf::from("b.json"),
+                Utf8PathBuf::from("holder.dbevalue"),
+            ]
+        );
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Id": 5, "Target": 2 })
+        );
+        assert_eq!(
+            value_json(&project, "b.json"),
+            json!({ "Id": 2, "Target": 5 })
+        );
+        assert_eq!(
+            value_json(&project, "holder.dbevalue"),
+            json!({
+                "List": [5, 2, 5],
+                "Map": { "a": 5, "b": 2 },
+                "Optional": 5,
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_taken_ids() {
+        let mut project = project();
+        assert!(project
+            .change_numeric_id(
+                Ustr::from("test:item"),
+                ENumber::from(1.0),
+                ENumber::from(2.0)
+            )
+            .is_err());
+        assert!(project
+            .change_numeric_id(
+                Ustr::from("test:item"),
+                ENumber::from(3.0),
+                ENumber::from(4.0)
+            )
+            .is_err());
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Id": 1, "Target": 2 })
+        );
+    }
+
+    #[test]
+    fn should_use_unsaved_edits() {
+        let mut project = project();
+        // ID of `b.json` is changed in memory, without revalidation
+        let mut value = value_file(&project, "b.json").clone();
+        visit_numeric_ids_mut(&project.registry, &mut value, &mut |kind, _, id| {
+            if kind == NumericIdKind::Id {
+                *id = ENumber::from(3.0);
+            }
+            Ok(())
+        })
+        .unwrap();
+        *value_file_mut(&mut project, "b.json") = value;
+
+        // ID 2 is now free
+        project
+            .change_numeric_id(
+                Ustr::from("test:item"),
+                ENumber::from(1.0),
+                ENumber::from(2.0),
+            )
+            .unwrap();
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Id": 2, "Target": 2 })
+        );
+    }
+
+    #[test]
+    fn should_compact_ids() {
+        let mut project = sparse_project();
+        project
+            .compact_numeric_ids(Ustr::from("test:item"), 1..=10)
+            .unwrap();
+
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Id": 1, "Target": 3 })
+        );
+        assert_eq!(
+            value_json(&project, "b.json"),
+            json!({ "Id": 2, "Target": 1 })
+        );
+        assert_eq!(
+            value_json(&project, "c.json"),
+            json!({ "Id": 3, "Target": 2 })
+        );
+        // IDs outside of the range are left as is
+        assert_eq!(
+            value_json(&project, "d.json"),
+            json!({ "Id": 20, "Target": 1 })
+        );
+    }
+
+    #[test]
+    fn should_move_ids() {
+        let mut project = sparse_project();
+        project
+            .move_numeric_ids(
+                Ustr::from("test:item"),
+                &[ENumber::from(7.0), ENumber::from(5.0)],
+                20..=30,
+            )
+            .unwrap();
+
+        // ID 20 is taken, order of the moved IDs is preserved
+        assert_eq!(
+            value_json(&project, "b.json"),
+            json!({ "Id": 21, "Target": 3 })
+        );
+        assert_eq!(
+            value_json(&project, "c.json"),
+            json!({ "Id": 22, "Target": 21 })
+        );
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Id": 3, "Target": 22 })
+        );
+    }
+
+    #[test]
+    fn should_reject_move_without_free_ids() {
+        let mut project = sparse_project();
+        assert!(project
+            .move_numeric_ids(
+                Ustr::from("test:item"),
+                &[ENumber::from(5.0), ENumber::from(7.0)],
+                20..=21,
+            )
+            .is_err());
+        assert_eq!(
+            value_json(&project, "b.json"),
+            json!({ "Id": 5, "Target": 3 })
+        );
+    }
+
+    #[test]
+    fn should_swap_ids() {
+        let mut project = project();
+        project
+            .remap_numeric_ids(
+                Ustr::from("test:item"),
+                &[
+                    (ENumber::from(1.0), ENumber::from(2.0)),
+                    (ENumber::from(2.0), ENumber::from(1.0)),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            value_json(&project, "a.json"),
+            json!({ "Id": 2, "Target": 1 })
+        );
+        assert_eq!(
+            value_json(&project, "b.json"),
+            json!({ "Id": 1, "Target": 2 })
+        );
+        assert_eq!(
+            value_json(&project, "holder.dbevalue"),
+            json!({
+                "List": [2, 1, 2],
+                "Map": { "a": 2, "b": 1 },
+                "Optional": 2,
+            })
+        );
+    }
+}
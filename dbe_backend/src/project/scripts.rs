@@ -0,0 +1,344 @@
+//! Script nodes
+//!
+//! Scripts are small [Rhai](https://rhai.rs) programs defined in `.dbescript`
+//! TOML files, either in the project itself or in modules. Every script
+//! declares its typed inputs and outputs, and becomes available in graphs as
+//! a node, similar to node groups.
+//!
+//! Inputs are passed to the script as variables with the same names, and
+//! outputs are read from the variables with the same names once the script
+//! finishes. Values are passed to and from the script in their JSON form, so
+//! structs become object maps, and lists become arrays.
+
+use crate::etype::EDataType;
+use crate::graph::inputs::{GraphInput, GraphOutput};
+use crate::json_utils::JsonValue;
+use crate::registry::ETypesRegistry;
+use crate::value::EValue;
+use camino::{Utf8Path, Utf8PathBuf};
+use miette::{bail, miette, Context, IntoDiagnostic};
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::Deserialize;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, LazyLock};
+use utils::map::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Maximum amount of operations that a single script run can perform, to
+/// avoid freezing on infinite loops
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+pub(crate) static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine
+});
+
+/// Content of a `.dbescript` file
+#[derive(Debug, Deserialize)]
+struct ScriptFile {
+    id: Uuid,
+    name: String,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    inputs: Vec<ScriptPort>,
+    #[serde(default)]
+    outputs: Vec<ScriptPort>,
+    source: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptPort {
+    name: String,
+    ty: EDataType,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectScript {
+    pub id: Uuid,
+    pub name: String,
+    /// Categories of the node search that the script is listed in
+    pub categories: Vec<String>,
+    pub inputs: Vec<GraphInput>,
+    pub outputs: Vec<GraphOutput>,
+    pub source: String,
+    ast: Arc<AST>,
+}
+
+impl Hash for ProjectScript {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.name.hash(state);
+        self.categories.hash(state);
+        self.inputs.hash(state);
+        self.outputs.hash(state);
+        self.source.hash(state);
+    }
+}
+
+impl ProjectScript {
+    /// Parses and compiles the script from the `.dbescript` file content
+    pub fn parse_toml(source: &str) -> miette::Result<Self> {
+        let file = toml::de::from_str::<ScriptFile>(source)
+            .into_diagnostic()
+            .context("failed to deserialize script TOML")?;
+
+        let mut names = HashSet::default();
+        for port in file.inputs.iter().chain(&file.outputs) {
+            if !is_identifier(&port.name) {
+                bail!(
+                    "`{}` is not a valid script variable name. Names must consist of ASCII letters, digits and underscores, and must not start with a digit",
+                    port.name
+                );
+            }
+            if !names.insert(port.name.as_str()) {
+                bail!("input or output `{}` is declared multiple times", port.name);
+            }
+        }
+
+        let ast = ENGINE
+            .compile(&file.source)
+            .map_err(|err| miette!("{}", err))
+            .context("failed to compile script")?;
+
+        // Port IDs are derived from names, so connections survive reloading
+        let port_id = |name: &str| Uuid::new_v5(&file.id, name.as_bytes());
+
+        Ok(Self {
+            id: file.id,
+            inputs: file
+                .inputs
+                .iter()
+                .map(|port| GraphInput {
+                    ty: Some(port.ty),
+                    id: port_id(&port.name),
+                    name: port.name.clone(),
+                })
+                .collect(),
+            outputs: file
+                .outputs
+                .iter()
+                .map(|port| GraphOutput {
+                    ty: Some(port.ty),
+                    id: port_id(&port.name),
+                    name: port.name.clone(),
+                })
+                .collect(),
+            name: file.name,
+            categories: file.categories,
+            source: file.source,
+            ast: Arc::new(ast),
+        })
+    }
+
+    /// Runs the script
+    ///
+    /// Inputs and outputs are in the order of [ProjectScript::inputs] and
+    /// [ProjectScript::outputs]
+    pub fn execute(
+        &self,
+        registry: &ETypesRegistry,
+        inputs: &[EValue],
+    ) -> miette::Result<Vec<EValue>> {
+        let mut scope = Scope::new();
+        for (input, value) in self.inputs.iter().zip(inputs) {
+            let json = value.write_json(registry)?;
+            let value = rhai::serde::to_dynamic(json)
+                .map_err(|err| miette!("{}", err))
+                .with_context(|| format!("failed to pass input `{}` to the script", input.name))?;
+            scope.push(input.name.clone(), value);
+        }
+        for output in &self.outputs {
+            scope.push(output.name.clone(), Dynamic::UNIT);
+        }
+
+        ENGINE
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|err| miette!("{}", err))?;
+
+        self.outputs
+            .iter()
+            .map(|output| {
+                let value = scope
+                    .get_value::<Dynamic>(&output.name)
+                    .filter(|value| !value.is_unit())
+                    .ok_or_else(|| miette!("script did not assign output `{}`", output.name))?;
+                let ty = output.ty.unwrap_or_else(EDataType::null);
+                let mut json = rhai::serde::from_dynamic::<JsonValue>(&value)
+                    .map_err(|err| miette!("{}", err))?;
+                ty.parse_json(registry, &mut json, false).with_context(|| {
+                    format!("script returned bad value for output `{}`", output.name)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Scripts of the project and of the loaded modules
+#[derive(Debug, Default)]
+pub struct ProjectScripts {
+    pub scripts: HashMap<Uuid, ProjectScript>,
+    paths: HashMap<Uuid, Utf8PathBuf>,
+}
+
+impl ProjectScripts {
+    pub fn add_script(&mut self, path: Utf8PathBuf, script: ProjectScript) -> miette::Result<()> {
+        let id = script.id;
+        if let Some(other_path) = self.paths.get(&id) {
+            if other_path != &path {
+                bail!(
+                    "script with id {:?} already exists at `{}`. Were script files copied manually?",
+                    id,
+                    other_path
+                );
+            }
+        }
+
+        self.scripts.insert(id, script);
+        self.paths.insert(id, path);
+
+        Ok(())
+    }
+
+    /// Removes the script loaded from the given file, returning it if it was
+    /// present
+    pub fn remove_script_at(&mut self, path: &Utf8Path) -> Option<ProjectScript> {
+        let id = self
+            .paths
+            .iter()
+            .find(|(_, p)| p.as_path() == path)
+            .map(|(id, _)| *id)?;
+        self.paths.remove(&id);
+        self.scripts.remove(&id)
+    }
+}
+
+/// Checks if the name can be used as a script variable
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::scripts::ProjectScript;
+    use crate::project::test_utils::{load_project, test_io, test_project};
+    use crate::value::EValue;
+    use uuid::Uuid;
+
+    const SCRIPT_ID: Uuid = Uuid::from_u128(1);
+
+    /// Script file with `value` and `factor` inputs and `result` output
+    fn script_toml(source: &str) -> String {
+        format!(
+            r#"
+id = "{}"
+name = "Scale"
+source = '''{}'''
+
+[[inputs]]
+name = "value"
+ty = "Number"
+
+[[inputs]]
+name = "factor"
+ty = "Number"
+
+[[outputs]]
+name = "result"
+ty = "Number"
+"#,
+            SCRIPT_ID, source
+        )
+    }
+
+    fn script(source: &str) -> ProjectScript {
+        ProjectScript::parse_toml(&script_toml(source)).unwrap()
+    }
+
+    fn execute(script: &ProjectScript, value: f64, factor: f64) -> miette::Result<Vec<EValue>> {
+        let registry = test_project(&[]).registry;
+        script.execute(&registry, &[value.into(), factor.into()])
+    }
+
+    #[test]
+    fn should_load_project_scripts() {
+        let project = test_project(&[(
+            "scale.dbescript",
+            script_toml("result = value * factor;").as_str(),
+        )]);
+
+        let script = &project.graphs.scripts.scripts[&SCRIPT_ID];
+        assert_eq!(script.name, "Scale");
+        assert_eq!(
+            script
+                .inputs
+                .iter()
+                .map(|i| i.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["value", "factor"]
+        );
+        assert_eq!(script.outputs[0].name, "result");
+
+        // Port IDs are stable between reloads
+        assert_eq!(
+            script.inputs[0].id,
+            Uuid::new_v5(&SCRIPT_ID, "value".as_bytes())
+        );
+    }
+
+    #[test]
+    fn should_report_compile_errors() {
+        let err = ProjectScript::parse_toml(&script_toml("result = ;")).unwrap_err();
+        assert_eq!(err.to_string(), "failed to compile script");
+
+        let err = load_project(test_io(&[(
+            "scale.dbescript",
+            script_toml("result = ;").as_str(),
+        )]))
+        .unwrap_err();
+        assert!(
+            err.chain()
+                .any(|cause| cause.to_string() == "failed to compile script"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn should_reject_bad_port_names() {
+        let toml = script_toml("").replace("name = \"factor\"", "name = \"1factor\"");
+        let err = ProjectScript::parse_toml(&toml).unwrap_err();
+        assert!(err.to_string().contains("not a valid script variable name"));
+
+        let toml = script_toml("").replace("name = \"factor\"", "name = \"value\"");
+        let err = ProjectScript::parse_toml(&toml).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input or output `value` is declared multiple times"
+        );
+    }
+
+    #[test]
+    fn should_execute_script() {
+        let script = script("result = value * factor;");
+
+        assert_eq!(execute(&script, 2.0, 3.5).unwrap(), vec![EValue::from(7.0)]);
+    }
+
+    #[test]
+    fn should_report_bad_outputs() {
+        let err = execute(&script("let x = value;"), 1.0, 1.0).unwrap_err();
+        assert_eq!(err.to_string(), "script did not assign output `result`");
+
+        let err = execute(&script("result = \"text\";"), 1.0, 1.0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "script returned bad value for output `result`"
+        );
+    }
+}
@@ -1,20 +1,24 @@
+use crate::etype::EDataType;
 use crate::m_try;
 use crate::project::project_graph::EvaluationStage;
+use crate::project::side_effects::diagnostics::{parse_field_path, GraphDiagnostic};
 use crate::project::side_effects::mappings::Mappings;
 use crate::project::{Project, ProjectFile};
 use crate::registry::ETypesRegistry;
 use crate::value::EValue;
 use camino::{Utf8Path, Utf8PathBuf};
+use diagnostic::diagnostic::DiagnosticLevel;
 use egui_snarl::NodeId;
 use itertools::Itertools;
 use maybe_owned::MaybeOwnedMut;
 use miette::{bail, WrapErr};
-use std::collections::{btree_map, hash_map, BTreeMap};
+use std::collections::{btree_map, hash_map, BTreeMap, BTreeSet};
 use std::hash::{Hash, Hasher};
 use tracing::info;
-use utils::map::HashMap;
+use utils::map::{HashMap, HashSet};
 use uuid::Uuid;
 
+pub mod diagnostics;
 pub mod mappings;
 
 #[derive(Debug)]
@@ -31,6 +35,13 @@ pub enum SideEffect {
     ShowDebug {
         value: EValue,
     },
+    /// Reports a diagnostic against a field of the project file
+    EmitDiagnostic {
+        file: String,
+        path: String,
+        message: String,
+        level: DiagnosticLevel,
+    },
 }
 
 type SideEffectEmitter = (Utf8PathBuf, Vec<SideEffectPathItem>, usize);
@@ -40,11 +51,14 @@ fn clean_up_path(path: &str) -> String {
 }
 
 impl SideEffect {
+    /// Applies the side effect to the project
+    ///
+    /// Returns path of the emitted file, if any
     pub fn execute<Io>(
         self,
         emitter: SideEffectEmitter,
         project: &mut Project<Io>,
-    ) -> miette::Result<()> {
+    ) -> miette::Result<Option<Utf8PathBuf>> {
         fn extension(is_dbevalue: bool) -> &'static str {
             if is_dbevalue {
                 ".dbevalue"
@@ -69,7 +83,8 @@ impl SideEffect {
                 }
                 project
                     .files
-                    .insert(path, ProjectFile::GeneratedValue(value));
+                    .insert(path.clone(), ProjectFile::GeneratedValue(value));
+                Ok(Some(path))
             }
             SideEffect::EmitTransientFile { value, is_dbevalue } => {
                 let tmp_path = project.registry.project_config().emitted_dir.join(format!(
@@ -81,7 +96,8 @@ impl SideEffect {
                 ));
                 project
                     .files
-                    .insert(tmp_path, ProjectFile::GeneratedValue(value));
+                    .insert(tmp_path.clone(), ProjectFile::GeneratedValue(value));
+                Ok(Some(tmp_path))
             }
             SideEffect::ShowDebug { value } => {
                 info!(
@@ -91,10 +107,131 @@ impl SideEffect {
                     %value,
                     "Debug",
                 );
+                Ok(None)
+            }
+            SideEffect::EmitDiagnostic {
+                file,
+                path,
+                message,
+                level,
+            } => {
+                let file = Utf8PathBuf::from(clean_up_path(&file));
+                match project.files.get(&file) {
+                    Some(ProjectFile::Value(..) | ProjectFile::GeneratedValue(..)) => {}
+                    Some(_) => bail!("file `{}` is not a value", file),
+                    None => bail!("file `{}` does not exist", file),
+                }
+                let path = parse_field_path(&path)
+                    .with_context(|| format!("failed to parse field path `{}`", path))?;
+                project.graph_runs.push_diagnostic(
+                    emitter.0,
+                    GraphDiagnostic {
+                        file,
+                        path,
+                        message,
+                        level,
+                    },
+                );
+                Ok(None)
             }
         }
+    }
+}
 
-        Ok(())
+/// Project state read by a single graph during its evaluation
+///
+/// Used to detect whenever the graph needs to be re-evaluated
+#[derive(Debug, Clone, Default)]
+pub struct GraphInputs {
+    /// Hash of all project values, if the graph iterated over all project files
+    pub all_files: Option<u64>,
+    /// Hashes of project values of the given types, for types that the graph
+    /// iterated over
+    pub files_of_type: HashMap<EDataType, u64>,
+    /// Hashes of transient storage entries that were read before being
+    /// written by the graph itself
+    pub transient_keys: HashMap<String, u64>,
+    /// Mapping files that were loaded by the graph
+    pub mappings: BTreeSet<Utf8PathBuf>,
+}
+
+impl GraphInputs {
+    /// Checks whenever any of the recorded project files or transient storage
+    /// entries differ from their current state
+    ///
+    /// Mapping files are not checked, since they are modified by the graphs
+    /// that use them
+    pub fn changed(
+        &self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        effects: &mut SideEffects,
+    ) -> bool {
+        let hashes = effects.value_hashes(files);
+        let files_changed = self.all_files.is_some_and(|hash| hashes.all() != hash)
+            || self
+                .files_of_type
+                .iter()
+                .any(|(ty, hash)| hashes.of_type(*ty) != *hash);
+        files_changed
+            || self
+                .transient_keys
+                .iter()
+                .any(|(key, hash)| hash_of(effects.transistent_storage.get(key)) != *hash)
+    }
+}
+
+/// Hashes of paths and contents of the project values, grouped by the value
+/// type
+///
+/// Hashes of the individual files are combined in an order-independent way,
+/// so a single file change only requires re-hashing of that file
+#[derive(Debug, Default)]
+pub struct ValueHashes {
+    /// Type and hash of each value file
+    files: HashMap<Utf8PathBuf, (EDataType, u64)>,
+    /// Combined hashes of the values of each type
+    types: HashMap<EDataType, u64>,
+    /// Combined hash of all values
+    all: u64,
+}
+
+impl ValueHashes {
+    pub fn new(files: &BTreeMap<Utf8PathBuf, ProjectFile>) -> Self {
+        let mut hashes = Self::default();
+        for (path, file) in files {
+            hashes.update(path, Some(file));
+        }
+        hashes
+    }
+
+    /// Updates the hashes after the file at the given path was changed, or
+    /// removed if `file` is `None`
+    pub fn update(&mut self, path: &Utf8Path, file: Option<&ProjectFile>) {
+        if let Some((ty, hash)) = self.files.remove(path) {
+            self.all = self.all.wrapping_sub(hash);
+            let type_hash = self.types.entry(ty).or_default();
+            *type_hash = type_hash.wrapping_sub(hash);
+        }
+
+        let Some(ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) = file else {
+            return;
+        };
+        let ty = value.ty();
+        let hash = hash_of((path, value));
+        self.all = self.all.wrapping_add(hash);
+        let type_hash = self.types.entry(ty).or_default();
+        *type_hash = type_hash.wrapping_add(hash);
+        self.files.insert(path.to_path_buf(), (ty, hash));
+    }
+
+    /// Hash of all project values
+    pub fn all(&self) -> u64 {
+        self.all
+    }
+
+    /// Hash of the project values of the given type
+    pub fn of_type(&self, ty: EDataType) -> u64 {
+        self.types.get(&ty).copied().unwrap_or_default()
     }
 }
 
@@ -104,6 +241,14 @@ pub struct SideEffects {
     mappings: HashMap<Utf8PathBuf, (u64, Mappings)>,
     transistent_storage: HashMap<String, EValue>,
     current_stage: EvaluationStage,
+    /// Inputs read by the currently evaluated graph
+    inputs: GraphInputs,
+    /// Transient storage keys written by the currently evaluated graph
+    transient_written: HashSet<String>,
+    /// Files emitted by each graph, keyed by the graph path
+    emitted: HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>,
+    /// Hashes of the project values, built on the first use
+    value_hashes: Option<ValueHashes>,
 }
 
 impl SideEffects {
@@ -113,6 +258,10 @@ impl SideEffects {
             mappings: Default::default(),
             transistent_storage: Default::default(),
             current_stage: EvaluationStage::Data,
+            inputs: Default::default(),
+            transient_written: Default::default(),
+            emitted: Default::default(),
+            value_hashes: None,
         }
     }
 
@@ -131,7 +280,11 @@ impl SideEffects {
             assert!(iter <= 1000, "Side effects formed an infinite loop");
             let mut effects = std::mem::take(&mut self.effects);
             for (emitter, effect) in effects.drain(..) {
-                effect.execute(emitter, project)?;
+                let graph = emitter.0.clone();
+                if let Some(path) = effect.execute(emitter, project)? {
+                    self.file_changed(&path, project.files.get(&path));
+                    self.emitted.entry(graph).or_default().push(path);
+                }
             }
             if self.effects.is_empty() {
                 self.effects = effects;
@@ -145,13 +298,17 @@ impl SideEffects {
 
     pub fn save_mappings<Io>(&mut self, project: &mut Project<Io>) -> miette::Result<()> {
         for (path, (hash, mappings)) in &mut self.mappings {
+            let hashes = &mut self.value_hashes;
             m_try(|| {
                 match project.files.entry(path.clone()) {
                     btree_map::Entry::Vacant(entry) => {
                         if mappings.has_persistent_ids() {
                             let value = mappings.as_evalue(&project.registry)?;
                             *hash = hash_of(&value);
-                            entry.insert(ProjectFile::Value(value));
+                            let file = entry.insert(ProjectFile::Value(value));
+                            if let Some(hashes) = hashes {
+                                hashes.update(path, Some(&*file));
+                            }
                         }
                     }
                     btree_map::Entry::Occupied(mut entry) => {
@@ -172,6 +329,9 @@ impl SideEffects {
                         let value = mappings.as_evalue(&project.registry)?;
                         *hash = hash_of(&value);
                         entry.insert(ProjectFile::Value(value));
+                        if let Some(hashes) = hashes {
+                            hashes.update(path, Some(entry.get()));
+                        }
                     }
                 }
                 Ok(())
@@ -189,6 +349,7 @@ impl SideEffects {
         path: &Utf8Path,
         ranges: Option<&EValue>,
     ) -> miette::Result<&mut Mappings> {
+        self.inputs.mappings.insert(path.to_path_buf());
         m_try(|| match self.mappings.entry(path.to_path_buf()) {
             hash_map::Entry::Occupied(entry) => {
                 let mappings = &mut entry.into_mut().1;
@@ -213,19 +374,106 @@ impl SideEffects {
     }
 
     pub fn get_transient_storage(&mut self, key: &str) -> Option<&EValue> {
+        self.record_transient_read(key);
         self.transistent_storage.get(key)
     }
 
     pub fn set_transient_storage(&mut self, key: String, value: EValue) {
+        self.transient_written.insert(key.clone());
         self.transistent_storage.insert(key, value);
     }
 
-    pub fn has_transient_storage(&self, key: &str) -> bool {
+    pub fn has_transient_storage(&mut self, key: &str) -> bool {
+        self.record_transient_read(key);
         self.transistent_storage.contains_key(key)
     }
 
     pub fn clear_transient_storage(&mut self) {
         self.transistent_storage.clear();
+        self.transient_written.clear();
+    }
+
+    /// Prepares for evaluation of the next graph, clearing the transient
+    /// storage and the recorded inputs
+    pub fn start_graph(&mut self) {
+        self.clear_transient_storage();
+        self.inputs = Default::default();
+    }
+
+    /// Inserts a value written by another graph into the transient storage
+    ///
+    /// Unlike [SideEffects::set_transient_storage], the value is not
+    /// considered to be written by the current graph
+    pub fn provide_transient_storage(&mut self, key: String, value: EValue) {
+        self.transistent_storage.insert(key, value);
+    }
+
+    /// Returns transient storage entries written by the current graph
+    pub fn transient_outputs(&self) -> Vec<(String, EValue)> {
+        self.transient_written
+            .iter()
+            .filter_map(|key| {
+                self.transistent_storage
+                    .get(key)
+                    .map(|value| (key.clone(), value.clone()))
+            })
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect()
+    }
+
+    /// Returns inputs read by the graph since the last [SideEffects::start_graph] call
+    pub fn take_inputs(&mut self) -> GraphInputs {
+        std::mem::take(&mut self.inputs)
+    }
+
+    /// Returns files emitted by the side effects of the graph at the given path
+    pub fn take_emitted(&mut self, graph: &Utf8Path) -> Vec<Utf8PathBuf> {
+        self.emitted.remove(graph).unwrap_or_default()
+    }
+
+    /// Records that the graph has read project values, optionally only the
+    /// values of the given type
+    pub fn record_files_read(
+        &mut self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        ty: Option<EDataType>,
+    ) {
+        match ty {
+            None => {
+                if self.inputs.all_files.is_none() {
+                    self.inputs.all_files = Some(self.value_hashes(files).all());
+                }
+            }
+            Some(ty) => {
+                if !self.inputs.files_of_type.contains_key(&ty) {
+                    let hash = self.value_hashes(files).of_type(ty);
+                    self.inputs.files_of_type.insert(ty, hash);
+                }
+            }
+        }
+    }
+
+    /// Updates the hashes of the project values after the file was changed
+    /// by something other than the side effects, or removed if `file` is
+    /// `None`
+    pub fn file_changed(&mut self, path: &Utf8Path, file: Option<&ProjectFile>) {
+        if let Some(hashes) = &mut self.value_hashes {
+            hashes.update(path, file);
+        }
+    }
+
+    /// Returns hashes of the project values, building them on the first call
+    fn value_hashes(&mut self, files: &BTreeMap<Utf8PathBuf, ProjectFile>) -> &ValueHashes {
+        self.value_hashes
+            .get_or_insert_with(|| ValueHashes::new(files))
+    }
+
+    fn record_transient_read(&mut self, key: &str) {
+        if self.transient_written.contains(key) || self.inputs.transient_keys.contains_key(key) {
+            return;
+        }
+        let hash = hash_of(self.transistent_storage.get(key));
+        self.inputs.transient_keys.insert(key.to_string(), hash);
     }
 
     pub fn set_stage(&mut self, stage: EvaluationStage) {
@@ -412,18 +660,46 @@ impl<'a> SideEffectsContext<'a> {
         }
     }
 
-    pub fn has_transient_storage(&self, key: &str) -> miette::Result<bool> {
+    pub fn has_transient_storage(&mut self, key: &str) -> miette::Result<bool> {
         match self {
             SideEffectsContext::Context { effects, .. } => Ok(effects.has_transient_storage(key)),
             SideEffectsContext::Unavailable => bail!("Side effects context is unavailable"),
         }
     }
 
+    /// Iterates over all project files
+    ///
+    /// The graph will be considered dependent on all project values
     pub fn project_files_iter(
-        &self,
+        &mut self,
     ) -> miette::Result<impl Iterator<Item = (&Utf8PathBuf, &ProjectFile)>> {
         match self {
-            SideEffectsContext::Context { files, .. } => Ok(files.iter()),
+            SideEffectsContext::Context { effects, files, .. } => {
+                effects.record_files_read(files, None);
+                Ok(files.iter())
+            }
+            SideEffectsContext::Unavailable => bail!("Side effects context is unavailable"),
+        }
+    }
+
+    /// Iterates over all project values of the given type
+    ///
+    /// The graph will be considered dependent only on the values of this type
+    pub fn project_values_of_type(
+        &mut self,
+        ty: EDataType,
+    ) -> miette::Result<impl Iterator<Item = (&Utf8PathBuf, &EValue)>> {
+        match self {
+            SideEffectsContext::Context { effects, files, .. } => {
+                effects.record_files_read(files, Some(ty));
+                Ok(files.iter().filter_map(move |(path, file)| {
+                    let (ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) = file
+                    else {
+                        return None;
+                    };
+                    (value.ty() == ty).then_some((path, value))
+                }))
+            }
             SideEffectsContext::Unavailable => bail!("Side effects context is unavailable"),
         }
     }
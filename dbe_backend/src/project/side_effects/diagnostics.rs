@@ -0,0 +1,299 @@
+//! Diagnostics emitted by the project graphs
+//!
+//! Graph diagnostics are reported against a project file and a field path
+//! inside of it, and are re-emitted every time the file is validated, so
+//! they show up alongside the regular validation results.
+
+use camino::Utf8PathBuf;
+use diagnostic::context::DiagnosticContextMut;
+use diagnostic::diagnostic::DiagnosticLevel;
+use diagnostic::path::DiagnosticPathSegment;
+use miette::{bail, miette};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone)]
+pub struct GraphDiagnostic {
+    /// Project file the diagnostic is reported against
+    pub file: Utf8PathBuf,
+    /// Path to the field inside the file
+    pub path: Vec<DiagnosticPathSegment>,
+    pub message: String,
+    pub level: DiagnosticLevel,
+}
+
+impl GraphDiagnostic {
+    /// Emits the diagnostic into the context of its file
+    pub fn emit(&self, ctx: DiagnosticContextMut) {
+        fn emit_at(
+            mut ctx: DiagnosticContextMut,
+            path: &[DiagnosticPathSegment],
+            diagnostic: &GraphDiagnostic,
+        ) {
+            match path.split_first() {
+                None => ctx.emit(miette!("{}", diagnostic.message), diagnostic.level),
+                Some((segment, rest)) => emit_at(ctx.enter(segment.clone()), rest, diagnostic),
+            }
+        }
+
+        emit_at(ctx, &self.path, self);
+    }
+}
+
+/// Parses the field path in the same format as diagnostic paths are
+/// displayed, for example `Items[0].Data<Weapon>.Damage`
+///
+/// Numeric bracketed segments are treated as list indices, and all other
+/// bracketed segments are treated as map keys. Empty path refers to the
+/// file itself
+pub fn parse_field_path(path: &str) -> miette::Result<Vec<DiagnosticPathSegment>> {
+    fn push_field(
+        segments: &mut Vec<DiagnosticPathSegment>,
+        field: &mut String,
+    ) -> miette::Result<()> {
+        let name = field.trim();
+        if name.is_empty() {
+            bail!("field name is empty");
+        }
+        segments.push(DiagnosticPathSegment::Field(Cow::Owned(name.to_string())));
+        field.clear();
+        Ok(())
+    }
+
+    let mut segments = vec![];
+    let mut field = String::new();
+    let mut chars = path.trim().chars();
+
+    // Whenever the field name is expected before the next delimiter, as
+    // opposed to following a closing bracket
+    let mut expects_field = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                if expects_field {
+                    push_field(&mut segments, &mut field)?;
+                }
+                expects_field = true;
+            }
+            '[' | '<' => {
+                if !field.is_empty() {
+                    push_field(&mut segments, &mut field)?;
+                } else if expects_field && !segments.is_empty() {
+                    bail!("field name is empty");
+                }
+                let close = if c == '[' { ']' } else { '>' };
+                let mut content = String::new();
+                loop {
+                    match chars.next() {
+                        Some(next) if next == close => break,
+                        Some(next) => content.push(next),
+                        None => bail!("missing closing `{}`", close),
+                    }
+                }
+                let content = content.trim();
+                if content.is_empty() {
+                    bail!("empty `{}{}` segment", c, close);
+                }
+                segments.push(if c == '<' {
+                    DiagnosticPathSegment::Variant(Cow::Owned(content.to_string()))
+                } else if let Ok(index) = content.parse::<usize>() {
+                    DiagnosticPathSegment::Index(index)
+                } else {
+                    DiagnosticPathSegment::MapKey(Cow::Owned(content.to_string()))
+                });
+                expects_field = false;
+            }
+            ']' | '>' => bail!("unexpected `{}`", c),
+            c => {
+                if !expects_field {
+                    bail!("expected `.`, `[` or `<` after `]` or `>`, got `{}`", c);
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if expects_field && !(segments.is_empty() && field.is_empty()) {
+        push_field(&mut segments, &mut field)?;
+    }
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_field_path;
+    use crate::graph::editing::GraphEditingContext;
+    use crate::graph::node::commands::SnarlCommands;
+    use crate::project::docs::Docs;
+    use crate::project::io::MemoryIO;
+    use crate::project::side_effects::SideEffectsContext;
+    use crate::project::test_utils::{graph_node, test_project, DAMAGE_ITEM_TYPE};
+    use crate::project::Project;
+    use camino::Utf8Path;
+    use diagnostic::diagnostic::DiagnosticLevel;
+    use diagnostic::path::DiagnosticPathSegment;
+    use itertools::Itertools;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const GRAPH_PATH: &str = "check.dbegraph";
+    const GRAPH_ID: Uuid = Uuid::from_u128(1);
+
+    /// Validation graph emitting an error at the `Damage` field of the file
+    /// and a warning at the file itself
+    fn project(file: &str) -> Project<MemoryIO> {
+        let node = |id: &str| graph_node(id, json!(null));
+        let inline = |node: usize, file: &str, path: &str, message: &str| {
+            [
+                json!([{ "node": node, "input": 0 }, file]),
+                json!([{ "node": node, "input": 1 }, path]),
+                json!([{ "node": node, "input": 2 }, message]),
+            ]
+        };
+        let graph = json!({
+            "version": "V1",
+            "id": GRAPH_ID,
+            "stage": "Validation",
+            "graph": {
+                "nodes": [[0, node("emit_error")], [1, node("emit_warning")]],
+                "edges": [],
+                "inline_values": inline(0, file, "Damage", "Damage is too high")
+                    .into_iter()
+                    .chain(inline(1, file, "", "Item is unused"))
+                    .collect_vec(),
+            },
+        })
+        .to_string();
+        test_project(&[
+            ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+            ("a.json", r#"{ "Damage": 1 }"#),
+            (GRAPH_PATH, &graph),
+        ])
+    }
+
+    /// Path, level and message of every diagnostic reported for the file
+    fn diagnostics(
+        project: &Project<MemoryIO>,
+        path: &str,
+    ) -> Vec<(String, DiagnosticLevel, String)> {
+        project
+            .diagnostics
+            .diagnostics
+            .get(path)
+            .into_iter()
+            .flat_map(|reports| {
+                reports.iter().flat_map(|(path, reports)| {
+                    reports.iter().map(move |report| {
+                        (path.to_string(), report.level, report.info.to_string())
+                    })
+                })
+            })
+            .sorted()
+            .collect()
+    }
+
+    fn expected() -> Vec<(String, DiagnosticLevel, String)> {
+        vec![
+            (
+                String::new(),
+                DiagnosticLevel::Warning,
+                "Item is unused".to_string(),
+            ),
+            (
+                "Damage".to_string(),
+                DiagnosticLevel::Error,
+                "Damage is too high".to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn should_report_graph_diagnostics_at_target_field() {
+        let mut project = project("a.json");
+        project.clean_validate().unwrap();
+        assert_eq!(diagnostics(&project, "a.json"), expected());
+    }
+
+    #[test]
+    fn should_keep_diagnostics_of_skipped_graphs() {
+        let mut project = project("a.json");
+        project.clean_validate().unwrap();
+
+        project.incremental_validate().unwrap();
+        assert!(!project.graph_runs.was_evaluated(Utf8Path::new(GRAPH_PATH)));
+        assert_eq!(diagnostics(&project, "a.json"), expected());
+    }
+
+    #[test]
+    fn should_fail_on_missing_target_file() {
+        let mut project = project("missing.json");
+        let err = project.clean_validate().unwrap_err();
+        let chain = err.chain().map(|e| e.to_string()).collect_vec();
+        assert!(
+            chain.contains(&"file `missing.json` does not exist".to_string()),
+            "{:?}",
+            chain
+        );
+    }
+
+    #[test]
+    fn should_drop_diagnostics_that_are_no_longer_emitted() {
+        let mut project = project("a.json");
+        project.clean_validate().unwrap();
+
+        let graph = project.graphs.graphs.get_mut(&GRAPH_ID).unwrap();
+        let mut outputs = None;
+        let mut ctx = GraphEditingContext::from_graph(
+            graph.graph_mut(),
+            &project.registry,
+            &Docs::Stub,
+            None,
+            SideEffectsContext::Unavailable,
+            false,
+            &[],
+            &mut outputs,
+        );
+        let error = ctx
+            .snarl
+            .node_ids()
+            .find(|(_, node)| node.id().as_str() == "emit_error")
+            .map(|(id, _)| id)
+            .unwrap();
+        ctx.remove_node(error, &mut SnarlCommands::new()).unwrap();
+
+        project.incremental_validate().unwrap();
+        assert!(project.graph_runs.was_evaluated(Utf8Path::new(GRAPH_PATH)));
+        assert_eq!(diagnostics(&project, "a.json"), expected()[..1]);
+    }
+
+    #[test]
+    fn field_path_parsing() {
+        let path = parse_field_path("Items[0].Data<Weapon>.Damage[key]").unwrap();
+        assert_eq!(
+            path,
+            vec![
+                DiagnosticPathSegment::Field("Items".into()),
+                DiagnosticPathSegment::Index(0),
+                DiagnosticPathSegment::Field("Data".into()),
+                DiagnosticPathSegment::Variant("Weapon".into()),
+                DiagnosticPathSegment::Field("Damage".into()),
+                DiagnosticPathSegment::MapKey("key".into()),
+            ]
+        );
+
+        assert_eq!(parse_field_path("").unwrap(), vec![]);
+        assert_eq!(
+            parse_field_path("[1][2]").unwrap(),
+            vec![
+                DiagnosticPathSegment::Index(1),
+                DiagnosticPathSegment::Index(2)
+            ]
+        );
+
+        assert!(parse_field_path("Items.").is_err());
+        assert!(parse_field_path("Items..Data").is_err());
+        assert!(parse_field_path("Items[0]Data").is_err());
+        assert!(parse_field_path("Items[]").is_err());
+        assert!(parse_field_path("Items[0").is_err());
+    }
+}
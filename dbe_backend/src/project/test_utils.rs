@@ -0,0 +1,127 @@
+use crate::project::io::{MemoryIO, ProjectIO as _};
+use crate::project::{Project, ProjectConfig, ProjectFile, MODULE_FILE, PROJECT_FILE};
+use crate::value::EValue;
+use camino::Utf8Path;
+use miette::{Context as _, IntoDiagnostic as _};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Root of the in-memory test projects
+pub const TEST_ROOT: &str = "/project";
+
+/// Default project configuration, importing `test:item` values
+pub const TEST_PROJECT_TOML: &str = "[types]\nimport = \"test:item\"\n";
+
+/// Default configuration of the `test` module
+pub const TEST_MODULE_TOML: &str = "namespace = \"test\"\nversion = \"1.0.0\"\n";
+
+/// Type with a numeric ID and a reference to another item, for use as
+/// `test.dbemodule/types/item.kdl`
+pub const NUMERIC_ID_ITEM_TYPE: &str = r#"struct {
+    object "Id" "sys:ids/numeric" {
+        const "Id" "test:item"
+    }
+    object "Target" "sys:ids/numeric_ref" {
+        const "Id" "test:item"
+    }
+}"#;
+
+/// Type with a single number field, for use as `test.dbemodule/types/item.kdl`
+pub const DAMAGE_ITEM_TYPE: &str = r#"struct { number "Damage"; }"#;
+
+/// Path of the graph added by [damage_project]
+pub const TEST_GRAPH_PATH: &str = "graph.dbegraph";
+
+/// Graph without any nodes
+pub const EMPTY_GRAPH: &str =
+    r#"{ "version": "V1", "graph": { "nodes": [], "edges": [], "inline_values": [] } }"#;
+
+/// Creates in-memory IO with the given files, adding the default project
+/// configuration and `test` module configuration if they are missing
+pub fn test_io(files: &[(&str, &str)]) -> MemoryIO {
+    let io = MemoryIO::new(PathBuf::from(TEST_ROOT));
+    for (path, content) in files {
+        io.insert(path, *content);
+    }
+    if io.get(PROJECT_FILE).is_none() {
+        io.insert(PROJECT_FILE, TEST_PROJECT_TOML);
+    }
+    let module_file = Path::new("test.dbemodule").join(MODULE_FILE);
+    if io.get(&module_file).is_none() {
+        io.insert(module_file, TEST_MODULE_TOML);
+    }
+    io
+}
+
+/// Loads the project from the in-memory IO
+pub fn load_project(io: MemoryIO) -> miette::Result<Project<MemoryIO>> {
+    let config = String::from_utf8(io.get(PROJECT_FILE).unwrap_or_default()).into_diagnostic()?;
+    let config: ProjectConfig = toml::de::from_str(&config)
+        .into_diagnostic()
+        .context("Failed to parse project configuration")?;
+    let files = io.list_files()?;
+    Project::from_files(TEST_ROOT, config, files, io)
+}
+
+/// Creates and loads the in-memory project with the given files
+pub fn test_project(files: &[(&str, &str)]) -> Project<MemoryIO> {
+    load_project(test_io(files)).unwrap()
+}
+
+/// Creates the project with the [DAMAGE_ITEM_TYPE] item `a.json` and an
+/// empty graph at [TEST_GRAPH_PATH], followed by the given files
+pub fn damage_project(files: &[(&str, &str)]) -> Project<MemoryIO> {
+    let mut all = vec![
+        ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+        ("a.json", r#"{ "Damage": 1 }"#),
+        (TEST_GRAPH_PATH, EMPTY_GRAPH),
+    ];
+    all.extend_from_slice(files);
+    test_project(&all)
+}
+
+/// Serialized graph node of the given type
+pub fn graph_node(id: &str, data: serde_json::Value) -> serde_json::Value {
+    json!({
+        "color_scheme": null,
+        "custom_title": null,
+        "data": data,
+        "id": id,
+        "open": true,
+        "pos": { "x": 0.0, "y": 0.0 },
+    })
+}
+
+/// ID of the graph file at the given path
+pub fn graph_id(project: &Project<MemoryIO>, path: &str) -> Uuid {
+    let Some(ProjectFile::Graph(id)) = project.files.get(Utf8Path::new(path)) else {
+        panic!("graph `{}` is missing", path);
+    };
+    *id
+}
+
+/// Value of the plain or generated value file at the given path
+pub fn value_file<'a>(project: &'a Project<MemoryIO>, path: &str) -> &'a EValue {
+    match project.files.get(Utf8Path::new(path)) {
+        Some(ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) => value,
+        _ => panic!("value file `{}` is missing", path),
+    }
+}
+
+/// Mutable value of the plain or generated value file at the given path
+pub fn value_file_mut<'a>(project: &'a mut Project<MemoryIO>, path: &str) -> &'a mut EValue {
+    match project.files.get_mut(Utf8Path::new(path)) {
+        Some(ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) => value,
+        _ => panic!("value file `{}` is missing", path),
+    }
+}
+
+/// Sets the number field of the struct value file, without notifying the
+/// project about the change
+pub fn set_field(project: &mut Project<MemoryIO>, path: &str, field: &str, value: f64) {
+    let EValue::Struct { fields, .. } = value_file_mut(project, path) else {
+        panic!("value file `{}` is not a struct", path);
+    };
+    fields.insert(field.into(), EValue::from(value));
+}
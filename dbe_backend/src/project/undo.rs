@@ -1,16 +1,22 @@
+use crate::graph::history::{GraphEditLog, GraphEdits, MergeKey};
+use crate::graph::Graph;
 use crate::project::project_graph::{ProjectGraph, ProjectGraphs};
-use crate::project::ProjectFile;
+use crate::project::{generated_marker_path, ProjectFile};
 use crate::value::EValue;
 use camino::{Utf8Path, Utf8PathBuf};
-use miette::{bail, WrapErr};
-use std::collections::BTreeMap;
+use itertools::Itertools;
+use miette::{bail, miette, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::hash::{Hash, Hasher};
 use strum::EnumIs;
 use tracing::warn;
-use utils::map::{hash_of, HashMap};
+use utils::map::{hash_of, HashMap, HashSet};
 use utils::ring_stack::RingStack;
 use uuid::Uuid;
 
+mod persist;
+
 #[derive(Debug)]
 pub struct UndoHistory {
     settings: UndoSettings,
@@ -25,6 +31,8 @@ pub struct UndoHistory {
     /// Actions that need to be performed to redo the undone history
     redo_snapshots: Vec<FileSnapshot>,
     flux: Option<Flux>,
+    /// Graph edits step that can be extended by the following edits
+    graph_merge: Option<GraphMerge>,
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +67,7 @@ impl UndoHistory {
             undone_history: Default::default(),
             redo_snapshots: Default::default(),
             last_snapshot: Default::default(),
+            graph_merge: None,
         }
     }
 
@@ -67,15 +76,17 @@ impl UndoHistory {
         files: &BTreeMap<Utf8PathBuf, ProjectFile>,
         graphs: &ProjectGraphs,
         time: f64,
-    ) {
+    ) -> miette::Result<()> {
         self.cur_time = time;
         if let Some(flux) = &self.flux {
             if flux.since_start(self.cur_time) > self.settings.auto_save_interval
                 || flux.since_last_change(self.cur_time) > self.settings.stable_time
             {
-                self.interrupt_flux(files, graphs).unwrap();
+                self.interrupt_flux(files, graphs)?;
             }
         }
+
+        Ok(())
     }
 
     pub fn ensure_file_state(
@@ -156,13 +167,15 @@ impl UndoHistory {
             .insert(path.to_path_buf(), new_snapshot)
             .expect("Snapshot existence was ensured earlier");
 
-        let snapshot = FileSnapshot {
-            id: self.next_change_index(),
-            kind: SnapshotKind::Change,
-            path: path.to_path_buf(),
-            state: hash_of(&snapshot),
-            value: snapshot,
-        };
+        let snapshot = FileSnapshot::new(
+            self.next_change_index(),
+            SnapshotKind::Change,
+            vec![PathSnapshot {
+                path: path.to_path_buf(),
+                value: snapshot,
+                deletion: None,
+            }],
+        );
 
         self.push_snapshot(snapshot);
 
@@ -175,35 +188,341 @@ impl UndoHistory {
         Ok(())
     }
 
+    /// Notify the undo history that a graph file may have changed, providing
+    /// the edits recorded by the graph since the last check
+    ///
+    /// If the recorded edits fully explain the change, they are saved as a
+    /// history step, which is undone by reverting the edits instead of
+    /// replacing the whole graph. Node moves and edits of the same inline
+    /// value that follow each other within
+    /// [`UndoSettings::stable_time`](field@UndoSettings::stable_time) are
+    /// merged into a single step. Otherwise, this works like
+    /// [UndoHistory::check_file]
+    pub fn check_graph(
+        &mut self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &ProjectGraphs,
+        path: impl AsRef<Utf8Path>,
+        edits: GraphEditLog,
+        force_snapshot: bool,
+    ) -> miette::Result<()> {
+        let path = path.as_ref();
+        let Some(file) = files.get(path) else {
+            bail!("File not found: {:?}", path);
+        };
+
+        let ProjectFile::Graph(id) = file else {
+            return self.check_file(files, graphs, path, force_snapshot);
+        };
+
+        let graph = graphs
+            .graphs
+            .get(id)
+            .expect("Graph of the graph file should be present");
+
+        let explained = !edits.is_empty()
+            && !self.flux.as_ref().is_some_and(|flux| flux.path == path)
+            && edits.head() == hash_of(graph.graph())
+            && matches!(
+                self.last_snapshot.get(path),
+                Some(ItemSnapshot::Graph(last_id, last))
+                    if last_id == id
+                        && last.metadata_hash() == graph.metadata_hash()
+                        && hash_of(last.graph()) == edits.base
+            );
+
+        if !explained {
+            return self.check_file(files, graphs, path, force_snapshot);
+        }
+
+        self.interrupt_flux(files, graphs)?;
+
+        self.last_known_state
+            .insert(path.to_path_buf(), state_of(file, graphs)?);
+        self.last_snapshot
+            .insert(path.to_path_buf(), ItemSnapshot::from_file(file, graphs)?);
+
+        let key = edits.merge_key();
+        let undo = GraphEdits::undo_of(&edits.actions);
+
+        let merge = self.graph_merge.as_mut().filter(|merge| {
+            !force_snapshot
+                && self.redo_snapshots.is_empty()
+                && merge.path == path
+                && key.as_ref() == Some(&merge.key)
+                && self.cur_time - merge.time <= self.settings.stable_time
+        });
+
+        if let Some(merge) = merge {
+            let top = self
+                .history
+                .iter_mut()
+                .next_back()
+                .filter(|top| top.id == merge.id);
+
+            if let Some(top) = top {
+                if let ItemSnapshot::GraphEdits(_, previous) = &mut top.items[0].value {
+                    *previous = undo.then(std::mem::take(previous));
+                    top.state = hash_of(&top.items[0].value);
+                    merge.time = self.cur_time;
+                    return Ok(());
+                }
+            }
+        }
+
+        let snapshot = FileSnapshot::new(
+            self.next_change_index(),
+            SnapshotKind::Change,
+            vec![PathSnapshot {
+                path: path.to_path_buf(),
+                value: ItemSnapshot::GraphEdits(*id, undo),
+                deletion: None,
+            }],
+        );
+        let snapshot_id = snapshot.id;
+
+        self.push_snapshot(snapshot);
+
+        self.graph_merge = key.map(|key| GraphMerge {
+            path: path.to_path_buf(),
+            key,
+            time: self.cur_time,
+            id: snapshot_id,
+        });
+
+        Ok(())
+    }
+
+    /// Notify the undo history that multiple files were changed as a part of
+    /// a single operation, saving a single snapshot that covers all of them.
+    ///
+    /// All the files must be checked with [UndoHistory::check_file] before
+    /// being changed, so their previous state is known. Files that didn't
+    /// actually change are not included in the snapshot
+    pub fn check_files_together(
+        &mut self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &ProjectGraphs,
+        paths: &[Utf8PathBuf],
+    ) -> miette::Result<()> {
+        self.interrupt_flux(files, graphs)?;
+
+        let mut changed = vec![];
+        for path in paths {
+            let Some(file) = files.get(path) else {
+                bail!("File not found: {:?}", path);
+            };
+
+            let state = state_of(file, graphs)?;
+
+            let Some(last_state) = self.last_known_state.get(path) else {
+                bail!(
+                    "File {:?} was changed without its previous state being known",
+                    path
+                );
+            };
+
+            if state == *last_state {
+                continue;
+            }
+
+            self.last_known_state.insert(path.clone(), state);
+
+            let new_snapshot = ItemSnapshot::from_file(file, graphs)?;
+            let snapshot = self
+                .last_snapshot
+                .insert(path.clone(), new_snapshot)
+                .expect("Snapshot existence is ensured together with the state");
+
+            changed.push(PathSnapshot {
+                path: path.clone(),
+                value: snapshot,
+                deletion: None,
+            });
+        }
+
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        let snapshot = FileSnapshot::new(self.next_change_index(), SnapshotKind::Change, changed);
+
+        self.push_snapshot(snapshot);
+
+        Ok(())
+    }
+
+    /// Starts a transaction, recording the state of all project files
+    ///
+    /// Pending changes are saved as separate snapshots before the transaction
+    /// starts. Use [UndoHistory::commit_transaction] to record all the
+    /// changes made since this call as a single history step
+    ///
+    /// Only the state hashes are recorded. File contents are kept in the
+    /// latest snapshots between transactions, so only the files that changed
+    /// since they were last captured are cloned
+    pub fn begin_transaction(
+        &mut self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &ProjectGraphs,
+        to_delete: &HashSet<Utf8PathBuf>,
+    ) -> miette::Result<TransactionStart> {
+        self.interrupt_flux(files, graphs)?;
+
+        let known = self.last_known_state.keys().cloned().collect_vec();
+        for path in known {
+            if files
+                .get(&path)
+                .is_some_and(|file| file.is_value() || file.is_graph())
+            {
+                self.check_file(files, graphs, &path, true)?;
+            }
+        }
+
+        let mut start = TransactionStart {
+            files: Default::default(),
+            bad: Default::default(),
+            to_delete: to_delete.clone(),
+        };
+
+        for (path, file) in files {
+            if file.is_bad() {
+                start.bad.insert(path.clone());
+            } else {
+                let state = self.track_file(path, file, graphs)?;
+                start.files.insert(path.clone(), state);
+            }
+        }
+
+        Ok(start)
+    }
+
+    /// Records all changes made since the transaction start as a single
+    /// history step
+    ///
+    /// Created and deleted files, as well as their deletion state, are
+    /// restored by undo and redo. Files that failed to load are not tracked
+    pub fn commit_transaction(
+        &mut self,
+        start: TransactionStart,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &ProjectGraphs,
+        to_delete: &HashSet<Utf8PathBuf>,
+    ) -> miette::Result<()> {
+        let TransactionStart {
+            files: before,
+            bad,
+            to_delete: to_delete_before,
+        } = start;
+
+        let paths = before
+            .keys()
+            .chain(files.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        let mut changed = vec![];
+        for path in paths {
+            if bad.contains(&path) {
+                continue;
+            }
+
+            let current = files.get(&path);
+            let current_state = match current {
+                None => None,
+                Some(file) if file.is_bad() => {
+                    warn!(%path, "File failed to load during a transaction, it can't be undone");
+                    continue;
+                }
+                Some(file) => Some(TrackedState::of(file, graphs)?),
+            };
+            let previous_state = before.get(&path).copied();
+
+            let deletion_before = DeletionState::of(&path, &to_delete_before);
+            let deletion_after = DeletionState::of(&path, to_delete);
+
+            if previous_state == current_state && deletion_before == deletion_after {
+                continue;
+            }
+
+            let previous = match previous_state {
+                None => ItemSnapshot::Absent,
+                Some(state) if self.tracked_state(&path) == Some(state) => self
+                    .last_snapshot
+                    .remove(&path)
+                    .expect("Tracked state implies a snapshot"),
+                Some(_) => {
+                    warn!(%path, "Snapshot of the file was replaced during a transaction, it can't be undone");
+                    continue;
+                }
+            };
+
+            match current {
+                Some(file) => {
+                    let snapshot = ItemSnapshot::capture(Some(file), graphs)?;
+                    self.last_known_state
+                        .insert(path.clone(), hash_of(&snapshot));
+                    self.last_snapshot.insert(path.clone(), snapshot);
+                }
+                None => {
+                    self.last_known_state.remove(&path);
+                    self.last_snapshot.remove(&path);
+                }
+            }
+
+            changed.push(PathSnapshot {
+                path,
+                value: previous,
+                deletion: Some(deletion_before),
+            });
+        }
+
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        let snapshot = FileSnapshot::new(self.next_change_index(), SnapshotKind::Change, changed);
+
+        self.push_snapshot(snapshot);
+
+        Ok(())
+    }
+
     /// Undo the last change.
     pub fn undo(
         &mut self,
         files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
         graphs: &mut ProjectGraphs,
+        to_delete: &mut HashSet<Utf8PathBuf>,
     ) -> miette::Result<Utf8PathBuf> {
         self.interrupt_flux(files, graphs)?;
+        self.graph_merge = None;
         let Some(last_snapshot) = self.history.pop() else {
             bail!("Nothing to undo");
         };
 
-        let redo_snapshot = last_snapshot
-            .value
-            .restore(&last_snapshot.path, files, graphs)?;
+        let redo_items = match last_snapshot.restore(files, graphs, to_delete) {
+            Ok(items) => items,
+            Err(err) => {
+                self.history.push(last_snapshot);
+                return Err(err);
+            }
+        };
 
         let path = last_snapshot.path.clone();
 
-        self.redo_snapshots.push(FileSnapshot {
-            id: last_snapshot.id,
-            kind: SnapshotKind::Undo(last_snapshot.id),
-            path: path.clone(),
-            state: hash_of(&redo_snapshot),
-            value: redo_snapshot,
-        });
+        self.redo_snapshots.push(FileSnapshot::new(
+            last_snapshot.id,
+            SnapshotKind::Undo(last_snapshot.id),
+            redo_items,
+        ));
 
-        self.undone_history.push(last_snapshot);
+        for path in last_snapshot.paths() {
+            self.update_last_known_state(path.clone(), files, graphs)
+                .context("Failed to recalculate last known file state")?;
+        }
 
-        self.update_last_known_state(path.clone(), files, graphs)
-            .context("Failed to recalculate last known file state")?;
+        self.undone_history.push(last_snapshot);
 
         Ok(path)
     }
@@ -213,12 +532,22 @@ impl UndoHistory {
         &mut self,
         files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
         graphs: &mut ProjectGraphs,
+        to_delete: &mut HashSet<Utf8PathBuf>,
     ) -> miette::Result<Utf8PathBuf> {
         self.interrupt_flux(files, graphs)?;
+        self.graph_merge = None;
         let Some(redo_snapshot) = self.redo_snapshots.pop() else {
             bail!("Nothing to redo");
         };
 
+        let last_items = match redo_snapshot.restore(files, graphs, to_delete) {
+            Ok(items) => items,
+            Err(err) => {
+                self.redo_snapshots.push(redo_snapshot);
+                return Err(err);
+            }
+        };
+
         let undone = self
             .undone_history
             .pop()
@@ -226,20 +555,13 @@ impl UndoHistory {
 
         debug_assert_eq!(undone.path, redo_snapshot.path);
 
-        let last_snapshot = redo_snapshot
-            .value
-            .restore(&redo_snapshot.path, files, graphs)?;
-
-        self.history.push(FileSnapshot {
-            id: undone.id,
-            kind: undone.kind,
-            path: redo_snapshot.path.clone(),
-            state: hash_of(&last_snapshot),
-            value: last_snapshot,
-        });
+        self.history
+            .push(FileSnapshot::new(undone.id, undone.kind, last_items));
 
-        self.update_last_known_state(redo_snapshot.path.clone(), files, graphs)
-            .context("Failed to recalculate last known file state")?;
+        for path in redo_snapshot.paths() {
+            self.update_last_known_state(path.clone(), files, graphs)
+                .context("Failed to recalculate last known file state")?;
+        }
 
         Ok(redo_snapshot.path)
     }
@@ -266,6 +588,30 @@ impl UndoHistory {
         Ok(())
     }
 
+    /// Removes all history entries of the file
+    ///
+    /// Should be called when the file is removed from the project by means
+    /// other than undo/redo
+    pub fn forget_file(&mut self, path: impl AsRef<Utf8Path>) {
+        let path = path.as_ref();
+        if self.flux.as_ref().is_some_and(|flux| flux.path == path) {
+            self.flux = None;
+        }
+        self.last_known_state.remove(path);
+        self.last_snapshot.remove(path);
+
+        let history = self.history.drain().collect::<Vec<_>>();
+        for snapshot in history {
+            if !snapshot.affects(path) {
+                self.history.push(snapshot);
+            }
+        }
+        self.undone_history
+            .retain(|snapshot| !snapshot.affects(path));
+        self.redo_snapshots
+            .retain(|snapshot| !snapshot.affects(path));
+    }
+
     /// Iterator over the change history. From oldest to newest.
     pub fn history(&self) -> impl ExactSizeIterator<Item = &FileSnapshot> {
         self.history.iter()
@@ -281,6 +627,10 @@ impl UndoHistory {
         self.redo_snapshots.iter()
     }
 
+    pub fn settings(&self) -> &UndoSettings {
+        &self.settings
+    }
+
     pub fn can_undo(&self) -> bool {
         !self.history.is_empty()
     }
@@ -297,8 +647,14 @@ impl UndoHistory {
         files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
         graphs: &mut ProjectGraphs,
     ) -> miette::Result<()> {
-        let Some(file) = files.get(&path) else {
-            bail!("File not found: {:?}", path);
+        let Some(file) = files
+            .get(&path)
+            .filter(|file| file.is_value() || file.is_graph())
+        else {
+            // File was removed or can't be tracked by itself anymore
+            self.last_known_state.remove(&path);
+            self.last_snapshot.remove(&path);
+            return Ok(());
         };
 
         let snapshot = ItemSnapshot::from_file(file, graphs)?;
@@ -311,6 +667,33 @@ impl UndoHistory {
         Ok(())
     }
 
+    /// State of the latest snapshot of the file
+    fn tracked_state(&self, path: &Utf8Path) -> Option<TrackedState> {
+        Some(TrackedState {
+            generated: self.last_snapshot.get(path)?.is_generated_value(),
+            hash: *self.last_known_state.get(path)?,
+        })
+    }
+
+    /// Updates the latest snapshot of the file if it's outdated, returning
+    /// the current state of the file
+    fn track_file(
+        &mut self,
+        path: &Utf8Path,
+        file: &ProjectFile,
+        graphs: &ProjectGraphs,
+    ) -> miette::Result<TrackedState> {
+        let state = TrackedState::of(file, graphs)?;
+        if self.tracked_state(path) != Some(state) {
+            self.last_known_state.insert(path.to_path_buf(), state.hash);
+            self.last_snapshot.insert(
+                path.to_path_buf(),
+                ItemSnapshot::capture(Some(file), graphs)?,
+            );
+        }
+        Ok(state)
+    }
+
     fn push_snapshot(&mut self, snapshot: FileSnapshot) {
         for x in self.undone_history.drain(..).rev() {
             self.history.push(x);
@@ -330,6 +713,16 @@ impl UndoHistory {
     }
 }
 
+/// History step that consecutive graph edits are merged into
+#[derive(Debug)]
+struct GraphMerge {
+    path: Utf8PathBuf,
+    key: MergeKey,
+    time: f64,
+    /// ID of the step, merging stops once it's not the latest one
+    id: usize,
+}
+
 #[derive(Debug)]
 struct Flux {
     start_time: f64,
@@ -347,16 +740,261 @@ impl Flux {
     }
 }
 
+/// State of all project files at the start of a transaction
+#[derive(Debug)]
+pub struct TransactionStart {
+    files: BTreeMap<Utf8PathBuf, TrackedState>,
+    /// Files that failed to load, those are not tracked
+    bad: BTreeSet<Utf8PathBuf>,
+    to_delete: HashSet<Utf8PathBuf>,
+}
+
+/// State of a file that can be restored by a transaction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct TrackedState {
+    generated: bool,
+    hash: u64,
+}
+
+impl TrackedState {
+    /// Files that failed to load must be filtered out beforehand
+    fn of(file: &ProjectFile, graphs: &ProjectGraphs) -> miette::Result<Self> {
+        let hash = match file {
+            ProjectFile::GeneratedValue(value) => hash_of(value),
+            file => state_of(file, graphs)?,
+        };
+        Ok(Self {
+            generated: file.is_generated(),
+            hash,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct FileSnapshot {
     pub id: usize,
     pub kind: SnapshotKind,
+    /// Path of the main file of the change
     pub path: Utf8PathBuf,
     pub state: u64,
+    /// Snapshots of all files affected by the change, starting with the
+    /// main file
+    items: Vec<PathSnapshot>,
+}
+
+impl FileSnapshot {
+    fn new(id: usize, kind: SnapshotKind, items: Vec<PathSnapshot>) -> Self {
+        let main = items
+            .first()
+            .expect("Snapshot should affect at least one file");
+        Self {
+            id,
+            kind,
+            path: main.path.clone(),
+            state: hash_of(&main.value),
+            items,
+        }
+    }
+
+    /// Paths of the other files that were changed together with the main file
+    pub fn linked_paths(&self) -> impl ExactSizeIterator<Item = &Utf8PathBuf> {
+        self.items.iter().skip(1).map(|item| &item.path)
+    }
+
+    /// Paths of all files affected by this snapshot
+    fn paths(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.items.iter().map(|item| &item.path)
+    }
+
+    fn affects(&self, path: &Utf8Path) -> bool {
+        self.paths().any(|p| p == path)
+    }
+
+    /// Restores the snapshot, returning the snapshots of the files that
+    /// were replaced
+    ///
+    /// All files are taken out first and only then restored, so graphs that
+    /// were moved between paths are restored correctly
+    fn restore(
+        &self,
+        files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &mut ProjectGraphs,
+        to_delete: &mut HashSet<Utf8PathBuf>,
+    ) -> miette::Result<Vec<PathSnapshot>> {
+        if let Some(item) = self
+            .items
+            .iter()
+            .find(|item| files.get(&item.path).is_some_and(ProjectFile::is_bad))
+        {
+            bail!(
+                "File {:?} failed to load and can't be restored, reload it first",
+                item.path
+            );
+        }
+
+        self.check_graph_ids(files, graphs)?;
+
+        // Graph edits are applied first, since they can fail if the graph
+        // doesn't match them
+        let mut reverted = vec![];
+        for item in &self.items {
+            let ItemSnapshot::GraphEdits(id, edits) = &item.value else {
+                continue;
+            };
+
+            let result = edited_graph(&item.path, *id, files, graphs).and_then(|graph| {
+                edits
+                    .apply(graph)
+                    .with_context(|| format!("Failed to restore {:?}", item.path))
+            });
+
+            match result {
+                Ok(redo) => reverted.push((item.path.clone(), *id, redo)),
+                Err(err) => {
+                    for (path, id, redo) in reverted.into_iter().rev() {
+                        if let Err(err) =
+                            edited_graph(&path, id, files, graphs).and_then(|g| redo.apply(g))
+                        {
+                            warn!(%path, "Failed to roll back graph edits: {:?}", err);
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let mut reverted = reverted.into_iter();
+        let replaced = self
+            .items
+            .iter()
+            .map(|item| PathSnapshot {
+                path: item.path.clone(),
+                value: if item.value.is_graph_edits() {
+                    let (_, id, redo) = reverted.next().expect("Graph edits should be applied");
+                    ItemSnapshot::GraphEdits(id, redo)
+                } else {
+                    ItemSnapshot::take(&item.path, files, graphs)
+                },
+                deletion: item
+                    .deletion
+                    .map(|_| DeletionState::of(&item.path, to_delete)),
+            })
+            .collect_vec();
+
+        for item in &self.items {
+            if item.value.is_graph_edits() {
+                continue;
+            }
+            item.value.put(&item.path, files, graphs);
+            if let Some(deletion) = item.deletion {
+                deletion.apply(&item.path, to_delete);
+            }
+        }
+
+        Ok(replaced)
+    }
+
+    /// Checks that the graphs of the snapshot can be added to the project
+    /// once the affected files are taken out, so restoring can't fail
+    /// halfway through
+    fn check_graph_ids(
+        &self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &ProjectGraphs,
+    ) -> miette::Result<()> {
+        let taken = self
+            .items
+            .iter()
+            .filter(|item| !item.value.is_graph_edits())
+            .filter_map(|item| match files.get(&item.path) {
+                Some(ProjectFile::Graph(id)) => Some(*id),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let mut added = HashSet::default();
+        for item in &self.items {
+            let ItemSnapshot::Graph(_, graph) = &item.value else {
+                continue;
+            };
+
+            let id = graph.id;
+            if !added.insert(id) || (graphs.graphs.contains_key(&id) && !taken.contains(&id)) {
+                bail!(
+                    "Can't restore {:?}, graph with id {:?} already exists in the project",
+                    item.path,
+                    id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the snapshot can be persisted between sessions. Graph edits
+    /// hold node data that can't be serialized
+    fn is_persistable(&self) -> bool {
+        !self.items.iter().any(|item| item.value.is_graph_edits())
+    }
+}
+
+/// Graph of the graph file that is edited in place by a snapshot
+fn edited_graph<'a>(
+    path: &Utf8Path,
+    id: Uuid,
+    files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+    graphs: &'a mut ProjectGraphs,
+) -> miette::Result<&'a mut Graph> {
+    match files.get(path) {
+        Some(ProjectFile::Graph(file_id)) if *file_id == id => Ok(graphs
+            .graphs
+            .get_mut(&id)
+            .ok_or_else(|| miette!("Graph {} of {:?} is missing", id, path))?
+            .graph_mut()),
+        _ => bail!("Graph {} is not present at {:?}", id, path),
+    }
+}
+
+/// Snapshot of a single file
+#[derive(Debug)]
+struct PathSnapshot {
+    path: Utf8PathBuf,
     value: ItemSnapshot,
+    /// Deletion state of the file, or `None` if the snapshot doesn't track
+    /// file deletion
+    deletion: Option<DeletionState>,
+}
+
+/// Whether the file and its generated marker are scheduled for deletion
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct DeletionState {
+    file: bool,
+    marker: bool,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, EnumIs)]
+impl DeletionState {
+    fn of(path: &Utf8Path, to_delete: &HashSet<Utf8PathBuf>) -> Self {
+        Self {
+            file: to_delete.contains(path),
+            marker: to_delete.contains(&generated_marker_path(path)),
+        }
+    }
+
+    fn apply(self, path: &Utf8Path, to_delete: &mut HashSet<Utf8PathBuf>) {
+        for (path, delete) in [
+            (path.to_path_buf(), self.file),
+            (generated_marker_path(path), self.marker),
+        ] {
+            if delete {
+                to_delete.insert(path);
+            } else {
+                to_delete.remove(&path);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, EnumIs, Serialize, Deserialize)]
 pub enum SnapshotKind {
     /// A user-made file change
     Change,
@@ -378,19 +1016,29 @@ fn state_of(file: &ProjectFile, graphs: &ProjectGraphs) -> miette::Result<u64> {
 }
 
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, EnumIs)]
 enum ItemSnapshot {
     Value(EValue),
     Graph(Uuid, ProjectGraph),
+    /// Edits that restore the graph, applied in place
+    GraphEdits(Uuid, GraphEdits),
+    /// Generated value, only tracked by transactions
+    GeneratedValue(EValue),
+    /// File doesn't exist, only tracked by transactions
+    Absent,
 }
 
 impl Hash for ItemSnapshot {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            ItemSnapshot::Value(value) => value.hash(state),
+            ItemSnapshot::Value(value) | ItemSnapshot::GeneratedValue(value) => value.hash(state),
             ItemSnapshot::Graph(id, graph) => {
                 (id, graph).hash(state);
             }
+            ItemSnapshot::GraphEdits(id, edits) => {
+                (id, edits).hash(state);
+            }
+            ItemSnapshot::Absent => {}
         }
     }
 }
@@ -409,36 +1057,321 @@ impl ItemSnapshot {
         }
     }
 
-    /// Restores the snapshot to the given path, returning the snapshot of the
-    /// file that was replaced.
-    fn restore(
-        &self,
-        path: &Utf8PathBuf,
+    /// Captures any file state that can be restored by a transaction
+    fn capture(file: Option<&ProjectFile>, graphs: &ProjectGraphs) -> miette::Result<Self> {
+        match file {
+            None => Ok(Self::Absent),
+            Some(ProjectFile::GeneratedValue(value)) => Ok(Self::GeneratedValue(value.clone())),
+            Some(file) => Self::from_file(file, graphs),
+        }
+    }
+
+    /// Takes the file out of the project, returning its snapshot
+    ///
+    /// Files that failed to load must be filtered out beforehand
+    fn take(
+        path: &Utf8Path,
         files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
         graphs: &mut ProjectGraphs,
-    ) -> miette::Result<Self> {
-        let mut old_graph = None;
-        let value = match &self {
-            ItemSnapshot::Value(value) => {
-                files.insert(path.clone(), ProjectFile::Value(value.clone()))
-            }
-            ItemSnapshot::Graph(id, graph) => {
-                old_graph = graphs.graphs.insert(*id, graph.clone());
-                files.insert(path.clone(), ProjectFile::Graph(*id))
+    ) -> Self {
+        match files.remove(path) {
+            None => Self::Absent,
+            Some(ProjectFile::Value(value)) => Self::Value(value),
+            Some(ProjectFile::GeneratedValue(value)) => Self::GeneratedValue(value),
+            Some(ProjectFile::Graph(id)) => Self::Graph(
+                id,
+                graphs
+                    .remove_graph(&id)
+                    .expect("Graph of the graph file should be present"),
+            ),
+            Some(ProjectFile::BadValue(_)) => {
+                panic!("Files that failed to load can't be taken by a snapshot")
             }
+        }
+    }
+
+    /// Puts the snapshot into the project
+    ///
+    /// The path must be vacant, and the graph ID must be checked with
+    /// [FileSnapshot::check_graph_ids] beforehand
+    fn put(
+        &self,
+        path: &Utf8Path,
+        files: &mut BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &mut ProjectGraphs,
+    ) {
+        let file = match self {
+            ItemSnapshot::Value(value) => ProjectFile::Value(value.clone()),
+            ItemSnapshot::GeneratedValue(value) => ProjectFile::GeneratedValue(value.clone()),
+            ItemSnapshot::Graph(_, graph) => graphs
+                .add_graph(path.to_path_buf(), graph.clone())
+                .expect("Graph IDs should be checked before restoring"),
+            ItemSnapshot::GraphEdits(..) => panic!("Graph edits can't replace a file"),
+            ItemSnapshot::Absent => return,
         };
 
-        let value = value.expect("File creation and deletion are handled separately");
+        files.insert(path.to_path_buf(), file);
+    }
+}
 
-        match value {
-            ProjectFile::Value(value) => Ok(Self::Value(value)),
-            ProjectFile::GeneratedValue(_) => {
-                bail!("Cannot undo generated values");
-            }
-            ProjectFile::Graph(id) => Ok(Self::Graph(id, old_graph.unwrap())),
-            ProjectFile::BadValue(_) => {
-                bail!("Cannot undo bad values");
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use crate::graph::editing::GraphEditingContext;
+    use crate::project::docs::Docs;
+    use crate::project::io::MemoryIO;
+    use crate::project::side_effects::SideEffectsContext;
+    use crate::project::test_utils::{
+        damage_project, graph_id, set_field, value_file, TEST_GRAPH_PATH,
+    };
+    use crate::project::{Project, ProjectFile};
+    use crate::value::EValue;
+    use camino::{Utf8Path, Utf8PathBuf};
+    use egui_snarl::NodeId;
+    use emath::{pos2, Pos2};
+    use uuid::Uuid;
+
+    fn project() -> Project<MemoryIO> {
+        damage_project(&[("b.json", r#"{ "Damage": 1 }"#)])
+    }
+
+    fn damage(project: &Project<MemoryIO>, path: &str) -> Option<EValue> {
+        let Some(ProjectFile::Value(EValue::Struct { fields, .. })) =
+            project.files.get(Utf8Path::new(path))
+        else {
+            return None;
+        };
+        fields.get(&"Damage".into()).cloned()
+    }
+
+    /// Edits `a.json`, deletes `b.json` and the graph, and creates `c.json`
+    fn edit_many(project: &mut Project<MemoryIO>) {
+        project
+            .transaction(|project| {
+                set_field(project, "a.json", "Damage", 2.0);
+                project.delete_file("b.json")?;
+
+                let id = graph_id(project, TEST_GRAPH_PATH);
+                project.files.remove(Utf8Path::new(TEST_GRAPH_PATH));
+                project.graphs.remove_graph(&id);
+                project.to_delete.insert(TEST_GRAPH_PATH.into());
+
+                let value = value_file(project, "a.json").clone();
+                project
+                    .files
+                    .insert("c.json".into(), ProjectFile::Value(value));
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    fn assert_edited(project: &Project<MemoryIO>) {
+        assert_eq!(damage(project, "a.json"), Some(EValue::from(2.0)));
+        assert_eq!(damage(project, "b.json"), None);
+        assert_eq!(damage(project, "c.json"), Some(EValue::from(2.0)));
+        assert!(!project.files.contains_key(Utf8Path::new(TEST_GRAPH_PATH)));
+        assert!(project.to_delete.contains(Utf8Path::new("b.json")));
+        assert!(project.to_delete.contains(Utf8Path::new(TEST_GRAPH_PATH)));
+    }
+
+    fn graph_changed(project: &mut Project<MemoryIO>) {
+        project
+            .file_changed(&Utf8PathBuf::from(TEST_GRAPH_PATH), false)
+            .unwrap();
+    }
+
+    fn create_node(project: &mut Project<MemoryIO>) -> NodeId {
+        let id = graph_id(project, TEST_GRAPH_PATH);
+        let graph = project.graphs.graphs.get_mut(&id).unwrap().graph_mut();
+        let mut outputs = None;
+        let mut ctx = GraphEditingContext::from_graph(
+            graph,
+            &project.registry,
+            &Docs::Stub,
+            None,
+            SideEffectsContext::Unavailable,
+            false,
+            &[],
+            &mut outputs,
+        );
+        let node = ctx.create_node("add".into(), Pos2::ZERO).unwrap()[0];
+        graph_changed(project);
+        node
+    }
+
+    fn node_pos(project: &Project<MemoryIO>, node: NodeId) -> Option<Pos2> {
+        let graph = project.graphs.graphs[&graph_id(project, TEST_GRAPH_PATH)].graph();
+        graph.snarl().get_node_info(node).map(|node| node.pos)
+    }
+
+    /// Moves the node at the given time, the way the graph view does
+    fn move_node(project: &mut Project<MemoryIO>, node: NodeId, pos: Pos2, time: f64) {
+        project
+            .history
+            .set_time(&project.files, &project.graphs, time)
+            .unwrap();
+        let id = graph_id(project, TEST_GRAPH_PATH);
+        let graph = project.graphs.graphs.get_mut(&id).unwrap().graph_mut();
+        graph.snarl_mut().get_node_info_mut(node).unwrap().pos = pos;
+        graph_changed(project);
+    }
+
+    /// Project whose graph records the edits, with a node created in it
+    fn graph_project() -> (Project<MemoryIO>, NodeId) {
+        let mut project = project();
+        graph_changed(&mut project);
+        let node = create_node(&mut project);
+        (project, node)
+    }
+
+    fn assert_original(project: &Project<MemoryIO>, graph: Uuid) {
+        assert_eq!(damage(project, "a.json"), Some(EValue::from(1.0)));
+        assert_eq!(damage(project, "b.json"), Some(EValue::from(1.0)));
+        assert_eq!(damage(project, "c.json"), None);
+        assert_eq!(graph_id(project, TEST_GRAPH_PATH), graph);
+        assert!(project.graphs.graphs.contains_key(&graph));
+        assert!(project.to_delete.is_empty());
+    }
+
+    #[test]
+    fn should_undo_transaction_as_single_step() {
+        let mut project = project();
+        let graph = graph_id(&project, TEST_GRAPH_PATH);
+
+        edit_many(&mut project);
+        assert_edited(&project);
+        assert_eq!(project.history.history().len(), 1);
+        let step = project.history.history().next().unwrap();
+        assert_eq!(
+            step.paths().cloned().collect::<Vec<_>>(),
+            ["a.json", "b.json", "c.json", TEST_GRAPH_PATH]
+                .map(Utf8PathBuf::from)
+                .to_vec()
+        );
+
+        project.undo().unwrap();
+        assert_original(&project, graph);
+        assert!(!project.history.can_undo());
+    }
+
+    #[test]
+    fn should_redo_transaction() {
+        let mut project = project();
+        let graph = graph_id(&project, TEST_GRAPH_PATH);
+
+        edit_many(&mut project);
+        project.undo().unwrap();
+        project.redo().unwrap();
+        assert_edited(&project);
+        assert!(!project.graphs.graphs.contains_key(&graph));
+
+        project.undo().unwrap();
+        assert_original(&project, graph);
+    }
+
+    #[test]
+    fn should_only_capture_changed_files() {
+        let mut project = project();
+        project.transaction(|_| Ok(())).unwrap();
+        assert!(!project.history.can_undo());
+
+        project
+            .transaction(|project| {
+                set_field(project, "b.json", "Damage", 3.0);
+                Ok(())
+            })
+            .unwrap();
+        let step = project.history.history().next().unwrap();
+        assert_eq!(step.path, "b.json");
+        assert_eq!(step.linked_paths().len(), 0);
+
+        project.undo().unwrap();
+        assert_eq!(damage(&project, "b.json"), Some(EValue::from(1.0)));
+        assert_eq!(damage(&project, "a.json"), Some(EValue::from(1.0)));
+    }
+
+    #[test]
+    fn failed_undo_should_leave_files_intact() {
+        let mut project = project();
+        edit_many(&mut project);
+
+        // A copy of the deleted graph occupies its ID, so it can't be restored
+        let graph = project.history.history().next().unwrap().items[3]
+            .value
+            .clone();
+        let super::ItemSnapshot::Graph(_, graph) = graph else {
+            panic!("graph snapshot is missing");
+        };
+        let copy = project
+            .graphs
+            .add_graph("copy.dbegraph".into(), graph)
+            .unwrap();
+        project.files.insert("copy.dbegraph".into(), copy);
+
+        assert!(project.undo().is_err());
+        assert_edited(&project);
+        assert!(project.history.can_undo());
+        assert!(!project.history.can_redo());
+    }
+
+    #[test]
+    fn should_record_graph_edits() {
+        let (mut project, node) = graph_project();
+        assert_eq!(project.history.history().len(), 1);
+        let step = project.history.history().next().unwrap();
+        assert!(step.items[0].value.is_graph_edits());
+
+        project.undo().unwrap();
+        assert_eq!(node_pos(&project, node), None);
+
+        project.redo().unwrap();
+        assert_eq!(node_pos(&project, node), Some(Pos2::ZERO));
+    }
+
+    #[test]
+    fn should_merge_consecutive_moves() {
+        let (mut project, node) = graph_project();
+
+        move_node(&mut project, node, pos2(1.0, 0.0), 0.0);
+        move_node(&mut project, node, pos2(2.0, 0.0), 0.5);
+        assert_eq!(project.history.history().len(), 2);
+
+        project.undo().unwrap();
+        assert_eq!(node_pos(&project, node), Some(Pos2::ZERO));
+
+        project.undo().unwrap();
+        assert_eq!(node_pos(&project, node), None);
+
+        project.redo().unwrap();
+        project.redo().unwrap();
+        assert_eq!(node_pos(&project, node), Some(pos2(2.0, 0.0)));
+    }
+
+    #[test]
+    fn should_not_merge_moves_after_stable_time() {
+        let (mut project, node) = graph_project();
+
+        move_node(&mut project, node, pos2(1.0, 0.0), 0.0);
+        move_node(&mut project, node, pos2(2.0, 0.0), 5.0);
+        assert_eq!(project.history.history().len(), 3);
+
+        project.undo().unwrap();
+        assert_eq!(node_pos(&project, node), Some(pos2(1.0, 0.0)));
+
+        project.redo().unwrap();
+        assert_eq!(node_pos(&project, node), Some(pos2(2.0, 0.0)));
+    }
+
+    #[test]
+    fn should_not_merge_moves_after_undo() {
+        let (mut project, node) = graph_project();
+
+        move_node(&mut project, node, pos2(1.0, 0.0), 0.0);
+        move_node(&mut project, node, pos2(2.0, 0.0), 0.1);
+        project.undo().unwrap();
+        move_node(&mut project, node, pos2(3.0, 0.0), 0.2);
+        assert_eq!(project.history.history().len(), 2);
+
+        project.undo().unwrap();
+        assert_eq!(node_pos(&project, node), Some(Pos2::ZERO));
     }
 }
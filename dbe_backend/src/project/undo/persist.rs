@@ -0,0 +1,434 @@
+//! Persistence of the undo history between editor sessions
+
+use crate::etype::EDataType;
+use crate::json_utils::JsonValue;
+use crate::project::project_graph::{ProjectGraph, ProjectGraphs};
+use crate::project::undo::{
+    DeletionState, FileSnapshot, ItemSnapshot, PathSnapshot, SnapshotKind, UndoHistory,
+    UndoSettings,
+};
+use crate::project::ProjectFile;
+use crate::registry::ETypesRegistry;
+use camino::{Utf8Path, Utf8PathBuf};
+use miette::{bail, Context, IntoDiagnostic};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::info;
+
+/// Version of the persisted history format. Histories of other versions
+/// are discarded
+const HISTORY_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedHistory {
+    version: u32,
+    change_index: usize,
+    /// Hashes of the affected files as they were on the disk when the
+    /// history was written, `None` for files that didn't exist
+    files: BTreeMap<Utf8PathBuf, Option<Vec<u8>>>,
+    history: Vec<PersistedSnapshot>,
+    undone_history: Vec<PersistedSnapshot>,
+    redo_snapshots: Vec<PersistedSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedSnapshot {
+    id: usize,
+    kind: SnapshotKind,
+    items: Vec<PersistedItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedItem {
+    path: Utf8PathBuf,
+    value: PersistedValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deletion: Option<DeletionState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum PersistedValue {
+    Value { ty: EDataType, value: JsonValue },
+    GeneratedValue { ty: EDataType, value: JsonValue },
+    Graph { graph: JsonValue },
+    Absent,
+}
+
+impl UndoHistory {
+    /// Serializes the history, so it can be restored with
+    /// [UndoHistory::deserialize] in a later session
+    ///
+    /// Should be called when the project files on the disk match the files
+    /// in memory, i.e. right after saving. `disk_hash` should return the
+    /// hash of the file content on the disk, or `None` if the file doesn't
+    /// exist
+    ///
+    /// Steps that edit graphs in place can't be persisted and are skipped,
+    /// so undoing after a restart reverts them together with the step
+    /// before them
+    pub fn serialize(
+        &mut self,
+        files: &BTreeMap<Utf8PathBuf, ProjectFile>,
+        graphs: &ProjectGraphs,
+        registry: &ETypesRegistry,
+        mut disk_hash: impl FnMut(&Utf8Path) -> miette::Result<Option<Vec<u8>>>,
+    ) -> miette::Result<Vec<u8>> {
+        self.interrupt_flux(files, graphs)?;
+
+        let paths = self
+            .history
+            .iter()
+            .chain(&self.undone_history)
+            .chain(&self.redo_snapshots)
+            .flat_map(FileSnapshot::paths)
+            .collect::<BTreeSet<_>>();
+
+        let mut hashes = BTreeMap::new();
+        for path in paths {
+            hashes.insert(path.clone(), disk_hash(path)?);
+        }
+
+        // Undone history and redo snapshots are pushed in pairs, so they are
+        // skipped in pairs too
+        let (undone, redo): (Vec<_>, Vec<_>) = self
+            .undone_history
+            .iter()
+            .zip(&self.redo_snapshots)
+            .filter(|(undone, redo)| undone.is_persistable() && redo.is_persistable())
+            .unzip();
+
+        let history = PersistedHistory {
+            version: HISTORY_VERSION,
+            change_index: self.change_index,
+            files: hashes,
+            history: persist_all(registry, self.history.iter().filter(|s| s.is_persistable()))?,
+            undone_history: persist_all(registry, undone)?,
+            redo_snapshots: persist_all(registry, redo)?,
+        };
+
+        serde_json::to_vec(&history)
+            .into_diagnostic()
+            .context("failed to serialize undo history")
+    }
+
+    /// Restores the history written by [UndoHistory::serialize]
+    ///
+    /// Snapshots that affect files that were changed on the disk since the
+    /// history was written are discarded. Only the latest
+    /// [`UndoSettings::history_length`](field@UndoSettings::history_length)
+    /// steps are kept
+    pub fn deserialize(
+        settings: UndoSettings,
+        registry: &ETypesRegistry,
+        data: &[u8],
+        mut disk_hash: impl FnMut(&Utf8Path) -> miette::Result<Option<Vec<u8>>>,
+    ) -> miette::Result<Self> {
+        let persisted: PersistedHistory = serde_json::from_slice(data)
+            .into_diagnostic()
+            .context("failed to deserialize undo history")?;
+
+        if persisted.version != HISTORY_VERSION {
+            bail!(
+                "undo history version {} is not supported, expected {}",
+                persisted.version,
+                HISTORY_VERSION
+            );
+        }
+
+        if persisted.undone_history.len() != persisted.redo_snapshots.len() {
+            bail!("undo history is corrupted: undone and redo snapshots don't match");
+        }
+
+        let mut stale = BTreeSet::new();
+        for (path, hash) in &persisted.files {
+            if disk_hash(path)? != *hash {
+                stale.insert(path.as_path());
+            }
+        }
+
+        if !stale.is_empty() {
+            info!(
+                files = stale.len(),
+                "Files were changed since the undo history was written, discarding their history"
+            );
+        }
+
+        let is_fresh = |snapshot: &PersistedSnapshot| {
+            snapshot
+                .items
+                .iter()
+                .all(|item| !stale.contains(item.path.as_path()))
+        };
+
+        let mut history = Self::new(settings);
+        history.change_index = persisted.change_index;
+
+        for snapshot in persisted.history.iter().filter(|s| is_fresh(s)) {
+            history.history.push(snapshot.restore(registry)?);
+        }
+
+        // Undone history and redo snapshots are pushed in pairs, so they are
+        // discarded in pairs too
+        for (undone, redo) in persisted
+            .undone_history
+            .iter()
+            .zip(&persisted.redo_snapshots)
+        {
+            if !is_fresh(undone) || !is_fresh(redo) {
+                continue;
+            }
+            history.undone_history.push(undone.restore(registry)?);
+            history.redo_snapshots.push(redo.restore(registry)?);
+        }
+
+        Ok(history)
+    }
+}
+
+fn persist_all<'a>(
+    registry: &ETypesRegistry,
+    snapshots: impl IntoIterator<Item = &'a FileSnapshot>,
+) -> miette::Result<Vec<PersistedSnapshot>> {
+    snapshots
+        .into_iter()
+        .map(|snapshot| snapshot.persist(registry))
+        .collect()
+}
+
+impl FileSnapshot {
+    fn persist(&self, registry: &ETypesRegistry) -> miette::Result<PersistedSnapshot> {
+        Ok(PersistedSnapshot {
+            id: self.id,
+            kind: self.kind,
+            items: self
+                .items
+                .iter()
+                .map(|item| {
+                    Ok(PersistedItem {
+                        path: item.path.clone(),
+                        value: item
+                            .value
+                            .persist(registry)
+                            .with_context(|| format!("failed to serialize `{}`", item.path))?,
+                        deletion: item.deletion,
+                    })
+                })
+                .collect::<miette::Result<_>>()?,
+        })
+    }
+}
+
+impl PersistedSnapshot {
+    fn restore(&self, registry: &ETypesRegistry) -> miette::Result<FileSnapshot> {
+        if self.items.is_empty() {
+            bail!("undo history snapshot {} doesn't affect any files", self.id);
+        }
+
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                Ok(PathSnapshot {
+                    path: item.path.clone(),
+                    value: item
+                        .value
+                        .restore(registry)
+                        .with_context(|| format!("failed to deserialize `{}`", item.path))?,
+                    deletion: item.deletion,
+                })
+            })
+            .collect::<miette::Result<_>>()?;
+
+        Ok(FileSnapshot::new(self.id, self.kind, items))
+    }
+}
+
+impl ItemSnapshot {
+    fn persist(&self, registry: &ETypesRegistry) -> miette::Result<PersistedValue> {
+        Ok(match self {
+            ItemSnapshot::Value(value) => PersistedValue::Value {
+                ty: value.ty(),
+                value: value.write_json(registry)?,
+            },
+            ItemSnapshot::GeneratedValue(value) => PersistedValue::GeneratedValue {
+                ty: value.ty(),
+                value: value.write_json(registry)?,
+            },
+            ItemSnapshot::Graph(_, graph) => PersistedValue::Graph {
+                graph: graph.write_json(registry)?,
+            },
+            ItemSnapshot::GraphEdits(..) => bail!("graph edits can't be persisted"),
+            ItemSnapshot::Absent => PersistedValue::Absent,
+        })
+    }
+}
+
+impl PersistedValue {
+    fn restore(&self, registry: &ETypesRegistry) -> miette::Result<ItemSnapshot> {
+        Ok(match self {
+            PersistedValue::Value { ty, value } => {
+                ItemSnapshot::Value(ty.parse_json(registry, &mut value.clone(), false)?)
+            }
+            PersistedValue::GeneratedValue { ty, value } => {
+                ItemSnapshot::GeneratedValue(ty.parse_json(registry, &mut value.clone(), false)?)
+            }
+            PersistedValue::Graph { graph } => {
+                let graph = ProjectGraph::parse_json(registry, &mut graph.clone())?;
+                ItemSnapshot::Graph(graph.id, graph)
+            }
+            PersistedValue::Absent => ItemSnapshot::Absent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::history::GraphEdits;
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{
+        damage_project, graph_id, set_field, value_file, TEST_GRAPH_PATH,
+    };
+    use crate::project::undo::{
+        FileSnapshot, ItemSnapshot, PathSnapshot, SnapshotKind, UndoHistory, UndoSettings,
+    };
+    use crate::project::{disk_hash, undo_history_path, Project};
+    use crate::value::EValue;
+
+    fn project() -> Project<MemoryIO> {
+        damage_project(&[("b.json", r#"{ "Damage": 1 }"#)])
+    }
+
+    fn damage(project: &Project<MemoryIO>, path: &str) -> EValue {
+        value_file(project, path)
+            .try_get_field("Damage")
+            .unwrap()
+            .clone()
+    }
+
+    /// Changes the value, recording the change as a separate history step
+    fn set_damage(project: &mut Project<MemoryIO>, path: &str, value: f64) {
+        project
+            .history
+            .ensure_file_state(&project.files, &project.graphs, path)
+            .unwrap();
+        set_field(project, path, "Damage", value);
+        project.file_changed(&path.into(), true).unwrap();
+    }
+
+    /// Records a graph edits step, which can't be persisted
+    fn edit_graph(project: &mut Project<MemoryIO>) {
+        let id = graph_id(project, TEST_GRAPH_PATH);
+        let snapshot = FileSnapshot::new(
+            project.history.next_change_index(),
+            SnapshotKind::Change,
+            vec![PathSnapshot {
+                path: TEST_GRAPH_PATH.into(),
+                value: ItemSnapshot::GraphEdits(id, GraphEdits::default()),
+                deletion: None,
+            }],
+        );
+        project.history.push_snapshot(snapshot);
+    }
+
+    fn paths<'a>(snapshots: impl Iterator<Item = &'a FileSnapshot>) -> Vec<&'a str> {
+        snapshots.map(|snapshot| snapshot.path.as_str()).collect()
+    }
+
+    #[test]
+    fn should_restore_history() {
+        let mut project = project();
+        set_damage(&mut project, "a.json", 2.0);
+        set_damage(&mut project, "b.json", 3.0);
+
+        project.write_files().unwrap();
+        assert!(project.io.get(undo_history_path()).is_some());
+        project.load_history().unwrap();
+        assert_eq!(paths(project.history.history()), ["a.json", "b.json"]);
+
+        project.undo().unwrap();
+        assert_eq!(damage(&project, "b.json"), EValue::from(1.0));
+        project.undo().unwrap();
+        assert_eq!(damage(&project, "a.json"), EValue::from(1.0));
+        assert!(!project.history.can_undo());
+
+        project.redo().unwrap();
+        assert_eq!(damage(&project, "a.json"), EValue::from(2.0));
+    }
+
+    #[test]
+    fn should_discard_history_of_changed_files() {
+        let mut project = project();
+        set_damage(&mut project, "a.json", 2.0);
+        set_damage(&mut project, "b.json", 3.0);
+
+        project.write_files().unwrap();
+        project
+            .io
+            .change_external("a.json", Some(br#"{ "Damage": 5 }"#.to_vec()));
+        project.load_history().unwrap();
+
+        assert_eq!(paths(project.history.history()), ["b.json"]);
+    }
+
+    #[test]
+    fn should_discard_undone_and_redo_snapshots_in_pairs() {
+        let mut project = project();
+        set_damage(&mut project, "a.json", 2.0);
+        set_damage(&mut project, "b.json", 3.0);
+        project.undo().unwrap();
+        project.undo().unwrap();
+
+        project.write_files().unwrap();
+        project
+            .io
+            .change_external("b.json", Some(br#"{ "Damage": 5 }"#.to_vec()));
+        project.load_history().unwrap();
+
+        assert!(!project.history.can_undo());
+        assert_eq!(paths(project.history.undone_history()), ["a.json"]);
+        assert_eq!(paths(project.history.future()), ["a.json"]);
+
+        project.redo().unwrap();
+        assert_eq!(damage(&project, "a.json"), EValue::from(2.0));
+        assert!(!project.history.can_redo());
+    }
+
+    #[test]
+    fn should_reject_unpaired_redo_snapshots() {
+        let mut project = project();
+        set_damage(&mut project, "a.json", 2.0);
+        project.undo().unwrap();
+        project.write_files().unwrap();
+
+        let data = project.io.get(undo_history_path()).unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        json["redo_snapshots"].as_array_mut().unwrap().clear();
+
+        let io = &project.io;
+        let result = UndoHistory::deserialize(
+            UndoSettings::default(),
+            &project.registry,
+            &serde_json::to_vec(&json).unwrap(),
+            |path| disk_hash(io, path),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_skip_graph_edits() {
+        let mut project = project();
+        set_damage(&mut project, "a.json", 2.0);
+        edit_graph(&mut project);
+        set_damage(&mut project, "b.json", 3.0);
+        edit_graph(&mut project);
+        project.undo().unwrap();
+        assert_eq!(project.history.future().len(), 1);
+
+        project.write_files().unwrap();
+        project.load_history().unwrap();
+
+        assert_eq!(paths(project.history.history()), ["a.json", "b.json"]);
+        assert!(!project.history.can_redo());
+    }
+}
@@ -0,0 +1,713 @@
+//! Incremental reloading of project files that were changed on disk by
+//! external tools
+
+use crate::etype::eitem::EItemInfo;
+use crate::etype::EDataType;
+use crate::graph::node::groups::tree_subgraph::TreeSubgraph;
+use crate::graph::node::NodeContext;
+use crate::graph::Graph;
+use crate::json_utils::JsonValue;
+use crate::m_try;
+use crate::project::io::{FileChangeKind, ProjectIO};
+use crate::project::module::find_dbemodule_path;
+use crate::project::project_graph::ProjectGraph;
+use crate::project::scripts::ProjectScript;
+use crate::project::{
+    generated_marker_path, is_cache_path, MiscJson, Project, ProjectFile, EXTENSION_GRAPH,
+    EXTENSION_SCRIPT, EXTENSION_TEST, EXTENSION_TYPE, EXTENSION_VALUE,
+};
+use crate::registry::ETypesRegistry;
+use crate::validation::clear_validation_cache;
+use crate::value::id::ETypeId;
+use crate::value::EValue;
+use camino::{Utf8Path, Utf8PathBuf};
+use miette::{bail, miette, Context, IntoDiagnostic, Report};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::{info, warn};
+use utils::map::HashSet;
+use uuid::Uuid;
+
+/// Outcome of [Project::reload_changed_files]
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    /// Files that were loaded from the disk
+    pub reloaded: Vec<Utf8PathBuf>,
+    /// Files that were removed from the project because they no longer
+    /// exist on the disk
+    pub removed: Vec<Utf8PathBuf>,
+    /// Files that were changed on disk while having unsaved changes. These
+    /// files were not reloaded, see [Project::resolve_conflict]
+    pub conflicts: Vec<Utf8PathBuf>,
+    /// Types that were rebuilt
+    pub rebuilt_types: Vec<ETypeId>,
+    /// Files that were changed, but can't be reloaded in place. The project
+    /// should be reopened to pick up those changes
+    pub requires_reopen: Vec<Utf8PathBuf>,
+    /// Errors that occurred during reloading
+    pub errors: Vec<Report>,
+}
+
+impl ReloadReport {
+    pub fn is_empty(&self) -> bool {
+        self.reloaded.is_empty()
+            && self.removed.is_empty()
+            && self.conflicts.is_empty()
+            && self.rebuilt_types.is_empty()
+            && self.requires_reopen.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ReloadKind {
+    Value,
+    Graph,
+}
+
+impl<IO: ProjectIO> Project<IO> {
+    /// Checks the project IO for files that were changed externally and
+    /// reloads them
+    ///
+    /// Only the changed files are reloaded. Changes to types rebuild the
+    /// changed types, and the values that use them are re-parsed. Changed
+    /// graphs and graphs that depend on changed types are marked as dirty,
+    /// while value changes are picked up by [Project::evaluate_graphs_incremental]
+    ///
+    /// Files that have unsaved changes are not reloaded, and instead are
+    /// recorded as conflicts
+    pub fn reload_changed_files(&mut self) -> miette::Result<ReloadReport> {
+        let changes = self.io.poll_changes()?;
+        let mut report = ReloadReport::default();
+        if changes.is_empty() {
+            return Ok(report);
+        }
+
+        let mut type_changes = vec![];
+        let mut file_changes = vec![];
+
+        for change in changes {
+            let path = self.relative_path(&change.path)?;
+            if is_cache_path(&path) {
+                continue;
+            }
+            let Some(ext) = path.extension().map(str::to_lowercase) else {
+                continue;
+            };
+            let module_path = find_dbemodule_path(&path);
+
+            match ext.as_str() {
+                EXTENSION_TYPE => {
+                    let Some(module) = module_path.and_then(|module_path| {
+                        self.modules
+                            .values()
+                            .find(|m| m.path.as_path() == module_path)
+                    }) else {
+                        report.requires_reopen.push(path);
+                        continue;
+                    };
+                    let id = match ETypeId::from_path(module, &path) {
+                        Ok(id) => id,
+                        Err(err) => {
+                            report.errors.push(err.wrap_err(format!(
+                                "failed to generate type identifier for `{}`",
+                                path
+                            )));
+                            continue;
+                        }
+                    };
+                    let source = if change.kind == FileChangeKind::Removed {
+                        None
+                    } else {
+                        match self.read_utf8(&path) {
+                            Ok(source) => Some(source),
+                            Err(err) => {
+                                report.errors.push(err);
+                                continue;
+                            }
+                        }
+                    };
+                    type_changes.push((id, source, path));
+                }
+                "json" | "json5" | EXTENSION_VALUE if module_path.is_none() => {
+                    file_changes.push((path, change.kind, ReloadKind::Value));
+                }
+                EXTENSION_GRAPH => {
+                    file_changes.push((path, change.kind, ReloadKind::Graph));
+                }
+                EXTENSION_TEST if module_path.is_none() => {
+                    // Test files are never edited in memory, so there are
+                    // no conflicts to check
+                    if change.kind == FileChangeKind::Removed {
+                        self.tests.suites.remove(&path);
+                        report.removed.push(path);
+                    } else {
+                        match self.read_utf8(&path) {
+                            Ok(source) => {
+                                self.tests.load(path.clone(), &source);
+                                report.reloaded.push(path);
+                            }
+                            Err(err) => report.errors.push(err),
+                        }
+                    }
+                }
+                EXTENSION_SCRIPT => {
+                    // Script files are never edited in memory either
+                    self.graphs.scripts.remove_script_at(&path);
+                    if change.kind == FileChangeKind::Removed {
+                        report.removed.push(path);
+                    } else {
+                        let result = self
+                            .read_utf8(&path)
+                            .and_then(|source| ProjectScript::parse_toml(&source))
+                            .and_then(|script| self.graphs.scripts.add_script(path.clone(), script))
+                            .with_context(|| format!("failed to reload script at `{}`", path));
+                        match result {
+                            Ok(()) => report.reloaded.push(path),
+                            Err(err) => report.errors.push(err),
+                        }
+                    }
+                }
+                "json" | "json5" | "toml" => {
+                    // Module configuration, docs and types config
+                    report.requires_reopen.push(path);
+                }
+                _ => {}
+            }
+        }
+
+        let mut changed_graphs = HashSet::default();
+
+        for (path, kind, reload_kind) in file_changes {
+            if self.is_dirty(&path) || self.conflicts.contains(&path) {
+                warn!(%path, "File was changed on disk while having unsaved changes");
+                self.conflicts.insert(path.clone());
+                report.conflicts.push(path);
+                continue;
+            }
+
+            if let Some(ProjectFile::Graph(id)) = self.files.get(&path) {
+                changed_graphs.insert(*id);
+            }
+
+            match self.reload_file(&path, kind, reload_kind) {
+                Ok(Some(id)) => {
+                    changed_graphs.insert(id);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    report.errors.push(err);
+                    continue;
+                }
+            }
+
+            if kind == FileChangeKind::Removed {
+                report.removed.push(path);
+            } else {
+                report.reloaded.push(path);
+            }
+        }
+
+        if !type_changes.is_empty() {
+            match self.reload_types(type_changes) {
+                Ok(rebuilt) => {
+                    let types = rebuilt.iter().copied().collect();
+                    changed_graphs.extend(
+                        self.graphs
+                            .graphs
+                            .values()
+                            .filter(|graph| self.graph_uses_types(graph.graph(), &types))
+                            .map(|graph| graph.id),
+                    );
+                    report.rebuilt_types = rebuilt;
+                }
+                Err(err) => report.errors.push(err),
+            }
+        }
+
+        if !changed_graphs.is_empty() {
+            let dirty = self.graphs.dependents_of(changed_graphs);
+            self.dirty_graphs.extend(dirty);
+        }
+
+        if !report.reloaded.is_empty()
+            || !report.removed.is_empty()
+            || !report.rebuilt_types.is_empty()
+        {
+            clear_validation_cache(&self.registry);
+            self.validate_all()?;
+            info!(
+                reloaded = report.reloaded.len(),
+                removed = report.removed.len(),
+                rebuilt_types = report.rebuilt_types.len(),
+                "Reloaded files changed on disk"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Resolves a conflict between the in-memory and on-disk versions of the file
+    ///
+    /// If `keep_memory` is true, in-memory version is kept and will overwrite
+    /// the disk version on the next save. Otherwise, in-memory changes are
+    /// discarded and the file is loaded from the disk
+    pub fn resolve_conflict(&mut self, path: &Utf8Path, keep_memory: bool) -> miette::Result<()> {
+        if !self.conflicts.remove(path) {
+            bail!("file `{}` has no conflicts", path);
+        }
+
+        if keep_memory {
+            return Ok(());
+        }
+
+        let ext = path.extension().map(str::to_lowercase);
+        let reload_kind = if ext.as_deref() == Some(EXTENSION_GRAPH) {
+            ReloadKind::Graph
+        } else {
+            ReloadKind::Value
+        };
+
+        let kind = if self.io.file_exists(path)? {
+            FileChangeKind::Modified
+        } else {
+            FileChangeKind::Removed
+        };
+
+        let mut changed_graphs = HashSet::default();
+        if let Some(ProjectFile::Graph(id)) = self.files.get(path) {
+            changed_graphs.insert(*id);
+        }
+        if let Some(id) = self.reload_file(path, kind, reload_kind)? {
+            changed_graphs.insert(id);
+        }
+        let dirty = self.graphs.dependents_of(changed_graphs);
+        self.dirty_graphs.extend(dirty);
+
+        clear_validation_cache(&self.registry);
+        self.validate_all()
+    }
+
+    /// Loads a single value or graph file from the disk, replacing the
+    /// in-memory version
+    ///
+    /// Returns ID of the loaded graph, if the file is a graph
+    fn reload_file(
+        &mut self,
+        path: &Utf8Path,
+        kind: FileChangeKind,
+        reload_kind: ReloadKind,
+    ) -> miette::Result<Option<Uuid>> {
+        m_try(|| {
+            // Make sure that the previous state of the file is recorded in
+            // the undo history
+            if self.files.get(path).is_some_and(|f| f.is_value() || f.is_graph()) {
+                self.history
+                    .ensure_file_state(&self.files, &self.graphs, path)?;
+            }
+
+            if kind == FileChangeKind::Removed {
+                if let Some(ProjectFile::Graph(id)) = self.files.remove(path) {
+                    self.graphs.remove_graph(&id);
+                }
+                self.history.forget_file(path);
+                self.mark_saved(path);
+                return Ok(None);
+            }
+
+            let mut graph_id = None;
+            let mut migrated = false;
+            let file = match reload_kind {
+                ReloadKind::Value => {
+                    let (file, applied) = self.load_value_file(path)?;
+                    migrated = self.migrations.record(path, applied, &file);
+                    file
+                }
+                ReloadKind::Graph => {
+                    let mut json: JsonValue = serde_json5::from_str(&self.read_utf8(path)?)
+                        .into_diagnostic()
+                        .context("failed to deserialize graph JSON")?;
+                    let graph = ProjectGraph::parse_json(&self.registry, &mut json)
+                        .context("failed to deserialize Graph")?;
+                    let old_id = match self.files.get(path) {
+                        Some(ProjectFile::Graph(id)) => Some(*id),
+                        _ => None,
+                    };
+                    if old_id != Some(graph.id) && self.graphs.graphs.contains_key(&graph.id) {
+                        bail!(
+                            "graph with id {:?} already exists in another file. Were graph files copied manually?",
+                            graph.id
+                        );
+                    }
+                    if let Some(old_id) = old_id {
+                        self.graphs.remove_graph(&old_id);
+                    }
+                    graph_id = Some(graph.id);
+                    self.graphs.add_graph(path.to_path_buf(), graph)?
+                }
+            };
+
+            let is_tracked = file.is_value() || file.is_graph();
+            self.files.insert(path.to_path_buf(), file);
+            if is_tracked {
+                self.history
+                    .check_file(&self.files, &self.graphs, path, true)?;
+            } else {
+                self.history.forget_file(path);
+            }
+            self.mark_saved(path);
+            // Migrated files are only written to the disk on save
+            if migrated {
+                self.saved_state.remove(path);
+            }
+
+            Ok(graph_id)
+        })
+        .with_context(|| format!("failed to reload file at `{}`", path))
+    }
+
+    /// Loads a value file from the disk, along with the descriptions of the
+    /// applied migrations
+    ///
+    /// Migrations are only applied to the files that failed to load with
+    /// the pending migrations. Deserialization errors are reported via
+    /// [ProjectFile::BadValue]
+    fn load_value_file(&self, path: &Utf8Path) -> miette::Result<(ProjectFile, Vec<String>)> {
+        let data = self.read_utf8(path)?;
+        let mut applied = vec![];
+        let parsed = m_try(|| {
+            let (mut json, ty) = if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(EXTENSION_VALUE))
+            {
+                let data: MiscJson = serde_json5::from_str(&data)
+                    .into_diagnostic()
+                    .context("failed to deserialize dbefile JSON")?;
+                (data.value, Some(data.ty))
+            } else {
+                let data: JsonValue = serde_json5::from_str(&data)
+                    .into_diagnostic()
+                    .context("failed to deserialize JSON")?;
+                (data, None)
+            };
+            if self.migrations.is_failed(path) {
+                let root_ty = ty.unwrap_or_else(|| self.import_root());
+                applied = self
+                    .migrations
+                    .migrate(&self.registry, root_ty, &mut json)
+                    .context("failed to migrate JSON")?;
+            }
+            self.deserialize_json(json, ty)
+        })
+        .with_context(|| format!("failed to deserialize JSON at `{}`", path));
+
+        let file = match parsed {
+            Ok(value) => {
+                if self.io.file_exists(generated_marker_path(path))? {
+                    ProjectFile::GeneratedValue(value)
+                } else {
+                    ProjectFile::Value(value)
+                }
+            }
+            Err(err) => ProjectFile::BadValue(err),
+        };
+
+        Ok((file, applied))
+    }
+
+    /// Rebuilds the changed types, and re-parses all the values that use
+    /// the rebuilt types
+    fn reload_types(
+        &mut self,
+        changes: Vec<(ETypeId, Option<String>, Utf8PathBuf)>,
+    ) -> miette::Result<Vec<ETypeId>> {
+        let paths = changes
+            .iter()
+            .map(|(_, _, path)| path.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        m_try(|| {
+            let affected = self
+                .registry
+                .dependent_types(changes.iter().map(|(id, _, _)| *id));
+
+            // Values are serialized with the old types and parsed back with
+            // the new ones
+            let mut stashed = vec![];
+            for (path, file) in &self.files {
+                let (ProjectFile::Value(value) | ProjectFile::GeneratedValue(value)) = file else {
+                    continue;
+                };
+                if !value_uses_types(value, &affected) {
+                    continue;
+                }
+                let json = self
+                    .serialize_json(value)
+                    .with_context(|| format!("failed to serialize value at `{}`", path))?;
+                stashed.push((path.clone(), json, value.ty()));
+            }
+
+            let rebuilt = self
+                .registry
+                .reload_types(changes.into_iter().map(|(id, source, _)| (id, source)))?;
+
+            for (path, json, ty) in stashed {
+                let data = self
+                    .deserialize_json(json, Some(ty))
+                    .with_context(|| format!("failed to deserialize JSON at `{}`", path));
+                let Some(file) = self.files.get_mut(&path) else {
+                    continue;
+                };
+                *file = match (data, &*file) {
+                    (Ok(data), ProjectFile::GeneratedValue(_)) => ProjectFile::GeneratedValue(data),
+                    (Ok(data), _) => ProjectFile::Value(data),
+                    (Err(err), _) => ProjectFile::BadValue(err),
+                };
+            }
+
+            Ok(rebuilt)
+        })
+        .with_context(|| format!("failed to reload types from {}", paths))
+    }
+
+    /// Checks if the graph has nodes with ports or inline values of the
+    /// given types, including nodes of the tree subgraphs
+    ///
+    /// Nodes whose ports can't be resolved are considered to use the types
+    fn graph_uses_types(&self, graph: &Graph, types: &BTreeSet<ETypeId>) -> bool {
+        let ctx = NodeContext {
+            registry: &self.registry,
+            docs: &self.docs,
+            inputs: graph.inputs(),
+            outputs: graph.outputs(),
+            regions: graph.regions(),
+            region_graph: graph.region_graph(),
+            graphs: Some(&self.graphs),
+        };
+        let uses_types = |ty: Option<EDataType>| {
+            ty.is_some_and(|ty| data_type_uses_types(&self.registry, ty, types))
+        };
+
+        graph
+            .inline_values()
+            .values()
+            .any(|value| value_uses_types(value, types))
+            || graph.inputs().iter().any(|input| uses_types(input.ty))
+            || graph.outputs().iter().any(|output| uses_types(output.ty))
+            || graph.snarl().nodes().any(|node| {
+                if let Some(tree) = node.node.downcast_ref::<TreeSubgraph>() {
+                    if self.graph_uses_types(tree.inner_graph(), types) {
+                        return true;
+                    }
+                }
+                (0..node.inputs_count(ctx)).any(|input| {
+                    node.try_input(ctx, input).map_or(true, |data| {
+                        uses_types(data.ty.item_info().map(EItemInfo::ty))
+                    })
+                }) || (0..node.outputs_count(ctx)).any(|output| {
+                    node.try_output(ctx, output).map_or(true, |data| {
+                        uses_types(data.ty.item_info().map(EItemInfo::ty))
+                    })
+                })
+            })
+    }
+
+    fn read_utf8(&self, path: &Utf8Path) -> miette::Result<String> {
+        String::from_utf8(self.io.read_file(path)?)
+            .into_diagnostic()
+            .with_context(|| {
+                format!(
+                    "failed to parse content of a file `{path}`. Are you sure it's UTF-8 encoded?"
+                )
+            })
+    }
+
+    fn relative_path(&self, path: &Path) -> miette::Result<Utf8PathBuf> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .map_err(|_| miette!("file `{}` is outside of the project", path.display()))?;
+        Utf8Path::from_path(relative)
+            .map(Utf8Path::to_path_buf)
+            .ok_or_else(|| miette!("Got non-UTF8 path at {}", relative.display()))
+    }
+}
+
+/// Checks if the data type is one of the given types, or a list or a map
+/// of them
+///
+/// Types that are no longer defined are considered to be affected
+fn data_type_uses_types(
+    registry: &ETypesRegistry,
+    ty: EDataType,
+    types: &BTreeSet<ETypeId>,
+) -> bool {
+    match ty {
+        EDataType::Object { ident } => {
+            types.contains(&ident) || registry.get_object(&ident).is_none()
+        }
+        EDataType::List { id } => registry
+            .get_list(&id)
+            .is_none_or(|list| data_type_uses_types(registry, list.value_type, types)),
+        EDataType::Map { id } => registry.get_map(&id).is_none_or(|map| {
+            data_type_uses_types(registry, map.key_type, types)
+                || data_type_uses_types(registry, map.value_type, types)
+        }),
+        EDataType::Boolean
+        | EDataType::Number
+        | EDataType::String
+        | EDataType::Const { .. }
+        | EDataType::Unknown => false,
+    }
+}
+
+/// Checks if the value contains any objects of the given types
+fn value_uses_types(value: &EValue, types: &BTreeSet<ETypeId>) -> bool {
+    match value {
+        EValue::Null | EValue::Boolean { .. } | EValue::Number { .. } | EValue::String { .. } => {
+            false
+        }
+        EValue::Struct { ident, fields } => {
+            types.contains(ident) || fields.values().any(|v| value_uses_types(v, types))
+        }
+        EValue::Enum { variant, data } => {
+            types.contains(&variant.enum_id()) || value_uses_types(data, types)
+        }
+        EValue::List { values, .. } => values.iter().any(|v| value_uses_types(v, types)),
+        EValue::Map { values, .. } => values
+            .iter()
+            .any(|(k, v)| value_uses_types(k, types) || value_uses_types(v, types)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::{
+        load_project, set_field, test_io, test_project, value_file, DAMAGE_ITEM_TYPE,
+    };
+    use crate::project::Project;
+    use crate::value::EValue;
+    use camino::{Utf8Path, Utf8PathBuf};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const WEAPON_TYPE: &str = r#"struct { number "Speed"; }"#;
+
+    fn damage(project: &Project<MemoryIO>) -> EValue {
+        value_file(project, "a.json")
+            .try_get_field("Damage")
+            .unwrap()
+            .clone()
+    }
+
+    fn graph_json(id: Uuid, ty: &str) -> String {
+        json!({
+            "version": "V1",
+            "id": id,
+            "graph": {
+                "nodes": [],
+                "edges": [],
+                "inline_values": [],
+                "inputs": [{
+                    "id": Uuid::from_u128(100),
+                    "name": "Input",
+                    "ty": { "Object": { "ident": ty } }
+                }],
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn should_reload_changed_value() {
+        let mut project = test_project(&[
+            ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+            ("a.json", r#"{ "Damage": 1 }"#),
+        ]);
+        assert_eq!(damage(&project), EValue::from(1.0));
+
+        project
+            .io
+            .change_external("a.json", Some(br#"{ "Damage": 5 }"#.to_vec()));
+        let report = project.reload_changed_files().unwrap();
+        assert_eq!(report.reloaded, vec![Utf8PathBuf::from("a.json")]);
+        assert!(report.errors.is_empty());
+        assert_eq!(damage(&project), EValue::from(5.0));
+        assert!(!project.is_dirty(Utf8Path::new("a.json")));
+
+        // Nothing changed since the last poll
+        assert!(project.reload_changed_files().unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_remove_deleted_value() {
+        let mut project = test_project(&[
+            ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+            ("a.json", r#"{ "Damage": 1 }"#),
+        ]);
+
+        project.io.change_external("a.json", None);
+        let report = project.reload_changed_files().unwrap();
+        assert_eq!(report.removed, vec![Utf8PathBuf::from("a.json")]);
+        assert!(!project.files.contains_key(Utf8Path::new("a.json")));
+    }
+
+    #[test]
+    fn should_not_reload_dirty_files() {
+        let mut project = test_project(&[
+            ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+            ("a.json", r#"{ "Damage": 1 }"#),
+        ]);
+        set_field(&mut project, "a.json", "Damage", 2.0);
+
+        project
+            .io
+            .change_external("a.json", Some(br#"{ "Damage": 5 }"#.to_vec()));
+        let report = project.reload_changed_files().unwrap();
+        assert_eq!(report.conflicts, vec![Utf8PathBuf::from("a.json")]);
+        assert!(report.reloaded.is_empty());
+        assert_eq!(damage(&project), EValue::from(2.0));
+
+        project
+            .resolve_conflict(Utf8Path::new("a.json"), false)
+            .unwrap();
+        assert_eq!(damage(&project), EValue::from(5.0));
+        assert!(project.conflicts.is_empty());
+    }
+
+    #[test]
+    fn should_rebuild_changed_types() {
+        let item_graph = Uuid::from_u128(1);
+        let weapon_graph = Uuid::from_u128(2);
+        let io = test_io(&[
+            ("test.dbemodule/types/item.kdl", DAMAGE_ITEM_TYPE),
+            ("test.dbemodule/types/weapon.kdl", WEAPON_TYPE),
+            ("a.json", r#"{ "Damage": 1 }"#),
+        ]);
+        io.insert("item.dbegraph", graph_json(item_graph, "test:item"));
+        io.insert("weapon.dbegraph", graph_json(weapon_graph, "test:weapon"));
+        let mut project = load_project(io).unwrap();
+        project.dirty_graphs.clear();
+
+        project.io.change_external(
+            "test.dbemodule/types/item.kdl",
+            Some(br#"struct { number "Damage" default=3; number "Range"; }"#.to_vec()),
+        );
+        let report = project.reload_changed_files().unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report
+            .rebuilt_types
+            .iter()
+            .any(|id| id.to_string() == "test:item"));
+
+        // Values are re-parsed with the new type
+        assert!(value_file(&project, "a.json")
+            .try_get_field("Range")
+            .is_ok());
+
+        // Only the graphs using the rebuilt type are affected
+        assert!(project.dirty_graphs.contains(&item_graph));
+        assert!(!project.dirty_graphs.contains(&weapon_graph));
+    }
+}
@@ -9,6 +9,7 @@ use crate::etype::EDataType;
 use crate::graph::node::all_node_factories;
 use crate::json_utils::repr::{JsonRepr, Repr};
 use crate::json_utils::JsonValue;
+use crate::m_try;
 use crate::project::ProjectConfig;
 use crate::registry::config::ExtraConfig;
 use crate::serialization::deserialize_etype;
@@ -23,7 +24,7 @@ use parking_lot::RwLock;
 use smallvec::SmallVec;
 use std::any::{Any, TypeId};
 use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::sync::{Arc, LazyLock};
@@ -35,6 +36,7 @@ use utils::whatever_ref::WhateverRef;
 
 pub mod config;
 pub mod optional_helpers;
+pub mod pair_helpers;
 
 pub static OPTIONAL_PREFIX: &str = "sys:optional";
 
@@ -46,6 +48,7 @@ pub static OPTIONAL_BOOLEAN_ID: LazyLock<ETypeId> =
     LazyLock::new(|| ETypeId::from_raw("sys:optional<Item=boolean>".into()));
 pub static OPTIONAL_NUMBER_ID: LazyLock<ETypeId> =
     LazyLock::new(|| ETypeId::from_raw("sys:optional<Item=number>".into()));
+pub static PAIR_ID: LazyLock<ETypeId> = LazyLock::new(|| ETypeId::from_raw("sys:pair".into()));
 
 #[derive(Debug, Copy, Clone)]
 pub struct ListData {
@@ -201,6 +204,8 @@ pub struct ETypesRegistry {
     cache: RwLock<HashMap<String, Arc<dyn Any + Send + Sync>>>,
     /// Read-only configuration used by various editors, validators, etc
     extra_config: BTreeMap<String, SmallVec<[(Utf8PathBuf, JsonValue); 1]>>,
+    /// Raw sources of the types, used for reloading
+    sources: BTreeMap<ETypeId, String>,
     /// Whenever all types are deserialized and ready
     ready: bool,
 }
@@ -212,11 +217,15 @@ impl ETypesRegistry {
     ) -> miette::Result<Self> {
         default_properties::register_extra_properties();
 
-        let iter = data.into_iter();
+        let sources: BTreeMap<ETypeId, String> = data.into_iter().collect();
 
-        let types: BTreeMap<ETypeId, RegistryItem> = iter
+        let types: BTreeMap<ETypeId, RegistryItem> = sources
+            .iter()
             .map(|(id, v)| {
-                Result::<(ETypeId, RegistryItem), miette::Error>::Ok((id, RegistryItem::Raw(v)))
+                Result::<(ETypeId, RegistryItem), miette::Error>::Ok((
+                    *id,
+                    RegistryItem::Raw(v.clone()),
+                ))
             })
             .try_collect()
             .context("While grouping entries")?;
@@ -231,6 +240,7 @@ impl ETypesRegistry {
             extra_data: Default::default(),
             cache: Default::default(),
             extra_config: Default::default(),
+            sources,
             ready: false,
         };
 
@@ -257,6 +267,157 @@ impl ETypesRegistry {
         }
     }
 
+    /// Replaces sources of the given types and re-deserializes them, along
+    /// with all the types that embed their data
+    ///
+    /// A `None` source removes the type. Types that only reference the changed
+    /// types by ID are left as-is.
+    ///
+    /// On failure, the registry is restored to its previous state.
+    ///
+    /// Returns IDs of all types that were rebuilt or removed
+    pub fn reload_types(
+        &mut self,
+        changes: impl IntoIterator<Item = (ETypeId, Option<String>)>,
+    ) -> miette::Result<Vec<ETypeId>> {
+        self.apply_pending();
+
+        let changes: BTreeMap<ETypeId, Option<String>> = changes.into_iter().collect();
+        let affected = self.dependent_types(changes.keys().copied());
+
+        let mut backup_types = vec![];
+        let mut backup_sources = vec![];
+        let mut regenerate = vec![];
+
+        for id in &affected {
+            let is_generic_instance = self
+                .types
+                .get(id)
+                .and_then(|item| match item {
+                    RegistryItem::Ready(obj) => Some(obj.generic_parent_id().is_some()),
+                    _ => None,
+                })
+                .unwrap_or(false);
+
+            let source = match changes.get(id) {
+                Some(source) => {
+                    backup_sources.push((*id, self.sources.get(id).cloned()));
+                    match source {
+                        None => self.sources.remove(id),
+                        Some(source) => self.sources.insert(*id, source.clone()),
+                    };
+                    source.clone()
+                }
+                None => self.sources.get(id).cloned(),
+            };
+
+            let old = match source {
+                Some(source) if !is_generic_instance => {
+                    self.types.insert(*id, RegistryItem::Raw(source))
+                }
+                _ => {
+                    if is_generic_instance {
+                        regenerate.push(*id);
+                    }
+                    self.types.remove(id)
+                }
+            };
+            backup_types.push((*id, old));
+        }
+
+        self.ready = false;
+        let result = m_try(|| {
+            let keys = self
+                .types
+                .iter()
+                .filter(|(_, item)| matches!(item, RegistryItem::Raw(_)))
+                .map(|(id, _)| *id)
+                .collect_vec();
+            for id in keys {
+                self.fetch_or_deserialize(id)
+                    .with_context(|| format!("failed to deserialize `{id}`"))?;
+            }
+
+            for id in &regenerate {
+                if let Err(err) = self.type_for_raw_id(id.as_raw()) {
+                    error!(%id, ?err, "Failed to regenerate generic type after reload");
+                }
+            }
+
+            Ok(())
+        });
+        self.ready = true;
+
+        if let Err(err) = result {
+            for (id, item) in backup_types {
+                match item {
+                    None => self.types.remove(&id),
+                    Some(item) => self.types.insert(id, item),
+                };
+            }
+            for (id, source) in backup_sources {
+                match source {
+                    None => self.sources.remove(&id),
+                    Some(source) => self.sources.insert(id, source),
+                };
+            }
+            return Err(err);
+        }
+
+        self.default_objects_cache.borrow_mut().clear();
+        self.cache.write().clear();
+
+        Ok(affected.into_iter().collect())
+    }
+
+    /// Returns the given types, along with all ready types whose data
+    /// embeds the data of the given types (generic instances, enums and
+    /// structs including them)
+    pub(crate) fn dependent_types(
+        &self,
+        ids: impl IntoIterator<Item = ETypeId>,
+    ) -> BTreeSet<ETypeId> {
+        let mut affected: BTreeSet<ETypeId> = ids.into_iter().collect();
+
+        loop {
+            let mut changed = false;
+            for (id, item) in &self.types {
+                if affected.contains(id) {
+                    continue;
+                }
+                let RegistryItem::Ready(obj) = item else {
+                    continue;
+                };
+
+                let depends = match &**obj {
+                    EObjectType::Struct(data) => {
+                        data.generic_parent_id
+                            .is_some_and(|parent| affected.contains(&parent))
+                            || data.includes.iter().any(|id| affected.contains(id))
+                    }
+                    EObjectType::Enum(data) => {
+                        data.generic_parent_id
+                            .is_some_and(|parent| affected.contains(&parent))
+                            || data.variants().iter().any(|variant| {
+                                matches!(variant.data.ty(), EDataType::Object { ident } if affected.contains(&ident))
+                            })
+                    }
+                };
+
+                if depends {
+                    affected.insert(*id);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        affected
+    }
+
     /// Iterator over all ready objects
     ///
     /// Does not include pending objects
@@ -458,6 +619,25 @@ impl ETypesRegistry {
             .expect("Optional enum creation should not fail")
     }
 
+    pub fn pair_of(&self, first: EDataType, second: EDataType) -> EDataType {
+        EDataType::Object {
+            ident: self.pair_id_of(first, second),
+        }
+    }
+
+    pub fn pair_id_of(&self, first: EDataType, second: EDataType) -> ETypeId {
+        static NAMES: LazyLock<[Ustr; 2]> =
+            LazyLock::new(|| [Ustr::from("First"), Ustr::from("Second")]);
+        let map: UstrMap<EItemInfo> = [
+            (NAMES[0], EItemInfo::simple_type(first)),
+            (NAMES[1], EItemInfo::simple_type(second)),
+        ]
+        .into_iter()
+        .collect();
+        self.make_generic_pending(*PAIR_ID, map)
+            .expect("Pair struct creation should not fail")
+    }
+
     /// Creates a generic type with the specified arguments, or returns the existing one
     pub fn make_generic(
         &mut self,
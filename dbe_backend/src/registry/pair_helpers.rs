@@ -0,0 +1,54 @@
+use crate::etype::EDataType;
+use crate::registry::{ETypesRegistry, PAIR_ID};
+use crate::value::EValue;
+use miette::bail;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use ustr::Ustr;
+
+static FIRST: LazyLock<Ustr> = LazyLock::new(|| Ustr::from("first"));
+static SECOND: LazyLock<Ustr> = LazyLock::new(|| Ustr::from("second"));
+
+/// Creates a pair value with the given element types
+pub fn make_pair(
+    registry: &ETypesRegistry,
+    first_ty: EDataType,
+    second_ty: EDataType,
+    first: EValue,
+    second: EValue,
+) -> EValue {
+    EValue::Struct {
+        ident: registry.pair_id_of(first_ty, second_ty),
+        fields: BTreeMap::from([(*FIRST, first), (*SECOND, second)]),
+    }
+}
+
+/// Returns the elements of the pair value
+pub fn unwrap_pair(value: &EValue) -> miette::Result<(&EValue, &EValue)> {
+    let EValue::Struct { fields, .. } = value else {
+        bail!("Expected a pair, got {}", value.ty().name());
+    };
+
+    match (fields.get(&*FIRST), fields.get(&*SECOND)) {
+        (Some(first), Some(second)) => Ok((first, second)),
+        _ => bail!("Expected a pair, got {}", value.ty().name()),
+    }
+}
+
+/// Returns the element types of the pair type, or [None] if the type is not
+/// a pair
+pub fn pair_types(registry: &ETypesRegistry, ty: EDataType) -> Option<(EDataType, EDataType)> {
+    let EDataType::Object { ident } = ty else {
+        return None;
+    };
+
+    let data = registry.get_struct(&ident)?;
+    if data.generic_parent_id != Some(*PAIR_ID) {
+        return None;
+    }
+
+    match data.generic_arguments_values.as_slice() {
+        [first, second] => Some((first.ty(), second.ty())),
+        _ => None,
+    }
+}
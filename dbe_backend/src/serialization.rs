@@ -8,6 +8,7 @@ use crate::json_utils::repr::Repr;
 use crate::m_try;
 use crate::registry::{EObjectType, ETypesRegistry};
 use crate::serialization::item::ThingItem;
+use crate::validation::rules::{RuleLevel, StructRule};
 use crate::validation::{validator_by_name, Validator};
 use crate::value::id::ETypeId;
 use itertools::Itertools;
@@ -60,10 +61,38 @@ struct ThingStruct {
     pub repr: Option<Repr>,
     #[knus(properties)]
     pub extra_properties: HashMap<String, ETypeConst>,
+    #[knus(children(name = "include"))]
+    pub includes: Vec<ThingInclude>,
+    #[knus(children(name = "rule"))]
+    pub rules: Vec<ThingRule>,
     #[knus(children)]
     pub fields: Vec<ThingItem>,
 }
 
+/// Another struct whose fields and rules are included into the struct
+///
+/// Included fields come first, in the order of inclusion. Fields declared
+/// in the struct itself replace the included fields with the same name,
+/// keeping their position
+#[derive(Debug, knus::Decode)]
+struct ThingInclude {
+    #[knus(argument)]
+    pub ty: String,
+}
+
+/// Cross-field validation rule, see [crate::validation::rules]
+#[derive(Debug, knus::Decode)]
+struct ThingRule {
+    #[knus(argument)]
+    pub expression: String,
+    #[knus(property)]
+    pub message: Option<String>,
+    #[knus(property, str)]
+    pub field: Option<Ustr>,
+    #[knus(property, str)]
+    pub level: Option<RuleLevel>,
+}
+
 #[derive(Debug, knus::Decode)]
 struct ThingEnum {
     #[knus(arguments, str)]
@@ -92,17 +121,68 @@ impl ThingStruct {
             self.repr,
             object_props(self.extra_properties)?,
         );
+        for include in self.includes {
+            m_try(|| {
+                let id = ETypeId::parse(&include.ty)?;
+                let included = registry.fetch_or_deserialize(id)?.clone();
+                let Some(included) = included.as_struct() else {
+                    bail!("`{}` is not a struct", id);
+                };
+                if !included.generic_arguments.is_empty() {
+                    bail!("generic struct `{}` can't be included", id);
+                }
+                for field in &included.fields {
+                    if data.fields.iter().any(|f| f.name == field.name) {
+                        bail!(
+                            "field `{}` is already included from another struct",
+                            field.name
+                        );
+                    }
+                    data.add_field(field.clone())?;
+                }
+                data.validators.extend(included.validators.iter().cloned());
+                data.includes.push(id);
+
+                Ok(())
+            })
+            .with_context(|| format!("failed to include struct `{}`", include.ty))?;
+        }
+
+        let included_count = data.fields.len();
         for e in self.fields {
             let field_name = e.name;
             m_try(|| {
                 let (name, item) = e.into_item(registry, &data.generic_arguments)?;
-                data.add_field(EStructField { name, ty: item })?;
+                let field = EStructField { name, ty: item };
+                // Overrides of included fields keep their position
+                if let Some(included) = data.fields[..included_count]
+                    .iter_mut()
+                    .find(|f| f.name == name)
+                {
+                    *included = field;
+                } else {
+                    data.add_field(field)?;
+                }
 
                 Ok(())
             })
             .with_context(|| format!("failed to initialize field {}", field_name))?;
         }
 
+        let field_names = data.fields.iter().map(|f| f.name).collect_vec();
+        for rule in self.rules {
+            let expression = rule.expression.clone();
+            let rule = StructRule::new(
+                rule.expression,
+                rule.message,
+                rule.field,
+                rule.level.unwrap_or_default(),
+                &field_names,
+            )
+            .with_context(|| format!("failed to initialize rule `{}`", expression))?;
+            data.validators.push(Validator::new(rule));
+        }
+
         Ok(data)
     }
 }
@@ -210,7 +290,172 @@ impl<S: ErrorSpan> DecodeScalar<S> for ETypeConst {
     }
 }
 
+#[cfg(test)]
 mod tests {
+    use crate::etype::EDataType;
+    use crate::project::test_utils::{load_project, test_io, test_project};
+    use crate::registry::ETypesRegistry;
+    use crate::value::id::ETypeId;
+    use diagnostic::diagnostic::DiagnosticLevel;
+    use itertools::Itertools;
+    use serde_json::json;
+
+    const BASE_TYPE: &str = r#"struct {
+    string "Name"
+    number "Level"
+    rule "Level > 0" message="Level must be positive" field="Level"
+}"#;
+
+    const STATS_TYPE: &str = r#"struct {
+    number "Speed"
+}"#;
+
+    fn item_type(props: &str) -> String {
+        format!(
+            r#"struct {props} {{
+    include "test:base"
+    include "test:stats"
+    number "Own"
+    number "Name"
+}}"#
+        )
+    }
+
+    fn include_error(files: &[(&str, &str)]) -> String {
+        let Err(err) = load_project(test_io(files)) else {
+            panic!("project should fail to load");
+        };
+        err.chain().map(|e| e.to_string()).join("\n")
+    }
+
+    /// Parses the JSON as `test:item` and writes it back
+    fn round_trip(registry: &ETypesRegistry, mut value: serde_json::Value) -> serde_json::Value {
+        let ty = EDataType::Object {
+            ident: ETypeId::parse("test:item").unwrap(),
+        };
+        let parsed = ty.parse_json(registry, &mut value, false).unwrap();
+        let written = parsed.write_json(registry).unwrap();
+        assert_eq!(
+            ty.parse_json(registry, &mut written.clone(), false)
+                .unwrap(),
+            parsed
+        );
+        written
+    }
+
+    #[test]
+    fn should_merge_included_fields() {
+        let item = item_type("");
+        let project = test_project(&[
+            ("test.dbemodule/types/base.kdl", BASE_TYPE),
+            ("test.dbemodule/types/stats.kdl", STATS_TYPE),
+            ("test.dbemodule/types/item.kdl", &item),
+        ]);
+
+        let data = project
+            .registry
+            .get_struct(&ETypeId::parse("test:item").unwrap())
+            .unwrap();
+        let fields = data
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.ty.ty()))
+            .collect_vec();
+        // `Name` is redeclared as a number, keeping its included position
+        assert_eq!(
+            fields,
+            [
+                ("Name", EDataType::Number),
+                ("Level", EDataType::Number),
+                ("Speed", EDataType::Number),
+                ("Own", EDataType::Number),
+            ]
+        );
+        assert_eq!(
+            data.includes,
+            [
+                ETypeId::parse("test:base").unwrap(),
+                ETypeId::parse("test:stats").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn should_carry_over_included_validators() {
+        let item = item_type("");
+        let project = test_project(&[
+            ("test.dbemodule/types/base.kdl", BASE_TYPE),
+            ("test.dbemodule/types/stats.kdl", STATS_TYPE),
+            ("test.dbemodule/types/item.kdl", &item),
+            ("a.json", r#"{ "Level": 0 }"#),
+            ("b.json", r#"{ "Level": 1 }"#),
+        ]);
+
+        let errors = |path: &str| {
+            project
+                .diagnostics
+                .diagnostics
+                .get(path)
+                .into_iter()
+                .flat_map(|reports| reports.values().flatten())
+                .filter(|report| report.level >= DiagnosticLevel::Error)
+                .map(|report| report.info.to_string())
+                .collect_vec()
+        };
+        assert_eq!(errors("a.json"), ["Level must be positive"]);
+        assert!(errors("b.json").is_empty());
+    }
+
+    #[test]
+    fn should_reject_include_cycles() {
+        let err = include_error(&[
+            (
+                "test.dbemodule/types/item.kdl",
+                "struct {\n    include \"test:other\"\n}",
+            ),
+            (
+                "test.dbemodule/types/other.kdl",
+                "struct {\n    include \"test:item\"\n}",
+            ),
+        ]);
+        assert!(err.contains("Recursion error"), "{}", err);
+    }
+
+    #[test]
+    fn should_reject_fields_included_twice() {
+        let item = item_type("");
+        let err = include_error(&[
+            ("test.dbemodule/types/base.kdl", BASE_TYPE),
+            (
+                "test.dbemodule/types/stats.kdl",
+                "struct {\n    number \"Level\"\n}",
+            ),
+            ("test.dbemodule/types/item.kdl", &item),
+        ]);
+        assert!(
+            err.contains("field `Level` is already included from another struct"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn should_round_trip_values_of_including_struct() {
+        let value = json!({ "Name": 3.0, "Level": 2.0, "Speed": 0.0, "Own": 1.0 });
+        for (props, expected) in [
+            ("", json!({ "Name": 3.0, "Level": 2.0, "Own": 1.0 })),
+            ("save_default_values=true", value.clone()),
+        ] {
+            let item = item_type(props);
+            let project = test_project(&[
+                ("test.dbemodule/types/base.kdl", BASE_TYPE),
+                ("test.dbemodule/types/stats.kdl", STATS_TYPE),
+                ("test.dbemodule/types/item.kdl", &item),
+            ]);
+            assert_eq!(round_trip(&project.registry, value.clone()), expected);
+        }
+    }
+
     #[test]
     fn parse_test() {
         let ty = super::parse_kdl(
@@ -235,4 +480,59 @@ enum "Item" editor="enum" port="hollow" {
             }
         }
     }
+
+    #[test]
+    fn parse_rules_test() {
+        let things = super::parse_kdl(
+            "test.kdl",
+            r#"
+struct {
+    number "MinLevel"
+    number "MaxLevel"
+    rule "MinLevel <= MaxLevel" message="MinLevel must not exceed MaxLevel" level="warning"
+}
+        "#,
+        )
+        .map_err(miette::Report::from)
+        .unwrap();
+
+        let [super::ThingVariant::Struct(thing)] = things.as_slice() else {
+            panic!("Expected a single struct, got {things:?}");
+        };
+        assert_eq!(thing.fields.len(), 2);
+        assert_eq!(thing.rules.len(), 1);
+        assert_eq!(
+            thing.rules[0].level,
+            Some(crate::validation::rules::RuleLevel::Warning)
+        );
+    }
+
+    #[test]
+    fn parse_includes_test() {
+        let things = super::parse_kdl(
+            "test.kdl",
+            r#"
+struct {
+    include "test:base_item"
+    include "test:stats"
+    number "Level" min=1
+}
+        "#,
+        )
+        .map_err(miette::Report::from)
+        .unwrap();
+
+        let [super::ThingVariant::Struct(thing)] = things.as_slice() else {
+            panic!("Expected a single struct, got {things:?}");
+        };
+        assert_eq!(
+            thing
+                .includes
+                .iter()
+                .map(|i| i.ty.as_str())
+                .collect::<Vec<_>>(),
+            ["test:base_item", "test:stats"]
+        );
+        assert_eq!(thing.fields.len(), 1);
+    }
 }
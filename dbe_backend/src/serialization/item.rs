@@ -5,6 +5,7 @@ use crate::etype::EDataType;
 use crate::m_try;
 use crate::registry::ETypesRegistry;
 use crate::serialization::validators;
+use crate::validation::constraints::constraint_validators;
 use crate::value::id::ETypeId;
 use itertools::Itertools;
 use miette::{bail, Context, Diagnostic};
@@ -157,7 +158,8 @@ impl ThingItem {
             }
         };
 
-        let validators = validators(&self.extra_properties)?;
+        let mut validators = validators(&self.extra_properties)?;
+        validators.extend(constraint_validators(&self.extra_properties, ty)?);
 
         Ok((
             self.name,
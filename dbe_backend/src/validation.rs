@@ -11,14 +11,24 @@ use std::fmt::Debug;
 use std::sync::{Arc, LazyLock};
 use ustr::{Ustr, UstrMap};
 
+pub mod constraints;
 pub mod ids;
+pub mod rules;
 
 static VALIDATORS: LazyLock<AtomicRefCell<UstrMap<Arc<dyn DataValidator>>>> =
     LazyLock::new(|| AtomicRefCell::new(default_validators().collect()));
 
 fn default_validators() -> impl Iterator<Item = (Ustr, Arc<dyn DataValidator>)> {
-    let v: Vec<Arc<dyn DataValidator>> =
-        vec![Arc::new(ids::numeric::Id), Arc::new(ids::numeric::Ref)];
+    let v: Vec<Arc<dyn DataValidator>> = vec![
+        Arc::new(ids::numeric::Id),
+        Arc::new(ids::numeric::Ref),
+        Arc::new(constraints::Range),
+        Arc::new(constraints::Integer),
+        Arc::new(constraints::Pattern),
+        Arc::new(constraints::NonEmpty),
+        Arc::new(constraints::Length),
+        Arc::new(constraints::Unique),
+    ];
     v.into_iter().map(|item| (Ustr::from(&item.name()), item))
 }
 
@@ -27,6 +37,9 @@ pub trait DataValidator: Send + Sync + Debug {
 
     fn clear_cache(&self, registry: &ETypesRegistry);
 
+    /// Clears cached data that was collected from the given file
+    fn clear_file_cache(&self, registry: &ETypesRegistry, file: &str);
+
     fn validate(
         &self,
         registry: &ETypesRegistry,
@@ -39,6 +52,14 @@ pub trait DataValidator: Send + Sync + Debug {
 #[derive(Debug, Clone)]
 pub struct Validator(Arc<dyn DataValidator>);
 
+impl Validator {
+    /// Wraps the validator that is not registered by name, like the ones
+    /// declared in type definitions
+    pub fn new(validator: impl DataValidator + 'static) -> Self {
+        Self(Arc::new(validator))
+    }
+}
+
 /// Looks up the validator given their name
 pub fn validator_by_name(name: Ustr) -> Option<Validator> {
     // trace!("looking up validator by name: {:?}", name);
@@ -54,6 +75,10 @@ impl DataValidator for Validator {
         self.0.clear_cache(registry);
     }
 
+    fn clear_file_cache(&self, registry: &ETypesRegistry, file: &str) {
+        self.0.clear_file_cache(registry, file);
+    }
+
     fn validate(
         &self,
         registry: &ETypesRegistry,
@@ -74,7 +99,8 @@ pub fn clear_validation_cache(registry: &ETypesRegistry) {
 /// Validates the provided data, writing all the userspace errors to the context
 ///
 /// This function will clear all the downstream reports in the context before
-/// starting the validation process
+/// starting the validation process. When the whole file is validated, data
+/// cached by validators for this file is cleared as well
 ///
 /// This function will return Ok(()) unless an internal error happens, usually
 /// indicating a corrupt application state
@@ -85,6 +111,11 @@ pub fn validate(
     data: &EValue,
 ) -> miette::Result<()> {
     ctx.clear_downstream();
+    if ctx.path().is_empty() {
+        for validator in VALIDATORS.borrow().values() {
+            validator.clear_file_cache(registry, ctx.ident());
+        }
+    }
     validate_inner(registry, ctx, item, data)
 }
 
@@ -122,6 +153,11 @@ fn validate_inner(
                     }
                 }
 
+                for v in &obj.validators {
+                    v.validate(registry, ctx.enter_inline(), item, data)
+                        .with_context(|| format!("validator `{}` failed", v.name()))?;
+                }
+
                 let default = data.ty().default_value(registry);
                 let default = default.try_as_struct().with_context(|| {
                     format!(
@@ -0,0 +1,652 @@
+//! Constraints declared via field properties in type definitions
+//!
+//! Constraint validators are attached to the field automatically when the
+//! corresponding property is present, so values edited outside the editor or
+//! produced by graphs are held to the same rules as the editor widgets.
+
+use crate::etype::econst::ETypeConst;
+use crate::etype::eitem::EItemInfo;
+use crate::etype::property::default_properties::{
+    PROP_FIELD_INTEGER, PROP_FIELD_MAX, PROP_FIELD_MAX_LENGTH, PROP_FIELD_MIN,
+    PROP_FIELD_MIN_LENGTH, PROP_FIELD_NON_EMPTY, PROP_FIELD_PATTERN, PROP_FIELD_UNIQUE,
+};
+use crate::etype::property::FieldPropertyId;
+use crate::etype::EDataType;
+use crate::registry::ETypesRegistry;
+use crate::validation::{validator_by_name, DataValidator, Validator};
+use crate::value::{ENumber, EValue};
+use diagnostic::context::DiagnosticContextMut;
+use miette::{bail, miette, Context, IntoDiagnostic};
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+use ustr::Ustr;
+use utils::map::{DashMap, HashMap, HashSet};
+
+/// Legacy field property holding the numeric type of the field
+const PROP_FIELD_TYPE: &str = "type";
+
+/// Compiled patterns, keyed by the pattern source
+static PATTERNS: LazyLock<DashMap<Ustr, Regex>> = LazyLock::new(Default::default);
+
+/// Returns the constraint validators required by the field properties
+///
+/// Properties that can't be applied to the field type are reported as errors,
+/// except for `min` and `max`, which are also used by custom editors of
+/// non-numeric fields
+pub(crate) fn constraint_validators(
+    props: &HashMap<String, ETypeConst>,
+    ty: EDataType,
+) -> miette::Result<Vec<Validator>> {
+    let number = |id: &str| -> miette::Result<Option<ENumber>> {
+        props
+            .get(id)
+            .map(|value| ENumber::try_from(*value))
+            .transpose()
+            .with_context(|| format!("failed to parse property `{}`", id))
+    };
+    let flag = |id: &str| -> miette::Result<bool> {
+        props
+            .get(id)
+            .map_or(Ok(false), |value| bool::try_from(*value))
+            .with_context(|| format!("failed to parse property `{}`", id))
+    };
+    let expect_ty = |id: &str, matches: bool, kinds: &str| -> miette::Result<()> {
+        if !matches {
+            bail!("property `{}` can only be used on {}", id, kinds);
+        }
+        Ok(())
+    };
+
+    let mut names = vec![];
+
+    if ty.is_number() {
+        let min = number(PROP_FIELD_MIN.info().id)?;
+        let max = number(PROP_FIELD_MAX.info().id)?;
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                bail!("`min` of {} is greater than `max` of {}", min, max);
+            }
+        }
+        if min.is_some() || max.is_some() {
+            names.push(Range.name());
+        }
+    }
+
+    let integer = flag(PROP_FIELD_INTEGER.info().id)?;
+    if integer {
+        expect_ty(
+            PROP_FIELD_INTEGER.info().id,
+            ty.is_number(),
+            "number fields",
+        )?;
+    }
+    // Older types mark whole-number fields with `type="int"` instead
+    let int_type = props
+        .get(PROP_FIELD_TYPE)
+        .and_then(|value| value.as_string())
+        .is_some_and(|value| value.as_str() == "int");
+    if integer || (int_type && ty.is_number()) {
+        names.push(Integer.name());
+    }
+
+    if let Some(pattern) = props.get(PROP_FIELD_PATTERN.info().id) {
+        expect_ty(
+            PROP_FIELD_PATTERN.info().id,
+            ty.is_string(),
+            "string fields",
+        )?;
+        let pattern = pattern
+            .as_string()
+            .ok_or_else(|| miette!("property `pattern` is expected to be a string"))?;
+        compile_pattern(pattern)?;
+        names.push(Pattern.name());
+    }
+
+    let is_collection = ty.is_string() || ty.is_list() || ty.is_map();
+    let collections = "string, list and map fields";
+
+    if flag(PROP_FIELD_NON_EMPTY.info().id)? {
+        expect_ty(PROP_FIELD_NON_EMPTY.info().id, is_collection, collections)?;
+        names.push(NonEmpty.name());
+    }
+
+    let min_length = number(PROP_FIELD_MIN_LENGTH.info().id)?;
+    let max_length = number(PROP_FIELD_MAX_LENGTH.info().id)?;
+    if min_length.is_some() || max_length.is_some() {
+        for (id, value) in [
+            (PROP_FIELD_MIN_LENGTH.info().id, min_length),
+            (PROP_FIELD_MAX_LENGTH.info().id, max_length),
+        ] {
+            let Some(value) = value else {
+                continue;
+            };
+            expect_ty(id, is_collection, collections)?;
+            if !value.is_finite() || value.0 < 0.0 || value.fract() != 0.0 {
+                bail!(
+                    "property `{}` must be a non-negative integer, got {}",
+                    id,
+                    value
+                );
+            }
+        }
+        if let (Some(min), Some(max)) = (min_length, max_length) {
+            if min > max {
+                bail!(
+                    "`min_length` of {} is greater than `max_length` of {}",
+                    min,
+                    max
+                );
+            }
+        }
+        names.push(Length.name());
+    }
+
+    if flag(PROP_FIELD_UNIQUE.info().id)? {
+        expect_ty(PROP_FIELD_UNIQUE.info().id, ty.is_list(), "list fields")?;
+        names.push(Unique.name());
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            validator_by_name(Ustr::from(&name))
+                .ok_or_else(|| miette!("!!INTERNAL ERROR!! validator `{}` is not registered", name))
+        })
+        .collect()
+}
+
+fn compile_pattern(pattern: Ustr) -> miette::Result<Regex> {
+    if let Some(regex) = PATTERNS.get(&pattern) {
+        return Ok(regex.clone());
+    }
+
+    // Pattern must match the whole value
+    let regex = Regex::new(&format!("^(?:{})$", pattern))
+        .into_diagnostic()
+        .with_context(|| format!("invalid regex pattern `{}`", pattern))?;
+    PATTERNS.insert(pattern, regex.clone());
+    Ok(regex)
+}
+
+fn props(item: Option<&EItemInfo>) -> Option<&HashMap<FieldPropertyId, ETypeConst>> {
+    item.map(EItemInfo::extra_properties)
+}
+
+/// Length of the string, list or map value
+fn collection_length(data: &EValue) -> Option<usize> {
+    match data {
+        EValue::String { value } => Some(value.chars().count()),
+        EValue::List { values, .. } => Some(values.len()),
+        EValue::Map { values, .. } => Some(values.len()),
+        _ => None,
+    }
+}
+
+/// Checks that the number is within the `min` and `max` bounds
+#[derive(Debug)]
+pub struct Range;
+
+impl DataValidator for Range {
+    fn name(&self) -> Cow<'static, str> {
+        "range".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        _registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        let (Some(props), EValue::Number { value }) = (props(item), data) else {
+            return Ok(());
+        };
+
+        if let Some(min) = PROP_FIELD_MIN.try_get(props) {
+            if *value < min {
+                ctx.emit_error(miette!(
+                    "value {} is less than the minimum of {}",
+                    value,
+                    min
+                ));
+            }
+        }
+        if let Some(max) = PROP_FIELD_MAX.try_get(props) {
+            if *value > max {
+                ctx.emit_error(miette!(
+                    "value {} is greater than the maximum of {}",
+                    value,
+                    max
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the number has no fractional part
+#[derive(Debug)]
+pub struct Integer;
+
+impl DataValidator for Integer {
+    fn name(&self) -> Cow<'static, str> {
+        "integer".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        _registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        _item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        let EValue::Number { value } = data else {
+            return Ok(());
+        };
+
+        if !value.is_finite() || value.fract() != 0.0 {
+            ctx.emit_error(miette!("value {} is expected to be an integer", value));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the string matches the `pattern` regex
+#[derive(Debug)]
+pub struct Pattern;
+
+impl DataValidator for Pattern {
+    fn name(&self) -> Cow<'static, str> {
+        "pattern".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        _registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        let (Some(props), EValue::String { value }) = (props(item), data) else {
+            return Ok(());
+        };
+        let Some(pattern) = PROP_FIELD_PATTERN.try_get(props) else {
+            return Ok(());
+        };
+
+        if !compile_pattern(pattern)?.is_match(value) {
+            ctx.emit_error(miette!(
+                "value `{}` does not match the pattern `{}`",
+                value,
+                pattern
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the string, list or map is not empty
+#[derive(Debug)]
+pub struct NonEmpty;
+
+impl DataValidator for NonEmpty {
+    fn name(&self) -> Cow<'static, str> {
+        "non_empty".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        _registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        _item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        if collection_length(data) == Some(0) {
+            ctx.emit_error(miette!("value must not be empty"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the length of the string, list or map is within the
+/// `min_length` and `max_length` bounds
+#[derive(Debug)]
+pub struct Length;
+
+impl DataValidator for Length {
+    fn name(&self) -> Cow<'static, str> {
+        "length".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        _registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        let (Some(props), Some(length)) = (props(item), collection_length(data)) else {
+            return Ok(());
+        };
+
+        if let Some(min) = PROP_FIELD_MIN_LENGTH.try_get(props) {
+            if (length as f64) < min.0 {
+                ctx.emit_error(miette!(
+                    "length {} is less than the minimum of {}",
+                    length,
+                    min
+                ));
+            }
+        }
+        if let Some(max) = PROP_FIELD_MAX_LENGTH.try_get(props) {
+            if (length as f64) > max.0 {
+                ctx.emit_error(miette!(
+                    "length {} is greater than the maximum of {}",
+                    length,
+                    max
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that all items of the list are unique
+#[derive(Debug)]
+pub struct Unique;
+
+impl DataValidator for Unique {
+    fn name(&self) -> Cow<'static, str> {
+        "unique".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        _registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        _item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        let EValue::List { values, .. } = data else {
+            return Ok(());
+        };
+
+        let mut seen = HashSet::default();
+        for (idx, value) in values.iter().enumerate() {
+            if !seen.insert(value) {
+                ctx.enter_index(idx)
+                    .emit_error(miette!("duplicate list item {}", value));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::etype::econst::ETypeConst;
+    use crate::etype::EDataType;
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::test_project;
+    use crate::project::Project;
+    use crate::validation::constraints::constraint_validators;
+    use crate::validation::DataValidator;
+    use crate::value::ENumber;
+    use diagnostic::diagnostic::DiagnosticLevel;
+    use itertools::Itertools;
+    use utils::map::HashMap;
+
+    const ITEM_TYPE: &str = r#"struct {
+        number "Level" min=1 max=10 integer=true
+        string "Name" pattern="[a-z]+" max_length=5
+        list "Tags" non_empty=true unique=true {
+            string "Item"
+        }
+    }"#;
+
+    fn number(value: f64) -> ETypeConst {
+        ETypeConst::Number(ENumber::from(value))
+    }
+
+    /// Names of the validators required by the properties
+    fn validators(props: &[(&str, ETypeConst)], ty: EDataType) -> miette::Result<Vec<String>> {
+        let props: HashMap<String, ETypeConst> = props
+            .iter()
+            .map(|(id, value)| (id.to_string(), *value))
+            .collect();
+        Ok(constraint_validators(&props, ty)?
+            .iter()
+            .map(|validator| validator.name().to_string())
+            .collect())
+    }
+
+    fn error(props: &[(&str, ETypeConst)], ty: EDataType) -> String {
+        validators(props, ty).unwrap_err().to_string()
+    }
+
+    /// Sorted error messages reported for the file
+    fn errors(project: &Project<MemoryIO>, path: &str) -> Vec<String> {
+        project
+            .diagnostics
+            .diagnostics
+            .get(path)
+            .into_iter()
+            .flat_map(|reports| reports.values().flatten())
+            .filter(|report| report.level >= DiagnosticLevel::Error)
+            .map(|report| report.info.to_string())
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn should_attach_validators_for_properties() {
+        let list = test_project(&[]).registry.list_of(EDataType::String);
+
+        assert_eq!(
+            validators(
+                &[("min", number(1.0)), ("integer", ETypeConst::Boolean(true))],
+                EDataType::Number
+            )
+            .unwrap(),
+            vec!["range", "integer"]
+        );
+        assert_eq!(
+            validators(
+                &[
+                    ("pattern", ETypeConst::String("[a-z]+".into())),
+                    ("non_empty", ETypeConst::Boolean(true)),
+                    ("max_length", number(5.0)),
+                ],
+                EDataType::String
+            )
+            .unwrap(),
+            vec!["pattern", "non_empty", "length"]
+        );
+        assert_eq!(
+            validators(&[("unique", ETypeConst::Boolean(true))], list).unwrap(),
+            vec!["unique"]
+        );
+        assert_eq!(
+            validators(
+                &[("type", ETypeConst::String("int".into()))],
+                EDataType::Number
+            )
+            .unwrap(),
+            vec!["integer"]
+        );
+        assert_eq!(
+            validators(
+                &[
+                    ("type", ETypeConst::String("int".into())),
+                    ("integer", ETypeConst::Boolean(true))
+                ],
+                EDataType::Number
+            )
+            .unwrap(),
+            vec!["integer"]
+        );
+        assert!(validators(
+            &[("type", ETypeConst::String("float".into()))],
+            EDataType::Number
+        )
+        .unwrap()
+        .is_empty());
+        // `min` and `max` of non-numeric fields are left to custom editors
+        assert!(validators(&[("min", number(1.0))], EDataType::String)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn should_reject_properties_of_wrong_field_type() {
+        assert!(error(
+            &[("pattern", ETypeConst::String("a".into()))],
+            EDataType::Number
+        )
+        .contains("can only be used on string fields"));
+        assert!(
+            error(&[("integer", ETypeConst::Boolean(true))], EDataType::String)
+                .contains("can only be used on number fields")
+        );
+        assert!(error(&[("min_length", number(1.0))], EDataType::Number)
+            .contains("can only be used on string, list and map fields"));
+        assert!(
+            error(&[("unique", ETypeConst::Boolean(true))], EDataType::String)
+                .contains("can only be used on list fields")
+        );
+        assert!(error(
+            &[("min", ETypeConst::String("a".into()))],
+            EDataType::Number
+        )
+        .contains("failed to parse property `min`"));
+    }
+
+    #[test]
+    fn should_reject_inverted_bounds() {
+        assert_eq!(
+            error(
+                &[("min", number(5.0)), ("max", number(1.0))],
+                EDataType::Number
+            ),
+            "`min` of 5 is greater than `max` of 1"
+        );
+        assert_eq!(
+            error(
+                &[("min_length", number(3.0)), ("max_length", number(1.0))],
+                EDataType::String
+            ),
+            "`min_length` of 3 is greater than `max_length` of 1"
+        );
+        assert!(error(&[("max_length", number(-1.0))], EDataType::String)
+            .contains("must be a non-negative integer"));
+    }
+
+    #[test]
+    fn should_reject_invalid_regex() {
+        assert_eq!(
+            error(
+                &[("pattern", ETypeConst::String("(".into()))],
+                EDataType::String
+            ),
+            "invalid regex pattern `(`"
+        );
+    }
+
+    #[test]
+    fn should_treat_int_type_as_integer() {
+        let project = test_project(&[
+            (
+                "test.dbemodule/types/item.kdl",
+                r#"struct {
+                    number "Count" type="int"
+                }"#,
+            ),
+            ("a.json", r#"{ "Count": 2 }"#),
+            ("b.json", r#"{ "Count": 2.5 }"#),
+        ]);
+
+        assert!(errors(&project, "a.json").is_empty());
+        assert_eq!(
+            errors(&project, "b.json"),
+            vec!["value 2.5 is expected to be an integer"]
+        );
+    }
+
+    #[test]
+    fn should_accept_valid_values() {
+        let project = test_project(&[
+            ("test.dbemodule/types/item.kdl", ITEM_TYPE),
+            (
+                "a.json",
+                r#"{ "Level": 5, "Name": "abc", "Tags": ["x", "y"] }"#,
+            ),
+        ]);
+
+        assert!(errors(&project, "a.json").is_empty());
+    }
+
+    #[test]
+    fn should_report_constraint_violations() {
+        let project = test_project(&[
+            ("test.dbemodule/types/item.kdl", ITEM_TYPE),
+            (
+                "a.json",
+                r#"{ "Level": 11.5, "Name": "ABCDEFG", "Tags": ["x", "y", "x"] }"#,
+            ),
+            ("b.json", r#"{ "Level": 0, "Name": "", "Tags": [] }"#),
+        ]);
+
+        let a = errors(&project, "a.json");
+        assert_eq!(a.len(), 5, "{:?}", a);
+        for message in [
+            "value 11.5 is greater than the maximum of 10",
+            "value 11.5 is expected to be an integer",
+            "does not match the pattern `[a-z]+`",
+            "length 7 is greater than the maximum of 5",
+            "duplicate list item",
+        ] {
+            assert!(a.iter().any(|e| e.contains(message)), "{:?}", a);
+        }
+
+        let b = errors(&project, "b.json");
+        assert_eq!(b.len(), 3, "{:?}", b);
+        for message in [
+            "value 0 is less than the minimum of 1",
+            "value `` does not match the pattern `[a-z]+`",
+            "value must not be empty",
+        ] {
+            assert!(b.iter().any(|e| e.contains(message)), "{:?}", b);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crate::etype::eitem::EItemInfo;
 use crate::etype::EDataType;
+use crate::graph::node::groups::tree_subgraph::TreeSubgraph;
+use crate::graph::Graph;
+use crate::json_utils::repr::JsonRepr;
 use crate::registry::config::merge::ConfigMerge;
 use crate::registry::ETypesRegistry;
 use crate::validation::DataValidator;
@@ -16,6 +19,7 @@ use smallvec::{smallvec, SmallVec};
 use std::borrow::Cow;
 use std::collections::BTreeSet;
 use std::fmt::Display;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use thiserror::Error;
 use ustr::{Ustr, UstrMap};
@@ -24,10 +28,140 @@ use utils::map::{HashMap, HashSet};
 #[derive(Default)]
 pub struct NumericIDsRegistry {
     ids: UstrMap<HashMap<ENumber, BTreeSet<String>>>,
+    /// Locations of the references to IDs
+    refs: UstrMap<HashMap<ENumber, BTreeSet<String>>>,
+    /// IDs and references recorded for each file, used to clear stale entries
+    /// when the file is validated again
+    files: HashMap<String, Vec<IndexEntry>>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    is_ref: bool,
+    ty: Ustr,
+    id: ENumber,
+    location: String,
+}
+
+impl NumericIDsRegistry {
+    fn record(&mut self, file: &str, entry: IndexEntry) {
+        let map = if entry.is_ref {
+            &mut self.refs
+        } else {
+            &mut self.ids
+        };
+        map.entry(entry.ty)
+            .or_default()
+            .entry(entry.id)
+            .or_default()
+            .insert(entry.location.clone());
+        self.files.entry(file.to_string()).or_default().push(entry);
+    }
+
+    fn clear_file(&mut self, file: &str) {
+        let Some(entries) = self.files.remove(file) else {
+            return;
+        };
+        for entry in entries {
+            let map = if entry.is_ref {
+                &mut self.refs
+            } else {
+                &mut self.ids
+            };
+            let Some(ids) = map.get_mut(&entry.ty) else {
+                continue;
+            };
+            if let Some(locations) = ids.get_mut(&entry.id) {
+                locations.remove(&entry.location);
+                if locations.is_empty() {
+                    ids.remove(&entry.id);
+                }
+            }
+        }
+    }
+}
+
+/// Definitions and references of a numeric ID
+#[derive(Debug, Clone, Default)]
+pub struct IdUsages {
+    /// Locations of the ID definitions. More than one definition means that
+    /// the ID is duplicated
+    pub definitions: Vec<String>,
+    /// Locations of the references to the ID, including references of the
+    /// types that can be satisfied by this ID type
+    pub references: Vec<String>,
+}
+
+/// Splits an ID location into the file path and the field path within the file
+pub fn split_location(location: &str) -> (&str, &str) {
+    location.split_once('@').unwrap_or((location, ""))
 }
 
 type Data = RwLock<NumericIDsRegistry>;
 
+/// Kind of numeric ID struct
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum NumericIdKind {
+    /// ID definition, `sys:ids/numeric`
+    Id,
+    /// Reference to an ID, `sys:ids/numeric_ref`
+    Ref,
+}
+
+impl NumericIdKind {
+    fn of_struct(registry: &ETypesRegistry, ident: &ETypeId) -> Option<Self> {
+        let repr = registry.get_struct(ident)?.repr.as_ref()?;
+        match repr.id() {
+            "ids/numeric" => Some(Self::Id),
+            "ids/numeric_ref" => Some(Self::Ref),
+            _ => None,
+        }
+    }
+}
+
+/// Calls the provided closure for every numeric ID and ID reference within
+/// the value, with the kind of the struct, value of its `Id` generic
+/// argument, and a mutable reference to the ID number
+pub fn visit_numeric_ids_mut(
+    registry: &ETypesRegistry,
+    value: &mut EValue,
+    cb: &mut impl FnMut(NumericIdKind, Ustr, &mut ENumber) -> miette::Result<()>,
+) -> miette::Result<()> {
+    match value {
+        EValue::Null | EValue::Boolean { .. } | EValue::Number { .. } | EValue::String { .. } => {}
+        EValue::Struct { ident, fields } => {
+            if let Some(kind) = NumericIdKind::of_struct(registry, ident) {
+                let ty = extract_generic_arg(registry, ident)?;
+                if let Some(EValue::Number { value }) = fields.get_mut(&Ustr::from("id")) {
+                    cb(kind, ty, value)?;
+                }
+                return Ok(());
+            }
+
+            for field in fields.values_mut() {
+                visit_numeric_ids_mut(registry, field, cb)?;
+            }
+        }
+        EValue::Enum { data, .. } => {
+            visit_numeric_ids_mut(registry, data, cb)?;
+        }
+        EValue::List { values, .. } => {
+            for value in values {
+                visit_numeric_ids_mut(registry, value, cb)?;
+            }
+        }
+        EValue::Map { values, .. } => {
+            for (mut key, mut value) in std::mem::take(values) {
+                visit_numeric_ids_mut(registry, &mut key, cb)?;
+                visit_numeric_ids_mut(registry, &mut value, cb)?;
+                values.insert(key, value);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Extracts the struct type and ID from an ID struct value
 fn ty_and_id(registry: &ETypesRegistry, data: &EValue) -> miette::Result<(Ustr, ENumber)> {
     let EValue::Struct { ident, fields } = data else {
@@ -301,6 +435,214 @@ impl<'a> NumericIDRegistry<'a> {
         Ok(location(&config, &reg, id, category, &mut smallvec![]))
     }
 
+    /// Returns all ID types that have either definitions or references
+    pub fn id_types(&self) -> Vec<Ustr> {
+        let reg = self.registry.extra_data::<Data>();
+        let reg = reg.read();
+
+        reg.ids
+            .iter()
+            .chain(reg.refs.iter())
+            .filter(|(_, ids)| !ids.is_empty())
+            .map(|(ty, _)| *ty)
+            .sorted_unstable_by_key(|ty| ty.as_str())
+            .dedup()
+            .collect()
+    }
+
+    /// Records references to IDs held in the inline values of the graph and
+    /// its tree subgraphs, replacing the entries previously recorded for the
+    /// graph file
+    ///
+    /// Graph inline values are not validated, so their references are not
+    /// recorded by the [Ref] validator
+    pub fn index_graph_refs(&self, file: &str, graph: &Graph) -> miette::Result<()> {
+        fn index(
+            registry: &ETypesRegistry,
+            reg: &mut NumericIDsRegistry,
+            file: &str,
+            prefix: &str,
+            graph: &Graph,
+        ) -> miette::Result<()> {
+            for (pin, value) in graph.inline_values() {
+                let location = format!("{}node {} input {}", prefix, pin.node.0, pin.input);
+                visit_numeric_ids_mut(registry, &mut value.clone(), &mut |kind, ty, id| {
+                    if kind == NumericIdKind::Ref {
+                        reg.record(
+                            file,
+                            IndexEntry {
+                                is_ref: true,
+                                ty,
+                                id: *id,
+                                location: format!("{}@{}", file, location),
+                            },
+                        );
+                    }
+                    Ok(())
+                })?;
+            }
+
+            for (node_id, node) in graph.snarl().node_ids() {
+                if let Some(tree) = node.node.downcast_ref::<TreeSubgraph>() {
+                    let prefix = format!("{}node {}.", prefix, node_id.0);
+                    index(registry, reg, file, &prefix, tree.inner_graph())?;
+                }
+            }
+
+            Ok(())
+        }
+
+        let reg = self.registry.extra_data::<Data>();
+        let mut reg = reg.write();
+        reg.clear_file(file);
+        index(self.registry, &mut reg, file, "", graph)
+    }
+
+    /// Returns locations of the definitions of the given ID
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type
+    pub fn definitions(&self, ty: Ustr, id: ENumber) -> Vec<String> {
+        let reg = self.registry.extra_data::<Data>();
+        let reg = reg.read();
+
+        reg.ids
+            .get(&ty)
+            .and_then(|ids| ids.get(&id))
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns all defined IDs of the given type, in ascending order
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type
+    pub fn defined_ids(&self, ty: Ustr) -> Vec<ENumber> {
+        let reg = self.registry.extra_data::<Data>();
+        let reg = reg.read();
+
+        reg.ids
+            .get(&ty)
+            .into_iter()
+            .flatten()
+            .filter(|(_, locations)| !locations.is_empty())
+            .map(|(id, _)| *id)
+            .sorted_unstable()
+            .collect()
+    }
+
+    /// Returns the definitions and references of the given ID
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type
+    pub fn usages(&self, ty: Ustr, id: ENumber) -> miette::Result<IdUsages> {
+        let ref_types = {
+            let reg = self.registry.extra_data::<Data>();
+            let reg = reg.read();
+            reg.refs.keys().copied().collect_vec()
+        };
+
+        let mut referencing = vec![];
+        for ref_ty in ref_types {
+            if self.is_id_assignable(ty, ref_ty)? {
+                referencing.push(ref_ty);
+            }
+        }
+
+        let definitions = self.definitions(ty, id);
+
+        let reg = self.registry.extra_data::<Data>();
+        let reg = reg.read();
+
+        let references = referencing
+            .iter()
+            .filter_map(|ref_ty| reg.refs.get(ref_ty).and_then(|ids| ids.get(&id)))
+            .flatten()
+            .cloned()
+            .sorted_unstable()
+            .dedup()
+            .collect();
+
+        drop(reg);
+
+        Ok(IdUsages {
+            definitions,
+            references,
+        })
+    }
+
+    /// Returns all IDs that can't be given to a new item of the given type,
+    /// that is IDs that are already defined or reserved for this type, for
+    /// any of the types it conflicts with, or for the types that satisfy
+    /// them, since references to such IDs would be ambiguous
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type
+    pub fn taken_ids(&self, ty: Ustr) -> miette::Result<HashSet<ENumber>> {
+        let config = config(self.registry)?;
+
+        let mut types: SmallVec<[Ustr; 1]> = smallvec![ty];
+        let mut idx = 0;
+        while let Some(cur) = types.get(idx).copied() {
+            idx += 1;
+            if let Some(cfg) = config.types.get(&cur) {
+                for conflicting in &cfg.conflicting_types {
+                    if !types.contains(conflicting) {
+                        types.push(*conflicting);
+                    }
+                }
+            }
+        }
+
+        // types that declare a conflict with this type
+        for (other, cfg) in &config.types {
+            if cfg.conflicting_types.contains(&ty) && !types.contains(other) {
+                types.push(*other);
+            }
+        }
+
+        let reg = self.registry.extra_data::<Data>();
+        let reg = reg.read();
+
+        let taken = AvailableIdsIter::new(&reg, &config, types)
+            .map(|(id, _)| *id)
+            .collect();
+
+        drop(reg);
+
+        Ok(taken)
+    }
+
+    /// Returns IDs within the range that can be given to a new item of the
+    /// given type, in ascending order
+    ///
+    /// `ty` is the value of the `Id` generic argument of the ID type
+    pub fn free_ids(
+        &self,
+        ty: Ustr,
+        range: RangeInclusive<i64>,
+    ) -> miette::Result<impl Iterator<Item = ENumber>> {
+        let taken = self.taken_ids(ty)?;
+        Ok(range
+            .map(|id| ENumber::from(id as f64))
+            .filter(move |id| !taken.contains(id)))
+    }
+
+    /// Returns the value of the `Id` generic argument of the given ID or
+    /// reference struct type
+    pub fn id_type_of(&self, ty: EDataType) -> miette::Result<Ustr> {
+        let EDataType::Object { ident } = ty else {
+            bail!("expected object type, got {:?}", ty);
+        };
+
+        extract_generic_arg(self.registry, &ident)
+    }
+
+    /// Returns the definitions and references of the given ID
+    ///
+    /// `ty` is either an ID or a reference struct type
+    pub fn usages_of_ty(&self, ty: EDataType, id: ENumber) -> miette::Result<IdUsages> {
+        self.usages(self.id_type_of(ty)?, id)
+    }
+
     /// Runs the provided closure with an iterator over available IDs for the
     /// given type, as well as the reserved IDs, and all IDs for types that
     /// this type is satisfied by
@@ -338,6 +680,8 @@ pub struct AvailableIdsIter<'a> {
     reg: &'a NumericIDsRegistry,
     cfg: &'a ReservedIdConfig,
     categories: SmallVec<[Ustr; 1]>,
+    /// Categories that were already iterated over
+    visited: SmallVec<[Ustr; 1]>,
     cur_iter: Option<std::collections::hash_map::Iter<'a, ENumber, BTreeSet<String>>>,
     cur_reserved_iter: Option<std::collections::hash_set::Iter<'a, ENumber>>,
 }
@@ -352,6 +696,7 @@ impl<'a> AvailableIdsIter<'a> {
             reg,
             cfg,
             categories,
+            visited: Default::default(),
             cur_iter: None,
             cur_reserved_iter: None,
         }
@@ -363,23 +708,27 @@ impl<'a> Iterator for AvailableIdsIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(iter) = &mut self.cur_iter {
-            if let Some(next) = iter.next() {
+            // Referenced IDs that are not defined have empty locations
+            if let Some(next) = iter.find(|(_, locations)| !locations.is_empty()) {
                 return Some((next.0, Some(next.1)));
             }
-            {
-                self.cur_iter = None;
-            }
-        } else if let Some(iter) = &mut self.cur_reserved_iter {
+            self.cur_iter = None;
+        }
+        if let Some(iter) = &mut self.cur_reserved_iter {
             if let Some(next) = iter.next() {
                 return Some((next, None));
-            } else {
-                self.cur_reserved_iter = None;
             }
+            self.cur_reserved_iter = None;
         }
 
         // cur iter is exhausted, get the next one
 
         while let Some(category) = self.categories.pop() {
+            if self.visited.contains(&category) {
+                continue;
+            }
+            self.visited.push(category);
+
             let reserved_ids = if let Some(config) = self.cfg.types.get(&category) {
                 self.categories
                     .extend(config.satisfied_by_types.iter().cloned());
@@ -421,6 +770,14 @@ impl DataValidator for Id {
         let ids = registry.extra_data::<Data>();
         let mut ids = ids.write();
         ids.ids.clear();
+        ids.refs.clear();
+        ids.files.clear();
+    }
+
+    fn clear_file_cache(&self, registry: &ETypesRegistry, file: &str) {
+        let ids = registry.extra_data::<Data>();
+        let mut ids = ids.write();
+        ids.clear_file(file);
     }
 
     fn validate(
@@ -472,17 +829,25 @@ impl DataValidator for Id {
                 }
             }
 
-            let ids = reg.ids.entry(ty).or_default().entry(id).or_default();
-
             // trace!("validating id: `{}` for type `{:?}`", id, ty);
 
             let mut filter_out_path = None;
             if top {
                 let path = ctx.full_path();
-                ids.insert(path.to_string());
+                reg.record(
+                    ctx.ident(),
+                    IndexEntry {
+                        is_ref: false,
+                        ty,
+                        id,
+                        location: path.clone(),
+                    },
+                );
                 filter_out_path = Some(path);
             }
 
+            let ids = reg.ids.entry(ty).or_default().entry(id).or_default();
+
             if ids.len() > if top { 1 } else { 0 } {
                 errors.push(IdValidationError::DuplicateId {
                     ty,
@@ -536,6 +901,10 @@ impl DataValidator for Ref {
         // cache is cleared by the `Id` validator
     }
 
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {
+        // cache is cleared by the `Id` validator
+    }
+
     fn validate(
         &self,
         registry: &ETypesRegistry,
@@ -583,6 +952,16 @@ impl DataValidator for Ref {
 
         let (ty, id) = ty_and_id(registry, data)?;
 
+        reg.record(
+            ctx.ident(),
+            IndexEntry {
+                is_ref: true,
+                ty,
+                id,
+                location: ctx.full_path(),
+            },
+        );
+
         if !check_id_exists(registry, &mut reg, ty, id, &mut smallvec![])? {
             ctx.emit_error(miette!("ID {} of type `{}` is not defined", id, ty));
         }
@@ -590,3 +969,52 @@ impl DataValidator for Ref {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::project::test_utils::{test_project, NUMERIC_ID_ITEM_TYPE};
+    use crate::validation::ids::numeric::NumericIDRegistry;
+    use crate::value::ENumber;
+    use serde_json::json;
+    use ustr::Ustr;
+
+    #[test]
+    fn should_index_graph_inline_refs() {
+        let graph = json!({
+            "version": "V1",
+            "graph": {
+                "nodes": [[0, {
+                    "id": "struct_node",
+                    "data": { "id": "test:item", "fields": ["Id", "Target"] },
+                    "pos": { "x": 0.0, "y": 0.0 },
+                    "open": true,
+                }]],
+                "edges": [],
+                "inline_values": [[{ "node": 0, "input": 1 }, 1]],
+            },
+        })
+        .to_string();
+        let project = test_project(&[
+            ("test.dbemodule/types/item.kdl", NUMERIC_ID_ITEM_TYPE),
+            ("a.json", r#"{ "Id": 1, "Target": 2 }"#),
+            ("b.json", r#"{ "Id": 2, "Target": 2 }"#),
+            ("graph.dbegraph", &graph),
+        ]);
+
+        let ids = NumericIDRegistry::of(&project.registry);
+        let usages = ids
+            .usages(Ustr::from("test:item"), ENumber::from(1.0))
+            .unwrap();
+        assert_eq!(usages.definitions.len(), 1);
+        assert_eq!(usages.references, vec!["graph.dbegraph@node 0 input 1"]);
+
+        let usages = ids
+            .usages(Ustr::from("test:item"), ENumber::from(2.0))
+            .unwrap();
+        assert_eq!(usages.references.len(), 2);
+        assert!(usages
+            .references
+            .iter()
+            .all(|location| !location.starts_with("graph.dbegraph")));
+    }
+}
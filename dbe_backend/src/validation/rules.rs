@@ -0,0 +1,319 @@
+//! Cross-field validation rules declared in struct definitions
+//!
+//! Rules are written as `rule` children of the struct node, with the
+//! condition being a [Rhai](https://rhai.rs) expression over the struct
+//! fields:
+//!
+//! ```kdl
+//! struct {
+//!     number "MinLevel"
+//!     number "MaxLevel"
+//!     rule "MinLevel <= MaxLevel" message="MinLevel must not exceed MaxLevel" field="MinLevel"
+//! }
+//! ```
+//!
+//! Fields are available as variables with the same names, in their JSON
+//! form, same as inputs of script nodes. The rule is reported when the
+//! expression evaluates to `false`.
+//!
+//! Only the fields mentioned in the expression are converted for the
+//! evaluation, so rules stay cheap on large structs.
+
+use crate::etype::eitem::EItemInfo;
+use crate::project::scripts::{is_identifier, ENGINE};
+use crate::registry::ETypesRegistry;
+use crate::validation::DataValidator;
+use crate::value::EValue;
+use diagnostic::context::DiagnosticContextMut;
+use miette::{bail, miette, Context};
+use rhai::{Scope, AST};
+use std::borrow::Cow;
+use std::sync::Arc;
+use strum::EnumString;
+use ustr::Ustr;
+use utils::map::HashSet;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum RuleLevel {
+    #[default]
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructRule {
+    /// Source of the rule expression
+    pub source: String,
+    /// Message reported when the rule is not satisfied
+    pub message: Option<String>,
+    /// Field to report the message at. The message is reported at the struct
+    /// itself if not set
+    pub field: Option<Ustr>,
+    pub level: RuleLevel,
+    /// Fields mentioned in the expression
+    used_fields: Vec<Ustr>,
+    ast: Arc<AST>,
+}
+
+impl StructRule {
+    /// Compiles the rule expression
+    ///
+    /// `fields` are the names of the struct fields, used to check that the
+    /// rule reports at the existing field
+    pub fn new(
+        source: String,
+        message: Option<String>,
+        field: Option<Ustr>,
+        level: RuleLevel,
+        fields: &[Ustr],
+    ) -> miette::Result<Self> {
+        if let Some(field) = field {
+            if !fields.contains(&field) {
+                bail!("field `{}` is not defined in the struct", field);
+            }
+        }
+
+        let ast = ENGINE
+            .compile_expression(&source)
+            .map_err(|err| miette!("{}", err))
+            .with_context(|| format!("failed to compile rule `{}`", source))?;
+
+        let words = source
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .collect::<HashSet<_>>();
+        let used_fields = fields
+            .iter()
+            .filter(|name| is_identifier(name) && words.contains(name.as_str()))
+            .copied()
+            .collect();
+
+        Ok(Self {
+            source,
+            message,
+            field,
+            level,
+            used_fields,
+            ast: Arc::new(ast),
+        })
+    }
+
+    fn check(&self, registry: &ETypesRegistry, data: &EValue) -> miette::Result<bool> {
+        let EValue::Struct { ident, fields } = data else {
+            bail!("expected a struct, got {}", data.ty().name());
+        };
+        let struct_data = registry
+            .get_struct(ident)
+            .ok_or_else(|| miette!("unknown struct `{}`", ident))?;
+
+        let mut scope = Scope::new();
+        for name in &self.used_fields {
+            let Some(field) = struct_data.fields.iter().find(|f| f.name == *name) else {
+                continue;
+            };
+            let value = match fields.get(&field.name) {
+                Some(value) => value.write_json(registry)?,
+                None => field.ty.default_value(registry).write_json(registry)?,
+            };
+            let value = rhai::serde::to_dynamic(value)
+                .map_err(|err| miette!("{}", err))
+                .with_context(|| format!("failed to pass field `{}` to the rule", field.name))?;
+            scope.push(field.name.to_string(), value);
+        }
+
+        ENGINE
+            .eval_ast_with_scope::<bool>(&mut scope, &self.ast)
+            .map_err(|err| miette!("{}", err))
+    }
+}
+
+impl DataValidator for StructRule {
+    fn name(&self) -> Cow<'static, str> {
+        "rule".into()
+    }
+
+    fn clear_cache(&self, _registry: &ETypesRegistry) {}
+
+    fn clear_file_cache(&self, _registry: &ETypesRegistry, _file: &str) {}
+
+    fn validate(
+        &self,
+        registry: &ETypesRegistry,
+        mut ctx: DiagnosticContextMut,
+        _item: Option<&EItemInfo>,
+        data: &EValue,
+    ) -> miette::Result<()> {
+        let report = match self.check(registry, data) {
+            Ok(true) => return Ok(()),
+            Ok(false) => match &self.message {
+                Some(message) => miette!("{}", message),
+                None => miette!("rule `{}` is not satisfied", self.source),
+            },
+            Err(err) => err.wrap_err(format!("failed to evaluate rule `{}`", self.source)),
+        };
+
+        let mut ctx = match self.field {
+            Some(field) => ctx.enter_field(field.as_str()),
+            None => ctx.enter_inline(),
+        };
+        match self.level {
+            RuleLevel::Error => ctx.emit_error(report),
+            RuleLevel::Warning => ctx.emit_warning(report),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::project::io::MemoryIO;
+    use crate::project::test_utils::test_project;
+    use crate::project::Project;
+    use crate::validation::rules::{RuleLevel, StructRule};
+    use diagnostic::diagnostic::DiagnosticLevel;
+    use itertools::Itertools;
+    use ustr::Ustr;
+
+    const ITEM_TYPE: &str = r#"struct {
+        number "MinLevel"
+        number "MaxLevel"
+        rule "MinLevel <= MaxLevel" message="MinLevel must not exceed MaxLevel" field="MinLevel"
+        rule "MaxLevel < 100"
+        rule "MaxLevel != 50" message="MaxLevel of 50 is reserved" level="warning"
+    }"#;
+
+    /// Path, level and message of every diagnostic reported for the file
+    fn diagnostics(
+        project: &Project<MemoryIO>,
+        path: &str,
+    ) -> Vec<(String, DiagnosticLevel, String)> {
+        project
+            .diagnostics
+            .diagnostics
+            .get(path)
+            .into_iter()
+            .flat_map(|reports| {
+                reports.iter().flat_map(|(path, reports)| {
+                    reports.iter().map(move |report| {
+                        (path.to_string(), report.level, report.info.to_string())
+                    })
+                })
+            })
+            .sorted_by(|a, b| a.2.cmp(&b.2))
+            .collect()
+    }
+
+    #[test]
+    fn should_report_at_configured_field() {
+        let project = test_project(&[
+            ("test.dbemodule/types/item.kdl", ITEM_TYPE),
+            ("a.json", r#"{ "MinLevel": 5, "MaxLevel": 3 }"#),
+            ("b.json", r#"{ "MinLevel": 1, "MaxLevel": 150 }"#),
+            ("c.json", r#"{ "MinLevel": 1, "MaxLevel": 10 }"#),
+        ]);
+
+        assert_eq!(
+            diagnostics(&project, "a.json"),
+            [(
+                "MinLevel".to_string(),
+                DiagnosticLevel::Error,
+                "MinLevel must not exceed MaxLevel".to_string()
+            )]
+        );
+        // Rules without `field` are reported at the struct itself
+        assert_eq!(
+            diagnostics(&project, "b.json"),
+            [(
+                String::new(),
+                DiagnosticLevel::Error,
+                "rule `MaxLevel < 100` is not satisfied".to_string()
+            )]
+        );
+        assert!(diagnostics(&project, "c.json").is_empty());
+    }
+
+    #[test]
+    fn should_report_warning_rules_as_warnings() {
+        let project = test_project(&[
+            ("test.dbemodule/types/item.kdl", ITEM_TYPE),
+            ("a.json", r#"{ "MinLevel": 1, "MaxLevel": 50 }"#),
+        ]);
+
+        assert_eq!(
+            diagnostics(&project, "a.json"),
+            [(
+                String::new(),
+                DiagnosticLevel::Warning,
+                "MaxLevel of 50 is reserved".to_string()
+            )]
+        );
+        assert!(!project.diagnostics.has_diagnostics(DiagnosticLevel::Error));
+    }
+
+    #[test]
+    fn should_report_rules_failing_to_evaluate() {
+        let project = test_project(&[
+            (
+                "test.dbemodule/types/item.kdl",
+                r#"struct {
+                    number "Level"
+                    rule "Missing > 0"
+                    rule "Level + 1"
+                }"#,
+            ),
+            ("a.json", r#"{ "Level": 1 }"#),
+        ]);
+
+        assert_eq!(
+            diagnostics(&project, "a.json"),
+            [
+                (
+                    String::new(),
+                    DiagnosticLevel::Error,
+                    "failed to evaluate rule `Level + 1`".to_string()
+                ),
+                (
+                    String::new(),
+                    DiagnosticLevel::Error,
+                    "failed to evaluate rule `Missing > 0`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_only_pass_mentioned_fields() {
+        let fields = ["MinLevel", "MaxLevel", "MinLevelBonus", "Other"].map(Ustr::from);
+        let rule = StructRule::new(
+            "MinLevel <= MaxLevel && \"Other\" != \"\"".to_string(),
+            None,
+            None,
+            RuleLevel::Error,
+            &fields,
+        )
+        .unwrap();
+
+        // Names inside string literals are passed too, which is harmless
+        assert_eq!(
+            rule.used_fields,
+            ["MinLevel", "MaxLevel", "Other"].map(Ustr::from)
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_report_field() {
+        let err = StructRule::new(
+            "true".to_string(),
+            None,
+            Some("Missing".into()),
+            RuleLevel::Error,
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "field `Missing` is not defined in the struct"
+        );
+    }
+}
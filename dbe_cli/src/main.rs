@@ -0,0 +1,384 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Parser, Subcommand, ValueEnum};
+use dbe_backend::diagnostic::context::DiagnosticContext;
+use dbe_backend::diagnostic::export;
+use dbe_backend::diagnostic::prelude::DiagnosticLevel;
+use dbe_backend::project::io::ProjectIO;
+use dbe_backend::project::{Project, ProjectFile};
+use miette::{bail, Context, IntoDiagnostic};
+use tracing::info;
+use tracing_panic::panic_hook;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::EnvFilter;
+
+/// Headless tool for checking and building squidhammer projects
+#[derive(Debug, Parser)]
+struct Args {
+    /// Path to the project directory
+    #[arg(short, long, env = "DBE_PROJECT", default_value = ".")]
+    project: Utf8PathBuf,
+    /// Format in which diagnostics are reported
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+    /// Directory to write per-node execution profiles of the evaluated graphs to
+    ///
+    /// For every graph, a `.txt` report and a `.folded` file in the folded
+    /// stacks format are written. The latter can be turned into a flame
+    /// graph with `inferno-flamegraph` or `flamegraph.pl`
+    #[arg(long)]
+    profile: Option<Utf8PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Subcommand)]
+enum Command {
+    /// Evaluates all graphs and validates the project without writing any files
+    Check,
+    /// Evaluates all graphs, validates the project and saves all files
+    Build,
+    /// Rewrites all project files in the canonical format, without evaluating graphs
+    Fmt,
+    /// Runs test cases of all node group test files, without evaluating graphs
+    Test,
+    /// Evaluates all graphs and records the generated files as the golden output snapshot
+    ///
+    /// Following evaluations report all differences from the snapshot
+    RecordGolden,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable reports, printed to stderr
+    Human,
+    /// JSON array of diagnostic records, printed to stdout
+    Json,
+    /// SARIF log, printed to stdout
+    Sarif,
+}
+
+pub fn main() -> miette::Result<()> {
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(tracing_subscriber::fmt::Layer::default().with_writer(std::io::stderr))
+        .with(
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::INFO.into())
+                .from_env_lossy(),
+        );
+
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+
+    color_backtrace::install();
+    let prev_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        panic_hook(panic_info);
+        prev_hook(panic_info);
+    }));
+
+    let Args {
+        project,
+        format,
+        profile,
+        command,
+    } = Args::parse();
+
+    m_try(|| {
+        let mut project = Project::from_path(&project)?;
+        project.debugger.profile_all = profile.is_some();
+        if !project.migrations.is_empty() {
+            info!(
+                files = project.migrations.files.len(),
+                "Project data was migrated to the new module versions"
+            );
+        }
+
+        run(&mut project, command, format)?;
+
+        if let Some(dir) = &profile {
+            write_profiles(&project, dir)?;
+        }
+
+        Ok(())
+    })
+    .with_context(|| format!("failed to process project at `{}`", project))
+}
+
+/// Runs the command on the loaded project
+///
+/// Any error, including diagnostics of the [DiagnosticLevel::Error] level, is
+/// returned, making the process exit with a non-zero code
+fn run<IO: ProjectIO>(
+    project: &mut Project<IO>,
+    command: Command,
+    format: OutputFormat,
+) -> miette::Result<()> {
+    match command {
+        Command::Check => check(project, format),
+        Command::Build => build(project, format),
+        Command::Fmt => fmt(project),
+        Command::Test => test(project, format),
+        Command::RecordGolden => record_golden(project, format),
+    }
+}
+
+fn check<IO: ProjectIO>(project: &mut Project<IO>, format: OutputFormat) -> miette::Result<()> {
+    let result = project.clean_validate();
+    report_diagnostics(&mut project.diagnostics, format)?;
+    result
+}
+
+fn build<IO: ProjectIO>(project: &mut Project<IO>, format: OutputFormat) -> miette::Result<()> {
+    let result = project.save();
+    report_diagnostics(&mut project.diagnostics, format)?;
+    result?;
+    info!("Project saved successfully");
+    Ok(())
+}
+
+fn fmt<IO: ProjectIO>(project: &mut Project<IO>) -> miette::Result<()> {
+    let mut bad_files = 0usize;
+    for (path, file) in &project.files {
+        if let ProjectFile::BadValue(report) = file {
+            bad_files += 1;
+            eprintln!("error in `{path}`: {report:?}");
+        }
+    }
+    if bad_files > 0 {
+        bail!("{bad_files} file(s) failed to load, nothing was written");
+    }
+    project.write_files()?;
+    info!("Project formatted successfully");
+    Ok(())
+}
+
+fn test<IO: ProjectIO>(project: &mut Project<IO>, format: OutputFormat) -> miette::Result<()> {
+    let result = project.run_graph_tests();
+    report_diagnostics(&mut project.diagnostics, format)?;
+    let report = result?;
+    info!(passed = report.passed, "All tests passed");
+    Ok(())
+}
+
+fn record_golden<IO: ProjectIO>(
+    project: &mut Project<IO>,
+    format: OutputFormat,
+) -> miette::Result<()> {
+    let result = project.clean_validate();
+    report_diagnostics(&mut project.diagnostics, format)?;
+    result?;
+    project.record_golden_output()
+}
+
+/// Writes profiles of all evaluated graphs into the directory
+fn write_profiles<IO>(project: &Project<IO>, dir: &Utf8Path) -> miette::Result<()> {
+    std::fs::create_dir_all(dir)
+        .into_diagnostic()
+        .with_context(|| format!("failed to create profiles directory at `{}`", dir))?;
+
+    for (path, file) in &project.files {
+        let ProjectFile::Graph(id) = file else {
+            continue;
+        };
+        let Some(profile) = project.debugger.graph(*id).and_then(|s| s.profile.as_ref()) else {
+            continue;
+        };
+
+        let name = path.as_str().replace(['/', '\\'], "_");
+
+        let mut report = String::new();
+        let mut folded = String::new();
+        profile.write_report(&mut report).into_diagnostic()?;
+        profile.write_folded(&mut folded).into_diagnostic()?;
+
+        for (ext, content) in [("txt", report), ("folded", folded)] {
+            let out = dir.join(format!("{name}.{ext}"));
+            std::fs::write(&out, content)
+                .into_diagnostic()
+                .with_context(|| format!("failed to write profile to `{}`", out))?;
+        }
+
+        info!(
+            graph=%path,
+            total_ms = profile.total_time().as_secs_f64() * 1000.0,
+            "Profile written"
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints all diagnostics from the context in the given format, draining them
+///
+/// Returns an error if any of the diagnostics is of the [DiagnosticLevel::Error] level
+fn report_diagnostics(
+    diagnostics: &mut DiagnosticContext,
+    format: OutputFormat,
+) -> miette::Result<()> {
+    let document = match format {
+        OutputFormat::Human => None,
+        OutputFormat::Json => Some(export::to_json(diagnostics)),
+        OutputFormat::Sarif => Some(export::to_sarif(
+            diagnostics,
+            env!("CARGO_BIN_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        )),
+    };
+    if let Some(document) = &document {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(document).into_diagnostic()?
+        );
+    }
+
+    let mut errors = 0usize;
+    let mut warnings = 0usize;
+
+    for (file, paths) in std::mem::take(&mut diagnostics.diagnostics) {
+        for (path, reports) in paths {
+            for diagnostic in reports {
+                let level = match diagnostic.level {
+                    DiagnosticLevel::Error => {
+                        errors += 1;
+                        "error"
+                    }
+                    DiagnosticLevel::Warning => {
+                        warnings += 1;
+                        "warning"
+                    }
+                    DiagnosticLevel::Info => "info",
+                    DiagnosticLevel::Debug => "debug",
+                    DiagnosticLevel::Trace => "trace",
+                };
+                let location = if path.is_empty() {
+                    format!("`{file}`")
+                } else {
+                    format!("`{file}` at `{path}`")
+                };
+                if document.is_none() {
+                    eprintln!(
+                        "{:?}",
+                        diagnostic.info.wrap_err(format!("{level} in {location}"))
+                    );
+                }
+            }
+        }
+    }
+
+    if errors > 0 {
+        bail!("project has {errors} error(s) and {warnings} warning(s)");
+    }
+
+    if warnings > 0 {
+        info!(warnings, "Project has warnings");
+    }
+
+    Ok(())
+}
+
+/// Helper for wrapping a code block to help with contextualizing errors
+/// Better editor support but slightly worse ergonomic than a macro
+#[inline(always)]
+pub(crate) fn m_try<T>(func: impl FnOnce() -> miette::Result<T>) -> miette::Result<T> {
+    func()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run, Command, OutputFormat};
+    use dbe_backend::project::io::{MemoryIO, ProjectIO};
+    use dbe_backend::project::{Project, ProjectConfig, TypesConfig, MODULE_FILE};
+    use dbe_backend::value::id::ETypeId;
+    use std::path::{Path, PathBuf};
+
+    const ROOT: &str = "/project";
+
+    const ITEM_TYPE: &str = r#"struct { number "Damage" min=0; }"#;
+
+    /// Adds `add` node summing 2 and 3 and writing the result to `out.dbevalue`
+    const GRAPH: &str = r#"{
+        "version": "V1",
+        "graph": {
+            "nodes": [
+                [0, { "color_scheme": null, "custom_title": null, "data": null, "id": "add", "open": true, "pos": { "x": 0.0, "y": 0.0 } }],
+                [1, { "color_scheme": null, "custom_title": null, "data": null, "id": "write_dbevalue", "open": true, "pos": { "x": 0.0, "y": 0.0 } }]
+            ],
+            "edges": [[{ "node": 0, "output": 0 }, { "node": 1, "input": 1 }]],
+            "inline_values": [
+                [{ "node": 0, "input": 0 }, 2],
+                [{ "node": 0, "input": 1 }, 3],
+                [{ "node": 1, "input": 0 }, "out"]
+            ]
+        }
+    }"#;
+
+    fn load(io: MemoryIO) -> Project<MemoryIO> {
+        let config = ProjectConfig {
+            types_config: TypesConfig {
+                import: ETypeId::parse("test:item").unwrap(),
+            },
+            emitted_dir: "emitted".into(),
+        };
+        let files = io.list_files().unwrap();
+        Project::from_files(ROOT, config, files, io).unwrap()
+    }
+
+    fn project(files: &[(&str, &str)]) -> Project<MemoryIO> {
+        let io = MemoryIO::new(PathBuf::from(ROOT));
+        io.insert(
+            Path::new("test.dbemodule").join(MODULE_FILE),
+            "namespace = \"test\"\nversion = \"1.0.0\"\n",
+        );
+        io.insert("test.dbemodule/types/item.kdl", ITEM_TYPE);
+        for (path, content) in files {
+            io.insert(path, *content);
+        }
+        load(io)
+    }
+
+    #[test]
+    fn check_should_fail_on_errors() {
+        let mut valid = project(&[("a.json", r#"{ "Damage": 1 }"#)]);
+        run(&mut valid, Command::Check, OutputFormat::Json).unwrap();
+
+        let mut invalid = project(&[("a.json", r#"{ "Damage": -1 }"#)]);
+        let err = run(&mut invalid, Command::Check, OutputFormat::Json).unwrap_err();
+        assert_eq!(err.to_string(), "project has 1 error(s) and 0 warning(s)");
+        // Nothing is written by the check
+        assert_eq!(
+            invalid.io.get("a.json").unwrap(),
+            br#"{ "Damage": -1 }"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn fmt_should_be_idempotent() {
+        let original = r#"{"Damage":   1}"#;
+        let mut project = project(&[("a.json", original)]);
+        run(&mut project, Command::Fmt, OutputFormat::Human).unwrap();
+        let formatted = project.io.get("a.json").unwrap();
+        assert_ne!(formatted, original.as_bytes());
+
+        let mut project = load(project.io);
+        run(&mut project, Command::Fmt, OutputFormat::Human).unwrap();
+        assert_eq!(project.io.get("a.json").unwrap(), formatted);
+    }
+
+    #[test]
+    fn build_should_write_generated_files() {
+        let mut project = project(&[("a.json", r#"{ "Damage": 1 }"#), ("graph.dbegraph", GRAPH)]);
+        assert!(project.io.get("out.dbevalue").is_none());
+        run(&mut project, Command::Build, OutputFormat::Human).unwrap();
+
+        let out: serde_json::Value =
+            serde_json::from_slice(&project.io.get("out.dbevalue").unwrap()).unwrap();
+        assert_eq!(out["value"], 5.0);
+    }
+
+    #[test]
+    fn build_should_not_write_projects_with_errors() {
+        let mut project = project(&[("a.json", r#"{ "Damage": -1 }"#), ("graph.dbegraph", GRAPH)]);
+        assert!(run(&mut project, Command::Build, OutputFormat::Human).is_err());
+        assert!(project.io.get("out.dbevalue").is_none());
+    }
+}
@@ -69,6 +69,9 @@ pub struct DbeApp {
     // Saving
     last_save_time: f64,
 
+    // Watching
+    last_watch_time: f64,
+
     check_for_updates_chan: (
         std::sync::mpsc::Sender<update_informer::Version>,
         std::sync::mpsc::Receiver<update_informer::Version>,
@@ -89,6 +92,9 @@ struct AppStorage {
 
 static ERROR_HAPPENED: AtomicBool = AtomicBool::new(false);
 
+/// Interval in seconds between checks for files changed on disk
+const WATCH_INTERVAL: f64 = 1.0;
+
 impl DbeApp {
     pub fn register_fonts(ctx: &Context) {
         let mut fonts = FontDefinitions::default();
@@ -138,6 +144,7 @@ impl DbeApp {
             info,
             show_settings_menu: false,
             last_save_time: 0.0,
+            last_watch_time: 0.0,
             check_for_updates_chan: std::sync::mpsc::channel(),
         }
     }
@@ -231,7 +238,13 @@ impl DbeApp {
             let time = ctx.input(|i| i.time);
             project
                 .history
-                .set_time(&project.files, &project.graphs, time);
+                .set_time(&project.files, &project.graphs, time)
+                .unwrap_or_else(report_error);
+
+            if self.settings.watch_files && time - self.last_watch_time > WATCH_INTERVAL {
+                self.last_watch_time = time;
+                self.reload_changed_files();
+            }
 
             if self.settings.autosave
                 && time - self.last_save_time > self.settings.autosave_interval as f64
@@ -300,7 +313,34 @@ impl DbeApp {
                 self.undo_buttons(ui);
                 if let Some(project) = &mut self.project {
                     if ui.button("Run Graphs").clicked() {
-                        project.clean_validate().unwrap_or_else(report_error);
+                        project.incremental_validate().unwrap_or_else(report_error);
+                    }
+                    if ui
+                        .button("Run Tests")
+                        .on_hover_text("Runs test cases of all node group test files")
+                        .clicked()
+                    {
+                        match project.run_graph_tests() {
+                            Ok(report) => {
+                                let text = format!(
+                                    "Tests finished: {} passed, {} failed, {} broken test files",
+                                    report.passed, report.failed, report.broken_files
+                                );
+                                self.toasts.push(Toast {
+                                    kind: if report.is_success() {
+                                        ToastKind::Success
+                                    } else {
+                                        ToastKind::Error
+                                    },
+                                    text: text.into(),
+                                    options: ToastOptions::default()
+                                        .duration_in_seconds(3.0)
+                                        .show_progress(true),
+                                    style: Default::default(),
+                                });
+                            }
+                            Err(err) => report_error(err),
+                        }
                     }
                 }
             });
@@ -532,6 +572,9 @@ impl DbeApp {
         self.remember_last_project(path.clone());
         match Project::from_path(&path) {
             Ok(data) => {
+                if let Err(err) = data.io.watch() {
+                    report_error(err);
+                }
                 self.project = Some(data);
                 info!(path=%path.display(), "Project loaded successfully");
                 self.toasts.push(Toast {
@@ -561,6 +604,66 @@ impl DbeApp {
 }
 
 impl DbeApp {
+    fn reload_changed_files(&mut self) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let report = match project.reload_changed_files() {
+            Ok(report) => report,
+            Err(err) => {
+                report_error(err);
+                return;
+            }
+        };
+
+        for err in report.errors {
+            report_error(err);
+        }
+
+        let reloaded = report.reloaded.len() + report.removed.len();
+        if reloaded > 0 || !report.rebuilt_types.is_empty() {
+            self.toasts.push(Toast {
+                kind: ToastKind::Info,
+                text: format!(
+                    "Reloaded {} file(s) and {} type(s) changed on disk",
+                    reloaded,
+                    report.rebuilt_types.len()
+                )
+                .into(),
+                options: ToastOptions::default()
+                    .duration_in_seconds(3.0)
+                    .show_progress(true),
+                style: Default::default(),
+            });
+        }
+
+        if !report.conflicts.is_empty() {
+            self.toasts.push(Toast {
+                kind: ToastKind::Warning,
+                text: format!(
+                    "Files with unsaved changes were changed on disk: {}\nResolve conflicts in the Diagnostics panel",
+                    report.conflicts.iter().join(", ")
+                )
+                .into(),
+                options: ToastOptions::default().duration(None).show_progress(true),
+                style: Default::default(),
+            });
+        }
+
+        if !report.requires_reopen.is_empty() {
+            self.toasts.push(Toast {
+                kind: ToastKind::Info,
+                text: format!(
+                    "Reopen the project to apply changes to: {}",
+                    report.requires_reopen.iter().join(", ")
+                )
+                .into(),
+                options: ToastOptions::default().duration(None).show_progress(true),
+                style: Default::default(),
+            });
+        }
+    }
+
     fn undo(&mut self) {
         let Some(project) = &mut self.project else {
             report_error(miette!("Cannot undo: no project loaded"));
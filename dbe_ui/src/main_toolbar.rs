@@ -6,6 +6,8 @@ use crate::DbeApp;
 use diagnostics_list::diagnostics_tab;
 use egui::Ui;
 use file_tree::file_tab;
+use golden_output::golden_output_tab;
+use id_usages::id_usages_tab;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
@@ -13,7 +15,9 @@ pub mod colors;
 mod diagnostics_list;
 pub mod docs;
 mod file_tree;
+mod golden_output;
 pub mod history;
+mod id_usages;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ToolPanel {
@@ -23,6 +27,8 @@ pub enum ToolPanel {
     Theme,
     Docs,
     History,
+    IdUsages,
+    GoldenOutput,
 }
 
 pub struct ToolPanelViewer<'a>(pub &'a mut DbeApp);
@@ -38,6 +44,8 @@ impl ToolbarViewer for ToolPanelViewer<'_> {
             ToolPanel::Theme => "Theme".into(),
             ToolPanel::Docs => "Docs".into(),
             ToolPanel::History => "Undo History".into(),
+            ToolPanel::IdUsages => "ID Usages".into(),
+            ToolPanel::GoldenOutput => "Golden Output".into(),
         }
     }
 
@@ -61,6 +69,8 @@ impl ToolbarViewer for ToolPanelViewer<'_> {
             ToolPanel::History => {
                 history::history_tab(ui, self.0);
             }
+            ToolPanel::IdUsages => id_usages_tab(ui, self.0),
+            ToolPanel::GoldenOutput => golden_output_tab(ui, self.0),
         }
     }
 
@@ -79,6 +89,8 @@ impl ToolbarViewer for ToolPanelViewer<'_> {
                 ToolPanel::Theme,
                 ToolPanel::Docs,
                 ToolPanel::History,
+                ToolPanel::IdUsages,
+                ToolPanel::GoldenOutput,
             ],
         )
     }
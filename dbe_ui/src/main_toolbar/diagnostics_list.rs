@@ -23,6 +23,39 @@ pub fn diagnostics_tab(ui: &mut Ui, app: &mut DbeApp) {
             project.clean_validate().unwrap_or_else(report_error);
         }
         btn_res.on_hover_text("Reruns all graphs and checks all files for errors");
+        if !project.conflicts.is_empty() {
+            ui.separator();
+            ui.label(
+                RichText::new("Changed on disk while having unsaved changes")
+                    .color(ui.style().visuals.warn_fg_color),
+            );
+            let mut resolved = None;
+            for path in &project.conflicts {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Keep Mine")
+                        .on_hover_text("Keep in-memory version, it will overwrite the file on save")
+                        .clicked()
+                    {
+                        resolved = Some((path.clone(), true));
+                    }
+                    if ui
+                        .button("Load From Disk")
+                        .on_hover_text("Discard in-memory changes and load the file from the disk")
+                        .clicked()
+                    {
+                        resolved = Some((path.clone(), false));
+                    }
+                    ui.label(path.as_str());
+                });
+            }
+            if let Some((path, keep_memory)) = resolved {
+                project
+                    .resolve_conflict(&path, keep_memory)
+                    .unwrap_or_else(report_error);
+            }
+            ui.separator();
+        }
         egui::ScrollArea::both()
             .auto_shrink(tweak!(false))
             .show(ui, |ui| {
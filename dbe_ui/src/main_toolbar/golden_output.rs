@@ -0,0 +1,170 @@
+use crate::error::report_error;
+use crate::DbeApp;
+use camino::Utf8PathBuf;
+use dbe_backend::project::golden::{FileDiff, GOLDEN_OUTPUT_FILE};
+use egui::{Grid, Label, RichText, Ui, Widget};
+use itertools::Itertools;
+
+enum Action {
+    Accept(Utf8PathBuf),
+    Reject(Utf8PathBuf),
+    Open(Utf8PathBuf),
+}
+
+pub fn golden_output_tab(ui: &mut Ui, app: &mut DbeApp) {
+    let Some(project) = &mut app.project else {
+        ui.vertical_centered_justified(|ui| {
+            ui.label("No project is open");
+        });
+        return;
+    };
+
+    ui.label("Golden Output");
+
+    if !project.golden.is_enabled() {
+        ui.label(format!(
+            "Records all generated files into `{}`, and reports every difference from it on the following evaluations",
+            GOLDEN_OUTPUT_FILE
+        ));
+        if ui
+            .button("Record Snapshot")
+            .on_hover_text("Runs all graphs and records the generated files")
+            .clicked()
+        {
+            project
+                .clean_validate()
+                .and_then(|_| project.record_golden_output())
+                .unwrap_or_else(report_error);
+        }
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        if ui
+            .button("Record Again")
+            .on_hover_text("Runs all graphs and replaces the snapshot with the generated files")
+            .clicked()
+        {
+            project
+                .clean_validate()
+                .and_then(|_| project.record_golden_output())
+                .unwrap_or_else(report_error);
+        }
+        if ui
+            .button("Disable")
+            .on_hover_text("Deletes the snapshot")
+            .clicked()
+        {
+            project.disable_golden_output().unwrap_or_else(report_error);
+        }
+    });
+
+    ui.separator();
+
+    if project.golden.diff().is_empty() {
+        ui.label("Generated files match the snapshot");
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} generated files differ from the snapshot",
+            project.golden.diff().len()
+        ));
+        if ui
+            .button("Accept All")
+            .on_hover_text("Updates the snapshot with all generated files")
+            .clicked()
+        {
+            project
+                .accept_all_golden_diffs()
+                .unwrap_or_else(report_error);
+        }
+    });
+
+    let mut action = None;
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+        for (path, diff) in project.golden.diff() {
+            let rejected = project.golden.is_rejected(path);
+            let state = egui::collapsing_header::CollapsingState::load_with_default_open(
+                ui.ctx(),
+                ui.id().with(path),
+                false,
+            );
+            state
+                .show_header(ui, |ui| {
+                    if ui.button("Accept").clicked() {
+                        action = Some(Action::Accept(path.clone()));
+                    }
+                    if ui
+                        .add_enabled(!rejected, egui::Button::new("Reject"))
+                        .on_hover_text("Reports the difference as an error until the generated file matches the snapshot again")
+                        .clicked()
+                    {
+                        action = Some(Action::Reject(path.clone()));
+                    }
+                    if ui.button("Open").clicked() {
+                        action = Some(Action::Open(path.clone()));
+                    }
+                    let style = ui.ctx().style();
+                    let color = if rejected {
+                        style.visuals.error_fg_color
+                    } else {
+                        style.visuals.warn_fg_color
+                    };
+                    let kind = match diff {
+                        FileDiff::Added(_) => "Added".to_string(),
+                        FileDiff::Removed => "Removed".to_string(),
+                        FileDiff::Changed { changes, .. } => format!("{} changes", changes.len()),
+                    };
+                    Label::new(RichText::new(kind).color(color))
+                        .selectable(false)
+                        .ui(ui);
+                    ui.label(path.to_string());
+                })
+                .body(|ui| match diff {
+                    FileDiff::Added(_) => {
+                        ui.label("File is not present in the snapshot");
+                    }
+                    FileDiff::Removed => {
+                        ui.label("File is present in the snapshot, but was not generated");
+                    }
+                    FileDiff::Changed { changes, .. } => {
+                        Grid::new(path).striped(true).show(ui, |ui| {
+                            ui.label("Field");
+                            ui.label("Recorded");
+                            ui.label("Generated");
+                            ui.end_row();
+                            for change in changes {
+                                ui.label(change.path.iter().join(""));
+                                for value in [&change.recorded, &change.current] {
+                                    match value {
+                                        None => ui.weak("<missing>"),
+                                        Some(value) => ui.label(value.to_string()),
+                                    };
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    }
+                });
+        }
+    });
+
+    match action {
+        None => {}
+        Some(Action::Accept(path)) => {
+            project
+                .accept_golden_diff(&path)
+                .unwrap_or_else(report_error);
+        }
+        Some(Action::Reject(path)) => {
+            project
+                .reject_golden_diff(&path)
+                .unwrap_or_else(report_error);
+        }
+        Some(Action::Open(path)) => app.open_tab_for(ui.ctx(), path),
+    }
+}
@@ -10,6 +10,14 @@ pub fn history_tab(ui: &mut Ui, app: &mut DbeApp) {
     }
 
     fn text_for_snapshot(snapshot: &FileSnapshot) -> String {
+        let text = text_for_kind(snapshot);
+        match snapshot.linked_paths().len() {
+            0 => text,
+            linked => format!("{} and {} more files", text, linked),
+        }
+    }
+
+    fn text_for_kind(snapshot: &FileSnapshot) -> String {
         match snapshot.kind {
             SnapshotKind::Change => {
                 format!(
@@ -0,0 +1,160 @@
+use crate::error::report_error;
+use crate::DbeApp;
+use camino::Utf8PathBuf;
+use dbe_backend::validation::ids::numeric::{split_location, IdUsages, NumericIDRegistry};
+use dbe_backend::value::ENumber;
+use egui::{ComboBox, DragValue, RichText, Ui};
+use egui_hooks::UseHookExt;
+use inline_tweak::tweak;
+use itertools::Itertools;
+use ustr::Ustr;
+
+pub fn id_usages_tab(ui: &mut Ui, app: &mut DbeApp) {
+    let Some(project) = &mut app.project else {
+        ui.vertical_centered_justified(|ui| {
+            ui.label("No project is open");
+        });
+        return;
+    };
+
+    let mut ty = ui.use_state(|| None::<Ustr>, ()).into_var();
+    let mut id = ui.use_state(|| 0.0f64, ()).into_var();
+    let mut new_id = ui.use_state(|| 0.0f64, ()).into_var();
+    let mut range = ui.use_state(|| (0i64, 0i64), ()).into_var();
+    let mut source = ui.use_state(|| (0i64, 0i64), ()).into_var();
+    let mut target = ui.use_state(|| (0i64, 0i64), ()).into_var();
+
+    let ids = NumericIDRegistry::of(&project.registry);
+    let types = ids.id_types();
+
+    ui.horizontal(|ui| {
+        ComboBox::new("id_usages_type", "")
+            .selected_text(ty.map_or("Select ID type", |ty| ty.as_str()))
+            .show_ui(ui, |ui| {
+                for item in &types {
+                    ui.selectable_value(&mut *ty, Some(*item), item.as_str());
+                }
+            });
+        ui.add(DragValue::new(&mut *id));
+    })
+    .response
+    .on_hover_text("Type and number of the ID to look up");
+
+    let Some(selected) = *ty else {
+        return;
+    };
+
+    let usages = match ids.usages(selected, ENumber::from(*id)) {
+        Ok(usages) => usages,
+        Err(err) => {
+            report_error(err);
+            IdUsages::default()
+        }
+    };
+
+    if usages.definitions.len() == 1 {
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut *new_id));
+            let btn = ui.add_enabled(*new_id != *id, egui::Button::new("Change ID"));
+            if btn.clicked() {
+                match project.change_numeric_id(
+                    selected,
+                    ENumber::from(*id),
+                    ENumber::from(*new_id),
+                ) {
+                    Ok(_) => *id = *new_id,
+                    Err(err) => report_error(err),
+                }
+            }
+            btn.on_hover_text("Changes the ID and updates all references to it");
+        });
+    }
+
+    ui.collapsing("Bulk operations", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("IDs from");
+            ui.add(DragValue::new(&mut range.0));
+            ui.label("to");
+            ui.add(DragValue::new(&mut range.1));
+            let btn = ui.button("Compact");
+            if btn.clicked() {
+                project
+                    .compact_numeric_ids(selected, range.0..=range.1)
+                    .map(|_| ())
+                    .unwrap_or_else(report_error);
+            }
+            btn.on_hover_text("Moves IDs within the range to the lowest free IDs of the range");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Move IDs from");
+            ui.add(DragValue::new(&mut source.0));
+            ui.label("to");
+            ui.add(DragValue::new(&mut source.1));
+        });
+        ui.horizontal(|ui| {
+            ui.label("into");
+            ui.add(DragValue::new(&mut target.0));
+            ui.label("to");
+            ui.add(DragValue::new(&mut target.1));
+            let btn = ui.button("Move");
+            if btn.clicked() {
+                let moved = NumericIDRegistry::of(&project.registry)
+                    .defined_ids(selected)
+                    .into_iter()
+                    .filter(|id| (source.0 as f64..=source.1 as f64).contains(&id.0))
+                    .collect_vec();
+                project
+                    .move_numeric_ids(selected, &moved, target.0..=target.1)
+                    .map(|_| ())
+                    .unwrap_or_else(report_error);
+            }
+            btn.on_hover_text(
+                "Moves IDs from the source range to the free IDs of the target range",
+            );
+        });
+    });
+
+    let mut open_file = None;
+
+    egui::ScrollArea::both()
+        .auto_shrink(tweak!(false))
+        .show(ui, |ui| {
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+
+            ui.label(RichText::new("Definitions").strong());
+            if usages.definitions.is_empty() {
+                ui.label("ID is not defined");
+            } else if usages.definitions.len() > 1 {
+                ui.label(
+                    RichText::new("ID is defined multiple times")
+                        .color(ui.style().visuals.error_fg_color),
+                );
+            }
+            for location in &usages.definitions {
+                location_row(ui, location, &mut open_file);
+            }
+
+            ui.separator();
+            ui.label(RichText::new(format!("References ({})", usages.references.len())).strong());
+            for location in &usages.references {
+                location_row(ui, location, &mut open_file);
+            }
+        });
+
+    if let Some(path) = open_file {
+        app.open_tab_for(ui.ctx(), path);
+    }
+}
+
+fn location_row(ui: &mut Ui, location: &str, open_file: &mut Option<Utf8PathBuf>) {
+    let (file, path) = split_location(location);
+    ui.horizontal(|ui| {
+        if ui.button("Open").clicked() {
+            *open_file = Some(Utf8PathBuf::from(file));
+        }
+        ui.label(file);
+        if !path.is_empty() {
+            ui.label(RichText::new(path).weak());
+        }
+    });
+}
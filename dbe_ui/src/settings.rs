@@ -12,6 +12,8 @@ pub struct AppSettings {
     pub autosave_interval: u32,
     #[serde(default = "d_bool::<true>")]
     pub check_for_updates: bool,
+    #[serde(default = "d_bool::<true>")]
+    pub watch_files: bool,
 }
 
 impl AppSettings {
@@ -27,6 +29,9 @@ impl AppSettings {
         toggle_button_label(ui, &mut self.exit_confirmation, "Exit Confirmation")
             .on_hover_text("Show exit confirmation dialog when a project is loaded");
 
+        toggle_button_label(ui, &mut self.watch_files, "Watch Files")
+            .on_hover_text("Reload project files when they are changed by other programs");
+
         toggle_button_label(ui, &mut self.autosave, "Autosave")
             .on_hover_text("Automatically save the project at a set interval");
 
@@ -55,6 +60,7 @@ impl Default for AppSettings {
             autosave: false,
             autosave_interval: 60,
             check_for_updates: true,
+            watch_files: true,
         }
     }
 }
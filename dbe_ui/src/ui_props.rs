@@ -1,6 +1,5 @@
 use dbe_backend::etype::econst::ETypeConst;
 use dbe_backend::extra_properties;
-use dbe_backend::value::ENumber;
 use egui::Color32;
 use miette::{bail, Context, IntoDiagnostic};
 use ustr::Ustr;
@@ -29,8 +28,6 @@ extra_properties! {
     pub prop<field> editor: Ustr;
     pub prop<object> editor: Ustr;
     pub prop<field> kind: ETypeConst;
-    pub prop<field> min: ENumber;
-    pub prop<field> max: ENumber;
     pub prop<field> logarithmic: bool;
     pub prop<field> multiline: bool;
     pub prop<field> show_file_path: bool;
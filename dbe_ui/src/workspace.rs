@@ -228,6 +228,22 @@ impl<Io: ProjectIO> TabViewer for WorkspaceTabViewer<'_, Io> {
         //     .ensure_file_state(&self.0.files, &self.0.graphs, &tab)
         //     .unwrap_or_else(report_error);
 
+        let standalone_graphs = if matches!(self.0.files.get(tab), Some(ProjectFile::Graph(_))) {
+            self.0
+                .files
+                .iter()
+                .filter_map(|(path, file)| {
+                    let ProjectFile::Graph(id) = file else {
+                        return None;
+                    };
+                    let graph = self.0.graphs.graphs.get(id)?;
+                    (!graph.is_node_group).then(|| (*id, path.to_string()))
+                })
+                .collect_vec()
+        } else {
+            vec![]
+        };
+
         let Some(data) = self.0.files.get_mut(tab) else {
             ui.centered_and_justified(|ui| {
                 ui.label(format!("!!INTERNAL ERROR!! the file {} is missing", tab));
@@ -297,6 +313,13 @@ impl<Io: ProjectIO> TabViewer for WorkspaceTabViewer<'_, Io> {
                     );
                 }
                 ProjectFile::Graph(id) => {
+                    let trace_outdated = self
+                        .0
+                        .debugger
+                        .graph(*id)
+                        .and_then(|state| state.trace.as_ref())
+                        .is_some_and(|trace| trace.graph_hash != self.0.graphs.contents_hash(*id));
+
                     let Some(graph) = self.0.graphs.graphs.get_mut(id) else {
                         ui.centered_and_justified(|ui| {
                             ui.label(format!("!!INTERNAL ERROR!! the graph {} is missing", id));
@@ -319,6 +342,8 @@ impl<Io: ProjectIO> TabViewer for WorkspaceTabViewer<'_, Io> {
                         &[
                             GraphTab::General,
                             GraphTab::Node,
+                            GraphTab::Trace,
+                            GraphTab::Profile,
                             #[cfg(debug_assertions)]
                             GraphTab::Debug,
                         ],
@@ -331,6 +356,9 @@ impl<Io: ProjectIO> TabViewer for WorkspaceTabViewer<'_, Io> {
                             selected_nodes: &selected_nodes,
                             registry: &self.0.registry,
                             docs: &self.0.docs,
+                            standalone_graphs: &standalone_graphs,
+                            debug_state: self.0.debugger.graph_mut(*id),
+                            trace_outdated,
                         },
                     );
 
@@ -345,6 +373,7 @@ impl<Io: ProjectIO> TabViewer for WorkspaceTabViewer<'_, Io> {
                             ..Default::default()
                         })
                         .show_inside(ui, |ui| {
+                            let debug_state = self.0.debugger.graph(*id);
                             self.0.graphs.edit_graph(*id, |graph, graphs| {
                                 let outputs = &mut None;
                                 let (mut ctx, snarl) = PartialGraphEditingContext::from_graph(
@@ -378,6 +407,7 @@ impl<Io: ProjectIO> TabViewer for WorkspaceTabViewer<'_, Io> {
                                     ctx,
                                     diagnostics.as_readonly(),
                                     rects.deref_mut(),
+                                    debug_state,
                                 );
 
                                 let style = SnarlStyle {
@@ -5,6 +5,7 @@ use crate::workspace::editors::consts::ConstEditor;
 use crate::workspace::editors::enum_flags::EnumFlagsEditor;
 use crate::workspace::editors::enums::EnumEditor;
 use crate::workspace::editors::errors::{ErrorEditor, ErrorProps};
+use crate::workspace::editors::id::IdEditor;
 use crate::workspace::editors::id_ref::IdRefEditor;
 use crate::workspace::editors::map::MapEditor;
 use crate::workspace::editors::number::NumberEditor;
@@ -12,7 +13,6 @@ use crate::workspace::editors::rgb::RgbEditor;
 use crate::workspace::editors::string::StringEditor;
 use crate::workspace::editors::structs::StructEditor;
 use crate::workspace::editors::utils::EditorSize;
-use crate::workspace::editors::wrapped::WrappedEditor;
 use ::utils::map::HashMap;
 use dbe_backend::diagnostic::context::DiagnosticContextRef;
 use dbe_backend::etype::econst::ETypeConst;
@@ -42,6 +42,7 @@ mod consts;
 mod enum_flags;
 mod enums;
 mod errors;
+mod id;
 mod id_ref;
 mod list;
 mod map;
@@ -67,10 +68,7 @@ fn default_editors() -> impl Iterator<Item = (Ustr, Box<dyn Editor>)> {
         ("list".into(), Box::new(ListEditor)),
         ("map".into(), Box::new(MapEditor)),
         ("enum_flags".into(), Box::new(EnumFlagsEditor)),
-        (
-            "ids/numeric".into(),
-            Box::new(WrappedEditor::new(NumberEditor::new(false), "id".into())),
-        ),
+        ("ids/numeric".into(), Box::new(IdEditor)),
         ("ids/numeric_ref".into(), Box::new(IdRefEditor)),
         // TODO: proper combobox editors
         ("eh:image".into(), Box::new(StringEditor)),
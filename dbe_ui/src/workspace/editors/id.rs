@@ -0,0 +1,89 @@
+use crate::error::report_error;
+use crate::workspace::editors::number::NumberEditor;
+use crate::workspace::editors::utils::{unsupported, EditorSize};
+use crate::workspace::editors::wrapped::WrappedEditor;
+use crate::workspace::editors::{DynProps, Editor, EditorContext, EditorResponse};
+use dbe_backend::diagnostic::context::DiagnosticContextRef;
+use dbe_backend::etype::eitem::EItemInfo;
+use dbe_backend::etype::EDataType;
+use dbe_backend::registry::ETypesRegistry;
+use dbe_backend::validation::ids::numeric::NumericIDRegistry;
+use dbe_backend::value::{ENumber, EValue};
+use egui::Ui;
+use miette::miette;
+
+/// Editor for numeric ID definitions, with an option to assign the next free
+/// ID of the type
+#[derive(Debug)]
+pub struct IdEditor;
+
+impl IdEditor {
+    fn inner() -> WrappedEditor<NumberEditor> {
+        WrappedEditor::new(NumberEditor::new(false), "id".into())
+    }
+}
+
+impl Editor for IdEditor {
+    fn props(
+        &self,
+        reg: &ETypesRegistry,
+        item: Option<&EItemInfo>,
+        object_props: DynProps,
+    ) -> miette::Result<DynProps> {
+        Self::inner().props(reg, item, object_props)
+    }
+
+    fn size(&self, props: &DynProps) -> EditorSize {
+        Self::inner().size(props)
+    }
+
+    fn edit(
+        &self,
+        ui: &mut Ui,
+        ctx: EditorContext,
+        diagnostics: DiagnosticContextRef,
+        field_name: &str,
+        value: &mut EValue,
+        props: &DynProps,
+    ) -> EditorResponse {
+        let EValue::Struct { ident, .. } = value else {
+            unsupported!(ui, field_name, value, self);
+        };
+
+        let ty = EDataType::Object { ident: *ident };
+        let registry = ctx.registry;
+
+        ui.horizontal(|ui| {
+            let mut res = Self::inner().edit(ui, ctx, diagnostics, field_name, value, props);
+
+            let btn = ui.small_button("Next free");
+            if btn.clicked() {
+                match next_free_id(registry, ty, value) {
+                    Ok(id) => {
+                        if let EValue::Struct { fields, .. } = value {
+                            fields.insert("id".into(), id.into());
+                            res.changed = true;
+                        }
+                    }
+                    Err(err) => report_error(err),
+                }
+            }
+            btn.on_hover_text("Assign the first free ID after the current one");
+
+            res
+        })
+        .inner
+    }
+}
+
+fn next_free_id(registry: &ETypesRegistry, ty: EDataType, value: &EValue) -> miette::Result<f64> {
+    let ids = NumericIDRegistry::of(registry);
+    let id_ty = ids.id_type_of(ty)?;
+    let current: ENumber = *value.try_get_field("id")?.try_as_number()?;
+
+    let start = current.0.max(0.0).ceil() as i64;
+    ids.free_ids(id_ty, start..=i64::MAX)?
+        .find(|id| *id != current)
+        .map(|id| id.0)
+        .ok_or_else(|| miette!("no free IDs of type `{}` left", id_ty))
+}
@@ -1,10 +1,11 @@
-use crate::ui_props::{PROP_FIELD_LOGARITHMIC, PROP_FIELD_MAX, PROP_FIELD_MIN};
+use crate::ui_props::PROP_FIELD_LOGARITHMIC;
 use crate::workspace::editors::utils::{labeled_field, unsupported, EditorSize};
 use crate::workspace::editors::{
     cast_props, DynProps, Editor, EditorContext, EditorProps, EditorResponse,
 };
 use dbe_backend::diagnostic::context::DiagnosticContextRef;
 use dbe_backend::etype::eitem::EItemInfo;
+use dbe_backend::etype::property::default_properties::{PROP_FIELD_MAX, PROP_FIELD_MIN};
 use dbe_backend::registry::ETypesRegistry;
 use dbe_backend::value::{ENumber, EValue};
 use egui::{DragValue, Slider, Ui};
@@ -15,6 +15,7 @@ use dbe_backend::graph::node::commands::SnarlCommands;
 use dbe_backend::graph::node::creation::NodeCombo;
 use dbe_backend::graph::node::ports::NodePortType;
 use dbe_backend::graph::node::SnarlNode;
+use dbe_backend::project::debugger::GraphDebugState;
 use dbe_backend::registry::ETypesRegistry;
 use egui::epaint::PathShape;
 use egui::{Color32, Frame, Painter, Pos2, Rect, ScrollArea, Stroke, Style, Ui};
@@ -39,6 +40,8 @@ pub struct GraphViewer<'a> {
     pub ctx: PartialGraphEditingContext<'a>,
     pub diagnostics: DiagnosticContextRef<'a>,
     pub node_rects: &'a mut NodeRects,
+    /// Breakpoints and trace of the graph, used for highlighting nodes
+    pub debug_state: Option<&'a GraphDebugState>,
     commands: SnarlCommands,
 }
 
@@ -47,12 +50,14 @@ impl<'a> GraphViewer<'a> {
         ctx: PartialGraphEditingContext<'a>,
         diagnostics: DiagnosticContextRef<'a>,
         node_rects: &'a mut NodeRects,
+        debug_state: Option<&'a GraphDebugState>,
     ) -> Self {
         Self {
             ctx,
             diagnostics,
             commands: Default::default(),
             node_rects,
+            debug_state,
         }
     }
 }
@@ -97,6 +102,19 @@ impl SnarlViewer<SnarlNode> for GraphViewer<'_> {
             default = default.fill(scheme.theme.tokens.subtle_background());
         }
 
+        if let Some(state) = self.debug_state {
+            let inspected = state
+                .trace
+                .as_ref()
+                .and_then(|t| t.trace.events().get(t.position?))
+                .is_some_and(|event| event.node == node);
+            if inspected {
+                default = default.stroke(Stroke::new(tweak!(3.0), Color32::YELLOW));
+            } else if state.breakpoints.contains(&node) {
+                default = default.stroke(Stroke::new(tweak!(2.0), Color32::RED));
+            }
+        }
+
         default
     }
 
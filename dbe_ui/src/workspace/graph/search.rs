@@ -48,6 +48,15 @@ pub fn category_tree(
                     .push(NodeCombo::Subgraph(*id, graph.name.clone()));
             }
         }
+
+        for (id, script) in &graphs.scripts.scripts {
+            for category in &script.categories {
+                categories
+                    .entry(category.clone())
+                    .or_default()
+                    .push(NodeCombo::Script(*id, script.name.clone()));
+            }
+        }
     }
 
     for obj in registry
@@ -116,6 +125,16 @@ impl GraphSearch {
             {
                 push_to_injector(&injector, node);
             }
+
+            for node in graphs
+                .scripts
+                .scripts
+                .iter()
+                .map(|x| NodeCombo::Script(*x.0, x.1.name.clone()))
+                .filter(|x| filter(x))
+            {
+                push_to_injector(&injector, node);
+            }
         }
 
         let objects = registry
@@ -3,6 +3,9 @@ use crate::widgets::rotated_label::RotLabelDirection;
 use crate::widgets::toggle_button::toggle_button_label;
 use crate::workspace::graph::toolbar::edit_inputs::edit_inputs_outputs;
 use crate::workspace::graph::toolbar::node_editor::edit_node_properties;
+use crate::workspace::graph::toolbar::profile::show_profile;
+use crate::workspace::graph::toolbar::trace::edit_trace;
+use dbe_backend::project::debugger::GraphDebugState;
 use dbe_backend::project::docs::Docs;
 use dbe_backend::project::project_graph::{EvaluationStage, ProjectGraph};
 use dbe_backend::registry::ETypesRegistry;
@@ -12,15 +15,24 @@ use list_edit::list_editor;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use strum::VariantArray;
+use uuid::Uuid;
 
 pub mod edit_inputs;
 pub mod node_editor;
+pub mod profile;
+pub mod trace;
 
 pub struct GraphToolbarViewer<'a> {
     pub graph: &'a mut ProjectGraph,
     pub selected_nodes: &'a [NodeId],
     pub registry: &'a ETypesRegistry,
     pub docs: &'a Docs,
+    /// IDs and paths of standalone project graphs, used for the
+    /// dependencies selection
+    pub standalone_graphs: &'a [(Uuid, String)],
+    pub debug_state: &'a mut GraphDebugState,
+    /// Whenever the graph was changed since the trace was recorded
+    pub trace_outdated: bool,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -28,6 +40,8 @@ pub enum GraphTab {
     General,
     Debug,
     Node,
+    Trace,
+    Profile,
 }
 
 impl ToolbarViewer for GraphToolbarViewer<'_> {
@@ -38,6 +52,8 @@ impl ToolbarViewer for GraphToolbarViewer<'_> {
             GraphTab::General => "General".into(),
             GraphTab::Debug => "Debug".into(),
             GraphTab::Node => "Node".into(),
+            GraphTab::Trace => "Trace".into(),
+            GraphTab::Profile => "Profile".into(),
         }
     }
 
@@ -73,6 +89,32 @@ impl ToolbarViewer for GraphToolbarViewer<'_> {
                         - Validation: intended for validation of the data produced in the main stages.\
                         "
                     );
+
+                    let res = CollapsingHeader::new("Dependencies")
+                        .default_open(!self.graph.dependencies.is_empty())
+                        .show(ui, |ui| {
+                            for (id, path) in self.standalone_graphs {
+                                if *id == self.graph.id {
+                                    continue;
+                                }
+                                let mut checked = self.graph.dependencies.contains(id);
+                                if ui.checkbox(&mut checked, path.as_str()).changed() {
+                                    if checked {
+                                        self.graph.dependencies.push(*id);
+                                    } else {
+                                        self.graph.dependencies.retain(|dep| dep != id);
+                                    }
+                                }
+                            }
+                        });
+
+                    res.header_response.on_hover_text(
+                        "Graphs of the same stage that should be evaluated before this graph.\n\
+                        Files and transient values emitted by the dependencies are visible to this graph.\n\
+                        Dependencies on transient values with constant keys are detected automatically, \
+                        and dependencies on emitted files are picked up from the previous evaluation.\
+                        "
+                    );
                 });
 
                 ui.add_enabled_ui(self.graph.is_node_group, |ui| {
@@ -123,6 +165,28 @@ impl ToolbarViewer for GraphToolbarViewer<'_> {
                     self.selected_nodes,
                 );
             }
+            GraphTab::Trace => {
+                if self.graph.is_node_group {
+                    ui.label("Node groups can't be traced on their own");
+                    return;
+                }
+                edit_trace(
+                    ui,
+                    self.registry,
+                    self.docs,
+                    self.graph.graph_mut(),
+                    self.debug_state,
+                    self.selected_nodes,
+                    self.trace_outdated,
+                );
+            }
+            GraphTab::Profile => {
+                if self.graph.is_node_group {
+                    ui.label("Node groups are profiled as a part of the graphs that use them");
+                    return;
+                }
+                show_profile(ui, self.debug_state);
+            }
         };
     }
 }
@@ -0,0 +1,139 @@
+use crate::widgets::toggle_button::toggle_button_label;
+use dbe_backend::graph::execution::profiler::{ExecutionProfile, ProfileEntry};
+use dbe_backend::project::debugger::GraphDebugState;
+use egui::{Grid, ScrollArea, Ui};
+use egui_hooks::UseHookExt;
+use std::cmp::Ordering;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ProfileColumn {
+    Node,
+    Calls,
+    Total,
+    SelfTime,
+}
+
+impl ProfileColumn {
+    fn title(&self) -> &'static str {
+        match self {
+            ProfileColumn::Node => "Node",
+            ProfileColumn::Calls => "Calls",
+            ProfileColumn::Total => "Total, ms",
+            ProfileColumn::SelfTime => "Self, ms",
+        }
+    }
+
+    fn compare(&self, profile: &ExecutionProfile, a: usize, b: usize) -> Ordering {
+        let entries = profile.entries();
+        let (ea, eb) = (&entries[a], &entries[b]);
+        match self {
+            ProfileColumn::Node => profile.entry_path(a).cmp(&profile.entry_path(b)),
+            ProfileColumn::Calls => ea.calls.cmp(&eb.calls),
+            ProfileColumn::Total => ea.total.cmp(&eb.total),
+            ProfileColumn::SelfTime => ea.self_time.cmp(&eb.self_time),
+        }
+    }
+}
+
+pub fn show_profile(ui: &mut Ui, state: &mut GraphDebugState) {
+    toggle_button_label(ui, &mut state.profiled, "Collect Profile").on_hover_text(
+        "When enabled, execution time of every node of this graph is measured during the project evaluation.\n\
+        Profiled graphs are always evaluated, even if their inputs didn't change.",
+    );
+
+    ui.separator();
+
+    let Some(profile) = &state.profile else {
+        ui.label("No profile was collected yet");
+        ui.label("Enable profiling and save the project to collect one");
+        return;
+    };
+
+    ui.label(format!(
+        "Total time: {:.3} ms",
+        profile.total_time().as_secs_f64() * 1000.0
+    ));
+
+    ui.horizontal(|ui| {
+        if ui
+            .button("Copy Report")
+            .on_hover_text("Copies the profile as a plain text table")
+            .clicked()
+        {
+            let mut report = String::new();
+            if profile.write_report(&mut report).is_ok() {
+                ui.ctx().copy_text(report);
+            }
+        }
+        if ui
+            .button("Copy Folded Stacks")
+            .on_hover_text(
+                "Copies the profile in the folded stacks format.\n\
+                It can be turned into a flame graph with `inferno-flamegraph` or `flamegraph.pl`",
+            )
+            .clicked()
+        {
+            let mut folded = String::new();
+            if profile.write_folded(&mut folded).is_ok() {
+                ui.ctx().copy_text(folded);
+            }
+        }
+    });
+
+    let mut sorting = ui
+        .use_state(|| (ProfileColumn::SelfTime, true), ())
+        .into_var();
+    let (column, descending) = *sorting;
+
+    let mut order = (0..profile.entries().len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        let ord = column.compare(profile, *a, *b);
+        if descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+
+    ScrollArea::both().show(ui, |ui| {
+        Grid::new("profile_report").striped(true).show(ui, |ui| {
+            for header in [
+                ProfileColumn::SelfTime,
+                ProfileColumn::Total,
+                ProfileColumn::Calls,
+                ProfileColumn::Node,
+            ] {
+                let arrow = match (header == column, descending) {
+                    (false, _) => "",
+                    (true, true) => " ⏷",
+                    (true, false) => " ⏶",
+                };
+                if ui
+                    .selectable_label(header == column, format!("{}{}", header.title(), arrow))
+                    .clicked()
+                {
+                    *sorting = if header == column {
+                        (header, !descending)
+                    } else {
+                        (header, true)
+                    };
+                }
+            }
+            ui.end_row();
+
+            for idx in order {
+                let ProfileEntry {
+                    calls,
+                    total,
+                    self_time,
+                    ..
+                } = &profile.entries()[idx];
+                ui.label(format!("{:.3}", self_time.as_secs_f64() * 1000.0));
+                ui.label(format!("{:.3}", total.as_secs_f64() * 1000.0));
+                ui.label(calls.to_string());
+                ui.label(profile.entry_path(idx));
+                ui.end_row();
+            }
+        });
+    });
+}
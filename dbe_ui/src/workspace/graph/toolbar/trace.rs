@@ -0,0 +1,237 @@
+use crate::widgets::toggle_button::toggle_button_label;
+use dbe_backend::graph::execution::trace::{RegionIteration, TraceEvent};
+use dbe_backend::graph::node::{NodeContext, SnarlNode};
+use dbe_backend::graph::region::RegionInfo;
+use dbe_backend::graph::Graph;
+use dbe_backend::project::debugger::GraphDebugState;
+use dbe_backend::project::docs::Docs;
+use dbe_backend::registry::ETypesRegistry;
+use egui::{Button, CollapsingHeader, Color32, Grid, RichText, ScrollArea, Slider, TextStyle, Ui};
+use egui_snarl::{NodeId, Snarl};
+
+pub fn edit_trace(
+    ui: &mut Ui,
+    registry: &ETypesRegistry,
+    docs: &Docs,
+    graph: &mut Graph,
+    state: &mut GraphDebugState,
+    selected_nodes: &[NodeId],
+    outdated: bool,
+) {
+    toggle_button_label(ui, &mut state.traced, "Record Trace").on_hover_text(
+        "When enabled, all node evaluations of this graph are recorded during the project evaluation.\n\
+        Traced graphs are always evaluated, even if their inputs didn't change.",
+    );
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(!selected_nodes.is_empty(), Button::new("Toggle Breakpoint"))
+            .on_hover_text("Toggles breakpoints on the selected nodes")
+            .clicked()
+        {
+            for node in selected_nodes {
+                state.toggle_breakpoint(*node);
+            }
+        }
+        if ui
+            .add_enabled(!state.breakpoints.is_empty(), Button::new("Clear"))
+            .on_hover_text("Removes all breakpoints")
+            .clicked()
+        {
+            state.breakpoints.clear();
+        }
+    });
+
+    let (snarl, context) = graph.snarl_and_context(registry, docs);
+    let snarl = &*snarl;
+
+    // Breakpoints of the removed nodes are dropped
+    state
+        .breakpoints
+        .retain(|node| snarl.get_node(*node).is_some());
+
+    CollapsingHeader::new(format!("Breakpoints ({})", state.breakpoints.len()))
+        .default_open(false)
+        .show(ui, |ui| {
+            if state.breakpoints.is_empty() {
+                ui.label("Select nodes and press `Toggle Breakpoint` to add breakpoints");
+            }
+            for node in &state.breakpoints {
+                ui.label(node_title(snarl, context, *node));
+            }
+        });
+
+    ui.separator();
+
+    let Some(recorded) = &mut state.trace else {
+        ui.label("No trace was recorded yet");
+        ui.label("Enable tracing and save the project to record one");
+        return;
+    };
+
+    if outdated {
+        ui.colored_label(
+            Color32::YELLOW,
+            "The graph was changed after the trace was recorded",
+        );
+    }
+    if recorded.trace.is_truncated() {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!(
+                "The trace was truncated, only the first {} node evaluations were recorded",
+                recorded.trace.events().len()
+            ),
+        );
+    }
+
+    let events_count = recorded.trace.events().len();
+    if events_count == 0 {
+        ui.label("No nodes were evaluated");
+        return;
+    }
+
+    let position = &mut recorded.position;
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(position.is_some_and(|p| p > 0), Button::new("⏮"))
+            .on_hover_text("Previous breakpoint hit")
+            .clicked()
+        {
+            if let Some(hit) = position.and_then(|p| recorded.trace.prev_hit(p, &state.breakpoints))
+            {
+                *position = Some(hit);
+            }
+        }
+        if ui
+            .add_enabled(position.is_some_and(|p| p > 0), Button::new("⏴"))
+            .on_hover_text("Step back")
+            .clicked()
+        {
+            *position = position.map(|p| p - 1);
+        }
+        if ui
+            .add_enabled(
+                position.is_none_or(|p| p + 1 < events_count),
+                Button::new("⏵"),
+            )
+            .on_hover_text("Step")
+            .clicked()
+        {
+            *position = Some(position.map_or(0, |p| p + 1));
+        }
+        if ui
+            .add_enabled(
+                position.is_none_or(|p| p + 1 < events_count),
+                Button::new("⏭"),
+            )
+            .on_hover_text("Next breakpoint hit")
+            .clicked()
+        {
+            if let Some(hit) = recorded.trace.next_hit(*position, &state.breakpoints) {
+                *position = Some(hit);
+            }
+        }
+    });
+
+    let mut index = position.unwrap_or(0);
+    if ui
+        .add(Slider::new(&mut index, 0..=events_count - 1).text("Event"))
+        .changed()
+    {
+        *position = Some(index);
+    }
+
+    let events = recorded.trace.events();
+    if let Some(event) = position.and_then(|p| events.get(p)) {
+        show_event(ui, snarl, context, event);
+    }
+
+    ui.separator();
+    ui.label("Timeline");
+
+    let row_height = ui.text_style_height(&TextStyle::Button) + ui.spacing().button_padding.y * 2.0;
+    ScrollArea::vertical().show_rows(ui, row_height, events_count, |ui, range| {
+        for i in range {
+            let event = &events[i];
+            let marker = if state.breakpoints.contains(&event.node) {
+                "● "
+            } else {
+                ""
+            };
+            let text = format!(
+                "{}{}: {}{}",
+                marker,
+                i,
+                node_title(snarl, context, event.node),
+                iterations_label(&event.iterations),
+            );
+            let mut text = RichText::new(text);
+            if event.outputs.is_err() {
+                text = text.color(Color32::RED);
+            }
+            if ui.selectable_label(*position == Some(i), text).clicked() {
+                *position = Some(i);
+            }
+        }
+    });
+}
+
+fn show_event(ui: &mut Ui, snarl: &Snarl<SnarlNode>, context: NodeContext, event: &TraceEvent) {
+    let node = snarl.get_node(event.node);
+
+    ui.heading(node_title(snarl, context, event.node));
+
+    for iteration in &event.iterations {
+        let color = context
+            .regions
+            .get(&iteration.region)
+            .map_or(Color32::GRAY, RegionInfo::color);
+        ui.colored_label(color, format!("Region iteration #{}", iteration.iteration));
+    }
+
+    ui.label("Inputs");
+    Grid::new("trace_inputs").striped(true).show(ui, |ui| {
+        for (i, value) in event.inputs.iter().enumerate() {
+            let name = node
+                .and_then(|node| node.try_input(context, i).ok())
+                .map_or_else(|| format!("#{}", i), |data| data.name.to_string());
+            ui.label(name);
+            ui.label(value.to_string());
+            ui.end_row();
+        }
+    });
+
+    match &event.outputs {
+        Ok(outputs) => {
+            ui.label("Outputs");
+            Grid::new("trace_outputs").striped(true).show(ui, |ui| {
+                for (i, value) in outputs.iter().enumerate() {
+                    let name = node
+                        .and_then(|node| node.try_output(context, i).ok())
+                        .map_or_else(|| format!("#{}", i), |data| data.name.to_string());
+                    ui.label(name);
+                    ui.label(value.to_string());
+                    ui.end_row();
+                }
+            });
+        }
+        Err(err) => {
+            ui.label(RichText::new(strip_ansi_escapes::strip_str(err)).color(Color32::RED));
+        }
+    }
+}
+
+fn node_title(snarl: &Snarl<SnarlNode>, context: NodeContext, node: NodeId) -> String {
+    match snarl.get_node(node) {
+        None => format!("<removed node #{}>", node.0),
+        Some(data) => format!("{} #{}", data.title(context), node.0),
+    }
+}
+
+fn iterations_label(iterations: &[RegionIteration]) -> String {
+    iterations
+        .iter()
+        .map(|i| format!(" [{}]", i.iteration))
+        .collect()
+}
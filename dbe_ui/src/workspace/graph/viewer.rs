@@ -1,6 +1,7 @@
 use crate::workspace::graph::viewer::default_view::DefaultNodeView;
 use crate::workspace::graph::viewer::destructuring::DestructuringNodeViewer;
 use crate::workspace::graph::viewer::reroute::RerouteNodeViewer;
+use crate::workspace::graph::viewer::script::ScriptNodeViewer;
 use crate::workspace::graph::viewer::subgraph::SubgraphNodeViewer;
 use crate::workspace::graph::viewer::tree_subgraph::TreeSubgraphNodeViewer;
 use crate::workspace::graph::GraphViewer;
@@ -17,6 +18,7 @@ use ustr::{Ustr, UstrMap};
 pub mod default_view;
 pub mod destructuring;
 pub mod reroute;
+pub mod script;
 pub mod subgraph;
 pub mod tree_subgraph;
 
@@ -30,6 +32,7 @@ fn default_viewers() -> impl Iterator<Item = (Ustr, Arc<dyn NodeView>)> {
         Arc::new(SubgraphNodeViewer),
         Arc::new(DestructuringNodeViewer),
         Arc::new(TreeSubgraphNodeViewer),
+        Arc::new(ScriptNodeViewer),
     ];
     v.into_iter().map(|item| (Ustr::from(&item.id()), item))
 }
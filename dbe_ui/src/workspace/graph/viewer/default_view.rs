@@ -99,30 +99,36 @@ impl NodeView for DefaultNodeView {
             };
 
             if pin.remotes.is_empty() {
-                let mut full_ctx = viewer.ctx.as_full(snarl);
-                if let Some(value) = full_ctx.get_inline_input_mut(pin.id)? {
-                    if input_data.ty.has_inline_value(registry) {
-                        if value.ty() != input_data.ty.ty() {
-                            *value = input_data.ty.default_value(registry).into_owned();
+                let docs_ref = ctx.docs_ref.clone();
+                let diagnostics = &mut viewer.diagnostics;
+                let edited = viewer
+                    .ctx
+                    .as_full(snarl)
+                    .edit_inline_input(pin.id, |value| {
+                        if input_data.ty.has_inline_value(registry) {
+                            if value.ty() != input_data.ty.ty() {
+                                *value = input_data.ty.default_value(registry).into_owned();
+                            }
+
+                            let editor = editor_for_item(registry, info);
+                            ui.vertical(|ui| {
+                                editor.show(
+                                    ui,
+                                    ctx,
+                                    diagnostics.enter_field(input_data.name.as_str()),
+                                    &input_data.name,
+                                    value,
+                                )
+                            });
+                        } else {
+                            ui.horizontal(|ui| {
+                                ui.label(format_value(value));
+                            });
                         }
+                    })?;
 
-                        let editor = editor_for_item(registry, info);
-                        ui.vertical(|ui| {
-                            editor.show(
-                                ui,
-                                ctx,
-                                viewer.diagnostics.enter_field(input_data.name.as_str()),
-                                &input_data.name,
-                                value,
-                            )
-                        });
-                    } else {
-                        ui.horizontal(|ui| {
-                            ui.label(format_value(value));
-                        });
-                    }
-                } else {
-                    docs_label(ui, &input_data.name, docs, registry, ctx.docs_ref);
+                if edited.is_none() {
+                    docs_label(ui, &input_data.name, docs, registry, docs_ref);
                 }
             } else {
                 has_connection = true;
@@ -0,0 +1,83 @@
+use crate::main_toolbar::docs::docs_label;
+use crate::workspace::graph::viewer::NodeView;
+use crate::workspace::graph::GraphViewer;
+use dbe_backend::graph::node::script_node::{ScriptNode, ScriptNodeFactory};
+use dbe_backend::graph::node::{Node, NodeFactory, SnarlNode};
+use dbe_backend::project::docs::DocsRef;
+use dbe_backend::project::scripts::ProjectScripts;
+use egui::Ui;
+use egui_hooks::UseHookExt;
+use egui_snarl::{InPin, NodeId, OutPin, Snarl};
+use std::ops::DerefMut;
+use ustr::{ustr, Ustr};
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ScriptNodeViewer;
+
+impl NodeView for ScriptNodeViewer {
+    fn id(&self) -> Ustr {
+        ScriptNodeFactory.id()
+    }
+
+    fn has_body(&self, _viewer: &mut GraphViewer, _node: &SnarlNode) -> miette::Result<bool> {
+        Ok(true)
+    }
+
+    fn show_body(
+        &self,
+        viewer: &mut GraphViewer,
+        node_id: NodeId,
+        _inputs: &[InPin],
+        _outputs: &[OutPin],
+        ui: &mut Ui,
+        _scale: f32,
+        snarl: &mut Snarl<SnarlNode>,
+    ) -> miette::Result<()> {
+        let node = snarl[node_id]
+            .downcast_mut::<ScriptNode>()
+            .expect("ScriptNodeViewer should only be used with ScriptNode");
+
+        let Some(graphs) = viewer.ctx.graphs else {
+            return Ok(());
+        };
+
+        ui.horizontal(|ui| {
+            docs_label(
+                ui,
+                "script",
+                viewer.ctx.docs,
+                viewer.ctx.registry,
+                DocsRef::NodeState(node.id(), ustr("script")),
+            );
+            scripts_combobox(ui, &mut node.script_id, &graphs.scripts);
+        });
+
+        Ok(())
+    }
+}
+
+fn scripts_combobox(ui: &mut Ui, selected: &mut Uuid, scripts: &ProjectScripts) {
+    ui.push_id("scripts_combobox", |ui| {
+        egui::ComboBox::from_id_salt("dropdown")
+            .selected_text(scripts.scripts.get(selected).map_or_else(
+                || "!!unknown script!!".to_string(),
+                |script| script.name.clone(),
+            ))
+            .show_ui(ui, |ui| {
+                let mut search_query = ui.use_state(|| "".to_string(), *selected).into_var();
+                let search_bar = ui.text_edit_singleline(search_query.deref_mut());
+                search_bar.request_focus();
+
+                let query = search_query.trim();
+
+                for (id, script) in scripts
+                    .scripts
+                    .iter()
+                    .filter(|(_, s)| query.is_empty() || s.name.contains(query))
+                {
+                    ui.selectable_value(selected, *id, &script.name);
+                }
+            })
+    });
+}
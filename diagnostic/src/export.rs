@@ -0,0 +1,376 @@
+//! Machine-readable export of [DiagnosticContext] contents, as plain JSON
+//! records or as a [SARIF](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log
+
+use crate::context::DiagnosticContext;
+use crate::diagnostic::DiagnosticLevel;
+use crate::path::DiagnosticPathSegment;
+use miette::{LabeledSpan, SourceCode};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Single diagnostic, detached from the context
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticRecord {
+    /// Identifier of the diagnostic context, usually a path to the file
+    pub file: String,
+    /// Path to the item within the file
+    pub path: Vec<DiagnosticPathSegment>,
+    /// Human-readable form of the [DiagnosticRecord::path]
+    pub path_string: String,
+    pub level: DiagnosticLevel,
+    #[serde(flatten)]
+    pub report: ReportRecord,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRecord {
+    pub message: String,
+    /// Messages of the underlying errors, outermost first
+    pub causes: Vec<String>,
+    pub code: Option<String>,
+    pub help: Option<String>,
+    pub labels: Vec<SpanRecord>,
+    pub related: Vec<ReportRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpanRecord {
+    pub label: Option<String>,
+    /// Name of the source this span points into, if known
+    pub source: Option<String>,
+    pub offset: usize,
+    pub length: usize,
+    /// Zero-based line of the span start, if source code is available
+    pub line: Option<usize>,
+    /// Zero-based column of the span start, if source code is available
+    pub column: Option<usize>,
+}
+
+/// Collects all diagnostics of the context into a flat list of records
+pub fn diagnostic_records(ctx: &DiagnosticContext) -> Vec<DiagnosticRecord> {
+    let mut records = vec![];
+    for (file, paths) in &ctx.diagnostics {
+        for (path, diagnostics) in paths {
+            for diagnostic in diagnostics {
+                records.push(DiagnosticRecord {
+                    file: file.clone(),
+                    path: path.iter().cloned().collect(),
+                    path_string: path.to_string(),
+                    level: diagnostic.level,
+                    report: report_record(diagnostic.info.as_ref()),
+                });
+            }
+        }
+    }
+    records
+}
+
+/// Exports all diagnostics of the context as a JSON array of [DiagnosticRecord]
+pub fn to_json(ctx: &DiagnosticContext) -> Value {
+    serde_json::to_value(diagnostic_records(ctx))
+        .expect("serialization of diagnostic records should not fail")
+}
+
+/// Exports all diagnostics of the context as a SARIF log
+///
+/// File identifiers are emitted as URIs relative to the `PROJECTROOT` base
+pub fn to_sarif(ctx: &DiagnosticContext, tool_name: &str, tool_version: &str) -> Value {
+    let results = diagnostic_records(ctx)
+        .into_iter()
+        .map(|record| sarif_result(&record))
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": tool_name,
+                    "version": tool_version,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn report_record(report: &dyn miette::Diagnostic) -> ReportRecord {
+    let mut causes = vec![];
+    let mut source = report.source();
+    while let Some(err) = source {
+        causes.push(err.to_string());
+        source = err.source();
+    }
+
+    let source_code = report.source_code();
+    let labels = report
+        .labels()
+        .into_iter()
+        .flatten()
+        .map(|span| span_record(&span, source_code))
+        .collect();
+
+    let related = report
+        .related()
+        .into_iter()
+        .flatten()
+        .map(report_record)
+        .collect();
+
+    ReportRecord {
+        message: report.to_string(),
+        causes,
+        code: report.code().map(|c| c.to_string()),
+        help: report.help().map(|h| h.to_string()),
+        labels,
+        related,
+    }
+}
+
+fn span_record(span: &LabeledSpan, source_code: Option<&dyn SourceCode>) -> SpanRecord {
+    let contents = source_code.and_then(|code| code.read_span(span.inner(), 0, 0).ok());
+    SpanRecord {
+        label: span.label().map(str::to_string),
+        source: contents.as_ref().and_then(|c| c.name()).map(str::to_string),
+        offset: span.offset(),
+        length: span.len(),
+        line: contents.as_ref().map(|c| c.line()),
+        column: contents.as_ref().map(|c| c.column()),
+    }
+}
+
+fn sarif_level(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+        DiagnosticLevel::Info => "note",
+        DiagnosticLevel::Debug | DiagnosticLevel::Trace => "none",
+    }
+}
+
+fn sarif_message(report: &ReportRecord) -> String {
+    let mut text = report.message.clone();
+    for cause in &report.causes {
+        text.push_str(": ");
+        text.push_str(cause);
+    }
+    if let Some(help) = &report.help {
+        text.push_str("\nhelp: ");
+        text.push_str(help);
+    }
+    text
+}
+
+/// Builds a SARIF physical location pointing into the given span
+///
+/// Spans without a named source can't be attributed to any artifact, so
+/// such locations only point to the file itself, without a region
+fn sarif_location(file: &str, span: Option<&SpanRecord>) -> Value {
+    let span = span.and_then(|s| s.source.as_deref().map(|source| (source, s)));
+    let uri = span.map(|(source, _)| source).unwrap_or(file);
+    let mut location = json!({
+        "artifactLocation": {
+            "uri": uri,
+            "uriBaseId": "PROJECTROOT",
+        }
+    });
+    if let Some((_, span)) = span {
+        // miette spans are measured in bytes
+        let mut region = json!({
+            "byteOffset": span.offset,
+            "byteLength": span.length,
+        });
+        if let (Some(line), Some(column)) = (span.line, span.column) {
+            region["startLine"] = json!(line + 1);
+            region["startColumn"] = json!(column + 1);
+        }
+        location["region"] = region;
+    }
+    location
+}
+
+fn sarif_result(record: &DiagnosticRecord) -> Value {
+    let mut location = json!({
+        "physicalLocation": sarif_location(&record.file, None),
+    });
+    if !record.path.is_empty() {
+        location["logicalLocations"] = json!([{
+            "fullyQualifiedName": record.path_string,
+            "kind": "member",
+        }]);
+    }
+
+    let mut related_locations = vec![];
+    for span in &record.report.labels {
+        related_locations.push(json!({
+            "id": related_locations.len(),
+            "physicalLocation": sarif_location(&record.file, Some(span)),
+            "message": { "text": span.label.clone().unwrap_or_default() },
+        }));
+    }
+    for related in &record.report.related {
+        let span = related.labels.first();
+        related_locations.push(json!({
+            "id": related_locations.len(),
+            "physicalLocation": sarif_location(&record.file, span),
+            "message": { "text": sarif_message(related) },
+        }));
+    }
+
+    let mut result = json!({
+        "level": sarif_level(record.level),
+        "message": { "text": sarif_message(&record.report) },
+        "locations": [location],
+    });
+    if let Some(code) = &record.report.code {
+        result["ruleId"] = json!(code);
+    }
+    if !related_locations.is_empty() {
+        result["relatedLocations"] = Value::Array(related_locations);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_json, to_sarif};
+    use crate::context::DiagnosticContext;
+    use crate::diagnostic::DiagnosticLevel;
+    use miette::{miette, LabeledSpan, NamedSource};
+    use serde_json::json;
+
+    fn context() -> DiagnosticContext {
+        let mut ctx = DiagnosticContext::default();
+        {
+            let mut file = ctx.enter("items/laser.json");
+            file.enter_field("Damage")
+                .enter_index(1)
+                .emit_error(miette!("too low").wrap_err("bad damage"));
+            file.enter_field("Stats")
+                .enter_map_key("speed")
+                .emit_warning(miette!(help = "use a positive value", "negative speed"));
+            file.emit(miette!("file note"), DiagnosticLevel::Info);
+            file.emit(miette!("file trace"), DiagnosticLevel::Trace);
+        }
+        ctx
+    }
+
+    #[test]
+    fn json_export() {
+        let json = to_json(&context());
+        let records = json.as_array().unwrap();
+        assert_eq!(records.len(), 4);
+
+        // Records are ordered by path, with the file itself first
+        assert_eq!(records[0]["file"], "items/laser.json");
+        assert_eq!(records[0]["path"], json!([]));
+        assert_eq!(records[0]["path_string"], "");
+        assert_eq!(records[0]["level"], "info");
+        assert_eq!(records[1]["level"], "trace");
+
+        let damage = &records[2];
+        assert_eq!(
+            damage["path"],
+            json!([
+                { "kind": "field", "value": "Damage" },
+                { "kind": "index", "value": 1 },
+            ])
+        );
+        assert_eq!(damage["path_string"], "Damage[1]");
+        assert_eq!(damage["level"], "error");
+        assert_eq!(damage["message"], "bad damage");
+        assert_eq!(damage["causes"], json!(["too low"]));
+
+        let speed = &records[3];
+        assert_eq!(
+            speed["path"],
+            json!([
+                { "kind": "field", "value": "Stats" },
+                { "kind": "map_key", "value": "speed" },
+            ])
+        );
+        assert_eq!(speed["level"], "warning");
+        assert_eq!(speed["help"], "use a positive value");
+    }
+
+    #[test]
+    fn sarif_export() {
+        let sarif = to_sarif(&context(), "dbe", "1.0.0");
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "dbe");
+        assert_eq!(run["tool"]["driver"]["version"], "1.0.0");
+
+        let results = run["results"].as_array().unwrap();
+        let levels = results
+            .iter()
+            .map(|r| r["level"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(levels, ["note", "none", "error", "warning"]);
+
+        let file_note = &results[0];
+        assert_eq!(
+            file_note["locations"][0]["physicalLocation"]["artifactLocation"],
+            json!({ "uri": "items/laser.json", "uriBaseId": "PROJECTROOT" })
+        );
+        assert!(file_note["locations"][0].get("logicalLocations").is_none());
+
+        let damage = &results[2];
+        assert_eq!(damage["message"]["text"], "bad damage: too low");
+        assert_eq!(
+            damage["locations"][0]["logicalLocations"],
+            json!([{ "fullyQualifiedName": "Damage[1]", "kind": "member" }])
+        );
+
+        assert_eq!(
+            results[3]["message"]["text"],
+            "negative speed\nhelp: use a positive value"
+        );
+    }
+
+    #[test]
+    fn sarif_regions() {
+        let mut ctx = DiagnosticContext::default();
+        {
+            let mut file = ctx.enter("items/laser.json");
+            let named = miette!(
+                labels = vec![LabeledSpan::at(9..14, "this one")],
+                "bad field"
+            )
+            .with_source_code(NamedSource::new("types/laser.kdl", "név = 1\nspeed = -2"));
+            file.enter_field("Named").emit_error(named);
+            let unnamed = miette!(labels = vec![LabeledSpan::at(0..3, "here")], "no name")
+                .with_source_code("abc");
+            file.enter_field("Unnamed").emit_error(unnamed);
+        }
+
+        let sarif = to_sarif(&ctx, "dbe", "1.0.0");
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+
+        // Offsets are reported in bytes, the span skips over a two-byte character
+        let named = &results[0]["relatedLocations"][0]["physicalLocation"];
+        assert_eq!(
+            named["artifactLocation"],
+            json!({ "uri": "types/laser.kdl", "uriBaseId": "PROJECTROOT" })
+        );
+        assert_eq!(named["region"]["byteOffset"], 9);
+        assert_eq!(named["region"]["byteLength"], 5);
+        assert_eq!(named["region"]["startLine"], 2);
+        assert!(named["region"].get("charOffset").is_none());
+
+        // Spans of unnamed sources are not attributed to the record file
+        let unnamed = &results[1]["relatedLocations"][0];
+        assert_eq!(
+            unnamed["physicalLocation"]["artifactLocation"],
+            json!({ "uri": "items/laser.json", "uriBaseId": "PROJECTROOT" })
+        );
+        assert!(unnamed["physicalLocation"].get("region").is_none());
+        assert_eq!(unnamed["message"]["text"], "here");
+    }
+}
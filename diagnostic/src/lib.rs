@@ -1,5 +1,6 @@
 pub mod context;
 pub mod diagnostic;
+pub mod export;
 pub mod path;
 
 pub mod prelude {
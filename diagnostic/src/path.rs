@@ -4,11 +4,13 @@ use std::hash::{Hash, Hasher};
 use std::sync::LazyLock;
 
 use lockfree_object_pool::{LinearObjectPool, LinearReusable};
+use serde::Serialize;
 
 static PATH_VEC_POOL: LazyLock<LinearObjectPool<Vec<DiagnosticPathSegment>>> =
     LazyLock::new(|| LinearObjectPool::new(Vec::new, Vec::clear));
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
 pub enum DiagnosticPathSegment {
     Index(usize),
     MapKey(Cow<'static, str>),